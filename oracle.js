@@ -1,8 +1,8 @@
 // server.js
 const express = require('express');
-const { Connection, PublicKey, Keypair, Transaction, SystemProgram } = require('@solana/web3.js');
+const { Connection, PublicKey, Keypair, Transaction, SystemProgram, SYSVAR_SLOT_HASHES_PUBKEY, SYSVAR_RENT_PUBKEY, SYSVAR_CLOCK_PUBKEY } = require('@solana/web3.js');
 const { Program, AnchorProvider, web3, BN, utils } = require('@coral-xyz/anchor');
-const { TOKEN_PROGRAM_ID, getAssociatedTokenAddress } = require('@solana/spl-token');
+const { ASSOCIATED_TOKEN_PROGRAM_ID, getAssociatedTokenAddress } = require('@solana/spl-token');
 const IDL = require('./idl.json'); // You'll need to generate this from your program
 
 const app = express();
@@ -12,6 +12,11 @@ app.use(express.json());
 const PROGRAM_ID = new PublicKey('GqEkgwLMtTZ2XmP4LnwJUQbAQWUR3PMfTN8pNojBH6ks');
 const RPC_URL = process.env.RPC_URL || 'https://api.devnet.solana.com';
 const PORT = process.env.PORT || 3000;
+const BPF_LOADER_UPGRADEABLE_PROGRAM_ID = new PublicKey('BPFLoaderUpgradeab1e11111111111111111111111');
+
+// Zero-copy price accounts store enums as raw bytes, indexed by these names
+const PRICE_STATUSES = ['trading', 'halted', 'auction', 'unknown', 'degraded', 'closed'];
+const CONFIDENCE_MODES = ['absolute', 'bps'];
 
 // Initialize connection
 const connection = new Connection(RPC_URL, 'confirmed');
@@ -26,6 +31,25 @@ const getProgram = (provider) => {
   return new Program(IDL, PROGRAM_ID, provider);
 };
 
+// The token mint may belong to SPL Token or Token-2022; its owner is the program to call
+const getTokenProgramId = async (mint) => {
+  const mintInfo = await connection.getAccountInfo(mint);
+  if (!mintInfo) {
+    throw new Error(`Token mint ${mint.toBase58()} not found`);
+  }
+  return mintInfo.owner;
+};
+
+// Governance can rotate the vault to a new custody authority and token account,
+// so every instruction after initialization takes them from the TokenVault
+const getVaultAccounts = async (program, tokenVault) => {
+  const vaultData = await program.account.tokenVault.fetch(tokenVault);
+  return {
+    vaultAuthority: vaultData.vaultAuthority,
+    vaultTokenAccount: vaultData.vaultTokenAccount,
+  };
+};
+
 // ============================================================================
 // HEALTH CHECK ENDPOINT
 // ============================================================================
@@ -37,6 +61,16 @@ const getProgram = (provider) => {
 app.get('/api/health', async (req, res) => {
   try {
     const currentSlot = await connection.getSlot();
+
+    // The deployment's recorded cluster must match the RPC it is reached through
+    const program = getProgram(getProvider({ publicKey: PROGRAM_ID }));
+    const [clusterConfig] = PublicKey.findProgramAddressSync(
+      [Buffer.from('cluster_config')],
+      program.programId
+    );
+    const clusterData = await program.account.clusterConfig.fetchNullable(clusterConfig);
+    const genesisHash = await connection.getGenesisHash();
+
     res.json({
       success: true,
       status: 'healthy',
@@ -44,6 +78,12 @@ app.get('/api/health', async (req, res) => {
       rpcUrl: RPC_URL,
       programId: PROGRAM_ID.toBase58(),
       currentSlot: currentSlot,
+      cluster: clusterData ? {
+        environment: Object.keys(clusterData.environment)[0],
+        tokenMint: clusterData.tokenMint.toBase58(),
+        label: clusterData.label,
+        genesisMatches: utils.bytes.bs58.encode(Buffer.from(clusterData.genesisHash)) === genesisHash
+      } : null,
       version: '1.0.0'
     });
   } catch (error) {
@@ -73,13 +113,16 @@ app.post('/api/initialize', async (req, res) => {
       votingPeriod,
       quorumPercentage,
       timelockDuration,
-      totalSupply
+      totalSupply,
+      environment,
+      label = ''
     } = req.body;
 
     const authority = Keypair.fromSecretKey(
       Uint8Array.from(Buffer.from(authoritySecretKey, 'base64'))
     );
     const tokenMint = new PublicKey(tokenMintAddress);
+    const tokenProgram = await getTokenProgramId(tokenMint);
 
     const provider = getProvider({ publicKey: authority.publicKey, signTransaction: async (tx) => tx, signAllTransactions: async (txs) => txs });
     const program = getProgram(provider);
@@ -107,9 +150,40 @@ app.post('/api/initialize', async (req, res) => {
     const vaultTokenAccount = await getAssociatedTokenAddress(
       tokenMint,
       vaultAuthority,
-      true
+      true,
+      tokenProgram
+    );
+
+    const [programData] = PublicKey.findProgramAddressSync(
+      [program.programId.toBuffer()],
+      BPF_LOADER_UPGRADEABLE_PROGRAM_ID
     );
 
+    // Records the cluster this deployment runs on, from the RPC's genesis hash
+    const [clusterConfig] = PublicKey.findProgramAddressSync(
+      [Buffer.from('cluster_config')],
+      program.programId
+    );
+    const postInstructions = [];
+    if (environment) {
+      const genesisHash = utils.bytes.bs58.decode(await connection.getGenesisHash());
+      postInstructions.push(await program.methods
+        .initializeClusterConfig(
+          { [environment.toLowerCase()]: {} },
+          Array.from(genesisHash),
+          label
+        )
+        .accounts({
+          globalState,
+          clusterConfig,
+          tokenMint,
+          authority: authority.publicKey,
+          payer: authority.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .instruction());
+    }
+
     const tx = await program.methods
       .initializeProgram(
         new BN(rewardRate),
@@ -126,9 +200,13 @@ app.post('/api/initialize', async (req, res) => {
         governanceState,
         tokenMint,
         vaultTokenAccount,
+        program: program.programId,
+        programData,
         authority: authority.publicKey,
+        payer: authority.publicKey,
         systemProgram: SystemProgram.programId,
       })
+      .postInstructions(postInstructions)
       .signers([authority])
       .rpc();
 
@@ -139,7 +217,8 @@ app.post('/api/initialize', async (req, res) => {
         globalState: globalState.toBase58(),
         vaultAuthority: vaultAuthority.toBase58(),
         tokenVault: tokenVault.toBase58(),
-        governanceState: governanceState.toBase58()
+        governanceState: governanceState.toBase58(),
+        clusterConfig: environment ? clusterConfig.toBase58() : null
       }
     });
   } catch (error) {
@@ -155,17 +234,25 @@ app.post('/api/products/create', async (req, res) => {
   try {
     const {
       authoritySecretKey,
+      payerSecretKey,
       symbol,
       assetType,
       description,
       priceType,
       minPublishers,
-      exponent
+      exponent,
+      tokenMintAddress,
+      emaAlpha,
+      emaConfidenceWeighted
     } = req.body;
 
     const authority = Keypair.fromSecretKey(
       Uint8Array.from(Buffer.from(authoritySecretKey, 'base64'))
     );
+    // A sponsor may pay the rent; defaults to the authority
+    const payer = payerSecretKey
+      ? Keypair.fromSecretKey(Uint8Array.from(Buffer.from(payerSecretKey, 'base64')))
+      : authority;
 
     const provider = getProvider({ publicKey: authority.publicKey });
     const program = getProgram(provider);
@@ -185,9 +272,68 @@ app.post('/api/products/create', async (req, res) => {
       program.programId
     );
 
+    // The authority locks listing collateral sized by the asset type's risk tier
+    const tokenMint = new PublicKey(tokenMintAddress);
+    const tokenProgram = await getTokenProgramId(tokenMint);
+
+    const [feedCollateral] = PublicKey.findProgramAddressSync(
+      [Buffer.from('feed_collateral'), priceAccount.toBuffer()],
+      program.programId
+    );
+
+    const [tokenVault] = PublicKey.findProgramAddressSync(
+      [Buffer.from('token_vault')],
+      program.programId
+    );
+
+    const creatorTokenAccount = await getAssociatedTokenAddress(
+      tokenMint,
+      authority.publicKey,
+      false,
+      tokenProgram
+    );
+
+    const { vaultTokenAccount } = await getVaultAccounts(program, tokenVault);
+
     const assetTypeEnum = { [assetType.toLowerCase()]: {} };
     const priceTypeEnum = { [priceType.toLowerCase()]: {} };
 
+    // The asset type's template supplies the feed defaults when it has been created
+    const [assetTemplate] = PublicKey.findProgramAddressSync(
+      [Buffer.from('asset_template'), Buffer.from(assetType.toLowerCase())],
+      program.programId
+    );
+    const assetTemplateInfo = await connection.getAccountInfo(assetTemplate);
+
+    // Confidence weighting is applied by the authority in the same transaction
+    const postInstructions = [];
+    if (emaConfidenceWeighted !== undefined) {
+      postInstructions.push(await program.methods
+        .updateFeedConfig({
+          confidenceStakeScale: null,
+          halted: null,
+          stalenessThreshold: null,
+          degradedConfidenceBps: null,
+          roundingMode: null,
+          highPrecision: null,
+          commitReveal: null,
+          sampleSize: null,
+          confidenceMode: null,
+          minOperators: null,
+          emaAlpha: null,
+          emaConfidenceWeighted,
+          minSlotsBetweenAggregations: null,
+          permissioned: null,
+          deviationAlertMads: null,
+        })
+        .accounts({
+          priceAccount,
+          feedAdmin: null,
+          admin: authority.publicKey,
+        })
+        .instruction());
+    }
+
     const tx = await program.methods
       .createProduct(
         symbol,
@@ -195,23 +341,34 @@ app.post('/api/products/create', async (req, res) => {
         description,
         priceTypeEnum,
         minPublishers,
-        exponent
+        exponent,
+        emaAlpha ?? 0
       )
       .accounts({
         globalState,
         productAccount,
         priceAccount,
+        feedCollateral,
+        tokenVault,
+        creatorTokenAccount,
+        vaultTokenAccount,
+        tokenMint,
+        tokenProgram,
+        assetTemplate: assetTemplateInfo ? assetTemplate : null,
         authority: authority.publicKey,
+        payer: payer.publicKey,
         systemProgram: SystemProgram.programId,
       })
-      .signers([authority])
+      .postInstructions(postInstructions)
+      .signers([authority, payer])
       .rpc();
 
     res.json({
       success: true,
       signature: tx,
       productAccount: productAccount.toBase58(),
-      priceAccount: priceAccount.toBase58()
+      priceAccount: priceAccount.toBase58(),
+      feedCollateral: feedCollateral.toBase58()
     });
   } catch (error) {
     res.status(500).json({ success: false, error: error.message });
@@ -243,6 +400,7 @@ app.post('/api/publishers/add', async (req, res) => {
       Uint8Array.from(Buffer.from(publisherAuthoritySecretKey, 'base64'))
     );
     const tokenMint = new PublicKey(tokenMintAddress);
+    const tokenProgram = await getTokenProgramId(tokenMint);
 
     const provider = getProvider({ publicKey: payer.publicKey });
     const program = getProgram(provider);
@@ -262,21 +420,14 @@ app.post('/api/publishers/add', async (req, res) => {
       program.programId
     );
 
-    const [vaultAuthority] = PublicKey.findProgramAddressSync(
-      [Buffer.from('vault_authority')],
-      program.programId
-    );
-
     const publisherTokenAccount = await getAssociatedTokenAddress(
       tokenMint,
-      publisherAuthority.publicKey
+      publisherAuthority.publicKey,
+      false,
+      tokenProgram
     );
 
-    const vaultTokenAccount = await getAssociatedTokenAddress(
-      tokenMint,
-      vaultAuthority,
-      true
-    );
+    const { vaultTokenAccount } = await getVaultAccounts(program, tokenVault);
 
     const tx = await program.methods
       .addPublisher(name, new BN(initialStake))
@@ -288,7 +439,8 @@ app.post('/api/publishers/add', async (req, res) => {
         vaultTokenAccount,
         publisherAuthority: publisherAuthority.publicKey,
         payer: payer.publicKey,
-        tokenProgram: TOKEN_PROGRAM_ID,
+        tokenMint,
+        tokenProgram,
         systemProgram: SystemProgram.programId,
       })
       .signers([payer, publisherAuthority])
@@ -314,12 +466,18 @@ app.post('/api/prices/update', async (req, res) => {
       publisherAuthoritySecretKey,
       symbol,
       price,
-      confidence
+      confidence,
+      sourceId = '0',
+      sequence,
+      cosignerSecretKey
     } = req.body;
 
     const publisherAuthority = Keypair.fromSecretKey(
       Uint8Array.from(Buffer.from(publisherAuthoritySecretKey, 'base64'))
     );
+    const cosigner = cosignerSecretKey
+      ? Keypair.fromSecretKey(Uint8Array.from(Buffer.from(cosignerSecretKey, 'base64')))
+      : null;
 
     const provider = getProvider({ publicKey: publisherAuthority.publicKey });
     const program = getProgram(provider);
@@ -344,16 +502,74 @@ app.post('/api/prices/update', async (req, res) => {
       program.programId
     );
 
+    const [tokenVault] = PublicKey.findProgramAddressSync(
+      [Buffer.from('token_vault')],
+      program.programId
+    );
+
+    // Quotes keep the feed's uptime stats current when they exist
+    const [feedStats] = PublicKey.findProgramAddressSync(
+      [Buffer.from('feed_stats'), priceAccount.toBuffer()],
+      program.programId
+    );
+    const feedStatsInfo = await connection.getAccountInfo(feedStats);
+
+    // Likewise the packed mirror read by non-Anchor programs
+    const [priceMirror] = PublicKey.findProgramAddressSync(
+      [Buffer.from('price_mirror'), priceAccount.toBuffer()],
+      program.programId
+    );
+    const priceMirrorInfo = await connection.getAccountInfo(priceMirror);
+
+    // And the feed's price history, appended whenever the quote aggregates
+    const [priceHistory] = PublicKey.findProgramAddressSync(
+      [Buffer.from('price_history'), productAccount.toBuffer()],
+      program.programId
+    );
+    const priceHistoryInfo = await connection.getAccountInfo(priceHistory);
+
+    // Feeds with submission quotas count every quote against them
+    const [feedQuota] = PublicKey.findProgramAddressSync(
+      [Buffer.from('feed_quota'), priceAccount.toBuffer()],
+      program.programId
+    );
+    const feedQuotaInfo = await connection.getAccountInfo(feedQuota);
+
+    // Permissioned feeds only take quotes from publishers holding a permission
+    const [feedPermission] = PublicKey.findProgramAddressSync(
+      [Buffer.from('feed_permission'), priceAccount.toBuffer(), publisherAuthority.publicKey.toBuffer()],
+      program.programId
+    );
+    const feedPermissionInfo = await connection.getAccountInfo(feedPermission);
+
+    // Without an explicit sequence, quote one above the publisher's last on this feed
+    let quoteSequence = sequence;
+    if (quoteSequence === undefined) {
+      const priceData = await program.account.priceAccount.fetch(priceAccount);
+      const current = priceData.publishers.find(
+        (p) => p.active !== 0 && p.publisher.equals(publisherAuthority.publicKey)
+      );
+      quoteSequence = current ? current.sequence + 1 : 1;
+    }
+
     const tx = await program.methods
-      .updatePrice(new BN(price), new BN(confidence))
+      .updatePrice(new BN(price), new BN(confidence), new BN(sourceId), quoteSequence)
       .accounts({
         globalState,
         productAccount,
         priceAccount,
         publisherAccount,
+        tokenVault,
+        slotHashes: SYSVAR_SLOT_HASHES_PUBKEY,
         publisherAuthority: publisherAuthority.publicKey,
+        cosigner: cosigner ? cosigner.publicKey : null,
+        feedStats: feedStatsInfo ? feedStats : null,
+        priceMirror: priceMirrorInfo ? priceMirror : null,
+        feedQuota: feedQuotaInfo ? feedQuota : null,
+        priceHistory: priceHistoryInfo ? priceHistory : null,
+        feedPermission: feedPermissionInfo ? feedPermission : null,
       })
-      .signers([publisherAuthority])
+      .signers(cosigner ? [publisherAuthority, cosigner] : [publisherAuthority])
       .rpc();
 
     res.json({
@@ -361,6 +577,7 @@ app.post('/api/prices/update', async (req, res) => {
       signature: tx,
       price,
       confidence,
+      sequence: quoteSequence,
       timestamp: new Date().toISOString()
     });
   } catch (error) {
@@ -384,6 +601,7 @@ app.post('/api/publishers/stake', async (req, res) => {
       Uint8Array.from(Buffer.from(publisherAuthoritySecretKey, 'base64'))
     );
     const tokenMint = new PublicKey(tokenMintAddress);
+    const tokenProgram = await getTokenProgramId(tokenMint);
 
     const provider = getProvider({ publicKey: publisherAuthority.publicKey });
     const program = getProgram(provider);
@@ -403,21 +621,14 @@ app.post('/api/publishers/stake', async (req, res) => {
       program.programId
     );
 
-    const [vaultAuthority] = PublicKey.findProgramAddressSync(
-      [Buffer.from('vault_authority')],
-      program.programId
-    );
-
     const publisherTokenAccount = await getAssociatedTokenAddress(
       tokenMint,
-      publisherAuthority.publicKey
+      publisherAuthority.publicKey,
+      false,
+      tokenProgram
     );
 
-    const vaultTokenAccount = await getAssociatedTokenAddress(
-      tokenMint,
-      vaultAuthority,
-      true
-    );
+    const { vaultTokenAccount } = await getVaultAccounts(program, tokenVault);
 
     const tx = await program.methods
       .stakeTokens(new BN(amount))
@@ -428,7 +639,8 @@ app.post('/api/publishers/stake', async (req, res) => {
         publisherTokenAccount,
         vaultTokenAccount,
         publisherAuthority: publisherAuthority.publicKey,
-        tokenProgram: TOKEN_PROGRAM_ID,
+        tokenMint,
+        tokenProgram,
       })
       .signers([publisherAuthority])
       .rpc();
@@ -500,13 +712,15 @@ app.post('/api/publishers/withdraw-unbonded', async (req, res) => {
   try {
     const {
       publisherAuthoritySecretKey,
-      tokenMintAddress
+      tokenMintAddress,
+      amount
     } = req.body;
 
     const publisherAuthority = Keypair.fromSecretKey(
       Uint8Array.from(Buffer.from(publisherAuthoritySecretKey, 'base64'))
     );
     const tokenMint = new PublicKey(tokenMintAddress);
+    const tokenProgram = await getTokenProgramId(tokenMint);
 
     const provider = getProvider({ publicKey: publisherAuthority.publicKey });
     const program = getProgram(provider);
@@ -521,11 +735,6 @@ app.post('/api/publishers/withdraw-unbonded', async (req, res) => {
       program.programId
     );
 
-    const [vaultAuthority] = PublicKey.findProgramAddressSync(
-      [Buffer.from('vault_authority')],
-      program.programId
-    );
-
     const [tokenVault] = PublicKey.findProgramAddressSync(
       [Buffer.from('token_vault')],
       program.programId
@@ -533,17 +742,24 @@ app.post('/api/publishers/withdraw-unbonded', async (req, res) => {
 
     const publisherTokenAccount = await getAssociatedTokenAddress(
       tokenMint,
-      publisherAuthority.publicKey
+      publisherAuthority.publicKey,
+      false,
+      tokenProgram
     );
 
-    const vaultTokenAccount = await getAssociatedTokenAddress(
-      tokenMint,
-      vaultAuthority,
-      true
-    );
+    const { vaultAuthority, vaultTokenAccount } = await getVaultAccounts(program, tokenVault);
+
+    // Outstanding stake receipts are burned from the publisher's receipt ATA
+    const globalStateData = await program.account.globalState.fetch(globalState);
+    const receiptMint = globalStateData.receiptMint.equals(PublicKey.default)
+      ? null
+      : globalStateData.receiptMint;
+    const publisherReceiptAccount = receiptMint
+      ? await getAssociatedTokenAddress(receiptMint, publisherAuthority.publicKey, false, tokenProgram)
+      : null;
 
     const tx = await program.methods
-      .withdrawUnbonded()
+      .withdrawUnbonded(amount !== undefined ? new BN(amount) : null)
       .accounts({
         globalState,
         publisherAccount,
@@ -551,8 +767,13 @@ app.post('/api/publishers/withdraw-unbonded', async (req, res) => {
         tokenVault,
         publisherTokenAccount,
         vaultTokenAccount,
+        receiptMint,
+        publisherReceiptAccount,
         publisherAuthority: publisherAuthority.publicKey,
-        tokenProgram: TOKEN_PROGRAM_ID,
+        tokenMint,
+        tokenProgram,
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
       })
       .signers([publisherAuthority])
       .rpc();
@@ -566,6 +787,82 @@ app.post('/api/publishers/withdraw-unbonded', async (req, res) => {
   }
 });
 
+/**
+ * POST /api/tokens/swap-migrated
+ * Swap old-mint tokens 1:1 for the new mint after a MigrateTokenMint proposal
+ */
+app.post('/api/tokens/swap-migrated', async (req, res) => {
+  try {
+    const {
+      holderSecretKey,
+      amount
+    } = req.body;
+
+    const holder = Keypair.fromSecretKey(
+      Uint8Array.from(Buffer.from(holderSecretKey, 'base64'))
+    );
+
+    const provider = getProvider({ publicKey: holder.publicKey });
+    const program = getProgram(provider);
+
+    const [globalState] = PublicKey.findProgramAddressSync(
+      [Buffer.from('global_state')],
+      program.programId
+    );
+
+    const [tokenVault] = PublicKey.findProgramAddressSync(
+      [Buffer.from('token_vault')],
+      program.programId
+    );
+
+    const vaultData = await program.account.tokenVault.fetch(tokenVault);
+    const tokenMint = vaultData.tokenMint;
+    const previousTokenMint = vaultData.previousTokenMint;
+    const tokenProgram = await getTokenProgramId(tokenMint);
+    const previousTokenProgram = await getTokenProgramId(previousTokenMint);
+
+    const holderPreviousTokenAccount = await getAssociatedTokenAddress(
+      previousTokenMint,
+      holder.publicKey,
+      false,
+      previousTokenProgram
+    );
+    const holderTokenAccount = await getAssociatedTokenAddress(
+      tokenMint,
+      holder.publicKey,
+      false,
+      tokenProgram
+    );
+
+    const tx = await program.methods
+      .swapMigratedTokens(new BN(amount))
+      .accounts({
+        globalState,
+        tokenVault,
+        vaultAuthority: vaultData.vaultAuthority,
+        vaultTokenAccount: vaultData.vaultTokenAccount,
+        tokenMint,
+        previousTokenMint,
+        migrationCounterparty: vaultData.migrationCounterparty,
+        holderPreviousTokenAccount,
+        holderTokenAccount,
+        holder: holder.publicKey,
+        tokenProgram,
+        previousTokenProgram,
+      })
+      .signers([holder])
+      .rpc();
+
+    res.json({
+      success: true,
+      signature: tx,
+      amount
+    });
+  } catch (error) {
+    res.status(500).json({ success: false, error: error.message });
+  }
+});
+
 // ============================================================================
 // GOVERNANCE ENDPOINTS
 // ============================================================================
@@ -578,15 +875,21 @@ app.post('/api/governance/proposals/create', async (req, res) => {
   try {
     const {
       proposerSecretKey,
+      payerSecretKey,
       proposalType,
       description,
+      mode = 'standard',
       tokenMintAddress
     } = req.body;
 
     const proposer = Keypair.fromSecretKey(
       Uint8Array.from(Buffer.from(proposerSecretKey, 'base64'))
     );
+    const payer = payerSecretKey
+      ? Keypair.fromSecretKey(Uint8Array.from(Buffer.from(payerSecretKey, 'base64')))
+      : proposer;
     const tokenMint = new PublicKey(tokenMintAddress);
+    const tokenProgram = await getTokenProgramId(tokenMint);
 
     const provider = getProvider({ publicKey: proposer.publicKey });
     const program = getProgram(provider);
@@ -611,23 +914,36 @@ app.post('/api/governance/proposals/create', async (req, res) => {
 
     const proposerTokenAccount = await getAssociatedTokenAddress(
       tokenMint,
-      proposer.publicKey
+      proposer.publicKey,
+      false,
+      tokenProgram
     );
 
     // Convert proposal type to program format
     const proposalTypeEnum = convertProposalType(proposalType);
+    const modeEnum = { [mode.toLowerCase()]: {} };
+
+    // Collateral draws extend a pending retirement of the feed
+    const feedCollateral = proposalType.type === 'DrawListingCollateral'
+      ? PublicKey.findProgramAddressSync(
+          [Buffer.from('feed_collateral'), new PublicKey(proposalType.feed).toBuffer()],
+          program.programId
+        )[0]
+      : null;
 
     const tx = await program.methods
-      .createProposal(proposalTypeEnum, description)
+      .createProposal(proposalTypeEnum, description, modeEnum)
       .accounts({
         globalState,
         governanceState,
         proposal,
         proposerTokenAccount,
         proposer: proposer.publicKey,
+        feedCollateral,
+        payer: payer.publicKey,
         systemProgram: SystemProgram.programId,
       })
-      .signers([proposer])
+      .signers([proposer, payer])
       .rpc();
 
     res.json({
@@ -654,6 +970,7 @@ app.post('/api/governance/proposals/:proposalId/vote', async (req, res) => {
       Uint8Array.from(Buffer.from(voterSecretKey, 'base64'))
     );
     const tokenMint = new PublicKey(tokenMintAddress);
+    const tokenProgram = await getTokenProgramId(tokenMint);
 
     const provider = getProvider({ publicKey: voter.publicKey });
     const program = getProgram(provider);
@@ -665,7 +982,9 @@ app.post('/api/governance/proposals/:proposalId/vote', async (req, res) => {
 
     const voterTokenAccount = await getAssociatedTokenAddress(
       tokenMint,
-      voter.publicKey
+      voter.publicKey,
+      false,
+      tokenProgram
     );
 
     const voteTypeEnum = { [vote.toLowerCase()]: {} };
@@ -698,6 +1017,7 @@ app.post('/api/governance/proposals/:proposalId/vote', async (req, res) => {
 app.post('/api/governance/proposals/:proposalId/execute', async (req, res) => {
   try {
     const { proposalId } = req.params;
+    const { priceAccount } = req.body;
 
     const provider = getProvider({ publicKey: web3.Keypair.generate().publicKey });
     const program = getProgram(provider);
@@ -712,11 +1032,72 @@ app.post('/api/governance/proposals/:proposalId/execute', async (req, res) => {
       program.programId
     );
 
+    // Parameter proposals write their pending value into the target when queued
+    const [globalState] = PublicKey.findProgramAddressSync(
+      [Buffer.from('global_state')],
+      program.programId
+    );
+
+    const [tokenVault] = PublicKey.findProgramAddressSync(
+      [Buffer.from('token_vault')],
+      program.programId
+    );
+
     const tx = await program.methods
       .executeProposal()
       .accounts({
         proposal,
         governanceState,
+        globalState,
+        tokenVault,
+        priceAccount: priceAccount ? new PublicKey(priceAccount) : null,
+      })
+      .rpc();
+
+    res.json({
+      success: true,
+      signature: tx,
+      proposalId
+    });
+  } catch (error) {
+    res.status(500).json({ success: false, error: error.message });
+  }
+});
+
+/**
+ * POST /api/governance/proposals/:proposalId/expire
+ * Clear the preview of a queued proposal that missed its execution window
+ */
+app.post('/api/governance/proposals/:proposalId/expire', async (req, res) => {
+  try {
+    const { proposalId } = req.params;
+    const { priceAccount } = req.body;
+
+    const provider = getProvider({ publicKey: web3.Keypair.generate().publicKey });
+    const program = getProgram(provider);
+
+    const [proposal] = PublicKey.findProgramAddressSync(
+      [Buffer.from('proposal'), new BN(proposalId).toArrayLike(Buffer, 'le', 8)],
+      program.programId
+    );
+
+    const [globalState] = PublicKey.findProgramAddressSync(
+      [Buffer.from('global_state')],
+      program.programId
+    );
+
+    const [tokenVault] = PublicKey.findProgramAddressSync(
+      [Buffer.from('token_vault')],
+      program.programId
+    );
+
+    const tx = await program.methods
+      .expireProposal()
+      .accounts({
+        proposal,
+        globalState,
+        tokenVault,
+        priceAccount: priceAccount ? new PublicKey(priceAccount) : null,
       })
       .rpc();
 
@@ -737,7 +1118,20 @@ app.post('/api/governance/proposals/:proposalId/execute', async (req, res) => {
 app.post('/api/governance/proposals/:proposalId/execute-action', async (req, res) => {
   try {
     const { proposalId } = req.params;
-    const { authoritySecretKey } = req.body;
+    const {
+      authoritySecretKey,
+      priceAccount,
+      publisherAccount,
+      feedSla,
+      roundArchive,
+      feedCollateral,
+      symbols = [],
+      tokenMintAddress,
+      compensationAccount,
+      upgradeBuffer,
+      spill,
+      newUpgradeAuthority
+    } = req.body;
 
     const authority = Keypair.fromSecretKey(
       Uint8Array.from(Buffer.from(authoritySecretKey, 'base64'))
@@ -766,6 +1160,83 @@ app.post('/api/governance/proposals/:proposalId/execute-action', async (req, res
       program.programId
     );
 
+    // The action runs once and writes one receipt per proposal
+    const proposalData = await program.account.proposal.fetch(proposal);
+    const [executionReceipt] = PublicKey.findProgramAddressSync(
+      [Buffer.from('execution_receipt'), proposal.toBuffer()],
+      program.programId
+    );
+
+    // SetAssetTemplate proposals update the template of their asset type
+    const templateChange = proposalData.proposalType.setAssetTemplate;
+    const assetTemplate = templateChange
+      ? PublicKey.findProgramAddressSync(
+        [Buffer.from('asset_template'), Buffer.from(Object.keys(templateChange.assetType)[0])],
+        program.programId
+      )[0]
+      : null;
+
+    // SetFeedQuota proposals update the quota account of their feed
+    const quotaChange = proposalData.proposalType.setFeedQuota;
+    const feedQuota = quotaChange
+      ? PublicKey.findProgramAddressSync(
+        [Buffer.from('feed_quota'), quotaChange.feed.toBuffer()],
+        program.programId
+      )[0]
+      : null;
+
+    // AttestIdentity proposals check the identity the publisher has bound
+    const identityAttestation = proposalData.proposalType.attestIdentity;
+    const publisherIdentity = identityAttestation
+      ? PublicKey.findProgramAddressSync(
+        [Buffer.from('publisher_identity'), identityAttestation.publisher.toBuffer()],
+        program.programId
+      )[0]
+      : null;
+
+    // Burn and compensation slashes and vault rotations move tokens out of the vault
+    const { vaultAuthority, vaultTokenAccount: currentVaultTokenAccount } =
+      await getVaultAccounts(program, tokenVault);
+    const tokenMint = tokenMintAddress ? new PublicKey(tokenMintAddress) : null;
+    const tokenProgram = tokenMint ? await getTokenProgramId(tokenMint) : null;
+    const vaultTokenAccount = tokenMint ? currentVaultTokenAccount : null;
+
+    // MigrateTokenMint proposals swap the vault's balance into the new mint and keep
+    // the cluster config, when there is one, on the staking mint
+    const mintMigration = proposalData.proposalType.migrateTokenMint;
+    const [clusterConfig] = PublicKey.findProgramAddressSync(
+      [Buffer.from('cluster_config')],
+      program.programId
+    );
+    const clusterConfigInfo = mintMigration ? await connection.getAccountInfo(clusterConfig) : null;
+
+    // RotateVault proposals move the vault to the next custody authority
+    const vaultRotation = proposalData.proposalType.rotateVault;
+    let newVaultAuthority = null;
+    if (vaultRotation) {
+      const globalStateData = await program.account.globalState.fetch(globalState);
+      [newVaultAuthority] = PublicKey.findProgramAddressSync(
+        [Buffer.from('vault_authority'), Buffer.from([globalStateData.custodyVersion + 1])],
+        program.programId
+      );
+    }
+
+    // Upgrade proposals act on this program's ProgramData through the upgradeable loader
+    const [programData] = PublicKey.findProgramAddressSync(
+      [program.programId.toBuffer()],
+      BPF_LOADER_UPGRADEABLE_PROGRAM_ID
+    );
+
+    // BulkUpdateFeeds targets are passed as (product, price) pairs
+    const remainingAccounts = symbols.flatMap((symbol) => [
+      [Buffer.from('product'), Buffer.from(symbol)],
+      [Buffer.from('price'), Buffer.from(symbol)],
+    ].map((seeds) => ({
+      pubkey: PublicKey.findProgramAddressSync(seeds, program.programId)[0],
+      isWritable: true,
+      isSigner: false,
+    })));
+
     const tx = await program.methods
       .executeGovernanceAction()
       .accounts({
@@ -773,17 +1244,45 @@ app.post('/api/governance/proposals/:proposalId/execute-action', async (req, res
         proposal,
         governanceState,
         tokenVault,
-        priceAccount: null,
-        publisherAccount: null,
+        priceAccount: priceAccount ? new PublicKey(priceAccount) : null,
+        publisherAccount: publisherAccount ? new PublicKey(publisherAccount) : null,
+        publisherIdentity,
+        feedSla: feedSla ? new PublicKey(feedSla) : null,
+        feedQuota,
+        roundArchive: roundArchive ? new PublicKey(roundArchive) : null,
+        feedCollateral: feedCollateral ? new PublicKey(feedCollateral) : null,
+        assetTemplate,
+        vaultAuthority: tokenMint ? vaultAuthority : null,
+        vaultTokenAccount,
+        tokenMint,
+        compensationAccount: compensationAccount ? new PublicKey(compensationAccount) : null,
+        tokenProgram,
+        newVaultAuthority,
+        newVaultTokenAccount: (vaultRotation || mintMigration)?.newVaultTokenAccount ?? null,
+        newTokenMint: mintMigration ? mintMigration.newMint : null,
+        migrationCounterparty: mintMigration ? mintMigration.counterparty : null,
+        clusterConfig: mintMigration && clusterConfigInfo ? clusterConfig : null,
+        programData,
+        upgradeProgram: upgradeBuffer ? program.programId : null,
+        upgradeBuffer: upgradeBuffer ? new PublicKey(upgradeBuffer) : null,
+        spill: upgradeBuffer ? new PublicKey(spill || authority.publicKey) : null,
+        newUpgradeAuthority: newUpgradeAuthority ? new PublicKey(newUpgradeAuthority) : null,
+        rent: upgradeBuffer ? SYSVAR_RENT_PUBKEY : null,
+        clock: upgradeBuffer ? SYSVAR_CLOCK_PUBKEY : null,
+        bpfLoader: BPF_LOADER_UPGRADEABLE_PROGRAM_ID,
         authority: authority.publicKey,
+        executionReceipt,
+        systemProgram: SystemProgram.programId,
       })
+      .remainingAccounts(remainingAccounts)
       .signers([authority])
       .rpc();
 
     res.json({
       success: true,
       signature: tx,
-      proposalId
+      proposalId,
+      executionReceipt: executionReceipt.toString()
     });
   } catch (error) {
     res.status(500).json({ success: false, error: error.message });
@@ -894,20 +1393,119 @@ app.get('/api/prices/:symbol', async (req, res) => {
 
     const priceData = await program.account.priceAccount.fetch(priceAccount);
 
+    const [fastPriceAccount] = PublicKey.findProgramAddressSync(
+      [Buffer.from('fast_price'), priceAccount.toBuffer()],
+      program.programId
+    );
+    const fastData = await program.account.fastPriceAccount.fetchNullable(fastPriceAccount);
+
+    const [inverseFeed] = PublicKey.findProgramAddressSync(
+      [Buffer.from('inverse_feed'), priceAccount.toBuffer()],
+      program.programId
+    );
+    const inverseData = await program.account.inverseFeed.fetchNullable(inverseFeed);
+
+    const [futuresContract] = PublicKey.findProgramAddressSync(
+      [Buffer.from('futures_contract'), priceAccount.toBuffer()],
+      program.programId
+    );
+    const futuresData = await program.account.futuresContract.fetchNullable(futuresContract);
+
+    const [feedStats] = PublicKey.findProgramAddressSync(
+      [Buffer.from('feed_stats'), priceAccount.toBuffer()],
+      program.programId
+    );
+    const statsData = await program.account.feedStats.fetchNullable(feedStats);
+
+    const [priceMirror] = PublicKey.findProgramAddressSync(
+      [Buffer.from('price_mirror'), priceAccount.toBuffer()],
+      program.programId
+    );
+    const mirrorInfo = await connection.getAccountInfo(priceMirror);
+
+    const [priceHistory] = PublicKey.findProgramAddressSync(
+      [Buffer.from('price_history'), priceData.productAccount.toBuffer()],
+      program.programId
+    );
+    const historyData = await program.account.priceHistoryAccount.fetchNullable(priceHistory);
+
+    const [feedReadStats] = PublicKey.findProgramAddressSync(
+      [Buffer.from('feed_reads'), priceAccount.toBuffer()],
+      program.programId
+    );
+    const readsData = await program.account.feedReadStats.fetchNullable(feedReadStats);
+
     res.json({
       success: true,
       symbol,
       price: priceData.aggregate.price.toString(),
       confidence: priceData.aggregate.confidence.toString(),
+      confidenceMode: CONFIDENCE_MODES[priceData.confidenceMode] ?? 'unknown',
       exponent: priceData.exponent,
       timestamp: priceData.aggregate.timestamp.toString(),
       slot: priceData.aggregate.slot.toString(),
-      status: Object.keys(priceData.aggregate.status)[0],
+      status: PRICE_STATUSES[priceData.aggregate.status] ?? 'unknown',
+      priceI128: priceData.aggregate.priceI128.isZero() ? null : priceData.aggregate.priceI128.toString(),
       publisherCount: priceData.publisherCount,
+      minPublishers: priceData.minPublishers,
+      minOperators: priceData.minOperators,
+      minSlotsBetweenAggregations: priceData.minSlotsBetweenAggregations.toString(),
+      emergencyHaltUntilSlot: priceData.emergencyHaltUntilSlot.toString(),
+      pendingMinPublishers: priceData.pendingMinPublishers.effectiveAt.isZero() ? null : {
+        value: priceData.pendingMinPublishers.value.toString(),
+        effectiveAt: priceData.pendingMinPublishers.effectiveAt.toString(),
+        proposalId: priceData.pendingMinPublishers.proposalId.toString()
+      },
+      distinctSources: priceData.distinctSources,
+      topSourceShareBps: priceData.topSourceShareBps,
+      sampleSlot: priceData.sampleSlot.toString(),
+      sampledPublishers: priceData.sampledPublishers.toString(2),
+      includedPublishers: priceData.includedPublishers.toString(2),
+      submissionQuota: priceData.submissionQuota !== 0,
+      permissioned: priceData.permissioned !== 0,
+      deviationAlertMads: priceData.deviationAlertMads,
+      volatilityUntilSlot: priceData.volatilityUntilSlot.toString(),
+      volatilityWideningBps: priceData.volatilityWideningBps,
+      quotesRoot: Buffer.from(priceData.quotesRoot).toString('hex'),
+      inverseOf: inverseData ? inverseData.sourceFeed.toString() : null,
+      futures: futuresData ? {
+        activeContract: futuresData.activeContract,
+        expiry: futuresData.expiry.toString(),
+        previousContract: futuresData.previousContract,
+        rollCount: futuresData.rollCount,
+        rollAdjustment: priceData.rollAdjustment.toString()
+      } : null,
+      fast: fastData ? {
+        price: fastData.aggregate.price.toString(),
+        confidence: fastData.aggregate.confidence.toString(),
+        slot: fastData.aggregate.slot.toString(),
+        status: PRICE_STATUSES[fastData.aggregate.status] ?? 'unknown'
+      } : null,
+      uptime: statsData ? {
+        lastUptimeBps: statsData.lastUptimeBps,
+        lastWindowStartSlot: statsData.lastWindowStartSlot.toString(),
+        windowStartSlot: statsData.windowStartSlot.toString(),
+        tradingSlots: statsData.tradingSlots.toString()
+      } : null,
+      priceMirror: mirrorInfo ? priceMirror.toString() : null,
+      priceCumulative: priceData.priceCumulative.toString(),
+      cumulativeTimestamp: priceData.cumulativeTimestamp.toString(),
+      priceHistory: historyData ? {
+        address: priceHistory.toString(),
+        totalObservations: historyData.totalObservations.toString()
+      } : null,
+      reads: readsData ? {
+        epoch: readsData.epoch.toString(),
+        reads: readsData.reads.toString(),
+        lastEpochReads: readsData.lastEpochReads.toString(),
+        totalReads: readsData.totalReads.toString()
+      } : null,
       ema: {
         price: priceData.ema.emaPrice.toString(),
         confidence: priceData.ema.emaConfidence.toString(),
-        observations: priceData.ema.numObservations.toString()
+        observations: priceData.ema.numObservations.toString(),
+        alpha: priceData.emaAlpha,
+        confidenceWeighted: priceData.emaConfidenceWeighted !== 0
       }
     });
   } catch (error) {
@@ -934,6 +1532,12 @@ app.get('/api/publishers/:address', async (req, res) => {
 
     const publisherData = await program.account.publisherAccount.fetch(publisherAccount);
 
+    const [publisherIdentity] = PublicKey.findProgramAddressSync(
+      [Buffer.from('publisher_identity'), publisherAccount.toBuffer()],
+      program.programId
+    );
+    const identityData = await program.account.publisherIdentity.fetchNullable(publisherIdentity);
+
     res.json({
       success: true,
       publisher: {
@@ -943,8 +1547,28 @@ app.get('/api/publishers/:address', async (req, res) => {
         reputation: publisherData.reputation.toString(),
         registeredAt: publisherData.registeredAt.toString(),
         slashCount: publisherData.slashCount,
-        unbondingAmount: publisherData.unbondingAmount.toString(),
-        unbondingStart: publisherData.unbondingStart.toString()
+        stakeState: publisherData.stakeState.unbonding ? 'unbonding' : 'bonded',
+        unbondingAmount: (publisherData.stakeState.unbonding?.amount ?? 0).toString(),
+        unbondingStart: (publisherData.stakeState.unbonding?.start ?? 0).toString(),
+        feedCount: publisherData.feedCount,
+        lastActiveEpoch: publisherData.lastActiveEpoch.toString(),
+        pendingRewards: publisherData.pendingRewards.toString(),
+        rewardsFrozenUntilSlot: publisherData.rewardsFrozenUntilSlot.toString(),
+        publishingBlockedUntilSlot: publisherData.publishingBlockedUntilSlot.toString(),
+        lockedStake: publisherData.lockedStake.toString(),
+        rewardVesting: {
+          amount: publisherData.rewardVesting.amount.toString(),
+          released: publisherData.rewardVesting.released.toString(),
+          start: publisherData.rewardVesting.start.toString(),
+          period: publisherData.rewardVesting.period.toString()
+        },
+        identity: identityData ? {
+          kind: Object.keys(identityData.kind)[0],
+          identifier: identityData.identifier,
+          proofHash: Buffer.from(identityData.proofHash).toString('hex'),
+          boundAt: identityData.boundAt.toString(),
+          attested: !publisherData.operatorId.isZero()
+        } : null
       }
     });
   } catch (error) {
@@ -983,6 +1607,10 @@ app.get('/api/governance/proposals/:proposalId', async (req, res) => {
         endSlot: proposalData.endSlot.toString(),
         executed: proposalData.executed,
         executionTime: proposalData.executionTime.toString(),
+        mode: Object.keys(proposalData.mode)[0],
+        conviction: proposalData.conviction.toString(),
+        convictionStaked: proposalData.convictionStaked.toString(),
+        linkedHashes: proposalData.linkedHashes.map((hash) => Buffer.from(hash).toString('hex')),
         proposalType: proposalData.proposalType
       }
     });
@@ -1012,7 +1640,9 @@ function convertProposalType(proposalType) {
       return { 
         slashPublisher: { 
           publisher: new PublicKey(params.publisher), 
-          percentage: params.percentage 
+          percentage: params.percentage,
+          destination: convertSlashDestination(params.destination, params.compensationAccount),
+          publishCooldownSlots: new BN(params.publishCooldownSlots || 0)
         } 
       };
     case 'EmergencyPause':
@@ -1026,13 +1656,159 @@ function convertProposalType(proposalType) {
           votingPeriod: params.votingPeriod ? new BN(params.votingPeriod) : null,
           quorumPercentage: params.quorumPercentage || null,
           timelockDuration: params.timelockDuration ? new BN(params.timelockDuration) : null,
+          convictionThresholdPercentage: params.convictionThresholdPercentage || null,
+          severityThresholdBps: params.severityThresholdBps || null,
         } 
       };
+    case 'UpdateStakePerFeed':
+      return { updateStakePerFeed: { stakePerFeed: new BN(params.stakePerFeed) } };
+    case 'FreezeFeed':
+      return { freezeFeed: { feed: new PublicKey(params.feed) } };
+    case 'UnfreezeFeed':
+      return { unfreezeFeed: { feed: new PublicKey(params.feed) } };
+    case 'ApprovePublisherRegistrations':
+      return { approvePublisherRegistrations: { count: params.count } };
+    case 'UpdateRegistrationLimit':
+      return { updateRegistrationLimit: { maxPerEpoch: params.maxPerEpoch } };
+    case 'SetFeedSla':
+      return {
+        setFeedSla: {
+          feed: new PublicKey(params.feed),
+          maxUpdateIntervalSlots: new BN(params.maxUpdateIntervalSlots),
+          maxConfidenceBps: params.maxConfidenceBps || 0,
+          rebateBps: params.rebateBps || 0,
+        }
+      };
+    case 'MigrateTokenMint':
+      return {
+        migrateTokenMint: {
+          newMint: new PublicKey(params.newMint),
+          newVaultTokenAccount: new PublicKey(params.newVaultTokenAccount),
+          counterparty: new PublicKey(params.counterparty),
+        }
+      };
+    case 'RotateVault':
+      return { rotateVault: { newVaultTokenAccount: new PublicKey(params.newVaultTokenAccount) } };
+    case 'DeclareVolatilityRegime':
+      return {
+        declareVolatilityRegime: {
+          feed: new PublicKey(params.feed),
+          wideningBps: params.wideningBps || 0,
+          durationSlots: new BN(params.durationSlots || 0),
+        }
+      };
+    case 'SetFeedQuota':
+      return {
+        setFeedQuota: {
+          feed: new PublicKey(params.feed),
+          minSubmissions: params.minSubmissions || 0,
+          maxSubmissions: params.maxSubmissions || 0,
+        }
+      };
+    case 'AttestIdentity':
+      return {
+        attestIdentity: {
+          publisher: new PublicKey(params.publisher),
+          operatorId: new BN(params.operatorId),
+        }
+      };
+    case 'UpdateUnbondingPeriod':
+      return { updateUnbondingPeriod: { period: new BN(params.period) } };
+    case 'UpdateMinStake':
+      return { updateMinStake: { minStake: new BN(params.minStake) } };
+    case 'SetReceiptTransferability':
+      return { setReceiptTransferability: { transferable: params.transferable } };
+    case 'SetPositionTransferability':
+      return { setPositionTransferability: { transferable: params.transferable } };
+    case 'SetAssetTemplate':
+      return {
+        setAssetTemplate: {
+          assetType: { [params.assetType.toLowerCase()]: {} },
+          template: {
+            stalenessThreshold: new BN(params.stalenessThreshold),
+            degradedConfidenceBps: params.degradedConfidenceBps,
+            minPublishers: params.minPublishers,
+            calendar: {
+              enabled: params.calendar.enabled,
+              weekdays: params.calendar.weekdays,
+              openMinute: params.calendar.openMinute,
+              closeMinute: params.calendar.closeMinute,
+              holidays: params.calendar.holidays ?? [],
+            },
+          },
+        },
+      };
+    case 'ResolveVaultDrift':
+      return { resolveVaultDrift: { drift: new BN(params.drift) } };
+    case 'UpgradeProgram':
+      return { upgradeProgram: { buffer: new PublicKey(params.buffer) } };
+    case 'SetRewardMint':
+      return { setRewardMint: { mint: new PublicKey(params.mint) } };
+    case 'UpdateRewardVesting':
+      return { updateRewardVesting: { period: new BN(params.period) } };
+    case 'UpdateListingCollateral':
+      return { updateListingCollateral: { collateral: new BN(params.collateral) } };
+    case 'DrawListingCollateral':
+      return {
+        drawListingCollateral: {
+          feed: new PublicKey(params.feed),
+          amount: new BN(params.amount),
+        }
+      };
+    case 'DisputeRound':
+      return {
+        disputeRound: {
+          round: new PublicKey(params.round),
+          excludedPublisher: params.excludedPublisher ? new PublicKey(params.excludedPublisher) : null,
+        }
+      };
+    case 'SetUpgradeAuthority':
+      return {
+        setUpgradeAuthority: {
+          newAuthority: params.newAuthority ? new PublicKey(params.newAuthority) : null,
+        }
+      };
+    case 'BulkUpdateFeeds':
+      return {
+        bulkUpdateFeeds: {
+          assetType: { [params.assetType.toLowerCase()]: {} },
+          update: {
+            confidenceStakeScale: params.confidenceStakeScale ? new BN(params.confidenceStakeScale) : null,
+            halted: params.halted ?? null,
+            stalenessThreshold: params.stalenessThreshold ? new BN(params.stalenessThreshold) : null,
+            degradedConfidenceBps: params.degradedConfidenceBps ?? null,
+            roundingMode: params.roundingMode ? { [params.roundingMode.charAt(0).toLowerCase() + params.roundingMode.slice(1)]: {} } : null,
+            highPrecision: params.highPrecision ?? null,
+            commitReveal: params.commitReveal ?? null,
+            sampleSize: params.sampleSize ?? null,
+            confidenceMode: params.confidenceMode ? { [params.confidenceMode.toLowerCase()]: {} } : null,
+            minOperators: params.minOperators ?? null,
+            emaAlpha: params.emaAlpha ?? null,
+            emaConfidenceWeighted: params.emaConfidenceWeighted ?? null,
+            minSlotsBetweenAggregations: params.minSlotsBetweenAggregations ? new BN(params.minSlotsBetweenAggregations) : null,
+            permissioned: params.permissioned ?? null,
+            deviationAlertMads: params.deviationAlertMads ?? null,
+          }
+        }
+      };
     default:
       throw new Error('Invalid proposal type');
   }
 }
 
+function convertSlashDestination(destination = 'insuranceFund', compensationAccount) {
+  switch (destination) {
+    case 'burn':
+      return { burn: {} };
+    case 'insuranceFund':
+      return { insuranceFund: {} };
+    case 'compensation':
+      return { compensation: { tokenAccount: new PublicKey(compensationAccount) } };
+    default:
+      throw new Error('Invalid slash destination');
+  }
+}
+
 // ============================================================================
 // SERVER START
 // ============================================================================
@@ -1,20 +1,115 @@
 use anchor_lang::prelude::*;
-use anchor_spl::token::{self, Token, TokenAccount, Mint, Transfer};
+use anchor_lang::Discriminator;
+use anchor_lang::solana_program::{bpf_loader_upgradeable, compute_units::sol_remaining_compute_units, instruction::Instruction, program::{invoke, invoke_signed}};
+use anchor_spl::associated_token::{self, AssociatedToken, get_associated_token_address_with_program_id};
+use anchor_spl::token_interface::{self, spl_token_2022, Burn, FreezeAccount, MintTo, ThawAccount, TokenInterface, TokenAccount, Mint, TransferChecked};
 
 declare_id!("GqEkgwLMtTZ2XmP4LnwJUQbAQWUR3PMfTN8pNojBH6ks");
 
+/// Metaplex token metadata program, which owns the metadata of stake position NFTs
+mod token_metadata {
+    anchor_lang::declare_id!("metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s");
+}
+
 // ============================================================================
 // Constants
 // ============================================================================
 
 const MAX_PUBLISHERS: usize = 100;
-const MIN_STAKE_AMOUNT: u64 = 10_000_000_000; // 10,000 tokens with 9 decimals
+const DEFAULT_MIN_STAKE_AMOUNT: u64 = 10_000_000_000; // 10,000 tokens with 9 decimals
+const MIN_STAKE_GRACE_EPOCHS: u64 = 4; // Existing publishers keep the old minimum this long after a raise
+const TOKEN_DECIMALS: u8 = 9;
 const STALENESS_THRESHOLD: i64 = 30;
 const HALTED_THRESHOLD: i64 = 60;
+const MAX_AGGREGATION_INTERVAL_SLOTS: u64 = 25; // Throttles longer than this would let feeds go stale
+const MAX_OBSERVATION_SKEW: i64 = 10; // Seconds a batch's observation time may differ from the cluster clock
 const OUTLIER_MAD_MULTIPLIER: i64 = 3;
-const EMA_ALPHA_SCALED: i64 = 100_000; // 0.1 * 1_000_000
-const UNBONDING_PERIOD: i64 = 604_800; // 7 days
-const PROGRAM_VERSION: u8 = 1;
+const EMA_ALPHA_SCALED: i64 = 100_000; // 0.1 * 1_000_000, for feeds without their own alpha
+const EMA_MIN_ALPHA_DIVISOR: i64 = 10; // Confidence weighting keeps at least this fraction of alpha
+const DEFAULT_UNBONDING_PERIOD: i64 = 604_800; // 7 days
+const MIN_UNBONDING_PERIOD: i64 = 86_400; // Governance cannot go below 1 day
+const CONVICTION_DECAY_SCALED: u64 = 999_990_000; // per-slot decay * 1e9 (~8h half-life)
+const DEFAULT_CONVICTION_THRESHOLD_PERCENTAGE: u8 = 10;
+const DEFAULT_OPTIMISTIC_CHALLENGE_PERIOD: u64 = 216_000; // ~1 day of slots
+const OPTIMISTIC_MAX_RATE_CHANGE_BPS: u64 = 2_500; // Routine changes move the reward rate by at most 25%
+const DEFAULT_EMERGENCY_VOTING_PERIOD: u64 = 9_000; // ~1 hour of slots
+const DEFAULT_EMERGENCY_QUORUM_PERCENTAGE: u8 = 67;
+const PROPOSAL_REVIEW_PERIOD: u64 = 9_000; // Slots between creation and voting start
+const PROPOSAL_EXECUTION_WINDOW: i64 = 604_800; // 7 days after its timelock to execute a queued proposal
+const MAX_PROPOSAL_LINKS: usize = 8;
+const DEFAULT_SEVERITY_THRESHOLD_BPS: [u32; 3] = [10_000, 25_000, 100_000]; // 1x, 2.5x and 10x the proposal threshold
+const MAX_RECEIPT_SNAPSHOT_LEN: usize = 96; // Largest borsh snapshot an ExecutionReceipt stores per side
+const DEFAULT_MAX_NEW_PUBLISHERS_PER_EPOCH: u32 = 5;
+const EPOCH_LENGTH_SLOTS: u64 = 432_000; // ~2 days, matching cluster epochs
+const PROBATION_EPOCHS: u64 = 4;
+const PROBATION_WEIGHT_BPS: u64 = 2_500; // Initial probationary weight (25%), ramping to full over PROBATION_EPOCHS
+const MAX_VOTING_PERIOD: u64 = 6_480_000; // ~30 days of slots
+const MAX_TIMELOCK_DURATION: u64 = 2_592_000; // 30 days
+const RETIREMENT_NOTICE_SECONDS: i64 = 5_529_600; // 64 days, longer than review, MAX_VOTING_PERIOD and MAX_TIMELOCK_DURATION together
+const REWARD_INDEX_SCALE: u128 = 1_000_000_000_000;
+const SLASH_REWARD_COOLDOWN_SLOTS: u64 = 1_512_000; // ~7 days without reward accrual after a slash
+const MAX_SLASH_PUBLISH_COOLDOWN_SLOTS: u64 = 6_480_000; // ~30 days
+const HALT_VOTE_WINDOW_SLOTS: u64 = 150; // Publisher halt votes older than this (~1 minute) lapse
+const HALT_SUPERMAJORITY_BPS: u64 = 6_667; // Share of the feed's quoting stake that must vote to halt
+const EMERGENCY_HALT_SLOTS: u64 = 18_000; // ~2 hours, room for a FreezeFeed proposal to ratify the halt
+const EMERGENCY_HALT_COOLDOWN_SLOTS: u64 = 216_000; // ~1 day after an unratified halt before publishers can vote again
+const MAX_VOLATILITY_REGIME_SLOTS: u64 = 216_000; // ~1 day per declaration
+const MAX_VOLATILITY_WIDENING_BPS: u32 = 50_000; // Bands at most 5x their normal width
+const AGGREGATION_BASE_COMPUTE_UNITS: u64 = 40_000; // Aggregation overhead plus writing the price account back
+const AGGREGATION_COMPUTE_UNITS_PER_QUOTE: u64 = 1_500;
+const CREATE_METADATA_ACCOUNT_V3_DISCRIMINATOR: u8 = 33;
+const POSITION_NFT_SYMBOL: &str = "SFDNPOS";
+const MAX_POSITION_URI_LEN: usize = 200; // Metaplex limit
+const FEED_ADMIN_SET_STATUS: u8 = 1 << 0;
+const FEED_ADMIN_SET_STALENESS: u8 = 1 << 1;
+const MAX_INSURANCE_SHARE_BPS: u16 = 5_000; // At most half of rewards can be routed to insurance
+const INSURANCE_DEDUCTIBLE_BPS: u64 = 5_000; // Share of each slash the publisher always bears
+const INSURANCE_COVERAGE_MULTIPLIER: u64 = 5; // Lifetime coverage per token contributed
+const PREMIUM_COVERAGE_MULTIPLIER: u64 = 10; // Consumer coverage per premium token, for the epoch paid
+const HIGH_PRECISION_DECIMALS: u32 = 12; // Extra decimals carried by PriceData::price_i128
+const MAX_REVEAL_DELAY_SLOTS: u64 = 8; // Commitments older than this can no longer be revealed
+const MAX_OPTIMISTIC_CONFIDENCE_BPS: u64 = 100; // Widest optimistic post, relative to its price, on feeds without a confidence band
+const OPTIMISTIC_CHALLENGE_BOND_BPS: u64 = 1_000; // Stake a challenger locks, as a share of the minimum stake; slashed if the challenge fails
+const MAX_IDENTITY_LEN: usize = 128; // Domain name or DID bound to a publisher
+const QUOTE_PRUNE_AGE: i64 = 3_600; // Quotes older than this can be pruned from a feed
+const INACTIVE_DEREGISTRATION_EPOCHS: u64 = 15; // Epochs without quotes before a publisher can be force-deregistered
+const MAX_REWARD_VESTING_PERIOD: i64 = 365 * 86_400;
+const DEFAULT_LISTING_COLLATERAL: u64 = 1_000_000_000_000; // 1,000 tokens per risk tier multiple
+const MAX_FAST_PUBLISHERS: usize = 8;
+const MAX_CLUSTER_LABEL_LEN: usize = 32;
+const MAINNET_GENESIS_HASH: &str = "5eykt4UsFv8P8NJdTREpY1vzqKqZKvdpKuc147dw2N9d";
+const DEVNET_GENESIS_HASH: &str = "EtWTRABZaYq6iMfeYKouRu166VU2xqa1wcaWoxPkrZBG";
+const TESTNET_GENESIS_HASH: &str = "4uhcVJyU9pJkvQyS88uRDiswHXSCkY3zQawwpjk2NsNY";
+const MAX_CALENDAR_HOLIDAYS: usize = 32;
+const ASSET_TEMPLATE_SPACE: usize = 8 + 1 + 8 + 2 + 1 + (1 + 1 + 2 + 2 + 4 + 2 * MAX_CALENDAR_HOLIDAYS) + 1;
+const MAX_CONFIG_SNAPSHOT_FEEDS: usize = 8; // Keeps an export_config result within the 1 KiB return data limit
+const MAX_CURRENCY_LEN: usize = 12;
+const MAX_CONTRACT_ID_LEN: usize = 16;
+const ROLL_ADJUSTMENT_SCALE: u64 = 1_000_000_000;
+const FAST_QUOTE_MAX_AGE_SLOTS: u64 = 2; // Fast-lane quotes older than this are ignored
+const PROGRAM_VERSION: u8 = 3;
+
+const GLOBAL_STATE_SPACE: usize = 8 + 32 + 32 + 32 + 32 + 32 + 1 + 8 + 8 + 8 + 4 + 8 + 8 + 8 + 4 + 4 + 8 + 8 + 8 + 8 + 32 + 1 + 4 * PENDING_PARAM_SPACE + 8 + 8 + 1 + 1 + 1 + 1 + 1 + 1;
+const TOKEN_VAULT_SPACE: usize = 8 + 8 + 8 + 8 + 8 + 16 + 8 + 8 + 8 + 8 + 8 + 8 + 8 + 8 + PENDING_PARAM_SPACE + 32 + 32 + 32 + 32 + 32 + 32 + 8 + 16 + 8 + 8 + 8 + 32 + 32 + 8 + 1;
+const PENDING_PARAM_SPACE: usize = 8 + 8 + 8;
+// Field sizes of the zero-copy PriceAccount in order; checked against the struct below
+const PRICE_ACCOUNT_SPACE: usize = 8 + 32 + 32 + 64 + 16 + 16 + 16 + 32 + (MAX_PUBLISHERS * 96) + 24 + PENDING_PARAM_SPACE
+    + (10 * 8) + (3 * 4) + (2 * 2) + 19 + 13;
+const _: () = assert!(8 + std::mem::size_of::<PriceAccount>() == PRICE_ACCOUNT_SPACE);
+const PRICE_HISTORY_LEN: usize = 128; // Aggregates kept per feed, one per slot at most
+const PRICE_HISTORY_SPACE: usize = 8 + 32 + 32 + (PRICE_HISTORY_LEN * 48) + 8 + 1 + 7;
+const _: () = assert!(8 + std::mem::size_of::<PriceHistoryAccount>() == PRICE_HISTORY_SPACE);
+const PRODUCT_ACCOUNT_SPACE: usize = 8 + 64 + 1 + 256 + 32 + 32 + (4 + MAX_CURRENCY_LEN) * 2 + (1 + 1 + 2 + 2 + 4 + 2 * MAX_CALENDAR_HOLIDAYS) + 1;
+// Sizes of the legacy product layouts `migrate_product_account` converts: before the
+// trading calendar, and with the calendar but before FX currencies
+const PRODUCT_ACCOUNT_V1_SPACE: usize = 8 + 64 + 1 + 256 + 32 + 32 + 1;
+const PRODUCT_ACCOUNT_V2_SPACE: usize = 8 + 64 + 1 + 256 + 32 + 32 + (1 + 1 + 2 + 2 + 4 + 2 * MAX_CALENDAR_HOLIDAYS) + 1;
+const PUBLISHER_ACCOUNT_SPACE: usize = 8 + 32 + 8 + 32 + 8 + 64 + 8 + 4 + 8 + (1 + 8 + 8 + 8) + 4 + 8 + 8 + 8 + 8 + 16 + 8 + 2 + 8 + 8 + 32 + 8 + 8 + (8 + 8 + 8 + 8) + 8 + 8 + 4 + 8 + 8 + 1;
+const PUBLISHER_ACCOUNT_V1_SPACE: usize = 8 + 32 + 8 + 32 + 8 + 64 + 8 + 4 + 8 + 8 + 8 + 1; // Before stake states and feed exposure
+const PROPOSAL_SPACE: usize = 8 + 32 + 256 + 256 + 8 + 8 + 8 + 8 + 8 + 1 + 8 + 8 + 1 + 8 + 8 + 8
+    + 4 + (MAX_PROPOSAL_LINKS * 32) + 1 + 1;
+const PROPOSAL_V1_SPACE: usize = 8 + 32 + 256 + 256 + 8 + 8 + 8 + 8 + 8 + 1 + 8 + 8 + 1; // Before proposal modes
+const GOVERNANCE_STATE_SPACE: usize = 8 + 32 + 8 + 8 + 1 + 8 + 8 + 8 + 1 + 8 + 8 + 8 + 8 + 1 + 12 + 32 + 1;
 
 // ============================================================================
 // Error Codes
@@ -64,18 +159,219 @@ pub enum ErrorCode {
     InvalidProposalType,
     #[msg("Voting period active")]
     VotingPeriodActive,
+    #[msg("Stake does not cover publisher feed exposure")]
+    InsufficientExposureStake,
+    #[msg("Operation not supported for this proposal mode")]
+    InvalidProposalMode,
+    #[msg("Conviction threshold not reached")]
+    ConvictionNotReached,
+    #[msg("Change is not eligible for the optimistic path")]
+    NotOptimisticEligible,
+    #[msg("Challenge window still active")]
+    ChallengeWindowActive,
+    #[msg("Challenge window ended")]
+    ChallengeWindowEnded,
+    #[msg("Optimistic change is not in the required state")]
+    InvalidOptimisticState,
+    #[msg("Price feed is frozen")]
+    FeedFrozen,
+    #[msg("Account does not match the proposal target")]
+    ProposalTargetMismatch,
+    #[msg("Voting has not started")]
+    VotingNotStarted,
+    #[msg("Proposal links are frozen once voting starts")]
+    ProposalLinksFrozen,
+    #[msg("Too many proposal links")]
+    TooManyProposalLinks,
+    #[msg("Invalid tolerance")]
+    InvalidTolerance,
+    #[msg("Publisher registration limit reached for this epoch")]
+    RegistrationLimitReached,
+    #[msg("Confidence is tighter than the publisher's stake allows")]
+    ConfidenceTooTight,
+    #[msg("Token mint does not match the expected configuration")]
+    InvalidMint,
+    #[msg("Vault token account is not exclusively controlled by the vault authority")]
+    InvalidVaultAccount,
+    #[msg("Invalid governance parameters")]
+    InvalidGovernanceParams,
+    #[msg("Account is not in the expected legacy layout")]
+    MigrationNotRequired,
+    #[msg("Invalid feed configuration")]
+    InvalidFeedConfig,
+    #[msg("Feed is meeting its SLA")]
+    SlaNotBreached,
+    #[msg("No rebate credits to claim")]
+    NoRebateCredits,
+    #[msg("Account is not a price account of this program")]
+    InvalidPriceAccount,
+    #[msg("Insurance share exceeds the maximum")]
+    InvalidInsuranceShare,
+    #[msg("Current epoch has not elapsed")]
+    EpochNotElapsed,
+    #[msg("Feed requires committed quotes")]
+    CommitRevealRequired,
+    #[msg("Feed does not use commit-reveal")]
+    CommitRevealDisabled,
+    #[msg("Commitment cannot be revealed in this slot")]
+    RevealNotReady,
+    #[msg("Revealed quote does not match the commitment")]
+    InvalidReveal,
+    #[msg("Sampled feeds need the SlotHashes sysvar to aggregate")]
+    SlotHashesRequired,
+    #[msg("Identity must be a non-empty domain or DID of at most 128 bytes")]
+    InvalidIdentity,
+    #[msg("Quote must be co-signed by the publisher's registered cosigner")]
+    CosignerRequired,
+    #[msg("Signer is not a fast-lane publisher for this feed")]
+    NotFastPublisher,
+    #[msg("Only the designated poster can post to this feed")]
+    NotOptimisticPoster,
+    #[msg("Optimistic price is disputed or its challenge window has closed")]
+    ChallengeWindowClosed,
+    #[msg("Challenge quote overlaps the posted price's confidence band")]
+    ChallengeRejected,
+    #[msg("Quote is older than the publisher's current quote")]
+    QuoteOutdated,
+    #[msg("Proof does not match the publisher's price batch")]
+    InvalidBatchProof,
+    #[msg("Stake receipt mint or token account missing or invalid")]
+    InvalidReceiptAccount,
+    #[msg("Receipts would exceed the publisher's stake")]
+    ReceiptsExceedStake,
+    #[msg("Payout account must be a stake-mint token account owned by the publisher")]
+    InvalidPayoutAccount,
+    #[msg("Inverse feed currencies must be the source pair swapped")]
+    CurrencyMismatch,
+    #[msg("Feed does not have the futures price type")]
+    NotFuturesFeed,
+    #[msg("Contract id must be 1-16 bytes and expire in the future")]
+    InvalidContract,
+    #[msg("Publisher quoted within the inactivity window")]
+    PublisherStillActive,
+    #[msg("Publisher still holds feed slots or stake receipts")]
+    PublisherHasExposure,
+    #[msg("Reward pool must be empty to change the reward mint")]
+    RewardPoolNotEmpty,
+    #[msg("Account snapshot does not hash to the trusted slot hash")]
+    SnapshotProofInvalid,
+    #[msg("Feed must be halted with no publishers left to be retired")]
+    FeedNotRetirable,
+    #[msg("Feed quotes no longer match the last aggregation")]
+    RoundNotArchivable,
+    #[msg("Round has already been disputed")]
+    RoundAlreadyDisputed,
+    #[msg("Stake is locked in a stake position")]
+    StakeLocked,
+    #[msg("Stake position has not reached its unlock time")]
+    PositionStillLocked,
+    #[msg("Stake position does not belong to this publisher")]
+    InvalidStakePosition,
+    #[msg("Position metadata URI is too long")]
+    InvalidPositionMetadata,
+    #[msg("Quote sequence number is not above the last accepted one")]
+    QuoteReplayed,
+    #[msg("Cluster configuration is invalid")]
+    InvalidClusterConfig,
+    #[msg("Transaction was built for a different deployment")]
+    WrongCluster,
+    #[msg("Publisher is in its post-slash cooldown")]
+    PublisherCoolingDown,
+    #[msg("Feed has submission quotas; pass its FeedQuota account")]
+    FeedQuotaRequired,
+    #[msg("Rewards are held in a separate reward vault; switch back to the staking mint first")]
+    SeparateRewardVault,
+    #[msg("New vault token account holds less than the vault's current balance")]
+    MigrationUnderfunded,
+    #[msg("Compact quote data does not match the feeds passed")]
+    InvalidCompactQuotes,
+    #[msg("Insurance premium already paid for this epoch")]
+    PremiumAlreadyPaid,
+    #[msg("No SLA breach covered by this epoch's premium left to claim")]
+    NoInsuranceClaim,
+    #[msg("Program was built without the mock-clock feature")]
+    MockClockDisabled,
+    #[msg("TWAP window must be a positive number of seconds")]
+    InvalidTwapWindow,
+    #[msg("Price history does not reach back to the start of the TWAP window")]
+    TwapUnavailable,
+    #[msg("Feed still has free quote slots")]
+    FeedNotFull,
+    #[msg("Publisher does not outrank the holder of the lowest-ranked quote slot")]
+    InsufficientRank,
+    #[msg("Publisher is not permitted to quote this feed")]
+    PublisherNotPermitted,
+    #[msg("Feed retirement has not been announced or its notice period has not passed")]
+    RetirementNoticePending,
+    #[msg("Vault drift changed since the proposal was created")]
+    VaultDriftChanged,
+    #[msg("Optimistic feed has no open dispute")]
+    NoOpenDispute,
+    #[msg("Feed is already halted")]
+    FeedAlreadyHalted,
+    #[msg("Publishers cannot halt this feed again until the cooldown after the last halt ends")]
+    HaltCoolingDown,
+    #[msg("Every quote slot on the feed holds a fresh quote")]
+    NoStaleQuote,
+    #[msg("The mock clock cannot be used on mainnet")]
+    MockClockOnMainnet,
+    #[msg("Mock-clock builds need the global state to read the program clock")]
+    GlobalStateRequired,
+    #[msg("Stored enum discriminant is out of range")]
+    InvalidDiscriminant,
+    #[msg("No token mint migration is open for swaps")]
+    NoMintMigration,
+    #[msg("The proposal was not executed within its execution window")]
+    ExecutionWindowClosed,
+    #[msg("The proposal is not past its execution window")]
+    ProposalNotExpired,
+    #[msg("The proposal's action has already been executed")]
+    ActionAlreadyExecuted,
+    #[msg("No rewards are accrued or vested to claim")]
+    NoRewardsToClaim,
 }
 
 // ============================================================================
 // Enums
 // ============================================================================
 
-#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
 pub enum PriceStatus {
     Trading,
     Halted,
     Auction,
     Unknown,
+    Degraded, // Fresh, but confidence is wide or publishers are at the minimum
+    Closed, // Outside the product's trading calendar; the last price is kept
+}
+
+impl PriceStatus {
+    /// Whether the aggregate carries a fresh price (possibly warranting a haircut)
+    pub fn is_fresh(&self) -> bool {
+        matches!(self, PriceStatus::Trading | PriceStatus::Degraded)
+    }
+}
+
+/// Cluster a deployment runs on, recorded in its ClusterConfig
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum ClusterEnvironment {
+    Localnet,
+    Devnet,
+    Testnet,
+    Mainnet,
+}
+
+impl ClusterEnvironment {
+    /// Genesis hash of the public cluster; `None` for local validators
+    pub fn genesis_hash(&self) -> Option<&'static str> {
+        match self {
+            ClusterEnvironment::Localnet => None,
+            ClusterEnvironment::Devnet => Some(DEVNET_GENESIS_HASH),
+            ClusterEnvironment::Testnet => Some(TESTNET_GENESIS_HASH),
+            ClusterEnvironment::Mainnet => Some(MAINNET_GENESIS_HASH),
+        }
+    }
 }
 
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
@@ -86,13 +382,180 @@ pub enum AssetType {
     Commodity,
 }
 
-#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
+/// Listing risk of a feed, fixed by its asset type. Scales the collateral the feed's
+/// creator locks at listing.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum RiskTier {
+    Low, // Forex
+    Medium, // Equities and commodities
+    High, // Crypto
+}
+
+impl AssetType {
+    /// Seed of the asset type's AssetTemplate PDA
+    pub fn template_seed(&self) -> &'static [u8] {
+        match self {
+            AssetType::Crypto => b"crypto",
+            AssetType::Equity => b"equity",
+            AssetType::Forex => b"forex",
+            AssetType::Commodity => b"commodity",
+        }
+    }
+}
+
+impl RiskTier {
+    pub fn for_asset_type(asset_type: &AssetType) -> Self {
+        match asset_type {
+            AssetType::Forex => RiskTier::Low,
+            AssetType::Equity | AssetType::Commodity => RiskTier::Medium,
+            AssetType::Crypto => RiskTier::High,
+        }
+    }
+
+    /// Multiple of `GlobalState::listing_collateral` locked at listing
+    pub fn collateral_multiple(&self) -> u64 {
+        match self {
+            RiskTier::Low => 1,
+            RiskTier::Medium => 2,
+            RiskTier::High => 4,
+        }
+    }
+}
+
+/// Lock length of a stake position. The locked stake stays bonded and slashable, and
+/// only becomes withdrawable again once the position is unlocked.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum LockTier {
+    ThreeMonths,
+    SixMonths,
+    TwelveMonths,
+}
+
+impl LockTier {
+    /// Seconds a position of this tier stays locked
+    pub fn lock_duration(&self) -> i64 {
+        match self {
+            LockTier::ThreeMonths => 7_776_000,
+            LockTier::SixMonths => 15_552_000,
+            LockTier::TwelveMonths => 31_536_000,
+        }
+    }
+
+    fn label(&self) -> &'static str {
+        match self {
+            LockTier::ThreeMonths => "3M",
+            LockTier::SixMonths => "6M",
+            LockTier::TwelveMonths => "12M",
+        }
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
 pub enum PriceType {
     Spot,
     Futures,
     Option,
 }
 
+/// How a round's aggregate was computed from its quotes, recorded with archived rounds
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum AggregationStrategy {
+    StakeWeightedMedian, // Quotes beyond OUTLIER_MAD_MULTIPLIER MADs dropped, then the stake-weighted median
+}
+
+/// Rounding applied wherever feed math divides (EMA, exponent scaling, derived feeds),
+/// so results can be reproduced bit-for-bit off-chain
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[repr(u8)]
+pub enum RoundingMode {
+    #[default]
+    Floor,
+    Ceil,
+    HalfEven,
+}
+
+/// Stored value checked by `audit_feed_scaling`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum ScalingField {
+    AggregateExponent, // Aggregate written at another exponent than the feed's
+    HighPrecisionPrice, // `price_i128` not at `HIGH_PRECISION_DECIMALS` below the aggregate
+    EmaPrice, // EMA an order of magnitude off the aggregate
+    PublisherQuote, // Quote an order of magnitude off the aggregate
+    RollAdjustment, // Roll adjustment on a feed that is not a futures feed
+}
+
+/// Unit of the confidence publishers submit on a feed. Aggregates always carry absolute
+/// confidence; use `PriceData::confidence_bps` for the relative form.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[repr(u8)]
+pub enum ConfidenceMode {
+    #[default]
+    Absolute, // Price units at the feed exponent
+    Bps, // Basis points of the quoted price
+}
+
+/// Lifecycle of the portion of a publisher's stake that is leaving the vault.
+/// Bonded stake lives in `staked_amount`; at most one unbonding batch exists at a time.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq, Default)]
+pub enum StakeState {
+    #[default]
+    Bonded,
+    Unbonding {
+        amount: u64,
+        start: i64,
+        period: i64, // GlobalState::unbonding_period captured when the batch last grew
+    },
+}
+
+impl StakeState {
+    /// Tokens waiting out the unbonding period (still slashable)
+    pub fn unbonding_amount(&self) -> u64 {
+        match self {
+            StakeState::Bonded => 0,
+            StakeState::Unbonding { amount, .. } => *amount,
+        }
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
+pub enum StakeTransition {
+    ToppedUp,
+    UnbondStarted,
+    UnbondExtended, // Further unstake merged into the pending batch, restarting its clock
+    Withdrawn,
+    Slashed,
+}
+
+/// Why a stored quote was left out of aggregation, or flagged when submitted
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum QuoteRejectReason {
+    Stale, // Already older than the feed's staleness threshold when submitted
+    Outlier, // Dropped by the MAD outlier filter; reported once per quote
+    BandViolation, // Confidence wider than the feed's confidence band; still aggregated, but can degrade the feed
+}
+
+/// Where slashed tokens go when a SlashPublisher proposal executes
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
+pub enum SlashDestination {
+    Burn,
+    InsuranceFund,
+    Compensation { token_account: Pubkey }, // Pool for affected consumers
+}
+
+/// How consumers verify a publisher identity off-chain
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
+pub enum IdentityKind {
+    DomainTxt, // TXT record on `identifier` whose content hashes to `proof_hash`
+    Did, // DID document at `identifier` whose proof hashes to `proof_hash`
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
+pub enum SlaBreachKind {
+    UpdateFrequency,
+    Confidence,
+}
+
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
 pub enum VoteType {
     Yes,
@@ -100,11 +563,31 @@ pub enum VoteType {
     Abstain,
 }
 
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
+pub enum ProposalMode {
+    Standard,
+    Conviction, // No voting window; passes once locked-token conviction crosses the threshold
+}
+
+/// Whitelisted low-risk parameter changes that may skip a full vote
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
+pub enum OptimisticParam {
+    RewardRate { new_rate: u64 },
+    MinPublishers { feed: Pubkey, new_min: u8 },
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
+pub enum OptimisticStatus {
+    Pending,
+    Vetoed,
+    Applied,
+}
+
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
 pub enum ProposalType {
     UpdateRewardRate { new_rate: u64 },
     UpdateMinPublishers { feed: Pubkey, new_min: u8 },
-    SlashPublisher { publisher: Pubkey, percentage: u8 },
+    SlashPublisher { publisher: Pubkey, percentage: u8, destination: SlashDestination, publish_cooldown_slots: u64 }, // No quotes for this many slots after execution
     EmergencyPause,
     EmergencyUnpause,
     UpdateGovernanceParams { 
@@ -112,24 +595,194 @@ pub enum ProposalType {
         voting_period: Option<u64>,
         quorum_percentage: Option<u8>,
         timelock_duration: Option<u64>,
+        conviction_threshold_percentage: Option<u8>,
+        severity_threshold_bps: Option<[u32; 3]>,
     },
+    UpdateStakePerFeed { stake_per_feed: u64 },
+    FreezeFeed { feed: Pubkey }, // Emergency path: short voting period, supermajority, no timelock
+    UnfreezeFeed { feed: Pubkey },
+    ApprovePublisherRegistrations { count: u32 },
+    UpdateRegistrationLimit { max_per_epoch: u32 },
+    UpdateUnbondingPeriod { period: i64 }, // Seconds; applies to unbondings started after execution
+    UpdateMinStake { min_stake: u64 },
+    SetReceiptTransferability { transferable: bool },
+    BulkUpdateFeeds { asset_type: AssetType, update: FeedConfigUpdate }, // Feeds passed as (product, price) pairs in remaining_accounts
+    SetFeedSla { feed: Pubkey, max_update_interval_slots: u64, max_confidence_bps: u16, rebate_bps: u16 },
+    ResolveVaultDrift { drift: i64 }, // Books the drift found by the last reconcile_vault against the treasury; must match at execution
+    UpgradeProgram { buffer: Pubkey }, // Needs the governance PDA as upgrade authority
+    SetUpgradeAuthority { new_authority: Option<Pubkey> }, // None makes the program immutable
+    SetRewardMint { mint: Pubkey }, // Needs an empty reward pool; the staking mint switches back
+    UpdateRewardVesting { period: i64 }, // Seconds; applies to rewards claimed after execution
+    DisputeRound { round: Pubkey, excluded_publisher: Option<Pubkey> }, // Re-aggregates an archived round
+    UpdateListingCollateral { collateral: u64 }, // Per risk tier multiple; applies to feeds listed after execution
+    DrawListingCollateral { feed: Pubkey, amount: u64 }, // Moves a feed's collateral into the insurance fund
+    SetPositionTransferability { transferable: bool },
+    SetAssetTemplate { asset_type: AssetType, template: AssetTemplateParams }, // Applies to feeds created after execution
+    SetFeedQuota { feed: Pubkey, min_submissions: u32, max_submissions: u32 }, // Per publisher per epoch; 0 = no bound
+    RotateVault { new_vault_token_account: Pubkey }, // Owned by the next custody authority
+    MigrateTokenMint { new_mint: Pubkey, new_vault_token_account: Pubkey, counterparty: Pubkey }, // Counterparty funds the new vault account and receives the old tokens
+    DeclareVolatilityRegime { feed: Pubkey, widening_bps: u32, duration_slots: u64 }, // Emergency path like FreezeFeed; 0 slots ends a regime
+    AttestIdentity { publisher: Pubkey, operator_id: u64 }, // Vets a bound identity so it counts towards min_operators
+}
+
+/// How much a proposal type can change, from routine parameter tweaks to handing over
+/// control of the program. Higher classes need a larger proposer balance.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum ProposalSeverity {
+    Parameter,
+    Slash,
+    Authority,
+}
+
+impl ProposalType {
+    pub fn severity(&self) -> ProposalSeverity {
+        match self {
+            ProposalType::SlashPublisher { .. }
+            | ProposalType::DrawListingCollateral { .. }
+            | ProposalType::DisputeRound { .. } => ProposalSeverity::Slash,
+            ProposalType::UpdateGovernanceParams { .. }
+            | ProposalType::UpgradeProgram { .. }
+            | ProposalType::SetUpgradeAuthority { .. }
+            | ProposalType::SetRewardMint { .. }
+            | ProposalType::RotateVault { .. }
+            | ProposalType::MigrateTokenMint { .. } => ProposalSeverity::Authority,
+            _ => ProposalSeverity::Parameter,
+        }
+    }
+
+    /// Emergency proposals vote for the shorter emergency period and execute without a
+    /// timelock once yes votes reach the emergency quorum
+    pub fn is_emergency(&self) -> bool {
+        matches!(self, ProposalType::FreezeFeed { .. } | ProposalType::DeclareVolatilityRegime { .. })
+    }
+
+    /// Whether voting waits PROPOSAL_REVIEW_PERIOD so the proposer can link amendments and
+    /// discussion first. Emergency proposals and pause toggles vote immediately.
+    pub fn has_review_period(&self) -> bool {
+        !self.is_emergency() && !matches!(self, ProposalType::EmergencyPause | ProposalType::EmergencyUnpause)
+    }
 }
 
 // ============================================================================
 // Data Structures
 // ============================================================================
 
-#[derive(AnchorSerialize, AnchorDeserialize, Clone, Default)]
+/// Stored inline in the zero-copy `PriceAccount`, so the layout has no implicit padding
+/// and `status` is kept as a raw byte: any bit pattern in the account is a valid value.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy)]
+#[repr(C)]
 pub struct PriceData {
     pub price: i64,
     pub confidence: u64,
-    pub exponent: i32,
     pub timestamp: i64,
     pub slot: u64,
-    pub status: PriceStatus,
+    pub price_i128: i128, // Aggregate price at `price_i128_exponent()`, for high-precision feeds (0 = none)
+    pub exponent: i32,
+    pub status: u8, // PriceStatus; read through `status()`
+    pub _padding: [u8; 11],
+}
+
+impl PriceData {
+    /// Stored status; an out-of-range byte reads as Unknown so consumers fail closed
+    pub fn status(&self) -> PriceStatus {
+        PriceStatus::try_from(self.status).unwrap_or(PriceStatus::Unknown)
+    }
+
+    pub fn price_i128_exponent(&self) -> i32 {
+        self.exponent - HIGH_PRECISION_DECIMALS as i32
+    }
+
+    /// Confidence as basis points of the price, rounded up. `None` for non-positive prices.
+    pub fn confidence_bps(&self) -> Option<u64> {
+        confidence_bps(self.price, self.confidence)
+    }
+
+    /// `self * other` at `target_expo`. Relative confidences add (first-order), so
+    /// `conf = |p1|*c2 + |p2|*c1`. Prices round down, confidence rounds up.
+    pub fn checked_mul(&self, other: &PriceData, target_expo: i32) -> Option<PriceData> {
+        let shift = self.exponent.checked_add(other.exponent)?.checked_sub(target_expo)?;
+        let (p1, p2) = (self.price as i128, other.price as i128);
+        let price = rescale(p1.checked_mul(p2)?, shift, RoundingMode::Floor)?;
+        let confidence = rescale(
+            p1.unsigned_abs().checked_mul(other.confidence as u128)?
+                .checked_add(p2.unsigned_abs().checked_mul(self.confidence as u128)?)? as i128,
+            shift,
+            RoundingMode::Ceil,
+        )?;
+        self.combine(other, price, confidence, target_expo)
+    }
+
+    /// `self / other` at `target_expo`, with `conf = (|p1|*c2 + |p2|*c1) / p2^2`
+    pub fn checked_div(&self, other: &PriceData, target_expo: i32) -> Option<PriceData> {
+        if other.price == 0 {
+            return None;
+        }
+        let shift = self.exponent.checked_sub(other.exponent)?.checked_sub(target_expo)?;
+        let (p1, p2) = (self.price as i128, other.price as i128);
+        let price = div_round(rescale(p1, shift.max(0), RoundingMode::Floor)?, p2, RoundingMode::Floor)
+            .and_then(|q| rescale(q, shift.min(0), RoundingMode::Floor))?;
+        let spread = p1.unsigned_abs().checked_mul(other.confidence as u128)?
+            .checked_add(p2.unsigned_abs().checked_mul(self.confidence as u128)?)? as i128;
+        let confidence = div_round(
+            rescale(spread, shift.max(0), RoundingMode::Ceil)?,
+            p2.checked_mul(p2)?,
+            RoundingMode::Ceil,
+        )
+        .and_then(|c| rescale(c, shift.min(0), RoundingMode::Ceil))?;
+        self.combine(other, price, confidence, target_expo)
+    }
+
+    /// `self + other` at `target_expo` (e.g. basket legs); confidences add
+    pub fn checked_add(&self, other: &PriceData, target_expo: i32) -> Option<PriceData> {
+        let scale = |value: i128, expo: i32, rounding| rescale(value, expo.checked_sub(target_expo)?, rounding);
+        let price = scale(self.price as i128, self.exponent, RoundingMode::Floor)?
+            .checked_add(scale(other.price as i128, other.exponent, RoundingMode::Floor)?)?;
+        let confidence = scale(self.confidence as i128, self.exponent, RoundingMode::Ceil)?
+            .checked_add(scale(other.confidence as i128, other.exponent, RoundingMode::Ceil)?)?;
+        self.combine(other, price, confidence, target_expo)
+    }
+
+    /// `1 / self` at `target_expo` (e.g. USD/JPY from JPY/USD), with `conf = c / p^2`
+    pub fn invert(&self, target_expo: i32) -> Option<PriceData> {
+        let one = PriceData {
+            price: 1,
+            confidence: 0,
+            exponent: 0,
+            timestamp: self.timestamp,
+            slot: self.slot,
+            status: self.status,
+            price_i128: 0,
+            _padding: [0; 11],
+        };
+        one.checked_div(self, target_expo)
+    }
+
+    /// Result of composing two prices: as old as the older input, with the weaker status
+    fn combine(&self, other: &PriceData, price: i128, confidence: i128, exponent: i32) -> Option<PriceData> {
+        let (own, theirs) = (self.status(), other.status());
+        let status = if !own.is_fresh() {
+            own
+        } else if !theirs.is_fresh() || theirs == PriceStatus::Degraded {
+            theirs
+        } else {
+            own
+        };
+
+        Some(PriceData {
+            price: i64::try_from(price).ok()?,
+            confidence: u64::try_from(confidence).ok()?,
+            exponent,
+            timestamp: self.timestamp.min(other.timestamp),
+            slot: self.slot.min(other.slot),
+            status: status as u8,
+            price_i128: 0,
+            _padding: [0; 11],
+        })
+    }
 }
 
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default)]
+#[repr(C)]
 pub struct PublisherPrice {
     pub publisher: Pubkey,
     pub price: i64,
@@ -137,10 +790,252 @@ pub struct PublisherPrice {
     pub timestamp: i64,
     pub slot: u64,
     pub stake: u64,
-    pub active: bool, // Track if this slot is in use
+    pub source_id: u64, // Upstream exchange bitmap or hash reported with the quote
+    pub operator: u64, // Publisher's identity-linked operator when quoted (0 = none)
+    pub sequence: u32, // Last sequence number the publisher quoted this feed with
+    pub active: u8, // Whether this slot is in use; read through `active()`
+    pub outlier_reported: u8, // QuoteRejected was emitted for this quote as an outlier
+    pub _padding: [u8; 2],
+}
+
+impl PublisherPrice {
+    pub fn active(&self) -> bool {
+        self.active != 0
+    }
+}
+
+/// Per-feed settings adjustable by the feed authority; `None` leaves a field unchanged.
+/// `halted` and `staleness_threshold` may also be set by a delegated `FeedAdmin`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Default, PartialEq, Eq)]
+pub struct FeedConfigUpdate {
+    pub confidence_stake_scale: Option<u64>,
+    pub halted: Option<bool>,
+    pub staleness_threshold: Option<i64>,
+    pub degraded_confidence_bps: Option<u16>,
+    pub rounding_mode: Option<RoundingMode>,
+    pub high_precision: Option<bool>,
+    pub commit_reveal: Option<bool>,
+    pub sample_size: Option<u8>,
+    pub confidence_mode: Option<ConfidenceMode>,
+    pub min_operators: Option<u8>,
+    pub ema_alpha: Option<u32>,
+    pub ema_confidence_weighted: Option<bool>,
+    pub min_slots_between_aggregations: Option<u64>,
+    pub permissioned: Option<bool>,
+    pub deviation_alert_mads: Option<u8>,
+}
+
+/// Governance-set parameters of the deployment, as returned by `export_config`. Running
+/// totals (stake, pools, counters) are left out so snapshots only differ on config changes.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
+pub struct ConfigSnapshot {
+    pub slot: u64,
+    pub version: u8,
+    pub token_mint: Pubkey,
+    pub paused: bool,
+    pub stake_per_feed: u64,
+    pub max_new_publishers_per_epoch: u32,
+    pub unbonding_period: i64,
+    pub min_stake: u64,
+    pub listing_collateral: u64,
+    pub receipts_transferable: bool,
+    pub positions_transferable: bool,
+    pub reward_rate: u64,
+    pub reward_mint: Pubkey,
+    pub reward_vesting_period: i64,
+    pub proposal_threshold: u64,
+    pub voting_period: u64,
+    pub quorum_percentage: u8,
+    pub timelock_duration: u64,
+    pub conviction_threshold_percentage: u8,
+    pub optimistic_challenge_period: u64,
+    pub optimistic_veto_stake: u64,
+    pub emergency_voting_period: u64,
+    pub emergency_quorum_percentage: u8,
+    pub severity_threshold_bps: [u32; 3],
+    pub feeds: Vec<FeedConfigSnapshot>,
 }
 
-#[derive(AnchorSerialize, AnchorDeserialize, Clone, Default)]
+/// A feed's settings. `config` has every field set, so it can be replayed as an update.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
+pub struct FeedConfigSnapshot {
+    pub price_account: Pubkey,
+    pub exponent: i32,
+    pub min_publishers: u8,
+    pub frozen: bool,
+    pub config: FeedConfigUpdate,
+}
+
+/// Weekly UTC trading session plus holidays. Sessions with `close_minute <= open_minute`
+/// run overnight; the weekday bit applies to the UTC day of the current minute.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Default, PartialEq, Eq)]
+pub struct TradingCalendar {
+    pub enabled: bool, // Disabled calendars (e.g. crypto) are always open
+    pub weekdays: u8, // Bit 0 = Monday .. bit 6 = Sunday
+    pub open_minute: u16, // Minutes after 00:00 UTC
+    pub close_minute: u16,
+    pub holidays: Vec<u16>, // Closed days, as days since 1970-01-01
+}
+
+impl TradingCalendar {
+    pub fn is_valid(&self) -> bool {
+        self.weekdays < 1 << 7
+            && self.open_minute < 1_440
+            && self.close_minute <= 1_440
+            && self.holidays.len() <= MAX_CALENDAR_HOLIDAYS
+    }
+
+    pub fn is_open(&self, unix_timestamp: i64) -> bool {
+        if !self.enabled {
+            return true;
+        }
+
+        let day = unix_timestamp.div_euclid(86_400);
+        let minute = (unix_timestamp.rem_euclid(86_400) / 60) as u16;
+        let weekday = (day + 3).rem_euclid(7); // 1970-01-01 was a Thursday
+
+        if self.weekdays & (1 << weekday) == 0 || self.holidays.contains(&(day as u16)) {
+            return false;
+        }
+
+        if self.open_minute < self.close_minute {
+            minute >= self.open_minute && minute < self.close_minute
+        } else {
+            minute >= self.open_minute || minute < self.close_minute
+        }
+    }
+}
+
+/// Defaults `create_product` applies to new feeds of an asset type
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
+pub struct AssetTemplateParams {
+    pub staleness_threshold: i64,
+    pub degraded_confidence_bps: u16, // Confidence cap before the feed reports Degraded
+    pub min_publishers: u8, // Floor on the creator's min_publishers
+    pub calendar: TradingCalendar,
+}
+
+impl AssetTemplateParams {
+    /// Starting point for each asset type, until governance changes it
+    pub fn defaults(asset_type: &AssetType) -> Self {
+        // Weekday bits 0-4: Monday to Friday
+        let weekdays = |open_minute, close_minute| TradingCalendar {
+            enabled: true,
+            weekdays: 0b001_1111,
+            open_minute,
+            close_minute,
+            holidays: Vec::new(),
+        };
+        match asset_type {
+            AssetType::Crypto => Self {
+                staleness_threshold: 15,
+                degraded_confidence_bps: 100,
+                min_publishers: 5,
+                calendar: TradingCalendar::default(),
+            },
+            AssetType::Equity => Self {
+                staleness_threshold: 30,
+                degraded_confidence_bps: 50,
+                min_publishers: 3,
+                calendar: weekdays(810, 1_200), // 13:30-20:00 UTC
+            },
+            AssetType::Forex => Self {
+                staleness_threshold: 30,
+                degraded_confidence_bps: 20,
+                min_publishers: 3,
+                calendar: weekdays(0, 1_440),
+            },
+            AssetType::Commodity => Self {
+                staleness_threshold: 30,
+                degraded_confidence_bps: 50,
+                min_publishers: 3,
+                calendar: weekdays(0, 1_440),
+            },
+        }
+    }
+
+    fn is_valid(&self) -> bool {
+        self.staleness_threshold > 0
+            && self.staleness_threshold <= HALTED_THRESHOLD
+            && self.degraded_confidence_bps <= 10_000
+            && self.min_publishers > 0
+            && self.calendar.is_valid()
+    }
+}
+
+/// Hypothetical quote folded into `simulate_aggregation` in place of the publisher's current one
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct SimulatedQuote {
+    pub price: i64,
+    pub confidence: u64,
+    pub source_id: u64,
+}
+
+/// Claimed rewards released linearly over `period`. A further claim merges the locked
+/// remainder into a fresh schedule; a slash claws back whatever is still locked.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default)]
+pub struct RewardVesting {
+    pub amount: u64, // Schedule total, including what was already released
+    pub released: u64,
+    pub start: i64,
+    pub period: i64,
+}
+
+impl RewardVesting {
+    pub fn vested(&self, now: i64) -> u64 {
+        let elapsed = now.saturating_sub(self.start);
+        if self.period <= 0 || elapsed >= self.period {
+            return self.amount;
+        }
+        (self.amount as u128 * elapsed.max(0) as u128 / self.period as u128) as u64
+    }
+
+    pub fn locked(&self, now: i64) -> u64 {
+        self.amount - self.vested(now)
+    }
+
+    pub fn releasable(&self, now: i64) -> u64 {
+        self.vested(now).saturating_sub(self.released)
+    }
+
+    pub fn unreleased(&self) -> u64 {
+        self.amount - self.released
+    }
+
+    /// Ends the schedule at what has vested so far, returning the locked remainder
+    fn claw_back(&mut self, now: i64) -> u64 {
+        let locked = self.locked(now);
+        self.amount -= locked;
+        self.period = 0;
+        locked
+    }
+}
+
+/// Parameter value a passed proposal has queued behind its timelock, written into the
+/// target account so readers see the upcoming config before it lands
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default)]
+#[repr(C)]
+pub struct PendingParam {
+    pub value: u64,
+    pub effective_at: i64, // Timelock expiry (0 = nothing queued)
+    pub proposal_id: u64,
+}
+
+impl PendingParam {
+    pub fn is_queued(&self) -> bool {
+        self.effective_at != 0
+    }
+
+    /// Drops the preview once its own proposal has been applied
+    fn clear(&mut self, proposal_id: u64) {
+        if self.proposal_id == proposal_id {
+            *self = PendingParam::default();
+        }
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default)]
+#[repr(C)]
 pub struct EmaData {
     pub ema_price: i64,
     pub ema_confidence: u64,
@@ -151,6 +1046,60 @@ pub struct EmaData {
 // Events
 // ============================================================================
 
+#[event]
+pub struct Initialized {
+    pub authority: Pubkey,
+    pub token_mint: Pubkey,
+    pub vault_token_account: Pubkey,
+    pub reward_rate: u64,
+    pub proposal_threshold: u64,
+    pub voting_period: u64,
+    pub quorum_percentage: u8,
+    pub timelock_duration: u64,
+    pub total_supply: u64,
+    pub version: u8,
+}
+
+#[event]
+pub struct ClusterConfigured {
+    pub environment: ClusterEnvironment,
+    pub token_mint: Pubkey,
+    pub genesis_hash: [u8; 32],
+    pub label: String,
+}
+
+#[event]
+pub struct Migrated {
+    pub from_version: u8,
+    pub to_version: u8,
+    pub authority: Pubkey,
+}
+
+#[event]
+pub struct PriceAccountMigrated {
+    pub price_account: Pubkey,
+    pub authority: Pubkey, // Feed authority
+}
+
+#[event]
+pub struct ProductAccountMigrated {
+    pub product_account: Pubkey,
+    pub authority: Pubkey,
+}
+
+#[event]
+pub struct PublisherAccountMigrated {
+    pub publisher_account: Pubkey,
+    pub authority: Pubkey,
+    pub feed_count: u32,
+}
+
+#[event]
+pub struct ProposalMigrated {
+    pub proposal: Pubkey,
+    pub proposal_id: u64,
+}
+
 #[event]
 pub struct PriceUpdated {
     pub product: Pubkey,
@@ -160,6 +1109,8 @@ pub struct PriceUpdated {
     pub timestamp: i64,
     pub slot: u64,
     pub status: PriceStatus,
+    pub distinct_sources: u8,
+    pub top_source_share_bps: u16,
 }
 
 #[event]
@@ -170,10 +1121,55 @@ pub struct PublisherAdded {
     pub name: String,
 }
 
+#[event]
+pub struct PublisherDeregistered {
+    pub publisher: Pubkey,
+    pub authority: Pubkey,
+    pub stake_returned: u64,
+    pub rewards_forfeited: u64,
+    pub last_active_epoch: u64,
+}
+
+#[event]
+pub struct RewardsFunded {
+    pub funder: Pubkey,
+    pub amount: u64,
+    pub reward_pool: u64,
+}
+
+#[event]
+pub struct RewardsClaimed {
+    pub publisher: Pubkey,
+    pub amount: u64,
+    pub insurance_contribution: u64, // Routed to the insurance fund out of `amount`
+    pub remaining_pending: u64,
+    pub paid_out: u64, // Matured vesting, plus the claim itself when vesting is off
+    pub vesting_locked: u64,
+}
+
+#[event]
+pub struct RewardsClawedBack {
+    pub publisher: Pubkey,
+    pub amount: u64, // Locked vesting returned to the reward pool
+}
+
+#[event]
+pub struct StakeChanged {
+    pub publisher: Pubkey,
+    pub transition: StakeTransition,
+    pub amount: u64,
+    pub staked_amount: u64,
+    pub unbonding_amount: u64,
+    pub timestamp: i64,
+}
+
 #[event]
 pub struct PublisherSlashed {
     pub publisher: Pubkey,
     pub slash_amount: u64,
+    pub unbonding_slash_amount: u64, // Portion of slash_amount taken from unbonding tokens
+    pub insurance_covered: u64, // Absorbed by the insurance fund instead of the publisher
+    pub destination: SlashDestination,
     pub slash_percentage: u8,
     pub reason: String,
 }
@@ -187,1017 +1183,12287 @@ pub struct ProposalCreated {
 }
 
 #[event]
-pub struct ProposalExecuted {
+pub struct ConvictionChanged {
     pub proposal_id: u64,
-    pub proposal_type: ProposalType,
+    pub voter: Pubkey,
+    pub locked_delta: i64,
+    pub conviction_staked: u64,
+    pub conviction: u64,
 }
 
 #[event]
-pub struct SystemPaused {
-    pub timestamp: i64,
-    pub authority: Pubkey,
+pub struct OptimisticChangeProposed {
+    pub change_id: u64,
+    pub proposer: Pubkey,
+    pub change: OptimisticParam,
+    pub challenge_end_slot: u64,
 }
 
 #[event]
-pub struct SystemUnpaused {
+pub struct OptimisticChangeVetoed {
+    pub change_id: u64,
+    pub vetoer: Pubkey,
+    pub veto_stake: u64,
+}
+
+#[event]
+pub struct OptimisticChangeApplied {
+    pub change_id: u64,
+    pub change: OptimisticParam,
+}
+
+#[event]
+pub struct ProposalLinksUpdated {
+    pub proposal_id: u64,
+    pub linked_hashes: Vec<[u8; 32]>,
+}
+
+#[event]
+pub struct ParamChangeQueued {
+    pub proposal_id: u64,
+    pub target: Pubkey,
+    pub value: u64,
+    pub effective_at: i64,
+}
+
+#[event]
+pub struct ProposalExecuted {
+    pub proposal_id: u64,
+    pub proposal_type: ProposalType,
+}
+
+#[event]
+pub struct ProposalExpired {
+    pub proposal_id: u64,
     pub timestamp: i64,
-    pub authority: Pubkey,
 }
 
-// ============================================================================
-// Accounts
-// ============================================================================
+#[event]
+pub struct ActionExecuted {
+    pub proposal_id: u64,
+    pub receipt: Pubkey,
+    pub slot: u64,
+}
 
-#[account]
-pub struct GlobalState {
-    pub authority: Pubkey,
-    pub token_mint: Pubkey,
-    pub token_vault: Pubkey,
-    pub vault_authority: Pubkey,
-    pub governance: Pubkey,
-    pub paused: bool,
-    pub total_products: u64,
-    pub total_publishers: u64,
-    pub version: u8,
-    pub bump: u8,
-    pub vault_authority_bump: u8,
+#[event]
+pub struct FeedFrozen {
+    pub feed: Pubkey,
+    pub frozen: bool,
+    pub proposal_id: u64,
+    pub timestamp: i64,
 }
 
-#[account]
-pub struct ProductAccount {
-    pub symbol: String,
-    pub asset_type: AssetType,
-    pub description: String,
+#[event]
+pub struct HaltVoteCast {
     pub price_account: Pubkey,
-    pub authority: Pubkey,
-    pub bump: u8,
+    pub publisher: Pubkey,
+    pub voted_stake: u64,
+    pub total_stake: u64,
+    pub round_start_slot: u64,
 }
 
-#[account]
-pub struct PriceAccount {
-    pub product_account: Pubkey,
-    pub price_type: PriceType,
-    pub aggregate: PriceData,
-    pub publishers: [PublisherPrice; MAX_PUBLISHERS], // Fixed-size array
-    pub publisher_count: u8,
-    pub min_publishers: u8,
-    pub last_update_slot: u64,
-    pub ema: EmaData,
-    pub authority: Pubkey,
-    pub exponent: i32,
-    pub bump: u8,
+#[event]
+pub struct VolatilityRegimeDeclared {
+    pub feed: Pubkey,
+    pub widening_bps: u32,
+    pub until_slot: u64,
+    pub proposal_id: u64,
 }
 
-#[account]
-pub struct PublisherAccount {
-    pub authority: Pubkey,
-    pub staked_amount: u64,
-    pub stake_account: Pubkey,
-    pub reputation: u64,
-    pub name: String,
-    pub registered_at: i64,
-    pub slash_count: u32,
-    pub last_slash_slot: u64,
-    pub unbonding_amount: u64,
-    pub unbonding_start: i64,
-    pub bump: u8,
+#[event]
+pub struct FeedEmergencyHalted {
+    pub price_account: Pubkey,
+    pub voted_stake: u64,
+    pub total_stake: u64,
+    pub halted_until_slot: u64,
 }
 
-#[account]
-pub struct TokenVault {
-    pub total_staked: u64,
-    pub total_rewards_distributed: u64,
-    pub reward_rate: u64,
-    pub last_distribution_slot: u64,
-    pub token_mint: Pubkey,
-    pub vault_token_account: Pubkey,
-    pub vault_authority: Pubkey,
-    pub authority: Pubkey,
-    pub bump: u8,
+#[event]
+pub struct FeedsBulkUpdated {
+    pub proposal_id: u64,
+    pub asset_type: AssetType,
+    pub feeds_updated: u32,
 }
 
-#[account]
-pub struct GovernanceState {
-    pub governance_token: Pubkey,
-    pub proposal_threshold: u64,
-    pub voting_period: u64,
-    pub quorum_percentage: u8,
-    pub timelock_duration: u64,
-    pub proposal_count: u64,
-    pub total_supply: u64, // Store total supply for quorum calculation
+#[event]
+pub struct ProductCreated {
+    pub product: Pubkey,
+    pub price_account: Pubkey,
+    pub symbol: String,
+    pub asset_type: AssetType,
+    pub description: String,
+    pub price_type: PriceType,
+    pub min_publishers: u8,
+    pub exponent: i32,
+    pub risk_tier: RiskTier,
     pub authority: Pubkey,
-    pub bump: u8,
+    pub slot: u64,
 }
 
-#[account]
-pub struct Proposal {
-    pub proposer: Pubkey,
-    pub proposal_type: ProposalType,
-    pub description: String,
-    pub yes_votes: u64,
-    pub no_votes: u64,
-    pub abstain_votes: u64,
-    pub start_slot: u64,
-    pub end_slot: u64,
-    pub executed: bool,
-    pub execution_time: i64,
-    pub proposal_id: u64,
-    pub bump: u8,
+/// A feed config update as applied, including `halted` changes that pause or resume the
+/// feed. `proposal_id` is set when the change came from a `BulkUpdateFeeds` proposal.
+#[event]
+pub struct FeedConfigChanged {
+    pub price_account: Pubkey,
+    pub update: FeedConfigUpdate,
+    pub changed_by: Pubkey,
+    pub proposal_id: Option<u64>,
+    pub slot: u64,
 }
 
-// ============================================================================
-// Program
-// ============================================================================
+#[event]
+pub struct TradingCalendarChanged {
+    pub product: Pubkey,
+    pub calendar: TradingCalendar,
+}
 
-#[program]
-pub mod sfdn_oracle {
-    use super::*;
+/// A quote that was accepted but will not count towards the aggregate. Hard failures
+/// (e.g. ConfidenceTooTight) revert the transaction and surface as the error code instead.
+#[event]
+pub struct QuoteRejected {
+    pub product: Pubkey,
+    pub publisher: Pubkey,
+    pub price: i64,
+    pub confidence: u64,
+    pub timestamp: i64,
+    pub reason: QuoteRejectReason,
+    pub slot: u64,
+}
 
-    // ========================================================================
-    // Initialization Instructions
-    // ========================================================================
+/// An outlier further from the median than the feed's `deviation_alert_mads`, for
+/// monitoring. Follows the quote's `QuoteRejected` event.
+#[event]
+pub struct QuoteDeviationAlert {
+    pub product: Pubkey,
+    pub publisher: Pubkey,
+    pub price: i64,
+    pub median: i64, // Plain median of the quotes the outlier filter ran over
+    pub mad: u64,
+    pub deviation: u64, // Distance of `price` from `median`
+    pub slot: u64,
+}
 
-    pub fn initialize_program(
-        ctx: Context<InitializeProgram>,
-        reward_rate: u64,
-        proposal_threshold: u64,
-        voting_period: u64,
-        quorum_percentage: u8,
-        timelock_duration: u64,
-        total_supply: u64,
-    ) -> Result<()> {
-        let global_state = &mut ctx.accounts.global_state;
-        global_state.authority = ctx.accounts.authaority.key();
-        global_state.token_mint = ctx.accounts.token_mint.key();
-        global_state.token_vault = ctx.accounts.token_vault.key();
-        global_state.vault_authority = ctx.accounts.vault_authority.key();
-        global_state.governance = ctx.accounts.governance_state.key();
-        global_state.paused = false;
-        global_state.total_products = 0;
-        global_state.total_publishers = 0;
-        global_state.version = PROGRAM_VERSION;
-        global_state.bump = ctx.bumps.global_state;
-        global_state.vault_authority_bump = ctx.bumps.vault_authority;
+/// A quote was stored but the transaction had too little compute left to aggregate;
+/// the next quote or an `aggregate_price` crank publishes the round instead
+#[event]
+pub struct AggregationDeferred {
+    pub product: Pubkey,
+    pub slot: u64,
+    pub remaining_compute_units: u64,
+    pub required_compute_units: u64,
+}
 
-        let token_vault = &mut ctx.accounts.token_vault;
-        token_vault.total_staked = 0;
-        token_vault.total_rewards_distributed = 0;
-        token_vault.reward_rate = reward_rate;
-        token_vault.last_distribution_slot = Clock::get()?.slot;
-        token_vault.token_mint = ctx.accounts.token_mint.key();
-        token_vault.vault_token_account = ctx.accounts.vault_token_account.key();
-        token_vault.vault_authority = ctx.accounts.vault_authority.key();
-        token_vault.authority = ctx.accounts.authority.key();
-        token_vault.bump = ctx.bumps.token_vault;
+/// Enough to recompute the sample: seed = keccak(seed_hash, product), then sample_quotes
+#[event]
+pub struct QuotesSampled {
+    pub product: Pubkey,
+    pub seed_slot: u64,
+    pub seed_hash: [u8; 32],
+    pub candidates: u8,
+    pub sampled_publishers: u128,
+}
 
-        let governance = &mut ctx.accounts.governance_state;
-        governance.governance_token = ctx.accounts.token_mint.key();
-        governance.proposal_threshold = proposal_threshold;
-        governance.voting_period = voting_period;
-        governance.quorum_percentage = quorum_percentage;
-        governance.timelock_duration = timelock_duration;
-        governance.proposal_count = 0;
-        governance.total_supply = total_supply;
-        governance.authority = ctx.accounts.authority.key();
-        governance.bump = ctx.bumps.governance_state;
+#[event]
+pub struct CrankReimbursed {
+    pub price_account: Pubkey,
+    pub keeper: Pubkey,
+    pub lamports: u64,
+    pub remaining: u64,
+}
 
-        Ok(())
-    }
+#[event]
+pub struct FeedPruned {
+    pub product: Pubkey,
+    pub pruned: u8,
+    pub publisher_count: u8,
+}
 
-    pub fn create_product(
-        ctx: Context<CreateProduct>,
-        symbol: String,
-        asset_type: AssetType,
-        description: String,
-        price_type: PriceType,
-        min_publishers: u8,
-        exponent: i32,
-    ) -> Result<()> {
-        require!(!ctx.accounts.global_state.paused, ErrorCode::SystemPaused);
+#[event]
+pub struct PublisherDisplaced {
+    pub product: Pubkey,
+    pub displaced: Pubkey,
+    pub displaced_by: Pubkey,
+    pub slot_index: u8,
+}
 
-        let product = &mut ctx.accounts.product_account;
-        product.symbol = symbol.clone();
-        product.asset_type = asset_type;
-        product.description = description;
-        product.price_account = ctx.accounts.price_account.key();
-        product.authority = ctx.accounts.authority.key();
-        product.bump = ctx.bumps.product_account;
+#[event]
+pub struct StakeReceiptsMinted {
+    pub publisher: Pubkey,
+    pub amount: u64,
+    pub receipt_balance: u64,
+}
 
-        let price_account = &mut ctx.accounts.price_account;
-        price_account.product_account = ctx.accounts.product_account.key();
-        price_account.price_type = price_type;
-        price_account.aggregate = PriceData::default();
-        price_account.publishers = [PublisherPrice::default(); MAX_PUBLISHERS];
-        price_account.publisher_count = 0;
-        price_account.min_publishers = min_publishers;
-        price_account.last_update_slot = 0;
-        price_account.ema = EmaData::default();
-        price_account.authority = ctx.accounts.authority.key();
-        price_account.exponent = exponent;
-        price_account.bump = ctx.bumps.price_account;
+#[event]
+pub struct StakeReceiptsBurned {
+    pub publisher: Pubkey,
+    pub amount: u64,
+    pub receipt_balance: u64,
+}
 
-        ctx.accounts.global_state.total_products += 1;
+#[event]
+pub struct StakePositionLocked {
+    pub publisher: Pubkey,
+    pub position_mint: Pubkey,
+    pub amount: u64,
+    pub tier: LockTier,
+    pub unlock_at: i64,
+    pub locked_stake: u64,
+}
 
-        Ok(())
+#[event]
+pub struct StakePositionUnlocked {
+    pub publisher: Pubkey,
+    pub position_mint: Pubkey,
+    pub amount: u64,
+    pub locked_stake: u64,
+}
+
+#[event]
+pub struct StakePositionRedeemed {
+    pub publisher: Pubkey,
+    pub position_mint: Pubkey,
+    pub holder: Pubkey,
+    pub amount: u64, // Paid to the holder
+    pub staked_amount: u64,
+    pub locked_stake: u64,
+}
+
+#[event]
+pub struct QuoteCosignerUpdated {
+    pub publisher: Pubkey,
+    pub cosigner: Option<Pubkey>,
+}
+
+#[event]
+pub struct PublisherIdentityBound {
+    pub publisher: Pubkey,
+    pub kind: IdentityKind,
+    pub identifier: String,
+    pub proof_hash: [u8; 32],
+}
+
+#[event]
+pub struct PublisherIdentityAttested {
+    pub publisher: Pubkey,
+    pub operator_id: u64,
+    pub proposal_id: u64,
+}
+
+#[event]
+pub struct PublisherIdentityUnbound {
+    pub publisher: Pubkey,
+    pub identifier: String,
+}
+
+#[event]
+pub struct FeedCollateralLocked {
+    pub price_account: Pubkey,
+    pub creator: Pubkey,
+    pub risk_tier: RiskTier,
+    pub amount: u64,
+}
+
+#[event]
+pub struct FeedCollateralDrawn {
+    pub price_account: Pubkey,
+    pub proposal_id: u64,
+    pub amount: u64, // Moved into the insurance fund
+    pub remaining: u64,
+}
+
+#[event]
+pub struct FeedRetirementAnnounced {
+    pub price_account: Pubkey,
+    pub retire_after: i64,
+}
+
+#[event]
+pub struct FeedRetired {
+    pub price_account: Pubkey,
+    pub collateral_returned: u64,
+}
+
+#[event]
+pub struct FeedUptimeRecorded {
+    pub price_account: Pubkey,
+    pub window_start_slot: u64,
+    pub uptime_bps: u16,
+}
+
+#[event]
+pub struct RoundArchived {
+    pub price_account: Pubkey,
+    pub round: Pubkey,
+    pub slot: u64,
+    pub quote_count: u8,
+}
+
+/// One stored value that disagrees with the feed's configured scale. `index` is the
+/// publisher slot for quote discrepancies and 0 otherwise.
+#[event]
+pub struct ScalingDiscrepancy {
+    pub price_account: Pubkey,
+    pub field: ScalingField,
+    pub index: u8,
+    pub expected: i128,
+    pub found: i128,
+}
+
+#[event]
+pub struct ScalingAudited {
+    pub price_account: Pubkey,
+    pub exponent: i32,
+    pub discrepancies: u8,
+}
+
+/// The archived aggregate is kept; the re-aggregated value is recorded next to it
+#[event]
+pub struct RoundDisputed {
+    pub price_account: Pubkey,
+    pub round: Pubkey,
+    pub proposal_id: u64,
+    pub original_price: i64,
+    pub reaggregated_price: i64,
+    pub reaggregated_status: PriceStatus,
+    pub excluded_publisher: Option<Pubkey>,
+}
+
+/// Emitted every aggregation so past roots stay available after the account moves on
+#[event]
+pub struct QuotesRootCommitted {
+    pub product: Pubkey,
+    pub slot: u64,
+    pub root: [u8; 32],
+    pub quote_count: u8,
+}
+
+#[event]
+pub struct FuturesRolled {
+    pub price_account: Pubkey,
+    pub previous_contract: String,
+    pub active_contract: String,
+    pub expiry: i64,
+    pub roll_adjustment: u64,
+    pub slot: u64,
+}
+
+#[event]
+pub struct FastPublishersUpdated {
+    pub price_account: Pubkey,
+    pub members: Vec<Pubkey>,
+    pub min_publishers: u8,
+}
+
+#[event]
+pub struct OptimisticPricePosted {
+    pub product: Pubkey,
+    pub poster: Pubkey,
+    pub price: i64,
+    pub confidence: u64,
+    pub slot: u64,
+}
+
+/// The challenger's bond is locked and the feed stays halted until the dispute is resolved
+#[event]
+pub struct OptimisticPriceChallenged {
+    pub product: Pubkey,
+    pub poster: Pubkey,
+    pub challenger: Pubkey,
+    pub posted_price: i64,
+    pub posted_confidence: u64,
+    pub challenge_price: i64,
+    pub challenge_confidence: u64,
+    pub posted_slot: u64,
+}
+
+#[event]
+pub struct OptimisticDisputeResolved {
+    pub product: Pubkey,
+    pub challenger: Pubkey,
+    pub challenge_upheld: bool,
+    pub bond_slashed: u64, // Moved into the insurance fund
+}
+
+#[event]
+pub struct PriceBatchSubmitted {
+    pub publisher: Pubkey,
+    pub root: [u8; 32],
+    pub slot: u64,
+    pub sequence: u64,
+    pub observed_timestamp: i64,
+    pub posted_timestamp: i64,
+}
+
+#[event]
+pub struct QuoteCommitted {
+    pub price_account: Pubkey,
+    pub publisher: Pubkey,
+    pub commitment: [u8; 32],
+    pub slot: u64,
+}
+
+#[event]
+pub struct FeedAdminUpdated {
+    pub price_account: Pubkey,
+    pub operator: Pubkey,
+    pub permissions: u8, // 0 when revoked
+}
+
+#[event]
+pub struct PublisherPermissionUpdated {
+    pub price_account: Pubkey,
+    pub publisher: Pubkey,
+    pub permitted: bool,
+}
+
+#[event]
+pub struct CrossRateDivergence {
+    pub direct_feed: Pubkey,
+    pub base_feed: Pubkey,
+    pub quote_feed: Pubkey,
+    pub implied_price: i64,
+    pub direct_price: i64,
+    pub deviation_bps: u64,
+    pub tolerance_bps: u16,
+    pub slot: u64,
+}
+
+#[event]
+pub struct FeedSlaUpdated {
+    pub feed: Pubkey,
+    pub max_update_interval_slots: u64,
+    pub max_confidence_bps: u16,
+    pub rebate_bps: u16,
+    pub proposal_id: u64,
+}
+
+#[event]
+pub struct FeedQuotaUpdated {
+    pub feed: Pubkey,
+    pub min_submissions: u32,
+    pub max_submissions: u32,
+    pub proposal_id: u64,
+}
+
+#[event]
+pub struct QuotaEpochClosed {
+    pub feed: Pubkey,
+    pub epoch: u64,
+    pub below_minimum: u128, // Bitmap of `publishers` indices that submitted fewer than min_submissions
+}
+
+#[event]
+pub struct SlaBreachRecorded {
+    pub feed: Pubkey,
+    pub kind: SlaBreachKind,
+    pub breach_count: u64,
+    pub rebate_amount: u64,
+    pub slot: u64,
+}
+
+#[event]
+pub struct Subscribed {
+    pub consumer: Pubkey,
+    pub feed: Pubkey,
+    pub fee: u64,
+    pub fees_paid: u64,
+}
+
+#[event]
+pub struct RebateClaimed {
+    pub consumer: Pubkey,
+    pub feed: Pubkey,
+    pub amount: u64,
+}
+
+#[event]
+pub struct InsurancePremiumPaid {
+    pub consumer: Pubkey,
+    pub feed: Pubkey,
+    pub epoch: u64,
+    pub premium: u64,
+    pub coverage: u64,
+}
+
+#[event]
+pub struct InsuranceClaimed {
+    pub consumer: Pubkey,
+    pub feed: Pubkey,
+    pub epoch: u64,
+    pub amount: u64,
+}
+
+#[event]
+pub struct VaultDiscrepancy {
+    pub expected: u64,
+    pub actual: u64,
+    pub drift: i64, // actual - expected
+    pub slot: u64,
+}
+
+#[event]
+pub struct ProgramUpgraded {
+    pub proposal_id: u64,
+    pub buffer: Pubkey,
+    pub slot: u64,
+}
+
+#[event]
+pub struct UpgradeAuthorityChanged {
+    pub previous_authority: Pubkey,
+    pub new_authority: Option<Pubkey>,
+}
+
+#[event]
+pub struct RewardFeePaid {
+    pub consumer: Pubkey,
+    pub feed: Pubkey,
+    pub fee: u64,
+    pub reward_pool: u64,
+}
+
+#[event]
+pub struct RewardMintChanged {
+    pub proposal_id: u64,
+    pub reward_mint: Pubkey,
+}
+
+#[event]
+pub struct TokenMintMigrated {
+    pub proposal_id: u64,
+    pub previous_mint: Pubkey,
+    pub new_mint: Pubkey,
+    pub previous_token_account: Pubkey,
+    pub new_token_account: Pubkey,
+    pub counterparty: Pubkey,
+    pub amount: u64, // Old-mint balance swapped 1:1 for the new mint
+    pub swap_reserve: u64, // New-mint tokens left for holders to swap into
+    pub slot: u64,
+}
+
+#[event]
+pub struct MigratedTokensSwapped {
+    pub holder: Pubkey,
+    pub previous_mint: Pubkey,
+    pub new_mint: Pubkey,
+    pub amount: u64,
+    pub swap_reserve: u64, // Left after this swap
+    pub slot: u64,
+}
+
+#[event]
+pub struct VaultRotated {
+    pub proposal_id: u64,
+    pub custody_version: u8,
+    pub previous_authority: Pubkey,
+    pub previous_token_account: Pubkey,
+    pub new_authority: Pubkey,
+    pub new_token_account: Pubkey,
+    pub amount: u64, // Balance moved out of the previous token account
+    pub received: u64, // Less than `amount` for mints with transfer fees
+    pub slot: u64,
+}
+
+#[event]
+pub struct VaultDriftResolved {
+    pub proposal_id: u64,
+    pub resolved: i64,
+    pub remaining_drift: i64,
+}
+
+#[event]
+pub struct EpochAdvanced {
+    pub epoch: u64,
+    pub start_slot: u64,
+    pub stake_snapshot: u64,
+    pub total_rewards_distributed: u64,
+}
+
+#[event]
+pub struct SystemPaused {
+    pub timestamp: i64,
+    pub authority: Pubkey,
+}
+
+#[event]
+pub struct SystemUnpaused {
+    pub timestamp: i64,
+    pub authority: Pubkey,
+}
+
+#[event]
+pub struct MockClockAdvanced {
+    pub offset: i64, // Seconds ahead of the cluster clock in total
+    pub timestamp: i64, // Program clock after the advance
+}
+
+// ============================================================================
+// Accounts
+// ============================================================================
+
+#[account]
+pub struct GlobalState {
+    pub authority: Pubkey,
+    pub token_mint: Pubkey,
+    pub token_vault: Pubkey,
+    pub vault_authority: Pubkey,
+    pub governance: Pubkey,
+    pub paused: bool,
+    pub total_products: u64,
+    pub total_publishers: u64,
+    pub stake_per_feed: u64, // Additional stake required per feed a publisher writes to (0 = disabled)
+    pub max_new_publishers_per_epoch: u32,
+    pub current_epoch: u64, // Program epoch, advanced by the advance_epoch crank
+    pub epoch_start_slot: u64,
+    pub epoch_stake_snapshot: u64, // TokenVault::total_staked when the epoch began
+    pub registrations_this_epoch: u32,
+    pub registration_allowance: u32, // Governance-approved registrations beyond the per-epoch limit
+    pub unbonding_period: i64,
+    pub min_stake: u64,
+    pub previous_min_stake: u64, // Floor for grandfathered publishers: the minimum in force before the current grace period
+    pub min_stake_raised_epoch: u64,
+    pub receipt_mint: Pubkey, // Stake receipt token (default = not created)
+    pub receipts_transferable: bool, // Receipt token accounts are kept frozen while false
+    pub positions_transferable: bool, // Stake position NFTs are kept frozen while false
+    pub pending_stake_per_feed: PendingParam,
+    pub pending_registration_limit: PendingParam,
+    pub pending_unbonding_period: PendingParam,
+    pub pending_min_stake: PendingParam,
+    pub listing_collateral: u64, // Collateral per RiskTier::collateral_multiple locked when a feed is listed
+    pub mock_time_offset: i64, // Seconds `program_clock` runs ahead of the cluster clock; mock-clock builds only
+    pub version: u8,
+    pub bump: u8,
+    pub vault_authority_bump: u8,
+    pub custody_version: u8, // Vault rotations so far; 0 = tokens held by the original vault authority
+    pub custody_bump: u8,
+}
+
+impl GlobalState {
+    /// Version seed of the authority holding the vault's tokens. The original vault
+    /// authority has none, so it stays `[b"vault_authority"]`; the receipt and position
+    /// mints keep that PDA as their authority across rotations.
+    pub fn custody_seed(&self) -> &[u8] {
+        if self.custody_version == 0 {
+            &[]
+        } else {
+            std::slice::from_ref(&self.custody_version)
+        }
     }
 
-    pub fn add_publisher(
-        ctx: Context<AddPublisher>,
-        name: String,
-        initial_stake: u64,
-    ) -> Result<()> {
-        require!(!ctx.accounts.global_state.paused, ErrorCode::SystemPaused);
-        require!(
-            initial_stake >= MIN_STAKE_AMOUNT,
-            ErrorCode::InsufficientStake
-        );
+    pub fn custody_signer_seeds(&self) -> [&[u8]; 3] {
+        [b"vault_authority", self.custody_seed(), std::slice::from_ref(&self.custody_bump)]
+    }
+}
 
-        // Transfer stake to vault using vault authority
-        let cpi_accounts = Transfer {
-            from: ctx.accounts.publisher_token_account.to_account_info(),
-            to: ctx.accounts.vault_token_account.to_account_info(),
-            authority: ctx.accounts.publisher_authority.to_account_info(),
-        };
-        let cpi_program = ctx.accounts.token_program.to_account_info();
-        let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts);
-        token::transfer(cpi_ctx, initial_stake)?;
+/// Which cluster this deployment belongs to. Clients check it against the RPC's genesis
+/// hash, and transactions can start with `assert_cluster` so that one built for another
+/// deployment fails instead of acting on this one.
+#[account]
+pub struct ClusterConfig {
+    pub environment: ClusterEnvironment,
+    pub token_mint: Pubkey, // Canonical staking and governance mint of this deployment
+    pub genesis_hash: [u8; 32],
+    pub label: String, // Free-form deployment name, e.g. "mainnet-v2"
+    pub program_version: u8, // PROGRAM_VERSION when the config was written
+    pub created_at: i64,
+    pub bump: u8,
+}
 
-        let publisher = &mut ctx.accounts.publisher_account;
-        publisher.authority = ctx.accounts.publisher_authority.key();
-        publisher.staked_amount = initial_stake;
-        publisher.stake_account = ctx.accounts.publisher_token_account.key();
-        publisher.reputation = 100;
-        publisher.name = name.clone();
-        publisher.registered_at = Clock::get()?.unix_timestamp;
-        publisher.slash_count = 0;
-        publisher.last_slash_slot = 0;
-        publisher.unbonding_amount = 0;
-        publisher.unbonding_start = 0;
-        publisher.bump = ctx.bumps.publisher_account;
+#[account]
+pub struct ProductAccount {
+    pub symbol: String,
+    pub asset_type: AssetType,
+    pub description: String,
+    pub price_account: Pubkey,
+    pub authority: Pubkey,
+    pub base_currency: String, // From "BASE/QUOTE" symbols, empty otherwise
+    pub quote_currency: String,
+    pub calendar: TradingCalendar,
+    pub bump: u8,
+}
+
+/// Zero-copy: accessed through `AccountLoader`, so price updates touch the fields they
+/// use instead of (de)serializing the whole publisher array. Fields are ordered by
+/// alignment with explicit padding, and enums and flags are stored as raw bytes behind
+/// checked accessors, so every bit pattern is a valid account. This is a different
+/// layout from the borsh one used before; `migrate_price_account` converts old feeds.
+#[account(zero_copy(unsafe))]
+#[repr(C)]
+pub struct PriceAccount {
+    pub product_account: Pubkey,
+    pub authority: Pubkey,
+    pub aggregate: PriceData,
+    pub sampled_publishers: u128, // Bitmap of `publishers` indices in the last sample
+    pub included_publishers: u128, // Bitmap of `publishers` indices whose quotes made the last aggregate
+    pub price_cumulative: i128, // Sum of the aggregate price times the seconds it stood, up to `cumulative_timestamp`
+    pub quotes_root: [u8; 32], // Merkle root of the quotes considered in the last aggregation
+    pub publishers: [PublisherPrice; MAX_PUBLISHERS], // Fixed-size array
+    pub ema: EmaData,
+    pub pending_min_publishers: PendingParam,
+    pub last_update_slot: u64,
+    pub confidence_stake_scale: u64, // Minimum confidence at DEFAULT_MIN_STAKE_AMOUNT, shrinking as stake grows (0 = disabled)
+    pub staleness_threshold: i64, // Seconds before a quote is ignored (0 = STALENESS_THRESHOLD)
+    pub sample_slot: u64, // Slot whose hash seeded the last sample (0 = not sampled)
+    pub roll_adjustment: u64, // Futures continuity factor applied to quotes, at ROLL_ADJUSTMENT_SCALE (0 = none)
+    pub min_slots_between_aggregations: u64, // Quotes within this many slots of the last aggregation don't trigger another
+    pub last_aggregation_slot: u64,
+    pub emergency_halt_until_slot: u64, // Set by a publisher supermajority; lapses unless governance freezes the feed
+    pub volatility_until_slot: u64, // End of a governance-declared volatility regime
+    pub cumulative_timestamp: i64, // When `price_cumulative` was last advanced (0 = never)
+    pub exponent: i32,
+    pub ema_alpha: u32, // Weight of a new observation in the EMA, in millionths (0 = EMA_ALPHA_SCALED)
+    pub volatility_widening_bps: u32, // Factor on the outlier band, confidence cap and halt threshold during it
+    pub top_source_share_bps: u16, // Stake share of the most common source id in the last aggregation
+    pub degraded_confidence_bps: u16, // Confidence/price above this reports Degraded (0 = disabled)
+    pub price_type: u8, // PriceType
+    pub publisher_count: u8,
+    pub min_publishers: u8,
+    pub min_operators: u8, // Distinct identity-linked operators required to publish (0 = no check)
+    pub distinct_sources: u8, // Distinct source ids among quotes in the last aggregation
+    pub sample_size: u8, // Quotes aggregated per round when more are valid (0 = all)
+    pub deviation_alert_mads: u8, // Outliers more than this many MADs from the median emit QuoteDeviationAlert (0 = off)
+    pub rounding_mode: u8, // RoundingMode
+    pub confidence_mode: u8, // ConfidenceMode: unit of submitted confidences
+    pub frozen: u8,
+    pub halted: u8, // Operational halt set by the feed authority or a FeedAdmin
+    pub high_precision: u8, // Also publish PriceData::price_i128
+    pub commit_reveal: u8, // Quotes must be committed a slot before they are revealed
+    pub optimistic: u8, // Aggregate written by an OptimisticFeed poster instead of aggregation
+    pub derived: u8, // Aggregate written by update_inverse_feed instead of aggregation
+    pub ema_confidence_weighted: u8, // Observations less precise than the EMA get proportionally less weight
+    pub submission_quota: u8, // Quotes must pass the feed's FeedQuota
+    pub permissioned: u8, // Only publishers with a FeedPermission may quote
+    pub bump: u8,
+    pub _padding: [u8; 13], // Room for new one-byte fields before the size must grow
+}
+
+impl PriceAccount {
+    /// Whether the feed is frozen by governance, halted by its authority or under a
+    /// publisher emergency halt at `slot`
+    pub fn is_halted(&self, slot: u64) -> bool {
+        self.frozen() || self.halted() || slot < self.emergency_halt_until_slot
+    }
+
+    pub fn price_type(&self) -> Result<PriceType> {
+        PriceType::try_from(self.price_type)
+    }
+
+    pub fn rounding_mode(&self) -> Result<RoundingMode> {
+        RoundingMode::try_from(self.rounding_mode)
+    }
+
+    pub fn confidence_mode(&self) -> Result<ConfidenceMode> {
+        ConfidenceMode::try_from(self.confidence_mode)
+    }
+
+    pub fn frozen(&self) -> bool {
+        self.frozen != 0
+    }
+
+    pub fn halted(&self) -> bool {
+        self.halted != 0
+    }
+
+    pub fn high_precision(&self) -> bool {
+        self.high_precision != 0
+    }
+
+    pub fn commit_reveal(&self) -> bool {
+        self.commit_reveal != 0
+    }
+
+    pub fn optimistic(&self) -> bool {
+        self.optimistic != 0
+    }
+
+    pub fn derived(&self) -> bool {
+        self.derived != 0
+    }
+
+    pub fn ema_confidence_weighted(&self) -> bool {
+        self.ema_confidence_weighted != 0
+    }
+
+    pub fn submission_quota(&self) -> bool {
+        self.submission_quota != 0
+    }
+
+    pub fn permissioned(&self) -> bool {
+        self.permissioned != 0
+    }
+
+    /// `price_cumulative` as of `now`, counting the current aggregate since it was last advanced
+    pub fn price_cumulative_at(&self, now: i64) -> i128 {
+        if self.cumulative_timestamp == 0 || now <= self.cumulative_timestamp {
+            return self.price_cumulative;
+        }
+        self.price_cumulative + self.aggregate.price as i128 * (now - self.cumulative_timestamp) as i128
+    }
+
+    /// Advances `price_cumulative` to `now`; called before each new aggregate is written
+    fn accrue_cumulative(&mut self, now: i64) {
+        self.price_cumulative = self.price_cumulative_at(now);
+        self.cumulative_timestamp = self.cumulative_timestamp.max(now);
+    }
+}
+
+/// Publisher votes towards an emergency halt of a feed. Votes count towards one round of
+/// at most HALT_VOTE_WINDOW_SLOTS and are weighted by each publisher's stake in the feed.
+#[account]
+pub struct FeedHaltVote {
+    pub price_account: Pubkey,
+    pub round_start_slot: u64,
+    pub voters: u128, // Bitmap of `publishers` indices that voted this round
+    pub last_halt_slot: u64, // Slot of the last halt these votes triggered (0 = never)
+    pub bump: u8,
+}
+
+#[account]
+pub struct PublisherAccount {
+    pub authority: Pubkey,
+    pub staked_amount: u64,
+    pub stake_account: Pubkey,
+    pub reputation: u64,
+    pub name: String,
+    pub registered_at: i64,
+    pub slash_count: u32,
+    pub last_slash_slot: u64,
+    pub stake_state: StakeState,
+    pub feed_count: u32, // Number of feeds this publisher writes to
+    pub registered_epoch: u64,
+    pub probation_end_epoch: u64,
+    pub pending_rewards: u64,
+    pub last_reward_slot: u64,
+    pub reward_index: u128, // TokenVault::reward_index_at its last accrual
+    pub rewards_frozen_until_slot: u64, // Set on slash; no accrual before this slot
+    pub insurance_share_bps: u16, // Share of claimed rewards paid into the insurance fund
+    pub insurance_contributed: u64,
+    pub insurance_claimed: u64, // Slash amounts already covered by insurance
+    pub quote_cosigner: Pubkey, // Second key that must sign every quote (default = none)
+    pub receipt_balance: u64, // Stake receipts minted and not yet burned
+    pub last_active_epoch: u64, // Epoch of the last quote on any feed
+    pub reward_vesting: RewardVesting,
+    pub operator_id: u64, // Derived from the bound identity once governance attests it (0 = none)
+    pub locked_stake: u64, // Part of `staked_amount` held by open stake positions and optimistic challenge bonds
+    pub position_count: u32, // Stake positions ever opened, seeding the next position mint
+    pub publishing_blocked_until_slot: u64, // Set on slash; no quotes before this slot
+    pub registered_min_stake: u64, // GlobalState::min_stake when the publisher registered
+    pub bump: u8,
+}
+
+#[account]
+pub struct TokenVault {
+    pub total_staked: u64,
+    pub total_rewards_distributed: u64,
+    pub reward_rate: u64, // Reward tokens per slot, shared pro-rata by stake
+    pub last_distribution_slot: u64, // Slot `reward_index` is settled up to
+    pub reward_index: u128, // Rewards per staked token since deployment, at REWARD_INDEX_SCALE
+    pub reward_pool: u64, // Funded rewards not yet claimed, in the reward mint
+    pub treasury: u64, // Subscription fees available to the protocol
+    pub pending_rebates: u64, // Treasury funds set aside for consumer SLA rebates
+    pub unreconciled_drift: i64, // Vault balance minus tracked liabilities at the last reconcile_vault
+    pub insurance_fund: u64, // Publisher contributions and consumer premiums backing coverage
+    pub insurance_coverage: u64, // Unclaimed publisher coverage across all publishers
+    pub total_conviction_locked: u64,
+    pub total_veto_stake: u64,
+    pub pending_reward_rate: PendingParam,
+    pub token_mint: Pubkey,
+    pub vault_token_account: Pubkey,
+    pub vault_authority: Pubkey,
+    pub authority: Pubkey,
+    pub reward_mint: Pubkey, // Mint rewards are funded and paid in (default = the staking mint)
+    pub reward_vault_token_account: Pubkey, // Vault authority's account for a separate reward mint
+    pub reward_mint_slot: u64, // Slot the reward mint last changed; older pending rewards lapse
+    pub reward_mint_index: u128, // `reward_index` when the reward mint last changed
+    pub reward_vesting_period: i64, // Seconds claimed rewards vest over (0 = paid out immediately)
+    pub vesting_rewards: u64, // Claimed rewards held back by vesting schedules, in the reward mint
+    pub listing_collateral: u64, // Locked by feed creators across all FeedCollateral accounts
+    pub previous_token_mint: Pubkey, // Mint holders may swap from after MigrateTokenMint (default = none)
+    pub migration_counterparty: Pubkey, // Old-mint account that receives swapped tokens
+    pub swap_reserve: u64, // New-mint tokens set aside for holder swaps
+    pub bump: u8,
+}
+
+impl TokenVault {
+    /// `reward_index` as of `slot`, counting `reward_rate` since it was last settled
+    pub fn reward_index_at(&self, slot: u64) -> u128 {
+        if self.total_staked == 0 {
+            return self.reward_index;
+        }
+        let elapsed = slot.saturating_sub(self.last_distribution_slot) as u128;
+        self.reward_index.saturating_add(
+            (self.reward_rate as u128).saturating_mul(elapsed).saturating_mul(REWARD_INDEX_SCALE)
+                / self.total_staked as u128,
+        )
+    }
+
+    /// Settles `reward_index` up to `slot`; done when an epoch closes and before
+    /// `reward_rate` changes, so a new rate never applies to slots already past
+    fn settle_reward_index(&mut self, slot: u64) {
+        self.reward_index = self.reward_index_at(slot);
+        self.last_distribution_slot = self.last_distribution_slot.max(slot);
+    }
+
+    /// Mint `reward_pool`, `reward_rate` and `total_rewards_distributed` are denominated in
+    pub fn payout_mint(&self) -> Pubkey {
+        if self.has_separate_reward_mint() {
+            self.reward_mint
+        } else {
+            self.token_mint
+        }
+    }
+
+    /// Token account rewards are funded into and claimed from
+    pub fn payout_vault(&self) -> Pubkey {
+        if self.has_separate_reward_mint() {
+            self.reward_vault_token_account
+        } else {
+            self.vault_token_account
+        }
+    }
+
+    pub fn has_separate_reward_mint(&self) -> bool {
+        self.reward_mint != Pubkey::default()
+    }
+}
+
+#[account]
+pub struct GovernanceState {
+    pub governance_token: Pubkey,
+    pub proposal_threshold: u64,
+    pub voting_period: u64,
+    pub quorum_percentage: u8,
+    pub timelock_duration: u64,
+    pub proposal_count: u64,
+    pub total_supply: u64, // Store total supply for quorum calculation
+    pub conviction_threshold_percentage: u8,
+    pub optimistic_challenge_period: u64,
+    pub optimistic_veto_stake: u64,
+    pub optimistic_change_count: u64,
+    pub emergency_voting_period: u64,
+    pub emergency_quorum_percentage: u8,
+    pub severity_threshold_bps: [u32; 3], // Proposal threshold multiplier per ProposalSeverity
+    pub authority: Pubkey,
+    pub bump: u8,
+}
+
+impl GovernanceState {
+    /// Token balance a proposer needs to open a proposal of the given severity
+    pub fn required_proposer_balance(&self, severity: ProposalSeverity) -> u64 {
+        let multiplier_bps = self.severity_threshold_bps[severity as usize];
+        (self.proposal_threshold as u128 * multiplier_bps as u128 / 10_000)
+            .min(u64::MAX as u128) as u64
+    }
+}
+
+#[account]
+pub struct Proposal {
+    pub proposer: Pubkey,
+    pub proposal_type: ProposalType,
+    pub description: String,
+    pub yes_votes: u64,
+    pub no_votes: u64,
+    pub abstain_votes: u64,
+    pub start_slot: u64,
+    pub end_slot: u64,
+    pub executed: bool,
+    pub execution_time: i64,
+    pub proposal_id: u64,
+    pub mode: ProposalMode,
+    pub conviction_staked: u64,
+    pub conviction: u64,
+    pub conviction_updated_slot: u64,
+    pub linked_hashes: Vec<[u8; 32]>, // Amendment/comment content hashes, frozen at start_slot
+    pub action_executed: bool, // Set once the action has run; each proposal's action runs once
+    pub bump: u8,
+}
+
+/// What the execution of a proposal's action changed. `before` and `after` are borsh
+/// encodings of the parameters the action touches, in the order listed in `action_snapshot`.
+#[account]
+pub struct ExecutionReceipt {
+    pub proposal: Pubkey,
+    pub proposal_id: u64,
+    pub before: Vec<u8>,
+    pub after: Vec<u8>,
+    pub slot: u64,
+    pub executed_at: i64,
+    pub executor: Pubkey,
+    pub bump: u8,
+}
+
+/// Checks that base/quote implies a cross rate consistent with an independently published direct feed
+#[account]
+pub struct CrossRateGuard {
+    pub base_feed: Pubkey,
+    pub quote_feed: Pubkey,
+    pub direct_feed: Pubkey,
+    pub tolerance_bps: u16,
+    pub last_implied_price: i64,
+    pub last_direct_price: i64,
+    pub last_deviation_bps: u64,
+    pub diverged: bool,
+    pub last_checked_slot: u64,
+    pub authority: Pubkey,
+    pub bump: u8,
+}
+
+/// Governance-set service levels for a premium feed and the breaches recorded against them
+#[account]
+pub struct FeedSla {
+    pub feed: Pubkey,
+    pub max_update_interval_slots: u64, // 0 = no SLA
+    pub max_confidence_bps: u16, // 0 = no confidence bound
+    pub breach_count: u64,
+    pub last_breach_slot: u64,
+    pub rebate_bps: u16, // Share of subscriber fees credited back per breach
+    pub total_subscribed: u64, // Fees paid by all subscribers, the pro-rata base for rebates
+    pub rebate_index_bps: u64, // Cumulative rebate per unit of fees paid, in bps
+    pub bump: u8,
+}
+
+/// Governance-set bounds on how many quotes each publisher submits to a feed per program
+/// epoch. Counts are indexed like `PriceAccount::publishers` and reset when the epoch advances.
+#[account]
+pub struct FeedQuota {
+    pub price_account: Pubkey,
+    pub min_submissions: u32, // Publishers below this when an epoch closes are reported (0 = none)
+    pub max_submissions: u32, // Quotes beyond this are dropped (0 = unlimited)
+    pub epoch: u64, // Program epoch the counts belong to
+    pub submissions: [u32; MAX_PUBLISHERS],
+    pub bump: u8,
+}
+
+/// A consumer's paid subscription to a feed with an SLA
+#[account]
+pub struct Subscription {
+    pub consumer: Pubkey,
+    pub feed: Pubkey,
+    pub fees_paid: u64,
+    pub rebate_index_bps: u64, // FeedSla::rebate_index_bps at the last settlement
+    pub credits: u64, // Settled, unclaimed rebates
+    pub rebates_earned: u64, // All rebates ever settled, capped at fees_paid
+    pub premium_epoch: u64, // Program epoch the premium covers
+    pub premium: u64, // Paid into the insurance fund for premium_epoch
+    pub premium_breach_count: u64, // FeedSla::breach_count when the premium was paid
+    pub coverage_claimed: u64, // Paid out of premium_epoch's coverage
+    pub bump: u8,
+}
+
+#[account]
+pub struct OptimisticChange {
+    pub proposer: Pubkey,
+    pub change: OptimisticParam,
+    pub created_slot: u64,
+    pub challenge_end_slot: u64,
+    pub status: OptimisticStatus,
+    pub vetoer: Pubkey,
+    pub veto_stake: u64,
+    pub change_id: u64,
+    pub bump: u8,
+}
+
+/// Lamports a feed owner sets aside to reimburse keepers who aggregate or prune the feed.
+/// The balance above rent exemption is the available budget.
+#[account]
+pub struct CrankBudget {
+    pub price_account: Pubkey,
+    pub owner: Pubkey,
+    pub reward_lamports: u64, // Paid per useful crank
+    pub min_interval_slots: u64, // At most one reimbursement per interval
+    pub last_paid_slot: u64,
+    pub total_paid: u64,
+    pub bump: u8,
+}
+
+/// Links a publisher to the off-chain entity operating it. The proof (TXT record or DID
+/// document) should name the publisher authority so the link cannot be claimed by others.
+#[account]
+pub struct PublisherIdentity {
+    pub publisher: Pubkey, // PublisherAccount
+    pub kind: IdentityKind,
+    pub identifier: String,
+    pub proof_hash: [u8; 32], // sha256 of the published proof
+    pub bound_at: i64,
+    pub bump: u8,
+}
+
+/// Low-latency companion to a PriceAccount, quoted every slot by a small set of publishers
+/// chosen by the feed authority. The main account stays the canonical stake-weighted value.
+#[account]
+pub struct FastPriceAccount {
+    pub price_account: Pubkey,
+    pub aggregate: PriceData,
+    pub members: [Pubkey; MAX_FAST_PUBLISHERS],
+    pub quotes: [PublisherPrice; MAX_FAST_PUBLISHERS], // quotes[i] belongs to members[i]
+    pub member_count: u8,
+    pub min_publishers: u8,
+    pub bump: u8,
+}
+
+/// A feed's state at the slot a consuming program acted on it, written by
+/// `attest_freshness` into an account the caller provides
+#[account]
+pub struct FreshnessAttestation {
+    pub feed: Pubkey,
+    pub slot: u64,
+    pub timestamp: i64,
+    pub status: PriceStatus, // Halted while the feed is frozen or halted, else the aggregate's
+    pub publish_time: i64, // Timestamp of the aggregate the status describes
+}
+
+/// Quotes behind one aggregation of a feed, kept so governance can dispute the round
+/// and re-aggregate it. PDA per feed and aggregate slot.
+#[account]
+pub struct RoundArchive {
+    pub price_account: Pubkey,
+    pub original: PriceData, // Aggregate as published
+    pub quotes: Vec<PublisherPrice>, // Quotes that were aggregated (the sample, on sampled feeds)
+    pub quotes_root: [u8; 32], // Root over all candidate quotes, as committed by the feed
+    pub disputed: bool,
+    pub reaggregated: Option<PriceData>, // Set when disputed
+    pub excluded_publisher: Pubkey, // Publisher left out of the re-aggregation (default = none)
+    pub included: u128, // Bit i set when quotes[i] made the aggregate; the rest were filtered as outliers
+    pub strategy: AggregationStrategy,
+    pub bump: u8,
+}
+
+impl RoundArchive {
+    pub fn space(quote_count: usize) -> usize {
+        8 + 32 + 128 + 4 + quote_count * 96 + 32 + 1 + (1 + 128) + 32 + 16 + 1 + 1
+    }
+}
+
+/// Fixed-layout copy of a feed's aggregate for programs that read accounts without Anchor
+/// or Borsh. `packed` starts at PRICE_MIRROR_PACKED_OFFSET; see `pack_price` for the layout.
+#[account]
+pub struct PriceMirror {
+    pub price_account: Pubkey,
+    pub packed: [u8; PACKED_PRICE_LEN],
+    pub bump: u8,
+}
+
+/// One aggregate as recorded in a PriceHistoryAccount, at the feed's exponent
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default)]
+#[repr(C)]
+pub struct PriceObservation {
+    pub price: i64,
+    pub confidence: u64,
+    pub slot: u64,
+    pub timestamp: i64,
+    pub price_cumulative: i128, // The feed's `price_cumulative` at `timestamp`
+}
+
+/// Circular buffer of a feed's last PRICE_HISTORY_LEN aggregates, appended by aggregation
+/// whenever it is passed. Aggregations in the same slot replace each other, so each
+/// observation is from a distinct slot. Zero-copy like the price account it follows.
+#[account(zero_copy(unsafe))]
+#[repr(C)]
+pub struct PriceHistoryAccount {
+    pub product_account: Pubkey,
+    pub price_account: Pubkey,
+    pub observations: [PriceObservation; PRICE_HISTORY_LEN],
+    pub total_observations: u64, // Ever appended; the next goes to `total_observations % PRICE_HISTORY_LEN`
+    pub bump: u8,
+    pub _padding: [u8; 7],
+}
+
+impl PriceHistoryAccount {
+    fn record(&mut self, price_account: &PriceAccount) {
+        let aggregate = &price_account.aggregate;
+        let observation = PriceObservation {
+            price: aggregate.price,
+            confidence: aggregate.confidence,
+            slot: aggregate.slot,
+            timestamp: aggregate.timestamp,
+            price_cumulative: price_account.price_cumulative,
+        };
+        match self.latest() {
+            Some(latest) if latest.slot == aggregate.slot => {
+                let newest = (self.total_observations - 1) as usize % PRICE_HISTORY_LEN;
+                self.observations[newest] = observation;
+            }
+            _ => {
+                self.observations[self.total_observations as usize % PRICE_HISTORY_LEN] = observation;
+                self.total_observations += 1;
+            }
+        }
+    }
+
+    pub fn latest(&self) -> Option<&PriceObservation> {
+        self.recent().next()
+    }
+
+    /// Recorded observations, newest first
+    pub fn recent(&self) -> impl Iterator<Item = &PriceObservation> {
+        let count = self.total_observations.min(PRICE_HISTORY_LEN as u64);
+        (1..=count).map(move |age| &self.observations[(self.total_observations - age) as usize % PRICE_HISTORY_LEN])
+    }
+
+    /// The feed's `price_cumulative` at `timestamp`, carried forward from the newest
+    /// observation at or before it. None when the history does not reach back that far.
+    pub fn price_cumulative_at(&self, timestamp: i64) -> Option<i128> {
+        self.recent()
+            .find(|observation| observation.timestamp <= timestamp)
+            .map(|observation| {
+                observation.price_cumulative + observation.price as i128 * (timestamp - observation.timestamp) as i128
+            })
+    }
+}
+
+/// Reads of a feed through `read_price`, per program epoch, as usage data for governance's
+/// fee and reward decisions. Programs deserializing the price account directly are not seen.
+#[account]
+pub struct FeedReadStats {
+    pub price_account: Pubkey,
+    pub epoch: u64, // Program epoch `reads` belongs to
+    pub reads: u64,
+    pub last_epoch_reads: u64, // Reads in the epoch before `epoch`
+    pub total_reads: u64,
+    pub bump: u8,
+}
+
+/// Uptime of a feed over windows of EPOCH_LENGTH_SLOTS: slots in which it had a fresh
+/// Trading aggregate. Integrated from observations made whenever quotes, aggregation
+/// cranks or `update_feed_stats` touch the feed, so unobserved aggregates are not counted.
+#[account]
+pub struct FeedStats {
+    pub price_account: Pubkey,
+    pub window_start_slot: u64,
+    pub trading_slots: u64, // In the current window so far
+    pub observed_slot: u64, // Slots before this one are accounted for
+    pub trading_until_slot: u64, // Freshness end of the last observed Trading aggregate (0 = not trading)
+    pub last_window_start_slot: u64,
+    pub last_uptime_bps: u16, // Share of the last complete window's slots with a Trading aggregate
+    pub bump: u8,
+}
+
+impl FeedStats {
+    fn accrue(&mut self, slot: u64) {
+        if slot <= self.observed_slot {
+            return;
+        }
+        let trading_end = slot.min(self.trading_until_slot);
+        if trading_end > self.observed_slot {
+            self.trading_slots += trading_end - self.observed_slot;
+        }
+        self.observed_slot = slot;
+    }
+}
+
+/// Per-asset-type feed defaults. PDA per asset type, created with the program's defaults
+/// and changed by SetAssetTemplate proposals.
+#[account]
+pub struct AssetTemplate {
+    pub asset_type: AssetType,
+    pub template: AssetTemplateParams,
+    pub bump: u8,
+}
+
+/// Collateral a feed's creator locked at listing. Governance can draw it into the
+/// insurance fund to cover losses caused by the feed; the rest is returned on retirement.
+#[account]
+pub struct FeedCollateral {
+    pub price_account: Pubkey,
+    pub creator: Pubkey,
+    pub risk_tier: RiskTier,
+    pub amount: u64, // Still locked, after any draws
+    pub bump: u8,
+    pub retire_after: i64, // Earliest retirement time once announced, 0 before
+}
+
+/// Stake a publisher locked for a `LockTier`, represented by a one-of-one NFT under
+/// `mint`. Whoever holds the NFT must return it to the publisher before the position can
+/// be unlocked, so it can back obligations elsewhere while transfers are allowed.
+#[account]
+pub struct StakePosition {
+    pub publisher: Pubkey, // PublisherAccount
+    pub mint: Pubkey,
+    pub index: u32,
+    pub amount: u64,
+    pub tier: LockTier,
+    pub locked_at: i64,
+    pub unlock_at: i64,
+    pub bump: u8,
+}
+
+/// Optimistic mode for a feed: one staked poster writes the aggregate directly and any
+/// other staked publisher may dispute a post within the challenge window.
+#[account]
+pub struct OptimisticFeed {
+    pub price_account: Pubkey,
+    pub poster: Pubkey, // Publisher authority
+    pub challenge_window_slots: u64,
+    pub posted_price: i64,
+    pub posted_confidence: u64,
+    pub posted_slot: u64,
+    pub disputed: bool, // Posting is suspended until the feed authority resolves the dispute
+    pub challenger: Pubkey,
+    pub challenge_price: i64,
+    pub challenge_confidence: u64,
+    pub challenge_bond: u64, // Challenger stake locked until the dispute is resolved
+    pub bump: u8,
+}
+
+/// Links a feed to the source whose price it inverts; `update_inverse_feed` keeps it current
+#[account]
+pub struct InverseFeed {
+    pub price_account: Pubkey,
+    pub source_feed: Pubkey,
+    pub bump: u8,
+}
+
+/// Contract currently quoted on a futures feed. Publishers quote the raw price of
+/// `active_contract`; quotes are scaled by the feed's `roll_adjustment` so the continuous
+/// series does not gap when the contract rolls.
+#[account]
+pub struct FuturesContract {
+    pub price_account: Pubkey,
+    pub active_contract: String, // Exchange contract code, e.g. "CLZ6"
+    pub expiry: i64,
+    pub previous_contract: String,
+    pub roll_count: u32,
+    pub last_roll_slot: u64,
+    pub bump: u8,
+}
+
+/// Merkle root of one publisher's quotes across many feeds, posted in a single transaction.
+/// Individual feeds pick their quote up via `apply_batched_price` with a proof.
+#[account]
+pub struct PriceBatch {
+    pub publisher: Pubkey, // Publisher authority
+    pub root: [u8; 32], // Sorted-pair tree over keccak(0x00, price_account, sequence, price, confidence, source_id)
+    pub timestamp: i64, // Cluster time the root was posted
+    pub observed_timestamp: i64, // Publisher's observation time, within MAX_OBSERVATION_SKEW of `timestamp`
+    pub slot: u64,
+    pub sequence: u64, // Of the current root; each submission must be higher
+    pub bump: u8,
+}
+
+/// A publisher's pending hidden quote on a commit-reveal feed
+#[account]
+pub struct QuoteCommitment {
+    pub price_account: Pubkey,
+    pub publisher: Pubkey, // Publisher authority
+    pub commitment: [u8; 32], // keccak(price_account, publisher, price, confidence, source_id, salt)
+    pub commit_slot: u64, // 0 once revealed
+    pub bump: u8,
+}
+
+/// Delegates a subset of feed admin powers for one feed to an operations key
+#[account]
+pub struct FeedAdmin {
+    pub price_account: Pubkey,
+    pub operator: Pubkey,
+    pub permissions: u8, // FEED_ADMIN_* bitmask
+    pub bump: u8,
+}
+
+/// Allows one publisher to quote a permissioned feed
+#[account]
+pub struct FeedPermission {
+    pub price_account: Pubkey,
+    pub publisher: Pubkey,
+    pub bump: u8,
+}
+
+#[account]
+pub struct ConvictionLock {
+    pub voter: Pubkey,
+    pub proposal: Pubkey,
+    pub amount: u64,
+    pub locked_slot: u64,
+    pub bump: u8,
+}
+
+// ============================================================================
+// Program
+// ============================================================================
+
+#[program]
+pub mod sfdn_oracle {
+    use super::*;
+
+    // ========================================================================
+    // Initialization Instructions
+    // ========================================================================
+
+    pub fn initialize_program(
+        ctx: Context<InitializeProgram>,
+        reward_rate: u64,
+        proposal_threshold: u64,
+        voting_period: u64,
+        quorum_percentage: u8,
+        timelock_duration: u64,
+        total_supply: u64,
+    ) -> Result<()> {
+        require!(
+            ctx.accounts.token_mint.decimals == TOKEN_DECIMALS,
+            ErrorCode::InvalidMint
+        );
+        require_supported_mint(&ctx.accounts.token_mint.to_account_info())?;
+        require!(
+            quorum_percentage > 0 && quorum_percentage <= 100,
+            ErrorCode::InvalidGovernanceParams
+        );
+        require!(
+            voting_period > 0 && voting_period <= MAX_VOTING_PERIOD,
+            ErrorCode::InvalidGovernanceParams
+        );
+        require!(
+            timelock_duration <= MAX_TIMELOCK_DURATION,
+            ErrorCode::InvalidGovernanceParams
+        );
+        require!(
+            total_supply > 0 && total_supply >= ctx.accounts.token_mint.supply,
+            ErrorCode::InvalidGovernanceParams
+        );
+        require!(
+            proposal_threshold > 0 && proposal_threshold <= total_supply,
+            ErrorCode::InvalidGovernanceParams
+        );
+
+        let global_state = &mut ctx.accounts.global_state;
+        global_state.authority = ctx.accounts.authority.key();
+        global_state.token_mint = ctx.accounts.token_mint.key();
+        global_state.token_vault = ctx.accounts.token_vault.key();
+        global_state.vault_authority = ctx.accounts.vault_authority.key();
+        global_state.governance = ctx.accounts.governance_state.key();
+        global_state.paused = false;
+        global_state.total_products = 0;
+        global_state.total_publishers = 0;
+        global_state.stake_per_feed = 0;
+        global_state.max_new_publishers_per_epoch = DEFAULT_MAX_NEW_PUBLISHERS_PER_EPOCH;
+        // Program epochs continue from the cluster epoch, which epoch fields held before
+        global_state.current_epoch = Clock::get()?.epoch;
+        global_state.epoch_start_slot = Clock::get()?.slot;
+        global_state.epoch_stake_snapshot = 0;
+        global_state.registrations_this_epoch = 0;
+        global_state.registration_allowance = 0;
+        global_state.unbonding_period = DEFAULT_UNBONDING_PERIOD;
+        global_state.min_stake = DEFAULT_MIN_STAKE_AMOUNT;
+        global_state.previous_min_stake = DEFAULT_MIN_STAKE_AMOUNT;
+        global_state.min_stake_raised_epoch = 0;
+        global_state.receipt_mint = Pubkey::default();
+        global_state.receipts_transferable = false;
+        global_state.positions_transferable = false;
+        global_state.pending_stake_per_feed = PendingParam::default();
+        global_state.pending_registration_limit = PendingParam::default();
+        global_state.pending_unbonding_period = PendingParam::default();
+        global_state.pending_min_stake = PendingParam::default();
+        global_state.listing_collateral = DEFAULT_LISTING_COLLATERAL;
+        global_state.mock_time_offset = 0;
+        global_state.version = PROGRAM_VERSION;
+        global_state.bump = ctx.bumps.global_state;
+        global_state.vault_authority_bump = ctx.bumps.vault_authority;
+        global_state.custody_version = 0;
+        global_state.custody_bump = ctx.bumps.vault_authority;
+
+        let token_vault = &mut ctx.accounts.token_vault;
+        token_vault.total_staked = 0;
+        token_vault.total_rewards_distributed = 0;
+        token_vault.reward_rate = reward_rate;
+        token_vault.last_distribution_slot = Clock::get()?.slot;
+        token_vault.reward_index = 0;
+        token_vault.reward_pool = 0;
+        token_vault.treasury = 0;
+        token_vault.pending_rebates = 0;
+        token_vault.unreconciled_drift = 0;
+        token_vault.insurance_fund = 0;
+        token_vault.insurance_coverage = 0;
+        token_vault.total_conviction_locked = 0;
+        token_vault.total_veto_stake = 0;
+        token_vault.pending_reward_rate = PendingParam::default();
+        token_vault.token_mint = ctx.accounts.token_mint.key();
+        token_vault.vault_token_account = ctx.accounts.vault_token_account.key();
+        token_vault.vault_authority = ctx.accounts.vault_authority.key();
+        token_vault.reward_mint = Pubkey::default();
+        token_vault.reward_vault_token_account = Pubkey::default();
+        token_vault.reward_mint_slot = 0;
+        token_vault.reward_mint_index = 0;
+        token_vault.reward_vesting_period = 0;
+        token_vault.vesting_rewards = 0;
+        token_vault.listing_collateral = 0;
+        token_vault.previous_token_mint = Pubkey::default();
+        token_vault.migration_counterparty = Pubkey::default();
+        token_vault.swap_reserve = 0;
+        token_vault.authority = ctx.accounts.authority.key();
+        token_vault.bump = ctx.bumps.token_vault;
+
+        let governance = &mut ctx.accounts.governance_state;
+        governance.governance_token = ctx.accounts.token_mint.key();
+        governance.proposal_threshold = proposal_threshold;
+        governance.voting_period = voting_period;
+        governance.quorum_percentage = quorum_percentage;
+        governance.timelock_duration = timelock_duration;
+        governance.proposal_count = 0;
+        governance.total_supply = total_supply;
+        governance.conviction_threshold_percentage = DEFAULT_CONVICTION_THRESHOLD_PERCENTAGE;
+        governance.optimistic_challenge_period = DEFAULT_OPTIMISTIC_CHALLENGE_PERIOD;
+        governance.optimistic_veto_stake = proposal_threshold;
+        governance.optimistic_change_count = 0;
+        governance.emergency_voting_period = DEFAULT_EMERGENCY_VOTING_PERIOD;
+        governance.emergency_quorum_percentage = DEFAULT_EMERGENCY_QUORUM_PERCENTAGE;
+        governance.severity_threshold_bps = DEFAULT_SEVERITY_THRESHOLD_BPS;
+        governance.authority = ctx.accounts.authority.key();
+        governance.bump = ctx.bumps.governance_state;
+
+        emit!(Initialized {
+            authority: ctx.accounts.authority.key(),
+            token_mint: ctx.accounts.token_mint.key(),
+            vault_token_account: ctx.accounts.vault_token_account.key(),
+            reward_rate,
+            proposal_threshold,
+            voting_period,
+            quorum_percentage,
+            timelock_duration,
+            total_supply,
+            version: PROGRAM_VERSION,
+        });
+
+        Ok(())
+    }
+
+    /// Lists a feed. The creator locks `listing_collateral` times the risk tier's multiple
+    /// of the asset type, returned by `retire_feed`.
+    #[allow(clippy::too_many_arguments)]
+    pub fn create_product<'info>(
+        ctx: Context<'_, '_, 'info, 'info, CreateProduct<'info>>,
+        symbol: String,
+        asset_type: AssetType,
+        description: String,
+        price_type: PriceType,
+        min_publishers: u8,
+        exponent: i32,
+        ema_alpha: u32,
+    ) -> Result<()> {
+        require!(!ctx.accounts.global_state.paused, ErrorCode::SystemPaused);
+        require!(ema_alpha <= 1_000_000, ErrorCode::InvalidFeedConfig);
+        let risk_tier = RiskTier::for_asset_type(&asset_type);
+
+        let product = &mut ctx.accounts.product_account;
+        product.symbol = symbol.clone();
+        product.asset_type = asset_type;
+        product.description = description;
+        product.price_account = ctx.accounts.price_account.key();
+        product.authority = ctx.accounts.authority.key();
+        let (base_currency, quote_currency) = currency_pair(&symbol);
+        product.base_currency = base_currency;
+        product.quote_currency = quote_currency;
+        product.calendar = TradingCalendar::default();
+        product.bump = ctx.bumps.product_account;
+
+        // The account starts zeroed, so every quote slot is already empty
+        let mut price_account = ctx.accounts.price_account.load_init()?;
+        price_account.product_account = ctx.accounts.product_account.key();
+        price_account.price_type = price_type as u8;
+        price_account.aggregate = PriceData::default();
+        price_account.publisher_count = 0;
+        price_account.min_publishers = min_publishers;
+        price_account.last_update_slot = 0;
+        price_account.ema = EmaData::default();
+        price_account.authority = ctx.accounts.authority.key();
+        price_account.exponent = exponent;
+        price_account.frozen = 0;
+        price_account.confidence_stake_scale = 0;
+        price_account.halted = 0;
+        price_account.staleness_threshold = 0;
+        price_account.distinct_sources = 0;
+        price_account.top_source_share_bps = 0;
+        price_account.degraded_confidence_bps = 0;
+        price_account.rounding_mode = RoundingMode::default() as u8;
+        price_account.high_precision = 0;
+        price_account.commit_reveal = 0;
+        price_account.sample_size = 0;
+        price_account.deviation_alert_mads = 0;
+        price_account.sample_slot = 0;
+        price_account.sampled_publishers = 0;
+        price_account.quotes_root = [0; 32];
+        price_account.optimistic = 0;
+        price_account.confidence_mode = ConfidenceMode::default() as u8;
+        price_account.derived = 0;
+        price_account.roll_adjustment = 0;
+        price_account.min_operators = 0;
+        price_account.ema_alpha = ema_alpha;
+        price_account.ema_confidence_weighted = 0;
+        price_account.min_slots_between_aggregations = 0;
+        price_account.last_aggregation_slot = 0;
+        price_account.emergency_halt_until_slot = 0;
+        price_account.included_publishers = 0;
+        price_account.submission_quota = 0;
+        price_account.permissioned = 0;
+        price_account.volatility_until_slot = 0;
+        price_account.volatility_widening_bps = 0;
+        price_account.pending_min_publishers = PendingParam::default();
+        price_account.bump = ctx.bumps.price_account;
+
+        // Asset-type defaults; the authority can still tune them with update_feed_config
+        if let Some(asset_template) = &ctx.accounts.asset_template {
+            let template = &asset_template.template;
+            ctx.accounts.product_account.calendar = template.calendar.clone();
+            price_account.staleness_threshold = template.staleness_threshold;
+            price_account.degraded_confidence_bps = template.degraded_confidence_bps;
+            price_account.min_publishers = min_publishers.max(template.min_publishers);
+        }
+        let min_publishers = price_account.min_publishers;
+        drop(price_account);
+
+        let collateral = ctx.accounts.global_state.listing_collateral
+            .checked_mul(risk_tier.collateral_multiple())
+            .ok_or(ErrorCode::Overflow)?;
+        let collateral = if collateral > 0 {
+            let vault_balance = ctx.accounts.vault_token_account.amount;
+            let cpi_accounts = TransferChecked {
+                from: ctx.accounts.creator_token_account.to_account_info(),
+                mint: ctx.accounts.token_mint.to_account_info(),
+                to: ctx.accounts.vault_token_account.to_account_info(),
+                authority: ctx.accounts.authority.to_account_info(),
+            };
+            let cpi_program = ctx.accounts.token_program.to_account_info();
+            let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts)
+                .with_remaining_accounts(ctx.remaining_accounts.to_vec());
+            transfer_checked_with_hooks(cpi_ctx, collateral, ctx.accounts.token_mint.decimals)?;
+
+            vault_received(&mut ctx.accounts.vault_token_account, vault_balance)?
+        } else {
+            0
+        };
+
+        let feed_collateral = &mut ctx.accounts.feed_collateral;
+        feed_collateral.price_account = ctx.accounts.price_account.key();
+        feed_collateral.creator = ctx.accounts.authority.key();
+        feed_collateral.risk_tier = risk_tier;
+        feed_collateral.amount = collateral;
+        feed_collateral.bump = ctx.bumps.feed_collateral;
+        feed_collateral.retire_after = 0;
+
+        ctx.accounts.token_vault.listing_collateral = ctx.accounts.token_vault.listing_collateral
+            .checked_add(collateral)
+            .ok_or(ErrorCode::Overflow)?;
+        ctx.accounts.global_state.total_products += 1;
+
+        emit!(ProductCreated {
+            product: ctx.accounts.product_account.key(),
+            price_account: ctx.accounts.price_account.key(),
+            symbol,
+            asset_type: ctx.accounts.product_account.asset_type.clone(),
+            description: ctx.accounts.product_account.description.clone(),
+            price_type,
+            min_publishers,
+            exponent,
+            risk_tier,
+            authority: ctx.accounts.authority.key(),
+            slot: program_clock(&ctx.accounts.global_state)?.slot,
+        });
+
+        emit!(FeedCollateralLocked {
+            price_account: feed_collateral.price_account,
+            creator: feed_collateral.creator,
+            risk_tier,
+            amount: collateral,
+        });
+
+        Ok(())
+    }
+
+    pub fn add_publisher<'info>(
+        ctx: Context<'_, '_, 'info, 'info, AddPublisher<'info>>,
+        name: String,
+        initial_stake: u64,
+    ) -> Result<()> {
+        require!(!ctx.accounts.global_state.paused, ErrorCode::SystemPaused);
+        require!(
+            initial_stake >= ctx.accounts.global_state.min_stake,
+            ErrorCode::InsufficientStake
+        );
+
+        // Rate-limit brand-new registrations; extra slots require governance approval
+        let global_state = &mut ctx.accounts.global_state;
+        let epoch = global_state.current_epoch;
+        if global_state.registrations_this_epoch >= global_state.max_new_publishers_per_epoch {
+            require!(
+                global_state.registration_allowance > 0,
+                ErrorCode::RegistrationLimitReached
+            );
+            global_state.registration_allowance -= 1;
+        }
+        global_state.registrations_this_epoch += 1;
+
+        // Transfer stake to vault using vault authority
+        let vault_balance = ctx.accounts.vault_token_account.amount;
+        let cpi_accounts = TransferChecked {
+            from: ctx.accounts.publisher_token_account.to_account_info(),
+            mint: ctx.accounts.token_mint.to_account_info(),
+            to: ctx.accounts.vault_token_account.to_account_info(),
+            authority: ctx.accounts.publisher_authority.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts)
+            .with_remaining_accounts(ctx.remaining_accounts.to_vec());
+        transfer_checked_with_hooks(cpi_ctx, initial_stake, ctx.accounts.token_mint.decimals)?;
+
+        // Token-2022 transfer fees are withheld in transit, so book what arrived
+        let initial_stake = vault_received(&mut ctx.accounts.vault_token_account, vault_balance)?;
+        require!(
+            initial_stake >= ctx.accounts.global_state.min_stake,
+            ErrorCode::InsufficientStake
+        );
+
+        let publisher = &mut ctx.accounts.publisher_account;
+        publisher.authority = ctx.accounts.publisher_authority.key();
+        publisher.staked_amount = initial_stake;
+        publisher.stake_account = ctx.accounts.publisher_token_account.key();
+        publisher.reputation = 100;
+        publisher.name = name.clone();
+        publisher.registered_at = program_clock(&ctx.accounts.global_state)?.unix_timestamp;
+        publisher.slash_count = 0;
+        publisher.last_slash_slot = 0;
+        publisher.stake_state = StakeState::Bonded;
+        publisher.feed_count = 0;
+        publisher.registered_epoch = epoch;
+        publisher.probation_end_epoch = epoch + PROBATION_EPOCHS;
+        publisher.pending_rewards = 0;
+        publisher.last_reward_slot = program_clock(&ctx.accounts.global_state)?.slot;
+        publisher.reward_index = ctx.accounts.token_vault.reward_index_at(publisher.last_reward_slot);
+        publisher.rewards_frozen_until_slot = 0;
+        publisher.insurance_share_bps = 0;
+        publisher.insurance_contributed = 0;
+        publisher.insurance_claimed = 0;
+        publisher.quote_cosigner = Pubkey::default();
+        publisher.receipt_balance = 0;
+        publisher.last_active_epoch = epoch;
+        publisher.reward_vesting = RewardVesting::default();
+        publisher.operator_id = 0;
+        publisher.locked_stake = 0;
+        publisher.position_count = 0;
+        publisher.publishing_blocked_until_slot = 0;
+        publisher.registered_min_stake = ctx.accounts.global_state.min_stake;
+        publisher.bump = ctx.bumps.publisher_account;
+
+        ctx.accounts.token_vault.total_staked += initial_stake;
+        ctx.accounts.global_state.total_publishers += 1;
+
+        emit!(PublisherAdded {
+            publisher: ctx.accounts.publisher_account.key(),
+            authority: ctx.accounts.publisher_authority.key(),
+            stake_amount: initial_stake,
+            name,
+        });
+
+        Ok(())
+    }
+
+    /// Rewrites the v1 GlobalState, TokenVault and GovernanceState accounts into the
+    /// current layouts, growing them in place and defaulting the newly added fields.
+    pub fn bootstrap_v2(
+        ctx: Context<BootstrapV2>,
+    ) -> Result<()> {
+        let global_info = ctx.accounts.global_state.to_account_info();
+        let vault_info = ctx.accounts.token_vault.to_account_info();
+        let governance_info = ctx.accounts.governance_state.to_account_info();
+
+        let legacy_global: GlobalStateV1 = read_legacy_account(&global_info, &GlobalState::DISCRIMINATOR)?;
+        let legacy_vault: TokenVaultV1 = read_legacy_account(&vault_info, &TokenVault::DISCRIMINATOR)?;
+        let legacy_governance: GovernanceStateV1 =
+            read_legacy_account(&governance_info, &GovernanceState::DISCRIMINATOR)?;
+
+        require!(legacy_global.version == 1, ErrorCode::MigrationNotRequired);
+        require!(
+            ctx.accounts.authority.key() == legacy_global.authority,
+            ErrorCode::Unauthorized
+        );
+
+        let payer = ctx.accounts.authority.to_account_info();
+        let system_program = ctx.accounts.system_program.to_account_info();
+
+        let global_state = GlobalState {
+            authority: legacy_global.authority,
+            token_mint: legacy_global.token_mint,
+            token_vault: legacy_global.token_vault,
+            vault_authority: legacy_global.vault_authority,
+            governance: legacy_global.governance,
+            paused: legacy_global.paused,
+            total_products: legacy_global.total_products,
+            total_publishers: legacy_global.total_publishers,
+            stake_per_feed: 0,
+            max_new_publishers_per_epoch: DEFAULT_MAX_NEW_PUBLISHERS_PER_EPOCH,
+            current_epoch: Clock::get()?.epoch,
+            epoch_start_slot: Clock::get()?.slot,
+            epoch_stake_snapshot: legacy_vault.total_staked,
+            registrations_this_epoch: 0,
+            registration_allowance: 0,
+            unbonding_period: DEFAULT_UNBONDING_PERIOD,
+            min_stake: DEFAULT_MIN_STAKE_AMOUNT,
+            previous_min_stake: DEFAULT_MIN_STAKE_AMOUNT,
+            min_stake_raised_epoch: 0,
+            receipt_mint: Pubkey::default(),
+            receipts_transferable: false,
+            positions_transferable: false,
+            pending_stake_per_feed: PendingParam::default(),
+            pending_registration_limit: PendingParam::default(),
+            pending_unbonding_period: PendingParam::default(),
+            pending_min_stake: PendingParam::default(),
+            listing_collateral: DEFAULT_LISTING_COLLATERAL,
+            mock_time_offset: 0,
+            version: PROGRAM_VERSION,
+            bump: legacy_global.bump,
+            vault_authority_bump: legacy_global.vault_authority_bump,
+            custody_version: 0,
+            custody_bump: legacy_global.vault_authority_bump,
+        };
+        rewrite_account(&global_info, &payer, &system_program, &global_state, GLOBAL_STATE_SPACE)?;
+
+        let token_vault = TokenVault {
+            total_staked: legacy_vault.total_staked,
+            total_rewards_distributed: legacy_vault.total_rewards_distributed,
+            reward_rate: legacy_vault.reward_rate,
+            last_distribution_slot: legacy_vault.last_distribution_slot,
+            reward_index: 0,
+            reward_pool: 0,
+            treasury: 0,
+            pending_rebates: 0,
+            unreconciled_drift: 0,
+            insurance_fund: 0,
+            insurance_coverage: 0,
+            total_conviction_locked: 0,
+            total_veto_stake: 0,
+            pending_reward_rate: PendingParam::default(),
+            token_mint: legacy_vault.token_mint,
+            vault_token_account: legacy_vault.vault_token_account,
+            vault_authority: legacy_vault.vault_authority,
+            authority: legacy_vault.authority,
+            reward_mint: Pubkey::default(),
+            reward_vault_token_account: Pubkey::default(),
+            reward_mint_slot: 0,
+            reward_mint_index: 0,
+            reward_vesting_period: 0,
+            vesting_rewards: 0,
+            listing_collateral: 0,
+            previous_token_mint: Pubkey::default(),
+            migration_counterparty: Pubkey::default(),
+            swap_reserve: 0,
+            bump: legacy_vault.bump,
+        };
+        rewrite_account(&vault_info, &payer, &system_program, &token_vault, TOKEN_VAULT_SPACE)?;
+
+        let governance_state = GovernanceState {
+            governance_token: legacy_governance.governance_token,
+            proposal_threshold: legacy_governance.proposal_threshold,
+            voting_period: legacy_governance.voting_period,
+            quorum_percentage: legacy_governance.quorum_percentage,
+            timelock_duration: legacy_governance.timelock_duration,
+            proposal_count: legacy_governance.proposal_count,
+            total_supply: legacy_governance.total_supply,
+            conviction_threshold_percentage: DEFAULT_CONVICTION_THRESHOLD_PERCENTAGE,
+            optimistic_challenge_period: DEFAULT_OPTIMISTIC_CHALLENGE_PERIOD,
+            optimistic_veto_stake: legacy_governance.proposal_threshold,
+            optimistic_change_count: 0,
+            emergency_voting_period: DEFAULT_EMERGENCY_VOTING_PERIOD,
+            emergency_quorum_percentage: DEFAULT_EMERGENCY_QUORUM_PERCENTAGE,
+            severity_threshold_bps: DEFAULT_SEVERITY_THRESHOLD_BPS,
+            authority: legacy_governance.authority,
+            bump: legacy_governance.bump,
+        };
+        rewrite_account(&governance_info, &payer, &system_program, &governance_state, GOVERNANCE_STATE_SPACE)?;
+
+        emit!(Migrated {
+            from_version: legacy_global.version,
+            to_version: PROGRAM_VERSION,
+            authority: legacy_global.authority,
+        });
+
+        Ok(())
+    }
+
+    /// Rewrites a v2 GlobalState into the current layout, which adds `mock_time_offset`
+    pub fn bootstrap_v3(
+        ctx: Context<BootstrapV3>,
+    ) -> Result<()> {
+        let global_info = ctx.accounts.global_state.to_account_info();
+        let legacy: GlobalStateV2 = read_legacy_account(&global_info, &GlobalState::DISCRIMINATOR)?;
+
+        require!(legacy.version == 2, ErrorCode::MigrationNotRequired);
+        require!(ctx.accounts.authority.key() == legacy.authority, ErrorCode::Unauthorized);
+
+        let global_state = GlobalState {
+            authority: legacy.authority,
+            token_mint: legacy.token_mint,
+            token_vault: legacy.token_vault,
+            vault_authority: legacy.vault_authority,
+            governance: legacy.governance,
+            paused: legacy.paused,
+            total_products: legacy.total_products,
+            total_publishers: legacy.total_publishers,
+            stake_per_feed: legacy.stake_per_feed,
+            max_new_publishers_per_epoch: legacy.max_new_publishers_per_epoch,
+            current_epoch: legacy.current_epoch,
+            epoch_start_slot: legacy.epoch_start_slot,
+            epoch_stake_snapshot: legacy.epoch_stake_snapshot,
+            registrations_this_epoch: legacy.registrations_this_epoch,
+            registration_allowance: legacy.registration_allowance,
+            unbonding_period: legacy.unbonding_period,
+            min_stake: legacy.min_stake,
+            previous_min_stake: legacy.previous_min_stake,
+            min_stake_raised_epoch: legacy.min_stake_raised_epoch,
+            receipt_mint: legacy.receipt_mint,
+            receipts_transferable: legacy.receipts_transferable,
+            positions_transferable: legacy.positions_transferable,
+            pending_stake_per_feed: legacy.pending_stake_per_feed,
+            pending_registration_limit: legacy.pending_registration_limit,
+            pending_unbonding_period: legacy.pending_unbonding_period,
+            pending_min_stake: legacy.pending_min_stake,
+            listing_collateral: legacy.listing_collateral,
+            mock_time_offset: 0,
+            version: PROGRAM_VERSION,
+            bump: legacy.bump,
+            vault_authority_bump: legacy.vault_authority_bump,
+            custody_version: legacy.custody_version,
+            custody_bump: legacy.custody_bump,
+        };
+        rewrite_account(
+            &global_info,
+            &ctx.accounts.authority.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+            &global_state,
+            GLOBAL_STATE_SPACE,
+        )?;
+
+        emit!(Migrated {
+            from_version: legacy.version,
+            to_version: PROGRAM_VERSION,
+            authority: legacy.authority,
+        });
+
+        Ok(())
+    }
+
+    /// Rewrites a product account created before products carried a trading calendar or
+    /// FX currencies. Permissionless: the conversion is fixed and the caller only pays for
+    /// the extra rent. Products without a calendar get a disabled one, so they stay open
+    /// until the authority calls `set_trading_calendar`.
+    pub fn migrate_product_account(
+        ctx: Context<MigrateProductAccount>,
+    ) -> Result<()> {
+        let info = ctx.accounts.product_account.to_account_info();
+        let product = match info.data_len() {
+            PRODUCT_ACCOUNT_V1_SPACE => {
+                let legacy: ProductAccountV1 = read_legacy_account(&info, &ProductAccount::DISCRIMINATOR)?;
+                let (base_currency, quote_currency) = currency_pair(&legacy.symbol);
+                ProductAccount {
+                    symbol: legacy.symbol,
+                    asset_type: legacy.asset_type,
+                    description: legacy.description,
+                    price_account: legacy.price_account,
+                    authority: legacy.authority,
+                    base_currency,
+                    quote_currency,
+                    calendar: TradingCalendar::default(),
+                    bump: legacy.bump,
+                }
+            }
+            PRODUCT_ACCOUNT_V2_SPACE => {
+                let legacy: ProductAccountV2 = read_legacy_account(&info, &ProductAccount::DISCRIMINATOR)?;
+                let (base_currency, quote_currency) = currency_pair(&legacy.symbol);
+                ProductAccount {
+                    symbol: legacy.symbol,
+                    asset_type: legacy.asset_type,
+                    description: legacy.description,
+                    price_account: legacy.price_account,
+                    authority: legacy.authority,
+                    base_currency,
+                    quote_currency,
+                    calendar: legacy.calendar,
+                    bump: legacy.bump,
+                }
+            }
+            _ => return err!(ErrorCode::MigrationNotRequired),
+        };
+
+        rewrite_account(
+            &info,
+            &ctx.accounts.payer.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+            &product,
+            PRODUCT_ACCOUNT_SPACE,
+        )?;
+
+        emit!(ProductAccountMigrated {
+            product_account: info.key(),
+            authority: product.authority,
+        });
+
+        Ok(())
+    }
+
+    /// Rewrites a price account still in the original borsh layout. Permissionless: the
+    /// conversion is fixed and the caller only pays for any extra rent. Settings added since
+    /// start at zero, which selects their defaults, as for a new feed without a template.
+    pub fn migrate_price_account(
+        ctx: Context<MigratePriceAccount>,
+    ) -> Result<()> {
+        let info = ctx.accounts.price_account.to_account_info();
+        let legacy = info.try_borrow_data()?.to_vec();
+        require!(
+            legacy.len() > 8 && legacy.len() != PRICE_ACCOUNT_SPACE && legacy[..8] == PriceAccount::DISCRIMINATOR,
+            ErrorCode::MigrationNotRequired
+        );
+
+        // Read field by field from a heap copy; the publisher array is too large for the stack
+        let mut reader = &legacy[8..];
+        let head = PriceAccountV1Head::deserialize(&mut reader)?;
+        let mut publishers = Vec::with_capacity(MAX_PUBLISHERS);
+        for _ in 0..MAX_PUBLISHERS {
+            publishers.push(PublisherPriceV1::deserialize(&mut reader)?);
+        }
+        let tail = PriceAccountV1Tail::deserialize(&mut reader)?;
+
+        resize_account(
+            &info,
+            &ctx.accounts.payer.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+            PRICE_ACCOUNT_SPACE,
+        )?;
+        let mut data = info.try_borrow_mut_data()?;
+        data[8..].fill(0);
+        let price_account: &mut PriceAccount =
+            anchor_lang::__private::bytemuck::from_bytes_mut(&mut data[8..PRICE_ACCOUNT_SPACE]);
+
+        price_account.product_account = head.product_account;
+        price_account.authority = tail.authority;
+        price_account.price_type = head.price_type as u8;
+        price_account.aggregate = PriceData {
+            price: head.aggregate.price,
+            confidence: head.aggregate.confidence,
+            timestamp: head.aggregate.timestamp,
+            slot: head.aggregate.slot,
+            price_i128: 0,
+            exponent: head.aggregate.exponent,
+            status: head.aggregate.status as u8,
+            _padding: [0; 11],
+        };
+        for (slot, quote) in price_account.publishers.iter_mut().zip(&publishers) {
+            *slot = PublisherPrice {
+                publisher: quote.publisher,
+                price: quote.price,
+                confidence: quote.confidence,
+                timestamp: quote.timestamp,
+                slot: quote.slot,
+                stake: quote.stake,
+                source_id: 0,
+                operator: 0,
+                sequence: 0,
+                active: quote.active as u8,
+                outlier_reported: 0,
+                _padding: [0; 2],
+            };
+        }
+        price_account.publisher_count = tail.publisher_count;
+        price_account.min_publishers = tail.min_publishers;
+        price_account.last_update_slot = tail.last_update_slot;
+        price_account.ema = tail.ema;
+        price_account.exponent = tail.exponent;
+        price_account.bump = tail.bump;
+
+        emit!(PriceAccountMigrated {
+            price_account: info.key(),
+            authority: tail.authority,
+        });
+
+        Ok(())
+    }
+
+    /// Rewrites a publisher account registered before stake states, feed exposure and
+    /// rewards were tracked. Only the global authority can call it, because the feeds the
+    /// publisher quotes on are passed as remaining accounts to rebuild `feed_count`.
+    /// Rewards accrue from the migration on; a pending unbonding keeps the 7-day period it
+    /// was started under.
+    pub fn migrate_publisher_account<'info>(
+        ctx: Context<'_, '_, 'info, 'info, MigratePublisherAccount<'info>>,
+    ) -> Result<()> {
+        let info = ctx.accounts.publisher_account.to_account_info();
+        require!(info.data_len() == PUBLISHER_ACCOUNT_V1_SPACE, ErrorCode::MigrationNotRequired);
+        let legacy: PublisherAccountV1 = read_legacy_account(&info, &PublisherAccount::DISCRIMINATOR)?;
+
+        let mut feed_count: u32 = 0;
+        let mut counted: Vec<Pubkey> = Vec::new();
+        for feed in ctx.remaining_accounts.iter() {
+            require!(!counted.contains(feed.key), ErrorCode::ProposalTargetMismatch);
+            counted.push(*feed.key);
+            let price_account = AccountLoader::<PriceAccount>::try_from(feed)?;
+            let price_account = price_account.load()?;
+            if price_account.publishers.iter().any(|p| p.active() && p.publisher == legacy.authority) {
+                feed_count += 1;
+            }
+        }
+
+        let global_state = &ctx.accounts.global_state;
+        let clock = program_clock(global_state)?;
+        let stake_state = if legacy.unbonding_amount > 0 {
+            StakeState::Unbonding {
+                amount: legacy.unbonding_amount,
+                start: legacy.unbonding_start,
+                period: DEFAULT_UNBONDING_PERIOD,
+            }
+        } else {
+            StakeState::Bonded
+        };
+        let publisher = PublisherAccount {
+            authority: legacy.authority,
+            staked_amount: legacy.staked_amount,
+            stake_account: legacy.stake_account,
+            reputation: legacy.reputation,
+            name: legacy.name,
+            registered_at: legacy.registered_at,
+            slash_count: legacy.slash_count,
+            last_slash_slot: legacy.last_slash_slot,
+            stake_state,
+            feed_count,
+            // Established publishers serve no probation
+            registered_epoch: global_state.current_epoch,
+            probation_end_epoch: global_state.current_epoch,
+            pending_rewards: 0,
+            last_reward_slot: clock.slot,
+            reward_index: ctx.accounts.token_vault.reward_index_at(clock.slot),
+            rewards_frozen_until_slot: 0,
+            insurance_share_bps: 0,
+            insurance_contributed: 0,
+            insurance_claimed: 0,
+            quote_cosigner: Pubkey::default(),
+            receipt_balance: 0,
+            last_active_epoch: global_state.current_epoch,
+            reward_vesting: RewardVesting::default(),
+            operator_id: 0,
+            locked_stake: 0,
+            position_count: 0,
+            publishing_blocked_until_slot: 0,
+            registered_min_stake: global_state.min_stake,
+            bump: legacy.bump,
+        };
+
+        rewrite_account(
+            &info,
+            &ctx.accounts.authority.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+            &publisher,
+            PUBLISHER_ACCOUNT_SPACE,
+        )?;
+
+        emit!(PublisherAccountMigrated {
+            publisher_account: info.key(),
+            authority: publisher.authority,
+            feed_count,
+        });
+
+        Ok(())
+    }
+
+    /// Rewrites a proposal created before proposal modes, conviction voting and links.
+    /// Permissionless: the conversion is fixed and the caller only pays for the extra rent.
+    /// Slashes keep the insurance-fund destination they had, and an executed proposal is
+    /// treated as having run its action, so it cannot be applied again.
+    pub fn migrate_proposal(
+        ctx: Context<MigrateProposal>,
+    ) -> Result<()> {
+        let info = ctx.accounts.proposal.to_account_info();
+        require!(info.data_len() == PROPOSAL_V1_SPACE, ErrorCode::MigrationNotRequired);
+        let legacy: ProposalV1 = read_legacy_account(&info, &Proposal::DISCRIMINATOR)?;
+
+        let proposal_type = match legacy.proposal_type {
+            ProposalTypeV1::UpdateRewardRate { new_rate } => ProposalType::UpdateRewardRate { new_rate },
+            ProposalTypeV1::UpdateMinPublishers { feed, new_min } => ProposalType::UpdateMinPublishers { feed, new_min },
+            ProposalTypeV1::SlashPublisher { publisher, percentage } => ProposalType::SlashPublisher {
+                publisher,
+                percentage,
+                destination: SlashDestination::InsuranceFund,
+                publish_cooldown_slots: 0,
+            },
+            ProposalTypeV1::EmergencyPause => ProposalType::EmergencyPause,
+            ProposalTypeV1::EmergencyUnpause => ProposalType::EmergencyUnpause,
+            ProposalTypeV1::UpdateGovernanceParams {
+                proposal_threshold,
+                voting_period,
+                quorum_percentage,
+                timelock_duration,
+            } => ProposalType::UpdateGovernanceParams {
+                proposal_threshold,
+                voting_period,
+                quorum_percentage,
+                timelock_duration,
+                conviction_threshold_percentage: None,
+                severity_threshold_bps: None,
+            },
+        };
+        let proposal = Proposal {
+            proposer: legacy.proposer,
+            proposal_type,
+            description: legacy.description,
+            yes_votes: legacy.yes_votes,
+            no_votes: legacy.no_votes,
+            abstain_votes: legacy.abstain_votes,
+            start_slot: legacy.start_slot,
+            end_slot: legacy.end_slot,
+            executed: legacy.executed,
+            execution_time: legacy.execution_time,
+            proposal_id: legacy.proposal_id,
+            mode: ProposalMode::Standard,
+            conviction_staked: 0,
+            conviction: 0,
+            conviction_updated_slot: legacy.start_slot,
+            linked_hashes: Vec::new(),
+            action_executed: legacy.executed,
+            bump: legacy.bump,
+        };
+
+        rewrite_account(
+            &info,
+            &ctx.accounts.payer.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+            &proposal,
+            PROPOSAL_SPACE,
+        )?;
+
+        emit!(ProposalMigrated {
+            proposal: info.key(),
+            proposal_id: proposal.proposal_id,
+        });
+
+        Ok(())
+    }
+
+    /// Records the deployment's cluster, canonical mint and label. The genesis hash must
+    /// be the public cluster's for Devnet, Testnet and Mainnet, and must not be any of
+    /// them for Localnet.
+    pub fn initialize_cluster_config(
+        ctx: Context<InitializeClusterConfig>,
+        environment: ClusterEnvironment,
+        genesis_hash: [u8; 32],
+        label: String,
+    ) -> Result<()> {
+        require!(label.len() <= MAX_CLUSTER_LABEL_LEN, ErrorCode::InvalidClusterConfig);
+        let genesis = anchor_lang::solana_program::hash::Hash::new_from_array(genesis_hash).to_string();
+        let valid = match environment.genesis_hash() {
+            Some(expected) => genesis == expected,
+            None => ![MAINNET_GENESIS_HASH, DEVNET_GENESIS_HASH, TESTNET_GENESIS_HASH].contains(&genesis.as_str()),
+        };
+        require!(valid, ErrorCode::InvalidClusterConfig);
+
+        let cluster_config = &mut ctx.accounts.cluster_config;
+        cluster_config.environment = environment;
+        cluster_config.token_mint = ctx.accounts.token_mint.key();
+        cluster_config.genesis_hash = genesis_hash;
+        cluster_config.label = label.clone();
+        cluster_config.program_version = PROGRAM_VERSION;
+        cluster_config.created_at = program_clock(&ctx.accounts.global_state)?.unix_timestamp;
+        cluster_config.bump = ctx.bumps.cluster_config;
+
+        emit!(ClusterConfigured {
+            environment,
+            token_mint: cluster_config.token_mint,
+            genesis_hash,
+            label,
+        });
+
+        Ok(())
+    }
+
+    /// Fails unless this deployment is the one described. Meant as the first instruction
+    /// of transactions that must not execute against another deployment.
+    pub fn assert_cluster(
+        ctx: Context<AssertCluster>,
+        environment: ClusterEnvironment,
+        token_mint: Pubkey,
+    ) -> Result<()> {
+        let cluster_config = &ctx.accounts.cluster_config;
+        require!(
+            cluster_config.environment == environment && cluster_config.token_mint == token_mint,
+            ErrorCode::WrongCluster
+        );
+        Ok(())
+    }
+
+    /// Returns the deployment's governance parameters and the settings of the price
+    /// accounts passed in remaining_accounts (at most MAX_CONFIG_SNAPSHOT_FEEDS), for
+    /// off-chain diffing across time and deployments. Read it by simulating the call.
+    pub fn export_config<'info>(
+        ctx: Context<'_, '_, 'info, 'info, ExportConfig<'info>>,
+    ) -> Result<ConfigSnapshot> {
+        require!(
+            ctx.remaining_accounts.len() <= MAX_CONFIG_SNAPSHOT_FEEDS,
+            ErrorCode::InvalidFeedConfig
+        );
+
+        let feeds = ctx.remaining_accounts
+            .iter()
+            .map(|info| {
+                let feed = AccountLoader::<PriceAccount>::try_from(info)?;
+                let price_account = feed.load()?;
+                Ok(FeedConfigSnapshot {
+                    price_account: feed.key(),
+                    exponent: price_account.exponent,
+                    min_publishers: price_account.min_publishers,
+                    frozen: price_account.frozen(),
+                    config: current_feed_config(&price_account)?,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let global_state = &ctx.accounts.global_state;
+        let governance = &ctx.accounts.governance_state;
+        let token_vault = &ctx.accounts.token_vault;
+        Ok(ConfigSnapshot {
+            slot: program_clock(&ctx.accounts.global_state)?.slot,
+            version: global_state.version,
+            token_mint: global_state.token_mint,
+            paused: global_state.paused,
+            stake_per_feed: global_state.stake_per_feed,
+            max_new_publishers_per_epoch: global_state.max_new_publishers_per_epoch,
+            unbonding_period: global_state.unbonding_period,
+            min_stake: global_state.min_stake,
+            listing_collateral: global_state.listing_collateral,
+            receipts_transferable: global_state.receipts_transferable,
+            positions_transferable: global_state.positions_transferable,
+            reward_rate: token_vault.reward_rate,
+            reward_mint: token_vault.payout_mint(),
+            reward_vesting_period: token_vault.reward_vesting_period,
+            proposal_threshold: governance.proposal_threshold,
+            voting_period: governance.voting_period,
+            quorum_percentage: governance.quorum_percentage,
+            timelock_duration: governance.timelock_duration,
+            conviction_threshold_percentage: governance.conviction_threshold_percentage,
+            optimistic_challenge_period: governance.optimistic_challenge_period,
+            optimistic_veto_stake: governance.optimistic_veto_stake,
+            emergency_voting_period: governance.emergency_voting_period,
+            emergency_quorum_percentage: governance.emergency_quorum_percentage,
+            severity_threshold_bps: governance.severity_threshold_bps,
+            feeds,
+        })
+    }
+
+    // ========================================================================
+    // Publisher Instructions
+    // ========================================================================
+
+    /// Quotes the feed at the current time. `sequence` must exceed the last one this
+    /// publisher quoted the feed with, so a delayed or replayed update cannot overwrite
+    /// a newer quote.
+    pub fn update_price(
+        ctx: Context<UpdatePrice>,
+        price: i64,
+        confidence: u64,
+        source_id: u64,
+        sequence: u32,
+    ) -> Result<()> {
+        require!(!ctx.accounts.price_account.load()?.commit_reveal(), ErrorCode::CommitRevealRequired);
+
+        record_quote(ctx.accounts, price, confidence, source_id, sequence)
+    }
+
+    pub fn create_price_batch(
+        ctx: Context<CreatePriceBatch>,
+    ) -> Result<()> {
+        let price_batch = &mut ctx.accounts.price_batch;
+        price_batch.publisher = ctx.accounts.publisher_authority.key();
+        price_batch.root = [0; 32];
+        price_batch.timestamp = 0;
+        price_batch.observed_timestamp = 0;
+        price_batch.slot = 0;
+        price_batch.sequence = 0;
+        price_batch.bump = ctx.bumps.price_batch;
+
+        Ok(())
+    }
+
+    /// Posts one Merkle root covering this publisher's quotes for many feeds, observed at
+    /// `observed_timestamp`. The quotes take effect per feed once applied with
+    /// `apply_batched_price`. `sequence` must exceed the current batch's, so a delayed
+    /// submission cannot replace a newer root.
+    pub fn submit_price_batch(
+        ctx: Context<SubmitPriceBatch>,
+        root: [u8; 32],
+        sequence: u64,
+        observed_timestamp: i64,
+    ) -> Result<()> {
+        require!(!ctx.accounts.global_state.paused, ErrorCode::SystemPaused);
+        require_cosigner(&ctx.accounts.publisher_account, ctx.accounts.cosigner.as_ref())?;
+
+        let clock = program_clock(&ctx.accounts.global_state)?;
+        require!(
+            (observed_timestamp - clock.unix_timestamp).abs() <= MAX_OBSERVATION_SKEW,
+            ErrorCode::InvalidTimestamp
+        );
+        let price_batch = &mut ctx.accounts.price_batch;
+        require!(sequence > price_batch.sequence, ErrorCode::QuoteReplayed);
+        price_batch.root = root;
+        price_batch.timestamp = clock.unix_timestamp;
+        price_batch.observed_timestamp = observed_timestamp;
+        price_batch.slot = clock.slot;
+        price_batch.sequence = sequence;
+
+        emit!(PriceBatchSubmitted {
+            publisher: price_batch.publisher,
+            root,
+            slot: clock.slot,
+            sequence,
+            observed_timestamp,
+            posted_timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Permissionless: writes one feed's quote from the publisher's latest batch
+    pub fn apply_batched_price(
+        ctx: Context<ApplyBatchedPrice>,
+        price: i64,
+        confidence: u64,
+        source_id: u64,
+        proof: Vec<[u8; 32]>,
+    ) -> Result<()> {
+        let price_batch = &ctx.accounts.price_batch;
+        let price_account = &mut ctx.accounts.price_account.load_mut()?;
+        // Commit-reveal feeds exist to keep quotes hidden until reveal
+        require!(!price_account.commit_reveal(), ErrorCode::CommitRevealRequired);
+        require!(
+            verify_merkle_proof(
+                &price_batch.root,
+                batch_leaf(&ctx.accounts.price_account.key(), price_batch.sequence, price, confidence, source_id),
+                &proof,
+            ),
+            ErrorCode::InvalidBatchProof
+        );
+
+        let publisher = price_batch.publisher;
+        require!(
+            !price_account.permissioned() || ctx.accounts.feed_permission.is_some(),
+            ErrorCode::PublisherNotPermitted
+        );
+        if !within_quota(
+            ctx.accounts.feed_quota.as_deref_mut(),
+            price_account,
+            &publisher,
+            ctx.accounts.global_state.current_epoch,
+        )? {
+            return Ok(());
+        }
+        let joined = quote_index(price_account, &publisher).is_none();
+
+        let mut price_history = ctx.accounts.price_history.as_ref().map(|history| history.load_mut()).transpose()?;
+        write_quote(
+            &ctx.accounts.global_state,
+            &ctx.accounts.token_vault,
+            price_account,
+            &mut ctx.accounts.publisher_account,
+            AggregationAccounts {
+                product: &ctx.accounts.product_account,
+                slot_hashes: ctx.accounts.slot_hashes.as_ref().map(|a| a.as_ref()),
+                price_history: price_history.as_deref_mut(),
+            },
+            QuoteInput {
+                price,
+                confidence,
+                source_id,
+                // Quotes age from when they were observed, but never from the future
+                timestamp: price_batch.observed_timestamp.min(price_batch.timestamp),
+                slot: price_batch.slot,
+                sequence: None,
+            },
+        )?;
+
+        if let Some(feed_quota) = ctx.accounts.feed_quota.as_deref_mut() {
+            count_submission(feed_quota, price_account, &publisher, joined);
+        }
+
+        Ok(())
+    }
+
+    /// Direct quotes for many feeds in one transaction, encoded with `quote_codec`. The
+    /// feeds are passed as (product, price) pairs in remaining_accounts, in the order of
+    /// the encoded quotes, and each price is encoded against that feed's current
+    /// aggregate. All quotes share `sequence`, which must exceed the publisher's last
+    /// sequence on every feed.
+    pub fn submit_compact_quotes<'info>(
+        ctx: Context<'_, '_, 'info, 'info, SubmitCompactQuotes<'info>>,
+        sequence: u32,
+        data: Vec<u8>,
+    ) -> Result<()> {
+        require_cosigner(&ctx.accounts.publisher_account, ctx.accounts.cosigner.as_ref())?;
+        require!(
+            !ctx.remaining_accounts.is_empty() && ctx.remaining_accounts.chunks_exact(2).remainder().is_empty(),
+            ErrorCode::InvalidCompactQuotes
+        );
+
+        let clock = program_clock(&ctx.accounts.global_state)?;
+        let mut data = data.as_slice();
+        for pair in ctx.remaining_accounts.chunks_exact(2) {
+            let product = Account::<ProductAccount>::try_from(&pair[0])?;
+            let feed = AccountLoader::<PriceAccount>::try_from(&pair[1])?;
+            require!(
+                product.price_account == feed.key() && pair[1].is_writable,
+                ErrorCode::InvalidCompactQuotes
+            );
+            let price_account = &mut feed.load_mut()?;
+            require!(!price_account.commit_reveal(), ErrorCode::CommitRevealRequired);
+            // No quota or permission accounts are passed, so those feeds take their quotes elsewhere
+            require!(!price_account.submission_quota(), ErrorCode::FeedQuotaRequired);
+            require!(!price_account.permissioned(), ErrorCode::PublisherNotPermitted);
+
+            let quote = quote_codec::decode(&mut data, price_account.aggregate.price)
+                .ok_or(ErrorCode::InvalidCompactQuotes)?;
+            write_quote(
+                &ctx.accounts.global_state,
+                &ctx.accounts.token_vault,
+                price_account,
+                &mut ctx.accounts.publisher_account,
+                AggregationAccounts {
+                    product: &product,
+                    slot_hashes: ctx.accounts.slot_hashes.as_ref().map(|a| a.as_ref()),
+                    price_history: None,
+                },
+                QuoteInput {
+                    price: quote.price,
+                    confidence: quote.confidence,
+                    source_id: quote.source_id,
+                    timestamp: clock.unix_timestamp,
+                    slot: clock.slot,
+                    sequence: Some(sequence),
+                },
+            )?;
+        }
+        // Leftover bytes mean the quotes and the feeds passed are out of step
+        require!(data.is_empty(), ErrorCode::InvalidCompactQuotes);
+
+        Ok(())
+    }
+
+    pub fn create_quote_commitment(
+        ctx: Context<CreateQuoteCommitment>,
+    ) -> Result<()> {
+        let quote_commitment = &mut ctx.accounts.quote_commitment;
+        quote_commitment.price_account = ctx.accounts.price_account.key();
+        quote_commitment.publisher = ctx.accounts.publisher_authority.key();
+        quote_commitment.commitment = [0; 32];
+        quote_commitment.commit_slot = 0;
+        quote_commitment.bump = ctx.bumps.quote_commitment;
+
+        Ok(())
+    }
+
+    /// First half of a commit-reveal quote: post the hash now, reveal it in a later slot
+    pub fn commit_price(
+        ctx: Context<CommitPrice>,
+        commitment: [u8; 32],
+    ) -> Result<()> {
+        require!(!ctx.accounts.global_state.paused, ErrorCode::SystemPaused);
+        require!(ctx.accounts.price_account.load()?.commit_reveal(), ErrorCode::CommitRevealDisabled);
+
+        let slot = program_clock(&ctx.accounts.global_state)?.slot;
+        let quote_commitment = &mut ctx.accounts.quote_commitment;
+        quote_commitment.commitment = commitment;
+        quote_commitment.commit_slot = slot;
+
+        emit!(QuoteCommitted {
+            price_account: quote_commitment.price_account,
+            publisher: quote_commitment.publisher,
+            commitment,
+            slot,
+        });
+
+        Ok(())
+    }
+
+    pub fn reveal_price(
+        ctx: Context<RevealPrice>,
+        price: i64,
+        confidence: u64,
+        source_id: u64,
+        salt: [u8; 32],
+        sequence: u32,
+    ) -> Result<()> {
+        require!(ctx.accounts.update.price_account.load()?.commit_reveal(), ErrorCode::CommitRevealDisabled);
+
+        let slot = program_clock(&ctx.accounts.update.global_state)?.slot;
+        let quote_commitment = &mut ctx.accounts.quote_commitment;
+
+        // Revealing in the commit slot would let others copy the quote within the same slot
+        require!(
+            quote_commitment.commit_slot > 0
+                && slot > quote_commitment.commit_slot
+                && slot <= quote_commitment.commit_slot + MAX_REVEAL_DELAY_SLOTS,
+            ErrorCode::RevealNotReady
+        );
+        require!(
+            quote_commitment_hash(
+                &quote_commitment.price_account,
+                &quote_commitment.publisher,
+                price,
+                confidence,
+                source_id,
+                &salt,
+            ) == quote_commitment.commitment,
+            ErrorCode::InvalidReveal
+        );
+        quote_commitment.commit_slot = 0;
+
+        record_quote(&mut ctx.accounts.update, price, confidence, source_id, sequence)
+    }
+
+    pub fn stake_tokens<'info>(
+        ctx: Context<'_, '_, 'info, 'info, StakeTokens<'info>>,
+        amount: u64,
+    ) -> Result<()> {
+        require!(!ctx.accounts.global_state.paused, ErrorCode::SystemPaused);
+        require!(amount > 0, ErrorCode::InsufficientStake);
+
+        let vault_balance = ctx.accounts.vault_token_account.amount;
+        let cpi_accounts = TransferChecked {
+            from: ctx.accounts.publisher_token_account.to_account_info(),
+            mint: ctx.accounts.token_mint.to_account_info(),
+            to: ctx.accounts.vault_token_account.to_account_info(),
+            authority: ctx.accounts.publisher_authority.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts)
+            .with_remaining_accounts(ctx.remaining_accounts.to_vec());
+        transfer_checked_with_hooks(cpi_ctx, amount, ctx.accounts.token_mint.decimals)?;
+
+        // Token-2022 transfer fees are withheld in transit, so book what arrived
+        let amount = vault_received(&mut ctx.accounts.vault_token_account, vault_balance)?;
+
+        // Top-ups only grow bonded stake; a pending unbonding batch keeps its amount and clock
+        let publisher = &mut ctx.accounts.publisher_account;
+        publisher.staked_amount = publisher.staked_amount
+            .checked_add(amount)
+            .ok_or(ErrorCode::Overflow)?;
+        ctx.accounts.token_vault.total_staked += amount;
+
+        emit!(StakeChanged {
+            publisher: publisher.key(),
+            transition: StakeTransition::ToppedUp,
+            amount,
+            staked_amount: publisher.staked_amount,
+            unbonding_amount: publisher.stake_state.unbonding_amount(),
+            timestamp: program_clock(&ctx.accounts.global_state)?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn unstake_tokens(
+        ctx: Context<UnstakeTokens>,
+        amount: u64,
+    ) -> Result<()> {
+        require!(!ctx.accounts.global_state.paused, ErrorCode::SystemPaused);
+        require!(amount > 0, ErrorCode::InsufficientStake);
+        
+        let publisher = &mut ctx.accounts.publisher_account;
+        let remaining = publisher.staked_amount.checked_sub(amount)
+            .ok_or(ErrorCode::InsufficientStake)?;
+        require!(remaining >= publisher.locked_stake, ErrorCode::StakeLocked);
+        
+        require!(
+            remaining >= min_stake_for(&ctx.accounts.global_state, publisher),
+            ErrorCode::InsufficientStake
+        );
+        require!(
+            remaining >= required_stake(&ctx.accounts.global_state, publisher, publisher.feed_count)?,
+            ErrorCode::InsufficientExposureStake
+        );
+
+        let now = program_clock(&ctx.accounts.global_state)?.unix_timestamp;
+        let (transition, unbonding) = match publisher.stake_state {
+            StakeState::Bonded => (StakeTransition::UnbondStarted, amount),
+            StakeState::Unbonding { amount: pending, .. } => (
+                StakeTransition::UnbondExtended,
+                pending.checked_add(amount).ok_or(ErrorCode::Overflow)?,
+            ),
+        };
+
+        publisher.stake_state = StakeState::Unbonding {
+            amount: unbonding,
+            start: now,
+            period: ctx.accounts.global_state.unbonding_period,
+        };
+        publisher.staked_amount = remaining;
+
+        emit!(StakeChanged {
+            publisher: publisher.key(),
+            transition,
+            amount,
+            staked_amount: remaining,
+            unbonding_amount: unbonding,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+
+    /// Pays out `amount` of the matured unbonding balance, or all of it when `None`.
+    /// The rest stays unbonded (and slashable) until a later withdrawal.
+    pub fn withdraw_unbonded<'info>(
+        ctx: Context<'_, '_, 'info, 'info, WithdrawUnbonded<'info>>,
+        amount: Option<u64>,
+    ) -> Result<()> {
+        let publisher = &mut ctx.accounts.publisher_account;
+        let clock = program_clock(&ctx.accounts.global_state)?;
+        
+        let (matured, start, period) = match publisher.stake_state {
+            StakeState::Bonded => return err!(ErrorCode::InsufficientStake),
+            StakeState::Unbonding { amount, start, period } => {
+                require!(
+                    clock.unix_timestamp - start >= period,
+                    ErrorCode::UnbondingPeriodActive
+                );
+                (amount, start, period)
+            },
+        };
+        let amount = amount.unwrap_or(matured);
+        require!(amount > 0 && amount <= matured, ErrorCode::InsufficientStake);
+
+        prepare_payout_account(
+            &ctx.accounts.publisher_token_account,
+            &ctx.accounts.publisher_authority,
+            &ctx.accounts.token_mint,
+            &ctx.accounts.token_program,
+            ctx.accounts.associated_token_program.as_ref(),
+            ctx.accounts.system_program.as_ref(),
+        )?;
+
+        // Transfer using vault authority PDA
+        let seeds = ctx.accounts.global_state.custody_signer_seeds();
+        let signer = &[&seeds[..]];
+
+        let cpi_accounts = TransferChecked {
+            from: ctx.accounts.vault_token_account.to_account_info(),
+            mint: ctx.accounts.token_mint.to_account_info(),
+            to: ctx.accounts.publisher_token_account.to_account_info(),
+            authority: ctx.accounts.vault_authority.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer)
+            .with_remaining_accounts(ctx.remaining_accounts.to_vec());
+        transfer_checked_with_hooks(cpi_ctx, amount, ctx.accounts.token_mint.decimals)?;
+
+        let remaining = matured - amount;
+        publisher.stake_state = if remaining == 0 {
+            StakeState::Bonded
+        } else {
+            StakeState::Unbonding { amount: remaining, start, period }
+        };
+
+        // Receipts may not outlive the stake they represent, including slashed stake
+        let burn_amount = publisher.receipt_balance.saturating_sub(publisher.staked_amount);
+        if burn_amount > 0 {
+            let receipt_mint = ctx.accounts.receipt_mint
+                .as_ref()
+                .ok_or(ErrorCode::InvalidReceiptAccount)?;
+            let receipt_account = ctx.accounts.publisher_receipt_account
+                .as_ref()
+                .ok_or(ErrorCode::InvalidReceiptAccount)?;
+            let token_program = ctx.accounts.token_program.to_account_info();
+            let vault_authority = ctx.accounts.vault_authority.to_account_info();
+
+            if receipt_account.is_frozen() {
+                let cpi_accounts = ThawAccount {
+                    account: receipt_account.to_account_info(),
+                    mint: receipt_mint.to_account_info(),
+                    authority: vault_authority.clone(),
+                };
+                token_interface::thaw_account(CpiContext::new_with_signer(token_program.clone(), cpi_accounts, signer))?;
+            }
+
+            let cpi_accounts = Burn {
+                mint: receipt_mint.to_account_info(),
+                from: receipt_account.to_account_info(),
+                authority: ctx.accounts.publisher_authority.to_account_info(),
+            };
+            token_interface::burn(CpiContext::new(token_program.clone(), cpi_accounts), burn_amount)?;
+
+            if !ctx.accounts.global_state.receipts_transferable {
+                let cpi_accounts = FreezeAccount {
+                    account: receipt_account.to_account_info(),
+                    mint: receipt_mint.to_account_info(),
+                    authority: vault_authority,
+                };
+                token_interface::freeze_account(CpiContext::new_with_signer(token_program, cpi_accounts, signer))?;
+            }
+
+            publisher.receipt_balance -= burn_amount;
+
+            emit!(StakeReceiptsBurned {
+                publisher: publisher.key(),
+                amount: burn_amount,
+                receipt_balance: publisher.receipt_balance,
+            });
+        }
+        ctx.accounts.token_vault.total_staked = ctx.accounts.token_vault.total_staked
+            .checked_sub(amount)
+            .ok_or(ErrorCode::Overflow)?;
+
+        emit!(StakeChanged {
+            publisher: publisher.key(),
+            transition: StakeTransition::Withdrawn,
+            amount,
+            staked_amount: publisher.staked_amount,
+            unbonding_amount: remaining,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    // ========================================================================
+    // Feed Configuration Instructions
+    // ========================================================================
+
+    pub fn update_feed_config(
+        ctx: Context<UpdateFeedConfig>,
+        update: FeedConfigUpdate,
+    ) -> Result<()> {
+        let is_authority = ctx.accounts.admin.key() == ctx.accounts.price_account.load()?.authority;
+        let permissions = if is_authority {
+            u8::MAX
+        } else {
+            ctx.accounts.feed_admin.as_ref().ok_or(ErrorCode::Unauthorized)?.permissions
+        };
+
+        let price_account = &mut ctx.accounts.price_account.load_mut()?;
+        apply_feed_config(price_account, &update, is_authority, permissions)?;
+
+        emit!(FeedConfigChanged {
+            price_account: ctx.accounts.price_account.key(),
+            update,
+            changed_by: ctx.accounts.admin.key(),
+            proposal_id: None,
+            slot: Clock::get()?.slot,
+        });
+
+        Ok(())
+    }
+
+    /// Sets the product's trading calendar; aggregates report `Closed` outside it
+    pub fn set_trading_calendar(
+        ctx: Context<SetTradingCalendar>,
+        calendar: TradingCalendar,
+    ) -> Result<()> {
+        require!(calendar.is_valid(), ErrorCode::InvalidFeedConfig);
+
+        ctx.accounts.product_account.calendar = calendar.clone();
+
+        emit!(TradingCalendarChanged {
+            product: ctx.accounts.product_account.key(),
+            calendar,
+        });
+
+        Ok(())
+    }
+
+    pub fn grant_feed_admin(
+        ctx: Context<GrantFeedAdmin>,
+        operator: Pubkey,
+        permissions: u8,
+    ) -> Result<()> {
+        require!(
+            permissions != 0 && permissions & !(FEED_ADMIN_SET_STATUS | FEED_ADMIN_SET_STALENESS) == 0,
+            ErrorCode::InvalidFeedConfig
+        );
+
+        let feed_admin = &mut ctx.accounts.feed_admin;
+        feed_admin.price_account = ctx.accounts.price_account.key();
+        feed_admin.operator = operator;
+        feed_admin.permissions = permissions;
+        feed_admin.bump = ctx.bumps.feed_admin;
+
+        emit!(FeedAdminUpdated {
+            price_account: feed_admin.price_account,
+            operator,
+            permissions,
+        });
+
+        Ok(())
+    }
+
+    pub fn revoke_feed_admin(
+        ctx: Context<RevokeFeedAdmin>,
+    ) -> Result<()> {
+        emit!(FeedAdminUpdated {
+            price_account: ctx.accounts.price_account.key(),
+            operator: ctx.accounts.feed_admin.operator,
+            permissions: 0,
+        });
+
+        Ok(())
+    }
+
+    /// Allows `publisher` to quote the feed once it is permissioned. Takes effect for
+    /// existing quote holders too: turning `permissioned` on stops everyone without one.
+    pub fn permission_publisher(
+        ctx: Context<PermissionPublisher>,
+        publisher: Pubkey,
+    ) -> Result<()> {
+        let feed_permission = &mut ctx.accounts.feed_permission;
+        feed_permission.price_account = ctx.accounts.price_account.key();
+        feed_permission.publisher = publisher;
+        feed_permission.bump = ctx.bumps.feed_permission;
+
+        emit!(PublisherPermissionUpdated {
+            price_account: feed_permission.price_account,
+            publisher,
+            permitted: true,
+        });
+
+        Ok(())
+    }
+
+    pub fn revoke_publisher_permission(
+        ctx: Context<RevokePublisherPermission>,
+    ) -> Result<()> {
+        emit!(PublisherPermissionUpdated {
+            price_account: ctx.accounts.price_account.key(),
+            publisher: ctx.accounts.feed_permission.publisher,
+            permitted: false,
+        });
+
+        Ok(())
+    }
+
+    /// Starts the notice period before a halted feed can be retired. The notice outlasts a
+    /// full governance cycle, so a DrawListingCollateral proposal raised in response can
+    /// still be executed before the collateral is returned.
+    pub fn announce_retirement(ctx: Context<AnnounceRetirement>) -> Result<()> {
+        require!(ctx.accounts.price_account.load()?.halted(), ErrorCode::FeedNotRetirable);
+
+        let clock = program_clock(&ctx.accounts.global_state)?;
+        let feed_collateral = &mut ctx.accounts.feed_collateral;
+        feed_collateral.retire_after = clock.unix_timestamp
+            .checked_add(RETIREMENT_NOTICE_SECONDS)
+            .ok_or(ErrorCode::Overflow)?;
+
+        emit!(FeedRetirementAnnounced {
+            price_account: feed_collateral.price_account,
+            retire_after: feed_collateral.retire_after,
+        });
+
+        Ok(())
+    }
+
+    /// Returns the listing collateral of a feed that has been wound down: halted by its
+    /// authority, left by every publisher and past its retirement notice. The feed is
+    /// frozen so it cannot be reopened without governance.
+    pub fn retire_feed<'info>(
+        ctx: Context<'_, '_, 'info, 'info, RetireFeed<'info>>,
+    ) -> Result<()> {
+        let clock = program_clock(&ctx.accounts.global_state)?;
+        let retire_after = ctx.accounts.feed_collateral.retire_after;
+        require!(
+            retire_after != 0 && clock.unix_timestamp >= retire_after,
+            ErrorCode::RetirementNoticePending
+        );
+
+        let price_account = &mut ctx.accounts.price_account.load_mut()?;
+        require!(
+            price_account.halted() && price_account.publisher_count == 0,
+            ErrorCode::FeedNotRetirable
+        );
+        price_account.frozen = 1;
+
+        let amount = ctx.accounts.feed_collateral.amount;
+        if amount > 0 {
+            let seeds = ctx.accounts.global_state.custody_signer_seeds();
+            let signer = &[&seeds[..]];
+
+            let cpi_accounts = TransferChecked {
+                from: ctx.accounts.vault_token_account.to_account_info(),
+                mint: ctx.accounts.token_mint.to_account_info(),
+                to: ctx.accounts.creator_token_account.to_account_info(),
+                authority: ctx.accounts.vault_authority.to_account_info(),
+            };
+            let cpi_program = ctx.accounts.token_program.to_account_info();
+            let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer)
+                .with_remaining_accounts(ctx.remaining_accounts.to_vec());
+            transfer_checked_with_hooks(cpi_ctx, amount, ctx.accounts.token_mint.decimals)?;
+
+            ctx.accounts.token_vault.listing_collateral = ctx.accounts.token_vault.listing_collateral
+                .checked_sub(amount)
+                .ok_or(ErrorCode::Overflow)?;
+        }
+
+        emit!(FeedRetired {
+            price_account: ctx.accounts.price_account.key(),
+            collateral_returned: amount,
+        });
+
+        Ok(())
+    }
+
+    /// Opens the account collecting publisher halt votes for a feed. Permissionless.
+    pub fn create_feed_halt_vote(
+        ctx: Context<CreateFeedHaltVote>,
+    ) -> Result<()> {
+        let halt_vote = &mut ctx.accounts.halt_vote;
+        halt_vote.price_account = ctx.accounts.price_account.key();
+        halt_vote.round_start_slot = 0;
+        halt_vote.voters = 0;
+        halt_vote.last_halt_slot = 0;
+        halt_vote.bump = ctx.bumps.halt_vote;
+
+        Ok(())
+    }
+
+    /// Votes to halt a feed the signing publisher quotes on, e.g. on upstream market
+    /// disruption. Once publishers holding HALT_SUPERMAJORITY_BPS of the feed's stake vote
+    /// within one round, the feed is halted for EMERGENCY_HALT_SLOTS. The halt lapses
+    /// unless governance ratifies it with a FreezeFeed proposal or the authority halts the feed.
+    /// After a halt lapses unratified, publishers must wait EMERGENCY_HALT_COOLDOWN_SLOTS
+    /// before voting again, so they cannot keep a feed halted without governance.
+    pub fn halt_feed(
+        ctx: Context<HaltFeed>,
+    ) -> Result<()> {
+        let slot = Clock::get()?.slot;
+        let price_account = &mut ctx.accounts.price_account.load_mut()?;
+        let halt_vote = &mut ctx.accounts.halt_vote;
+        let publisher = ctx.accounts.publisher_authority.key();
+
+        require!(!price_account.is_halted(slot), ErrorCode::FeedAlreadyHalted);
+        // Governance lifting a freeze clears emergency_halt_until_slot, which ends the cooldown
+        require!(
+            price_account.emergency_halt_until_slot == 0
+                || slot >= halt_vote.last_halt_slot.saturating_add(EMERGENCY_HALT_COOLDOWN_SLOTS),
+            ErrorCode::HaltCoolingDown
+        );
+
+        let index = price_account.publishers
+            .iter()
+            .position(|quote| quote.active() && quote.publisher == publisher)
+            .ok_or(ErrorCode::UnauthorizedPublisher)?;
+
+        if slot > halt_vote.round_start_slot.saturating_add(HALT_VOTE_WINDOW_SLOTS) {
+            halt_vote.round_start_slot = slot;
+            halt_vote.voters = 0;
+        }
+        halt_vote.voters |= 1u128 << index;
+
+        let (voted_stake, total_stake) = price_account.publishers
+            .iter()
+            .enumerate()
+            .filter(|(_, quote)| quote.active())
+            .fold((0u64, 0u64), |(voted, total), (i, quote)| {
+                let voted = if halt_vote.voters & (1u128 << i) != 0 {
+                    voted.saturating_add(quote.stake)
+                } else {
+                    voted
+                };
+                (voted, total.saturating_add(quote.stake))
+            });
+
+        emit!(HaltVoteCast {
+            price_account: ctx.accounts.price_account.key(),
+            publisher,
+            voted_stake,
+            total_stake,
+            round_start_slot: halt_vote.round_start_slot,
+        });
+
+        if total_stake > 0
+            && voted_stake as u128 * 10_000 >= total_stake as u128 * HALT_SUPERMAJORITY_BPS as u128
+        {
+            price_account.emergency_halt_until_slot = slot + EMERGENCY_HALT_SLOTS;
+            price_account.aggregate.status = PriceStatus::Halted as u8;
+            halt_vote.voters = 0;
+            halt_vote.last_halt_slot = slot;
+
+            emit!(FeedEmergencyHalted {
+                price_account: ctx.accounts.price_account.key(),
+                voted_stake,
+                total_stake,
+                halted_until_slot: price_account.emergency_halt_until_slot,
+            });
+        }
+
+        Ok(())
+    }
+
+    // ========================================================================
+    // Reward Instructions
+    // ========================================================================
+
+    pub fn fund_rewards<'info>(
+        ctx: Context<'_, '_, 'info, 'info, FundRewards<'info>>,
+        amount: u64,
+    ) -> Result<()> {
+        require!(amount > 0, ErrorCode::InvalidPrice);
+
+        let vault_balance = ctx.accounts.reward_vault_token_account.amount;
+        let cpi_accounts = TransferChecked {
+            from: ctx.accounts.funder_token_account.to_account_info(),
+            mint: ctx.accounts.reward_mint.to_account_info(),
+            to: ctx.accounts.reward_vault_token_account.to_account_info(),
+            authority: ctx.accounts.funder.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts)
+            .with_remaining_accounts(ctx.remaining_accounts.to_vec());
+        transfer_checked_with_hooks(cpi_ctx, amount, ctx.accounts.reward_mint.decimals)?;
+
+        // Token-2022 transfer fees are withheld in transit, so book what arrived
+        let amount = vault_received(&mut ctx.accounts.reward_vault_token_account, vault_balance)?;
+
+        let token_vault = &mut ctx.accounts.token_vault;
+        token_vault.reward_pool = token_vault.reward_pool
+            .checked_add(amount)
+            .ok_or(ErrorCode::Overflow)?;
+
+        emit!(RewardsFunded {
+            funder: ctx.accounts.funder.key(),
+            amount,
+            reward_pool: token_vault.reward_pool,
+        });
+
+        Ok(())
+    }
+
+    pub fn claim_rewards<'info>(
+        ctx: Context<'_, '_, 'info, 'info, ClaimRewards<'info>>,
+    ) -> Result<()> {
+        require!(!ctx.accounts.global_state.paused, ErrorCode::SystemPaused);
+
+        let slot = program_clock(&ctx.accounts.global_state)?.slot;
+        accrue_rewards(&mut ctx.accounts.publisher_account, &ctx.accounts.token_vault, slot)?;
+
+        // Pay what the pool can cover; the remainder stays pending
+        let amount = ctx.accounts.publisher_account.pending_rewards
+            .min(ctx.accounts.token_vault.reward_pool);
+        let now = program_clock(&ctx.accounts.global_state)?.unix_timestamp;
+        let vested = ctx.accounts.publisher_account.reward_vesting.releasable(now);
+        require!(amount > 0 || vested > 0, ErrorCode::NoRewardsToClaim);
+
+        // Opted-in publishers route part of each claim into the insurance fund, which
+        // is held in the staking mint
+        let insurance_contribution = if ctx.accounts.token_vault.has_separate_reward_mint() {
+            0
+        } else {
+            (amount as u128 * ctx.accounts.publisher_account.insurance_share_bps as u128 / 10_000) as u64
+        };
+
+        // With vesting on, the claim joins the schedule and only matured rewards are paid
+        let vesting_period = ctx.accounts.token_vault.reward_vesting_period;
+        let claimed = amount - insurance_contribution;
+        let (payout, newly_locked) = if vesting_period > 0 {
+            (vested, claimed)
+        } else {
+            (vested + claimed, 0)
+        };
+
+        if payout > 0 {
+            prepare_payout_account(
+                &ctx.accounts.publisher_token_account,
+                &ctx.accounts.publisher_authority,
+                &ctx.accounts.reward_mint,
+                &ctx.accounts.token_program,
+                ctx.accounts.associated_token_program.as_ref(),
+                ctx.accounts.system_program.as_ref(),
+            )?;
+
+            let seeds = ctx.accounts.global_state.custody_signer_seeds();
+            let signer = &[&seeds[..]];
+
+            let cpi_accounts = TransferChecked {
+                from: ctx.accounts.reward_vault_token_account.to_account_info(),
+                mint: ctx.accounts.reward_mint.to_account_info(),
+                to: ctx.accounts.publisher_token_account.to_account_info(),
+                authority: ctx.accounts.vault_authority.to_account_info(),
+            };
+            let cpi_program = ctx.accounts.token_program.to_account_info();
+            let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer)
+                .with_remaining_accounts(ctx.remaining_accounts.to_vec());
+            transfer_checked_with_hooks(cpi_ctx, payout, ctx.accounts.reward_mint.decimals)?;
+        }
+
+        let publisher = &mut ctx.accounts.publisher_account;
+        publisher.pending_rewards -= amount;
+        publisher.insurance_contributed = publisher.insurance_contributed
+            .checked_add(insurance_contribution)
+            .ok_or(ErrorCode::Overflow)?;
+        let vesting = &mut publisher.reward_vesting;
+        vesting.released += vested;
+        if newly_locked > 0 {
+            *vesting = RewardVesting {
+                amount: vesting.unreleased()
+                    .checked_add(newly_locked)
+                    .ok_or(ErrorCode::Overflow)?,
+                released: 0,
+                start: now,
+                period: vesting_period,
+            };
+        }
+
+        let token_vault = &mut ctx.accounts.token_vault;
+        token_vault.reward_pool -= amount;
+        token_vault.vesting_rewards = token_vault.vesting_rewards
+            .checked_add(newly_locked)
+            .and_then(|v| v.checked_sub(vested))
+            .ok_or(ErrorCode::Overflow)?;
+        token_vault.insurance_fund = token_vault.insurance_fund
+            .checked_add(insurance_contribution)
+            .ok_or(ErrorCode::Overflow)?;
+        token_vault.insurance_coverage = token_vault.insurance_coverage
+            .saturating_add(insurance_contribution.saturating_mul(INSURANCE_COVERAGE_MULTIPLIER));
+        token_vault.total_rewards_distributed = token_vault.total_rewards_distributed
+            .checked_add(amount)
+            .ok_or(ErrorCode::Overflow)?;
+
+        emit!(RewardsClaimed {
+            publisher: publisher.key(),
+            amount,
+            insurance_contribution,
+            remaining_pending: publisher.pending_rewards,
+            paid_out: payout,
+            vesting_locked: publisher.reward_vesting.unreleased(),
+        });
+
+        Ok(())
+    }
+
+    /// Creates the vault authority's token account for the reward mint chosen by a
+    /// `SetRewardMint` proposal. Rewards can be funded and claimed once it exists.
+    pub fn create_reward_vault(
+        ctx: Context<CreateRewardVault>,
+    ) -> Result<()> {
+        let token_vault = &ctx.accounts.token_vault;
+        require!(token_vault.has_separate_reward_mint(), ErrorCode::InvalidMint);
+        require_supported_mint(&ctx.accounts.reward_mint.to_account_info())?;
+
+        associated_token::create_idempotent(CpiContext::new(
+            ctx.accounts.associated_token_program.to_account_info(),
+            associated_token::Create {
+                payer: ctx.accounts.payer.to_account_info(),
+                associated_token: ctx.accounts.reward_vault_token_account.to_account_info(),
+                authority: ctx.accounts.vault_authority.to_account_info(),
+                mint: ctx.accounts.reward_mint.to_account_info(),
+                system_program: ctx.accounts.system_program.to_account_info(),
+                token_program: ctx.accounts.token_program.to_account_info(),
+            },
+        ))?;
+
+        ctx.accounts.token_vault.reward_vault_token_account = ctx.accounts.reward_vault_token_account.key();
+
+        Ok(())
+    }
+
+    /// Publishes who operates this publisher. Rebinding requires unbinding first so
+    /// consumers see every change as a pair of events.
+    pub fn bind_identity(
+        ctx: Context<BindIdentity>,
+        kind: IdentityKind,
+        identifier: String,
+        proof_hash: [u8; 32],
+    ) -> Result<()> {
+        require!(
+            !identifier.is_empty() && identifier.len() <= MAX_IDENTITY_LEN,
+            ErrorCode::InvalidIdentity
+        );
+
+        let identity = &mut ctx.accounts.publisher_identity;
+        identity.publisher = ctx.accounts.publisher_account.key();
+        identity.kind = kind.clone();
+        identity.identifier = identifier.clone();
+        identity.proof_hash = proof_hash;
+        identity.bound_at = optional_program_clock(ctx.accounts.global_state.as_deref())?.unix_timestamp;
+        identity.bump = ctx.bumps.publisher_identity;
+        // Anyone can claim any identifier, so it only counts once an AttestIdentity proposal vets it
+        ctx.accounts.publisher_account.operator_id = 0;
+
+        emit!(PublisherIdentityBound {
+            publisher: identity.publisher,
+            kind,
+            identifier,
+            proof_hash,
+        });
+
+        Ok(())
+    }
+
+    pub fn unbind_identity(
+        ctx: Context<UnbindIdentity>,
+    ) -> Result<()> {
+        ctx.accounts.publisher_account.operator_id = 0;
+
+        emit!(PublisherIdentityUnbound {
+            publisher: ctx.accounts.publisher_account.key(),
+            identifier: ctx.accounts.publisher_identity.identifier.clone(),
+        });
+
+        Ok(())
+    }
+
+    /// Requires a second key (e.g. a risk-check service) to co-sign every quote. Once set,
+    /// changing or removing the cosigner needs its signature too.
+    pub fn set_quote_cosigner(
+        ctx: Context<SetQuoteCosigner>,
+        cosigner: Option<Pubkey>,
+    ) -> Result<()> {
+        let publisher = &mut ctx.accounts.publisher_account;
+        require_cosigner(publisher, ctx.accounts.current_cosigner.as_ref())?;
+
+        publisher.quote_cosigner = cosigner.unwrap_or_default();
+
+        emit!(QuoteCosignerUpdated {
+            publisher: publisher.key(),
+            cosigner,
+        });
+
+        Ok(())
+    }
+
+    pub fn set_insurance_share(
+        ctx: Context<SetInsuranceShare>,
+        share_bps: u16,
+    ) -> Result<()> {
+        require!(share_bps <= MAX_INSURANCE_SHARE_BPS, ErrorCode::InvalidInsuranceShare);
+        ctx.accounts.publisher_account.insurance_share_bps = share_bps;
+        Ok(())
+    }
+
+    // ========================================================================
+    // Epoch Instructions
+    // ========================================================================
+
+    /// Permissionless crank that starts the next program epoch once EPOCH_LENGTH_SLOTS
+    /// have passed: settles the reward index, snapshots stake and resets per-epoch counters
+    pub fn advance_epoch(
+        ctx: Context<AdvanceEpoch>,
+    ) -> Result<()> {
+        let slot = program_clock(&ctx.accounts.global_state)?.slot;
+        let global_state = &mut ctx.accounts.global_state;
+        require!(
+            slot >= global_state.epoch_start_slot + EPOCH_LENGTH_SLOTS,
+            ErrorCode::EpochNotElapsed
+        );
+
+        let token_vault = &mut ctx.accounts.token_vault;
+        token_vault.settle_reward_index(slot);
+
+        global_state.current_epoch += 1;
+        global_state.epoch_start_slot = slot;
+        global_state.epoch_stake_snapshot = token_vault.total_staked;
+        global_state.registrations_this_epoch = 0;
+
+        emit!(EpochAdvanced {
+            epoch: global_state.current_epoch,
+            start_slot: slot,
+            stake_snapshot: global_state.epoch_stake_snapshot,
+            total_rewards_distributed: token_vault.total_rewards_distributed,
+        });
+
+        Ok(())
+    }
+
+    // ========================================================================
+    // Aggregation
+    // ========================================================================
+
+    pub fn aggregate_price(
+        ctx: Context<AggregatePrice>,
+    ) -> Result<()> {
+        let clock = optional_program_clock(ctx.accounts.global_state.as_deref())?;
+        let slot = clock.slot;
+        let price_account = &mut ctx.accounts.price_account.load_mut()?;
+        let previous_aggregate_slot = price_account.aggregate.slot;
+        let mut price_history = ctx.accounts.price_history.as_ref().map(|history| history.load_mut()).transpose()?;
+        aggregate_prices_internal(
+            price_account,
+            AggregationAccounts {
+                product: &ctx.accounts.product_account,
+                slot_hashes: ctx.accounts.slot_hashes.as_ref().map(|a| a.as_ref()),
+                price_history: price_history.as_deref_mut(),
+            },
+            &clock,
+        )?;
+
+        if let Some(feed_stats) = ctx.accounts.feed_stats.as_mut() {
+            record_feed_stats(feed_stats, price_account, slot);
+        }
+        if let Some(price_mirror) = ctx.accounts.price_mirror.as_mut() {
+            price_mirror.packed = pack_price(&price_account.aggregate);
+        }
+
+        // Only cranks that publish a fresh price are reimbursed; re-running an aggregation
+        // a quote already triggered this slot changes nothing
+        if previous_aggregate_slot < slot
+            && price_account.aggregate.slot == slot
+            && price_account.aggregate.status().is_fresh()
+        {
+            if let (Some(crank_budget), Some(keeper)) = (ctx.accounts.crank_budget.as_mut(), ctx.accounts.keeper.as_ref()) {
+                reimburse_keeper(crank_budget, keeper, slot)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Permissionless crank that frees slots held by quotes older than QUOTE_PRUNE_AGE.
+    /// The owning publisher accounts are passed writable in remaining_accounts so their
+    /// feed counts stay in sync; quotes of publishers not passed are left in place.
+    pub fn prune_feed<'info>(
+        ctx: Context<'_, '_, 'info, 'info, PruneFeed<'info>>,
+    ) -> Result<()> {
+        require!(!ctx.accounts.global_state.paused, ErrorCode::SystemPaused);
+        let clock = program_clock(&ctx.accounts.global_state)?;
+        let price_account = &mut ctx.accounts.price_account.load_mut()?;
+
+        let mut pruned: u8 = 0;
+        for info in ctx.remaining_accounts.iter() {
+            require!(info.is_writable, ErrorCode::UnauthorizedPublisher);
+            let mut publisher = Account::<PublisherAccount>::try_from(info)?;
+
+            for i in 0..MAX_PUBLISHERS {
+                let entry = &price_account.publishers[i];
+                if entry.active()
+                    && entry.publisher == publisher.authority
+                    && clock.unix_timestamp - entry.timestamp >= QUOTE_PRUNE_AGE
+                {
+                    price_account.publishers[i].active = 0;
+                    price_account.publisher_count -= 1;
+                    publisher.feed_count = publisher.feed_count.saturating_sub(1);
+                    pruned += 1;
+                }
+            }
+            publisher.exit(&crate::ID)?;
+        }
+
+        if pruned > 0 {
+            emit!(FeedPruned {
+                product: price_account.product_account,
+                pruned,
+                publisher_count: price_account.publisher_count,
+            });
+
+            if let (Some(crank_budget), Some(keeper)) = (ctx.accounts.crank_budget.as_mut(), ctx.accounts.keeper.as_ref()) {
+                reimburse_keeper(crank_budget, keeper, clock.slot)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Frees the lowest-ranked quote slot of a full feed for a publisher that outranks its
+    /// holder: higher reputation, or equal reputation and more stake. The slot is chosen
+    /// by `lowest_ranked_quote` and must hold a stale quote. Its holder's publisher account
+    /// is passed so its feed count stays in sync. Send it in the same transaction as the new publisher's quote,
+    /// or another publisher may take the slot first.
+    pub fn displace_publisher(
+        ctx: Context<DisplacePublisher>,
+    ) -> Result<()> {
+        require!(!ctx.accounts.global_state.paused, ErrorCode::SystemPaused);
+        let clock = program_clock(&ctx.accounts.global_state)?;
+        let price_account = &mut ctx.accounts.price_account.load_mut()?;
+        let publisher = &ctx.accounts.publisher_account;
+        let displaced = &mut ctx.accounts.displaced_publisher;
+
+        require!(price_account.publisher_count as usize == MAX_PUBLISHERS, ErrorCode::FeedNotFull);
+        require!(quote_index(price_account, &publisher.authority).is_none(), ErrorCode::UnauthorizedPublisher);
+        // Otherwise a publisher that may not quote could still evict one that may
+        require!(
+            !price_account.permissioned() || ctx.accounts.feed_permission.is_some(),
+            ErrorCode::PublisherNotPermitted
+        );
+        require!(
+            publisher.staked_amount >= required_stake(&ctx.accounts.global_state, publisher, publisher.feed_count + 1)?,
+            ErrorCode::InsufficientExposureStake
+        );
+
+        let index = lowest_ranked_quote(price_account, &clock).ok_or(ErrorCode::FeedNotFull)?;
+        require_keys_eq!(price_account.publishers[index].publisher, displaced.authority, ErrorCode::UnauthorizedPublisher);
+        // Stale quotes rank lowest, so a fresh one here means every publisher is quoting
+        let config = aggregation_config(price_account, clock.slot);
+        require!(
+            !aggregation::is_fresh(&config, price_account.publishers[index].timestamp, clock.unix_timestamp),
+            ErrorCode::NoStaleQuote
+        );
+        require!(
+            (publisher.reputation, publisher.staked_amount) > (displaced.reputation, displaced.staked_amount),
+            ErrorCode::InsufficientRank
+        );
+
+        price_account.publishers[index].active = 0;
+        price_account.publisher_count -= 1;
+        displaced.feed_count = displaced.feed_count.saturating_sub(1);
+
+        emit!(PublisherDisplaced {
+            product: price_account.product_account,
+            displaced: displaced.authority,
+            displaced_by: publisher.authority,
+            slot_index: index as u8,
+        });
+
+        Ok(())
+    }
+
+    /// Permissionless crank removing a publisher that has not quoted for
+    /// INACTIVE_DEREGISTRATION_EPOCHS, once `prune_feed` has freed its feed slots. A first
+    /// call moves any bonded stake into unbonding; once the unbonding period has passed, a
+    /// second call returns the stake to the publisher's token account, forfeits unclaimed
+    /// rewards to the pool and closes the account with its rent refunded to the publisher.
+    pub fn deregister_inactive_publisher<'info>(
+        ctx: Context<'_, '_, 'info, 'info, DeregisterInactivePublisher<'info>>,
+    ) -> Result<()> {
+        let global_state = &ctx.accounts.global_state;
+        require!(!global_state.paused, ErrorCode::SystemPaused);
+        let publisher = &mut ctx.accounts.publisher_account;
+        require!(
+            global_state.current_epoch >= publisher.last_active_epoch + INACTIVE_DEREGISTRATION_EPOCHS,
+            ErrorCode::PublisherStillActive
+        );
+        require!(
+            publisher.feed_count == 0 && publisher.receipt_balance == 0 && publisher.locked_stake == 0,
+            ErrorCode::PublisherHasExposure
+        );
+
+        let now = program_clock(&ctx.accounts.global_state)?.unix_timestamp;
+        if publisher.staked_amount > 0 {
+            let amount = publisher.staked_amount;
+            let (transition, unbonding) = match publisher.stake_state {
+                StakeState::Bonded => (StakeTransition::UnbondStarted, amount),
+                StakeState::Unbonding { amount: pending, .. } => (
+                    StakeTransition::UnbondExtended,
+                    pending.checked_add(amount).ok_or(ErrorCode::Overflow)?,
+                ),
+            };
+
+            publisher.stake_state = StakeState::Unbonding {
+                amount: unbonding,
+                start: now,
+                period: global_state.unbonding_period,
+            };
+            publisher.staked_amount = 0;
+
+            emit!(StakeChanged {
+                publisher: publisher.key(),
+                transition,
+                amount,
+                staked_amount: 0,
+                unbonding_amount: unbonding,
+                timestamp: now,
+            });
+            return Ok(());
+        }
+
+        let amount = match publisher.stake_state {
+            StakeState::Bonded => 0,
+            StakeState::Unbonding { amount, start, period } => {
+                require!(now - start >= period, ErrorCode::UnbondingPeriodActive);
+                amount
+            },
+        };
+
+        if amount > 0 {
+            let seeds = global_state.custody_signer_seeds();
+            let signer = &[&seeds[..]];
+
+            let cpi_accounts = TransferChecked {
+                from: ctx.accounts.vault_token_account.to_account_info(),
+                mint: ctx.accounts.token_mint.to_account_info(),
+                to: ctx.accounts.publisher_token_account.to_account_info(),
+                authority: ctx.accounts.vault_authority.to_account_info(),
+            };
+            let cpi_program = ctx.accounts.token_program.to_account_info();
+            let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer)
+                .with_remaining_accounts(ctx.remaining_accounts.to_vec());
+            transfer_checked_with_hooks(cpi_ctx, amount, ctx.accounts.token_mint.decimals)?;
+
+            ctx.accounts.token_vault.total_staked = ctx.accounts.token_vault.total_staked
+                .checked_sub(amount)
+                .ok_or(ErrorCode::Overflow)?;
+
+            emit!(StakeChanged {
+                publisher: publisher.key(),
+                transition: StakeTransition::Withdrawn,
+                amount,
+                staked_amount: 0,
+                unbonding_amount: 0,
+                timestamp: now,
+            });
+        }
+
+        // Unreleased vesting goes back to the pool along with unclaimed rewards
+        let unreleased = publisher.reward_vesting.unreleased();
+        let token_vault = &mut ctx.accounts.token_vault;
+        token_vault.vesting_rewards = token_vault.vesting_rewards
+            .checked_sub(unreleased)
+            .ok_or(ErrorCode::Overflow)?;
+        token_vault.insurance_coverage = token_vault.insurance_coverage
+            .saturating_sub(remaining_insurance_coverage(publisher));
+        token_vault.reward_pool = token_vault.reward_pool
+            .checked_add(unreleased)
+            .ok_or(ErrorCode::Overflow)?;
+
+        let global_state = &mut ctx.accounts.global_state;
+        global_state.total_publishers = global_state.total_publishers.saturating_sub(1);
+
+        emit!(PublisherDeregistered {
+            publisher: publisher.key(),
+            authority: publisher.authority,
+            stake_returned: amount,
+            rewards_forfeited: publisher.pending_rewards + unreleased,
+            last_active_epoch: publisher.last_active_epoch,
+        });
+
+        // Unclaimed rewards never left `reward_pool`, so dropping them returns them to it
+        publisher.close(ctx.accounts.publisher_authority.to_account_info())
+    }
+
+    /// Computes the aggregate the feed would produce right now, optionally with the
+    /// caller's quote replaced by `quote`, and returns it without mutating state.
+    /// Sampled feeds are simulated over all valid quotes rather than the subset a real
+    /// aggregation would draw, so their result can differ from the next aggregate.
+    pub fn simulate_aggregation(
+        ctx: Context<SimulateAggregation>,
+        quote: Option<SimulatedQuote>,
+    ) -> Result<PriceData> {
+        let clock = program_clock(&ctx.accounts.global_state)?;
+        let price_account = &ctx.accounts.price_account.load()?;
+
+        if price_account.is_halted(clock.slot) {
+            return Ok(PriceData {
+                status: PriceStatus::Halted as u8,
+                ..price_account.aggregate
+            });
+        }
+
+        let mut valid_prices = valid_publisher_prices(price_account, &clock);
+
+        let simulated = match quote {
+            Some(quote) => {
+                require!(quote.price > 0, ErrorCode::InvalidPrice);
+                let publisher = ctx.accounts.publisher_account.as_ref().ok_or(ErrorCode::UnauthorizedPublisher)?;
+                valid_prices.retain(|p| p.publisher != publisher.authority);
+                let confidence = quote_confidence(price_account, quote.price, quote.confidence)?;
+                let (price, confidence) = roll_adjusted(price_account, quote.price, confidence)?;
+                Some(PublisherPrice {
+                    publisher: publisher.authority,
+                    price,
+                    confidence,
+                    timestamp: clock.unix_timestamp,
+                    slot: clock.slot,
+                    stake: aggregation_weight(publisher, ctx.accounts.global_state.current_epoch),
+                    active: 1,
+                    source_id: quote.source_id,
+                    operator: publisher.operator_id,
+                    sequence: 0,
+                    outlier_reported: 0,
+                    _padding: [0; 2],
+                })
+            }
+            None => None,
+        };
+        if let Some(simulated) = simulated.as_ref() {
+            valid_prices.push(simulated);
+        }
+
+        match compute_aggregate(price_account, valid_prices, &clock) {
+            Some(aggregate) => Ok(aggregate),
+            None => Ok(PriceData {
+                status: PriceStatus::Unknown as u8,
+                ..price_account.aggregate
+            }),
+        }
+    }
+
+    /// Creates an asset type's template with the program defaults. Permissionless.
+    pub fn create_asset_template(
+        ctx: Context<CreateAssetTemplate>,
+        asset_type: AssetType,
+    ) -> Result<()> {
+        let asset_template = &mut ctx.accounts.asset_template;
+        asset_template.template = AssetTemplateParams::defaults(&asset_type);
+        asset_template.asset_type = asset_type;
+        asset_template.bump = ctx.bumps.asset_template;
+
+        Ok(())
+    }
+
+    /// Starts tracking a feed's uptime from the current slot. Permissionless.
+    pub fn create_feed_stats(
+        ctx: Context<CreateFeedStats>,
+    ) -> Result<()> {
+        let slot = Clock::get()?.slot;
+        let stats = &mut ctx.accounts.feed_stats;
+        stats.price_account = ctx.accounts.price_account.key();
+        stats.window_start_slot = slot;
+        stats.trading_slots = 0;
+        stats.observed_slot = slot;
+        stats.trading_until_slot = 0;
+        stats.last_window_start_slot = 0;
+        stats.last_uptime_bps = 0;
+        stats.bump = ctx.bumps.feed_stats;
+
+        let price_account = ctx.accounts.price_account.load()?;
+        record_feed_stats(stats, &price_account, slot);
+
+        Ok(())
+    }
+
+    /// Permissionless crank bringing a feed's uptime up to the current slot, e.g. to close
+    /// a window on a feed nobody is quoting
+    pub fn update_feed_stats(
+        ctx: Context<UpdateFeedStats>,
+    ) -> Result<()> {
+        let price_account = ctx.accounts.price_account.load()?;
+        record_feed_stats(&mut ctx.accounts.feed_stats, &price_account, Clock::get()?.slot);
+
+        Ok(())
+    }
+
+    /// Creates the feed's PriceMirror, filled from the current aggregate. Permissionless.
+    pub fn create_price_mirror(
+        ctx: Context<CreatePriceMirror>,
+    ) -> Result<()> {
+        let mirror = &mut ctx.accounts.price_mirror;
+        mirror.price_account = ctx.accounts.price_account.key();
+        mirror.packed = pack_price(&ctx.accounts.price_account.load()?.aggregate);
+        mirror.bump = ctx.bumps.price_mirror;
+
+        Ok(())
+    }
+
+    /// Permissionless crank copying the feed's current aggregate into its PriceMirror, for
+    /// aggregates published without the mirror being passed
+    pub fn sync_price_mirror(
+        ctx: Context<SyncPriceMirror>,
+    ) -> Result<()> {
+        ctx.accounts.price_mirror.packed = pack_price(&ctx.accounts.price_account.load()?.aggregate);
+
+        Ok(())
+    }
+
+    /// Creates the feed's PriceHistoryAccount, empty until the next aggregation it is
+    /// passed to. Permissionless.
+    pub fn create_price_history(
+        ctx: Context<CreatePriceHistory>,
+    ) -> Result<()> {
+        let price_history = &mut ctx.accounts.price_history.load_init()?;
+        price_history.product_account = ctx.accounts.product_account.key();
+        price_history.price_account = ctx.accounts.price_account.key();
+        price_history.total_observations = 0;
+        price_history.bump = ctx.bumps.price_history;
+
+        Ok(())
+    }
+
+    /// Starts counting a feed's `read_price` calls. Permissionless.
+    pub fn create_feed_read_stats(
+        ctx: Context<CreateFeedReadStats>,
+    ) -> Result<()> {
+        let read_stats = &mut ctx.accounts.feed_read_stats;
+        read_stats.price_account = ctx.accounts.price_account.key();
+        read_stats.epoch = ctx.accounts.global_state.current_epoch;
+        read_stats.reads = 0;
+        read_stats.last_epoch_reads = 0;
+        read_stats.total_reads = 0;
+        read_stats.bump = ctx.bumps.feed_read_stats;
+
+        Ok(())
+    }
+
+    /// Consumer getter for CPI: the feed's price as `OraclePrice::from_feed` reads it,
+    /// returned through return data. Each call counts towards the feed's read statistics.
+    pub fn read_price(
+        ctx: Context<ReadPrice>,
+        max_age: i64,
+    ) -> Result<OraclePrice> {
+        let epoch = ctx.accounts.global_state.current_epoch;
+        let read_stats = &mut ctx.accounts.feed_read_stats;
+        if read_stats.epoch != epoch {
+            read_stats.last_epoch_reads = if read_stats.epoch + 1 == epoch { read_stats.reads } else { 0 };
+            read_stats.epoch = epoch;
+            read_stats.reads = 0;
+        }
+        read_stats.reads = read_stats.reads.saturating_add(1);
+        read_stats.total_reads = read_stats.total_reads.saturating_add(1);
+
+        let price_account = ctx.accounts.price_account.load()?;
+        OraclePrice::from_feed(
+            &ctx.accounts.product_account,
+            &price_account,
+            max_age,
+            program_clock(&ctx.accounts.global_state)?.unix_timestamp,
+        )
+    }
+
+    /// Consumer getter for CPI: the feed's TWAP over the last `window_seconds` as
+    /// `Twap::from_history` computes it, returned through return data
+    pub fn get_twap(
+        ctx: Context<GetTwap>,
+        window_seconds: i64,
+    ) -> Result<Twap> {
+        let price_account = ctx.accounts.price_account.load()?;
+        let price_history = ctx.accounts.price_history.load()?;
+        Twap::from_history(
+            &price_account,
+            &price_history,
+            window_seconds,
+            program_clock(&ctx.accounts.global_state)?.unix_timestamp,
+        )
+    }
+
+    /// Consumer CPI: records the feed's status at the current slot in a new
+    /// FreshnessAttestation, so the caller keeps an auditable record of the oracle state
+    /// behind a decision such as a liquidation. The attestation account signs its own
+    /// creation, as a fresh keypair or a PDA of the calling program.
+    pub fn attest_freshness(
+        ctx: Context<AttestFreshness>,
+    ) -> Result<()> {
+        let clock = program_clock(&ctx.accounts.global_state)?;
+        let price_account = ctx.accounts.price_account.load()?;
+
+        let attestation = &mut ctx.accounts.attestation;
+        attestation.feed = ctx.accounts.price_account.key();
+        attestation.slot = clock.slot;
+        attestation.timestamp = clock.unix_timestamp;
+        // A fresh status on an aggregate older than the staleness threshold is recorded as Unknown,
+        // and as Closed outside the trading calendar even if no aggregation has run since
+        let staleness_threshold = aggregation_config(&price_account, clock.slot).staleness_threshold;
+        let aggregate = &price_account.aggregate;
+        attestation.status = if price_account.is_halted(clock.slot) {
+            PriceStatus::Halted
+        } else if !ctx.accounts.product_account.calendar.is_open(clock.unix_timestamp) {
+            PriceStatus::Closed
+        } else if aggregate.status().is_fresh()
+            && clock.unix_timestamp.saturating_sub(aggregate.timestamp) > staleness_threshold
+        {
+            PriceStatus::Unknown
+        } else {
+            aggregate.status()
+        };
+        attestation.publish_time = price_account.aggregate.timestamp;
+
+        Ok(())
+    }
+
+    /// Copies the quotes behind the feed's current aggregate into a RoundArchive so the
+    /// round can be disputed later. Permissionless, but only possible until the next
+    /// quote or aggregation changes the feed's committed quotes.
+    pub fn archive_round(
+        ctx: Context<ArchiveRound>,
+    ) -> Result<()> {
+        let price_account = &ctx.accounts.price_account.load()?;
+        let aggregate = &price_account.aggregate;
+        require!(
+            !matches!(aggregate.status(), PriceStatus::Unknown)
+                && !price_account.optimistic()
+                && !price_account.derived(),
+            ErrorCode::RoundNotArchivable
+        );
+
+        // Rebuild the candidate set as of the aggregation and check it against the root
+        let config = aggregation_config(price_account, aggregate.slot);
+        let candidates: Vec<(usize, &PublisherPrice)> = price_account
+            .publishers
+            .iter()
+            .enumerate()
+            .filter(|(_, p)| p.active() && aggregation::is_fresh(&config, p.timestamp, aggregate.timestamp))
+            .collect();
+        let candidate_quotes: Vec<&PublisherPrice> = candidates.iter().map(|(_, q)| *q).collect();
+        require!(
+            quote_merkle_root(&candidate_quotes) == price_account.quotes_root,
+            ErrorCode::RoundNotArchivable
+        );
+
+        let aggregated: Vec<(usize, &PublisherPrice)> = candidates
+            .into_iter()
+            .filter(|(i, _)| price_account.sample_slot == 0 || price_account.sampled_publishers & (1u128 << i) != 0)
+            .collect();
+        let included = aggregated
+            .iter()
+            .enumerate()
+            .filter(|(_, (i, _))| price_account.included_publishers & (1u128 << i) != 0)
+            .fold(0u128, |bitmap, (k, _)| bitmap | (1u128 << k));
+        let quotes: Vec<PublisherPrice> = aggregated.into_iter().map(|(_, q)| *q).collect();
+
+        let archive = &mut ctx.accounts.round_archive;
+        archive.price_account = ctx.accounts.price_account.key();
+        archive.original = *aggregate;
+        archive.quotes_root = price_account.quotes_root;
+        archive.disputed = false;
+        archive.reaggregated = None;
+        archive.excluded_publisher = Pubkey::default();
+        archive.included = included;
+        archive.strategy = AggregationStrategy::StakeWeightedMedian;
+        archive.bump = ctx.bumps.round_archive;
+
+        emit!(RoundArchived {
+            price_account: archive.price_account,
+            round: archive.key(),
+            slot: aggregate.slot,
+            quote_count: quotes.len() as u8,
+        });
+        archive.quotes = quotes;
+
+        Ok(())
+    }
+
+    /// Permissionless: checks the feed's stored exponents and scaled fields against its
+    /// configuration, e.g. after a migration, emitting a `ScalingDiscrepancy` for each
+    /// mismatch. Prices are compared by order of magnitude, so a quote or EMA at the
+    /// wrong exponent shows up while ordinary dispersion does not. Returns the number of
+    /// discrepancies found.
+    pub fn audit_feed_scaling(
+        ctx: Context<AuditFeedScaling>,
+    ) -> Result<u8> {
+        let feed = ctx.accounts.price_account.key();
+        let price_account = &ctx.accounts.price_account.load()?;
+        let aggregate = &price_account.aggregate;
+        let mut discrepancies: u8 = 0;
+        let mut report = |field: ScalingField, index: u8, expected: i128, found: i128| {
+            discrepancies = discrepancies.saturating_add(1);
+            emit!(ScalingDiscrepancy {
+                price_account: feed,
+                field,
+                index,
+                expected,
+                found,
+            });
+        };
+
+        if aggregate.slot != 0 && aggregate.exponent != price_account.exponent {
+            report(
+                ScalingField::AggregateExponent,
+                0,
+                price_account.exponent as i128,
+                aggregate.exponent as i128,
+            );
+        }
+
+        if aggregate.price_i128 != 0 {
+            let rescaled = rescale(aggregate.price_i128, -(HIGH_PRECISION_DECIMALS as i32), price_account.rounding_mode()?)
+                .ok_or(ErrorCode::Overflow)?;
+            if !same_magnitude(aggregate.price as i128, rescaled) {
+                report(ScalingField::HighPrecisionPrice, 0, aggregate.price as i128, rescaled);
+            }
+        }
+
+        if price_account.ema.num_observations > 0
+            && !same_magnitude(aggregate.price as i128, price_account.ema.ema_price as i128)
+        {
+            report(
+                ScalingField::EmaPrice,
+                0,
+                aggregate.price as i128,
+                price_account.ema.ema_price as i128,
+            );
+        }
+
+        for (index, quote) in price_account.publishers[..price_account.publisher_count as usize]
+            .iter()
+            .enumerate()
+        {
+            if quote.active() && quote.timestamp != 0 && !same_magnitude(aggregate.price as i128, quote.price as i128) {
+                report(ScalingField::PublisherQuote, index as u8, aggregate.price as i128, quote.price as i128);
+            }
+        }
+
+        if price_account.price_type()? != PriceType::Futures && price_account.roll_adjustment != 0 {
+            report(ScalingField::RollAdjustment, 0, 0, price_account.roll_adjustment as i128);
+        }
+
+        emit!(ScalingAudited {
+            price_account: feed,
+            exponent: price_account.exponent,
+            discrepancies,
+        });
+
+        Ok(discrepancies)
+    }
+
+    // ========================================================================
+    // Crank Budget Instructions
+    // ========================================================================
+
+    pub fn create_crank_budget(
+        ctx: Context<CreateCrankBudget>,
+        reward_lamports: u64,
+        min_interval_slots: u64,
+    ) -> Result<()> {
+        let crank_budget = &mut ctx.accounts.crank_budget;
+        crank_budget.price_account = ctx.accounts.price_account.key();
+        crank_budget.owner = ctx.accounts.authority.key();
+        crank_budget.reward_lamports = reward_lamports;
+        crank_budget.min_interval_slots = min_interval_slots;
+        crank_budget.last_paid_slot = 0;
+        crank_budget.total_paid = 0;
+        crank_budget.bump = ctx.bumps.crank_budget;
+
+        Ok(())
+    }
+
+    /// Anyone may top up a feed's crank budget
+    pub fn fund_crank_budget(
+        ctx: Context<FundCrankBudget>,
+        lamports: u64,
+    ) -> Result<()> {
+        let cpi_accounts = anchor_lang::system_program::Transfer {
+            from: ctx.accounts.funder.to_account_info(),
+            to: ctx.accounts.crank_budget.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new(ctx.accounts.system_program.to_account_info(), cpi_accounts);
+        anchor_lang::system_program::transfer(cpi_ctx, lamports)
+    }
+
+    /// Closes the budget, returning the remaining lamports to its owner
+    pub fn close_crank_budget(
+        _ctx: Context<CloseCrankBudget>,
+    ) -> Result<()> {
+        Ok(())
+    }
+
+    // ========================================================================
+    // Fast Lane Instructions
+    // ========================================================================
+
+    pub fn create_fast_feed(
+        ctx: Context<CreateFastFeed>,
+    ) -> Result<()> {
+        let fast_price = &mut ctx.accounts.fast_price_account;
+        fast_price.price_account = ctx.accounts.price_account.key();
+        fast_price.aggregate = PriceData {
+            exponent: ctx.accounts.price_account.load()?.exponent,
+            status: PriceStatus::Unknown as u8,
+            ..PriceData::default()
+        };
+        fast_price.members = [Pubkey::default(); MAX_FAST_PUBLISHERS];
+        fast_price.quotes = [PublisherPrice::default(); MAX_FAST_PUBLISHERS];
+        fast_price.member_count = 0;
+        fast_price.min_publishers = 1;
+        fast_price.bump = ctx.bumps.fast_price_account;
+
+        Ok(())
+    }
+
+    /// Replaces the fast-lane publisher set, dropping all current fast quotes
+    pub fn set_fast_publishers(
+        ctx: Context<SetFastPublishers>,
+        members: Vec<Pubkey>,
+        min_publishers: u8,
+    ) -> Result<()> {
+        require!(
+            members.len() <= MAX_FAST_PUBLISHERS
+                && min_publishers > 0
+                && min_publishers as usize <= members.len().max(1),
+            ErrorCode::InvalidFeedConfig
+        );
+
+        let fast_price = &mut ctx.accounts.fast_price_account;
+        fast_price.members = [Pubkey::default(); MAX_FAST_PUBLISHERS];
+        fast_price.members[..members.len()].copy_from_slice(&members);
+        fast_price.quotes = [PublisherPrice::default(); MAX_FAST_PUBLISHERS];
+        fast_price.member_count = members.len() as u8;
+        fast_price.min_publishers = min_publishers;
+        fast_price.aggregate.status = PriceStatus::Unknown as u8;
+
+        emit!(FastPublishersUpdated {
+            price_account: fast_price.price_account,
+            members,
+            min_publishers,
+        });
+
+        Ok(())
+    }
+
+    /// Fast-lane quote; the fast aggregate is recomputed from quotes of the last
+    /// FAST_QUOTE_MAX_AGE_SLOTS slots on every update
+    pub fn update_fast_price(
+        ctx: Context<UpdateFastPrice>,
+        price: i64,
+        confidence: u64,
+    ) -> Result<()> {
+        require!(!ctx.accounts.global_state.paused, ErrorCode::SystemPaused);
+        require!(price > 0, ErrorCode::InvalidPrice);
+
+        let clock = program_clock(&ctx.accounts.global_state)?;
+        let price_account = &ctx.accounts.price_account.load()?;
+        let publisher = &ctx.accounts.publisher_account;
+        let fast_price = &mut ctx.accounts.fast_price_account;
+
+        let index = fast_price.members[..fast_price.member_count as usize]
+            .iter()
+            .position(|member| *member == publisher.authority)
+            .ok_or(ErrorCode::NotFastPublisher)?;
+        require!(
+            publisher.staked_amount >= min_stake_for(&ctx.accounts.global_state, publisher),
+            ErrorCode::InsufficientStake
+        );
+        require!(clock.slot >= publisher.publishing_blocked_until_slot, ErrorCode::PublisherCoolingDown);
+
+        fast_price.quotes[index] = PublisherPrice {
+            publisher: publisher.authority,
+            price,
+            confidence: quote_confidence(price_account, price, confidence)?,
+            timestamp: clock.unix_timestamp,
+            slot: clock.slot,
+            stake: aggregation_weight(publisher, ctx.accounts.global_state.current_epoch),
+            active: 1,
+            source_id: 0,
+            operator: publisher.operator_id,
+            sequence: 0,
+            outlier_reported: 0,
+            _padding: [0; 2],
+        };
+        ctx.accounts.publisher_account.last_active_epoch = ctx.accounts.global_state.current_epoch;
+
+        if price_account.is_halted(clock.slot) {
+            fast_price.aggregate.status = PriceStatus::Halted as u8;
+            return Ok(());
+        }
+
+        let mut fresh: Vec<&PublisherPrice> = fast_price.quotes
+            .iter()
+            .filter(|q| q.active() && clock.slot - q.slot <= FAST_QUOTE_MAX_AGE_SLOTS)
+            .collect();
+        if fresh.len() < fast_price.min_publishers as usize {
+            fast_price.aggregate.status = PriceStatus::Unknown as u8;
+            return Ok(());
+        }
+        fresh.sort_by_key(|q| q.price);
+
+        let median = aggregation::stake_weighted_median(&fresh).ok_or(ErrorCode::InvalidPrice)?;
+        let confidence = aggregation::confidence(&fresh, median);
+        fast_price.aggregate = PriceData {
+            price: median,
+            confidence,
+            exponent: price_account.exponent,
+            timestamp: clock.unix_timestamp,
+            slot: clock.slot,
+            status: PriceStatus::Trading as u8,
+            price_i128: 0,
+            _padding: [0; 11],
+        };
+
+        Ok(())
+    }
+
+    // ========================================================================
+    // Optimistic Posting Instructions
+    // ========================================================================
+
+    /// Switches a feed to optimistic posting by `poster`
+    pub fn create_optimistic_feed(
+        ctx: Context<CreateOptimisticFeed>,
+        poster: Pubkey,
+        challenge_window_slots: u64,
+    ) -> Result<()> {
+        require!(challenge_window_slots > 0, ErrorCode::InvalidFeedConfig);
+
+        let optimistic_feed = &mut ctx.accounts.optimistic_feed;
+        optimistic_feed.price_account = ctx.accounts.price_account.key();
+        optimistic_feed.poster = poster;
+        optimistic_feed.challenge_window_slots = challenge_window_slots;
+        optimistic_feed.posted_price = 0;
+        optimistic_feed.posted_confidence = 0;
+        optimistic_feed.posted_slot = 0;
+        optimistic_feed.disputed = false;
+        optimistic_feed.challenger = Pubkey::default();
+        optimistic_feed.challenge_price = 0;
+        optimistic_feed.challenge_confidence = 0;
+        optimistic_feed.challenge_bond = 0;
+        optimistic_feed.bump = ctx.bumps.optimistic_feed;
+
+        ctx.accounts.price_account.load_mut()?.optimistic = 1;
+
+        Ok(())
+    }
+
+    /// Returns the feed to regular aggregation
+    pub fn close_optimistic_feed(
+        ctx: Context<CloseOptimisticFeed>,
+    ) -> Result<()> {
+        ctx.accounts.price_account.load_mut()?.optimistic = 0;
+        Ok(())
+    }
+
+    /// Writes the aggregate immediately. The post stands unless challenged within
+    /// `challenge_window_slots`.
+    pub fn post_optimistic_price(
+        ctx: Context<PostOptimisticPrice>,
+        price: i64,
+        confidence: u64,
+    ) -> Result<()> {
+        require!(!ctx.accounts.global_state.paused, ErrorCode::SystemPaused);
+        let price_account = &mut ctx.accounts.price_account.load_mut()?;
+        require!(!price_account.is_halted(program_clock(&ctx.accounts.global_state)?.slot), ErrorCode::FeedFrozen);
+        require!(price > 0, ErrorCode::InvalidPrice);
+
+        let optimistic_feed = &mut ctx.accounts.optimistic_feed;
+        require!(!optimistic_feed.disputed, ErrorCode::ChallengeWindowClosed);
+        let publisher = &ctx.accounts.publisher_account;
+        require!(
+            publisher.staked_amount >= min_stake_for(&ctx.accounts.global_state, publisher),
+            ErrorCode::InsufficientStake
+        );
+
+        let confidence = quote_confidence(price_account, price, confidence)?;
+        // A wide band would overlap any honest challenge, so posts must stay within the feed's
+        let max_confidence_bps = if price_account.degraded_confidence_bps > 0 {
+            price_account.degraded_confidence_bps as u64
+        } else {
+            MAX_OPTIMISTIC_CONFIDENCE_BPS
+        };
+        require!(
+            confidence as u128 * 10_000 <= max_confidence_bps as u128 * price as u128,
+            ErrorCode::ConfidenceTooLarge
+        );
+        let clock = program_clock(&ctx.accounts.global_state)?;
+        price_account.accrue_cumulative(clock.unix_timestamp);
+        price_account.aggregate = PriceData {
+            price,
+            confidence,
+            exponent: price_account.exponent,
+            timestamp: clock.unix_timestamp,
+            slot: clock.slot,
+            status: PriceStatus::Trading as u8,
+            price_i128: 0,
+            _padding: [0; 11],
+        };
+        price_account.ema = update_ema(price_account, price, confidence)?;
+        price_account.last_update_slot = clock.slot;
+        ctx.accounts.publisher_account.last_active_epoch = ctx.accounts.global_state.current_epoch;
+
+        optimistic_feed.posted_price = price;
+        optimistic_feed.posted_confidence = confidence;
+        optimistic_feed.posted_slot = clock.slot;
+
+        emit!(OptimisticPricePosted {
+            product: price_account.product_account,
+            poster: optimistic_feed.poster,
+            price,
+            confidence,
+            slot: clock.slot,
+        });
+
+        Ok(())
+    }
+
+    /// A staked publisher disputes the latest post with its own quote, locking a bond of
+    /// its stake. Succeeds only if the two confidence bands do not overlap; the feed then
+    /// halts pending resolution.
+    pub fn challenge_optimistic_price(
+        ctx: Context<ChallengeOptimisticPrice>,
+        price: i64,
+        confidence: u64,
+    ) -> Result<()> {
+        require!(price > 0, ErrorCode::InvalidPrice);
+
+        let slot = program_clock(&ctx.accounts.global_state)?.slot;
+        let optimistic_feed = &mut ctx.accounts.optimistic_feed;
+        require!(
+            !optimistic_feed.disputed
+                && optimistic_feed.posted_slot > 0
+                && slot <= optimistic_feed.posted_slot + optimistic_feed.challenge_window_slots,
+            ErrorCode::ChallengeWindowClosed
+        );
+
+        let publisher = &mut ctx.accounts.publisher_account;
+        require!(publisher.authority != optimistic_feed.poster, ErrorCode::UnauthorizedPublisher);
+        let min_stake = min_stake_for(&ctx.accounts.global_state, publisher);
+        require!(publisher.staked_amount >= min_stake, ErrorCode::InsufficientStake);
+
+        let bond = (min_stake as u128 * OPTIMISTIC_CHALLENGE_BOND_BPS as u128 / 10_000) as u64;
+        let locked_stake = publisher.locked_stake
+            .checked_add(bond)
+            .ok_or(ErrorCode::Overflow)?;
+        require!(locked_stake <= publisher.staked_amount, ErrorCode::InsufficientStake);
+        publisher.locked_stake = locked_stake;
+
+        let confidence = quote_confidence(&*ctx.accounts.price_account.load()?, price, confidence)?;
+        let distance = (optimistic_feed.posted_price as i128 - price as i128).unsigned_abs();
+        require!(
+            distance > optimistic_feed.posted_confidence as u128 + confidence as u128,
+            ErrorCode::ChallengeRejected
+        );
+
+        optimistic_feed.disputed = true;
+        optimistic_feed.challenger = publisher.authority;
+        optimistic_feed.challenge_price = price;
+        optimistic_feed.challenge_confidence = confidence;
+        optimistic_feed.challenge_bond = bond;
+
+        let price_account = &mut ctx.accounts.price_account.load_mut()?;
+        price_account.aggregate.status = PriceStatus::Halted as u8;
+
+        emit!(OptimisticPriceChallenged {
+            product: price_account.product_account,
+            poster: optimistic_feed.poster,
+            challenger: publisher.authority,
+            posted_price: optimistic_feed.posted_price,
+            posted_confidence: optimistic_feed.posted_confidence,
+            challenge_price: price,
+            challenge_confidence: confidence,
+            posted_slot: optimistic_feed.posted_slot,
+        });
+
+        Ok(())
+    }
+
+    /// Feed authority reopens posting after a dispute, optionally replacing the poster.
+    /// An upheld challenge replaces the post with the challenger's quote and releases its
+    /// bond; otherwise the post stands and the bond goes to the insurance fund. Slashing a
+    /// poster at fault goes through a SlashPublisher proposal.
+    pub fn resolve_optimistic_dispute(
+        ctx: Context<ResolveOptimisticDispute>,
+        new_poster: Option<Pubkey>,
+        challenge_upheld: bool,
+    ) -> Result<()> {
+        let optimistic_feed = &mut ctx.accounts.optimistic_feed;
+        require!(optimistic_feed.disputed, ErrorCode::NoOpenDispute);
+
+        let bond = optimistic_feed.challenge_bond;
+        let challenger = &mut ctx.accounts.challenger_account;
+        challenger.locked_stake = challenger.locked_stake.saturating_sub(bond);
+        // A slash of the challenger since may have left less than the bond
+        let bond_slashed = if challenge_upheld { 0 } else { bond.min(challenger.staked_amount) };
+        let slot = program_clock(&ctx.accounts.global_state)?.slot;
+        if bond_slashed > 0 {
+            let token_vault = &mut ctx.accounts.token_vault;
+            token_vault.settle_reward_index(slot);
+            challenger.staked_amount -= bond_slashed;
+            challenger.locked_stake = challenger.locked_stake.min(challenger.staked_amount);
+            token_vault.total_staked = token_vault.total_staked
+                .checked_sub(bond_slashed)
+                .ok_or(ErrorCode::Overflow)?;
+            token_vault.insurance_fund = token_vault.insurance_fund
+                .checked_add(bond_slashed)
+                .ok_or(ErrorCode::Overflow)?;
+        }
+
+        // Lift the dispute halt unless the feed is halted for another reason
+        let price_account = &mut ctx.accounts.price_account.load_mut()?;
+        if challenge_upheld {
+            price_account.aggregate.price = optimistic_feed.challenge_price;
+            price_account.aggregate.confidence = optimistic_feed.challenge_confidence;
+        }
+        if !price_account.is_halted(slot) {
+            price_account.aggregate.status = PriceStatus::Trading as u8;
+        }
+
+        emit!(OptimisticDisputeResolved {
+            product: price_account.product_account,
+            challenger: optimistic_feed.challenger,
+            challenge_upheld,
+            bond_slashed,
+        });
+
+        optimistic_feed.disputed = false;
+        optimistic_feed.challenger = Pubkey::default();
+        optimistic_feed.challenge_price = 0;
+        optimistic_feed.challenge_confidence = 0;
+        optimistic_feed.challenge_bond = 0;
+        optimistic_feed.posted_slot = 0;
+        if let Some(poster) = new_poster {
+            optimistic_feed.poster = poster;
+        }
+
+        Ok(())
+    }
+
+    // ========================================================================
+    // Inverse Feed Instructions
+    // ========================================================================
+
+    /// Turns a publisher-less feed into the inverse of `source_feed`. When both products
+    /// name a currency pair, the inverse must be the source pair swapped.
+    pub fn create_inverse_feed(
+        ctx: Context<CreateInverseFeed>,
+    ) -> Result<()> {
+        let product = &ctx.accounts.product_account;
+        let source_product = &ctx.accounts.source_product;
+        if !product.base_currency.is_empty() && !source_product.base_currency.is_empty() {
+            require!(
+                product.base_currency == source_product.quote_currency
+                    && product.quote_currency == source_product.base_currency,
+                ErrorCode::CurrencyMismatch
+            );
+        }
+        require!(ctx.accounts.price_account.load()?.publisher_count == 0, ErrorCode::InvalidFeedConfig);
+
+        let inverse_feed = &mut ctx.accounts.inverse_feed;
+        inverse_feed.price_account = ctx.accounts.price_account.key();
+        inverse_feed.source_feed = ctx.accounts.source_feed.key();
+        inverse_feed.bump = ctx.bumps.inverse_feed;
+
+        ctx.accounts.price_account.load_mut()?.derived = 1;
+
+        Ok(())
+    }
+
+    /// Returns the feed to regular aggregation
+    pub fn close_inverse_feed(
+        ctx: Context<CloseInverseFeed>,
+    ) -> Result<()> {
+        ctx.accounts.price_account.load_mut()?.derived = 0;
+        Ok(())
+    }
+
+    /// Permissionless crank: writes the inverted source aggregate at the feed's exponent.
+    /// The inverse carries the source's status and timestamp.
+    pub fn update_inverse_feed(
+        ctx: Context<UpdateInverseFeed>,
+    ) -> Result<()> {
+        let source = &ctx.accounts.source_feed.load()?;
+        let price_account = &mut ctx.accounts.price_account.load_mut()?;
+        if price_account.is_halted(Clock::get()?.slot) {
+            price_account.aggregate.status = PriceStatus::Halted as u8;
+            return Ok(());
+        }
+        require!(source.aggregate.price > 0, ErrorCode::InvalidPrice);
+
+        let aggregate = source
+            .aggregate
+            .invert(price_account.exponent)
+            .ok_or(ErrorCode::InvalidPrice)?;
+        if aggregate.status().is_fresh() {
+            price_account.ema = update_ema(price_account, aggregate.price, aggregate.confidence)?;
+        }
+        price_account.last_update_slot = Clock::get()?.slot;
+        price_account.distinct_sources = source.distinct_sources;
+        price_account.top_source_share_bps = source.top_source_share_bps;
+
+        emit!(PriceUpdated {
+            product: price_account.product_account,
+            symbol: ctx.accounts.product_account.symbol.clone(),
+            price: aggregate.price,
+            confidence: aggregate.confidence,
+            timestamp: aggregate.timestamp,
+            slot: aggregate.slot,
+            status: aggregate.status(),
+            distinct_sources: source.distinct_sources,
+            top_source_share_bps: source.top_source_share_bps,
+        });
+        price_account.accrue_cumulative(optional_program_clock(ctx.accounts.global_state.as_deref())?.unix_timestamp);
+        price_account.aggregate = aggregate;
+
+        Ok(())
+    }
+
+    // ========================================================================
+    // Futures Roll Instructions
+    // ========================================================================
+
+    pub fn create_futures_contract(
+        ctx: Context<CreateFuturesContract>,
+        contract_id: String,
+        expiry: i64,
+    ) -> Result<()> {
+        require!(
+            ctx.accounts.price_account.load()?.price_type()? == PriceType::Futures,
+            ErrorCode::NotFuturesFeed
+        );
+        validate_contract(&contract_id, expiry, ctx.accounts.global_state.as_deref())?;
+
+        let futures_contract = &mut ctx.accounts.futures_contract;
+        futures_contract.price_account = ctx.accounts.price_account.key();
+        futures_contract.active_contract = contract_id;
+        futures_contract.expiry = expiry;
+        futures_contract.previous_contract = String::new();
+        futures_contract.roll_count = 0;
+        futures_contract.last_roll_slot = 0;
+        futures_contract.bump = ctx.bumps.futures_contract;
+
+        Ok(())
+    }
+
+    /// Switches the feed to `next_contract`. The continuity factor is rescaled so that
+    /// `next_contract_price` maps onto the current continuous aggregate, which must be fresh.
+    pub fn roll_futures_contract(
+        ctx: Context<RollFuturesContract>,
+        next_contract: String,
+        next_expiry: i64,
+        next_contract_price: i64,
+    ) -> Result<()> {
+        validate_contract(&next_contract, next_expiry, ctx.accounts.global_state.as_deref())?;
+        require!(next_contract_price > 0, ErrorCode::InvalidPrice);
+
+        let price_account = &mut ctx.accounts.price_account.load_mut()?;
+        require!(
+            price_account.aggregate.status().is_fresh() && price_account.aggregate.price > 0,
+            ErrorCode::PriceNotTrading
+        );
+
+        // continuous = raw * adjustment, so the new factor is continuous / next raw price
+        let adjustment = div_round(
+            price_account.aggregate.price as i128 * ROLL_ADJUSTMENT_SCALE as i128,
+            next_contract_price as i128,
+            price_account.rounding_mode()?,
+        )
+        .and_then(|a| u64::try_from(a).ok())
+        .ok_or(ErrorCode::Overflow)?;
+        require!(adjustment > 0, ErrorCode::InvalidPrice);
+        price_account.roll_adjustment = adjustment;
+
+        let slot = Clock::get()?.slot;
+        let futures_contract = &mut ctx.accounts.futures_contract;
+        // Rolls only move forward; an earlier expiry would be the contract just rolled from
+        require!(next_expiry > futures_contract.expiry, ErrorCode::InvalidContract);
+        futures_contract.previous_contract =
+            std::mem::replace(&mut futures_contract.active_contract, next_contract);
+        futures_contract.expiry = next_expiry;
+        futures_contract.roll_count += 1;
+        futures_contract.last_roll_slot = slot;
+
+        emit!(FuturesRolled {
+            price_account: futures_contract.price_account,
+            previous_contract: futures_contract.previous_contract.clone(),
+            active_contract: futures_contract.active_contract.clone(),
+            expiry: next_expiry,
+            roll_adjustment: adjustment,
+            slot,
+        });
+
+        Ok(())
+    }
+
+    // ========================================================================
+    // Governance Instructions
+    // ========================================================================
+
+    pub fn create_proposal(
+        ctx: Context<CreateProposal>,
+        proposal_type: ProposalType,
+        description: String,
+        mode: ProposalMode,
+    ) -> Result<()> {
+        require!(!ctx.accounts.global_state.paused, ErrorCode::SystemPaused);
+        
+        // The required balance scales with what the proposal could change
+        let proposer_balance = ctx.accounts.proposer_token_account.amount;
+        require!(
+            proposer_balance >= ctx.accounts.governance_state.required_proposer_balance(proposal_type.severity()),
+            ErrorCode::Unauthorized
+        );
+
+        let clock = program_clock(&ctx.accounts.global_state)?;
+        let proposal = &mut ctx.accounts.proposal;
+        let governance = &mut ctx.accounts.governance_state;
+
+        proposal.proposer = ctx.accounts.proposer.key();
+        proposal.proposal_type = proposal_type.clone();
+        proposal.description = description.clone();
+        proposal.yes_votes = 0;
+        proposal.no_votes = 0;
+        proposal.abstain_votes = 0;
+        match proposal_type {
+            ProposalType::UpdateUnbondingPeriod { period } => {
+                require!(period >= MIN_UNBONDING_PERIOD, ErrorCode::InvalidGovernanceParams);
+            },
+            ProposalType::UpdateMinPublishers { new_min, .. } => {
+                require!(
+                    new_min > 0 && new_min as usize <= MAX_PUBLISHERS,
+                    ErrorCode::InvalidGovernanceParams
+                );
+            },
+            ProposalType::UpdateMinStake { min_stake } => {
+                require!(min_stake > 0, ErrorCode::InvalidGovernanceParams);
+            },
+            ProposalType::UpdateRewardVesting { period } => {
+                require!(
+                    (0..=MAX_REWARD_VESTING_PERIOD).contains(&period),
+                    ErrorCode::InvalidGovernanceParams
+                );
+            },
+            ProposalType::SetAssetTemplate { ref template, .. } => {
+                require!(template.is_valid(), ErrorCode::InvalidGovernanceParams);
+            },
+            ProposalType::DeclareVolatilityRegime { widening_bps, duration_slots, .. } => {
+                require!(
+                    duration_slots <= MAX_VOLATILITY_REGIME_SLOTS
+                        && (duration_slots == 0 || (widening_bps > 10_000 && widening_bps <= MAX_VOLATILITY_WIDENING_BPS)),
+                    ErrorCode::InvalidGovernanceParams
+                );
+            },
+            // Breaches are checked and throttled per update interval, so a confidence bound
+            // without one could never be recorded
+            ProposalType::SetFeedSla { max_update_interval_slots, max_confidence_bps, .. } => {
+                require!(
+                    max_confidence_bps == 0 || max_update_interval_slots > 0,
+                    ErrorCode::InvalidGovernanceParams
+                );
+            },
+            ProposalType::SetFeedQuota { min_submissions, max_submissions, .. } => {
+                require!(
+                    max_submissions == 0 || min_submissions <= max_submissions,
+                    ErrorCode::InvalidGovernanceParams
+                );
+            },
+            ProposalType::AttestIdentity { operator_id, .. } => {
+                require!(operator_id != 0, ErrorCode::InvalidGovernanceParams);
+            },
+            ProposalType::DrawListingCollateral { feed, .. } => {
+                // Bounded voting, and a pending retirement waits out the full governance path
+                require!(mode == ProposalMode::Standard, ErrorCode::InvalidProposalMode);
+                let feed_collateral = ctx.accounts.feed_collateral.as_mut().ok_or(ErrorCode::ProposalTargetMismatch)?;
+                require!(feed_collateral.price_account == feed, ErrorCode::ProposalTargetMismatch);
+                if feed_collateral.retire_after != 0 {
+                    let notice_end = clock.unix_timestamp
+                        .checked_add(RETIREMENT_NOTICE_SECONDS)
+                        .ok_or(ErrorCode::Overflow)?;
+                    feed_collateral.retire_after = feed_collateral.retire_after.max(notice_end);
+                }
+            },
+            ProposalType::SlashPublisher { publish_cooldown_slots, .. } => {
+                require!(
+                    publish_cooldown_slots <= MAX_SLASH_PUBLISH_COOLDOWN_SLOTS,
+                    ErrorCode::InvalidGovernanceParams
+                );
+            },
+            ProposalType::UpdateGovernanceParams { severity_threshold_bps: Some(curve), .. } => {
+                require!(
+                    curve[0] > 0 && curve.windows(2).all(|pair| pair[0] <= pair[1]),
+                    ErrorCode::InvalidGovernanceParams
+                );
+            },
+            _ => {},
+        }
+
+        let is_emergency = proposal_type.is_emergency();
+        require!(
+            !is_emergency || mode == ProposalMode::Standard,
+            ErrorCode::InvalidProposalMode
+        );
+        proposal.start_slot = if proposal_type.has_review_period() {
+            clock.slot + PROPOSAL_REVIEW_PERIOD
+        } else {
+            clock.slot
+        };
+        proposal.end_slot = match mode {
+            ProposalMode::Standard if is_emergency => proposal.start_slot + governance.emergency_voting_period,
+            ProposalMode::Standard => proposal.start_slot + governance.voting_period,
+            ProposalMode::Conviction => u64::MAX,
+        };
+        proposal.executed = false;
+        proposal.execution_time = 0;
+        proposal.proposal_id = governance.proposal_count;
+        proposal.mode = mode;
+        proposal.conviction_staked = 0;
+        proposal.conviction = 0;
+        proposal.conviction_updated_slot = proposal.start_slot;
+        proposal.linked_hashes = Vec::new();
+        proposal.action_executed = false;
+        proposal.bump = ctx.bumps.proposal;
+
+        governance.proposal_count += 1;
+
+        emit!(ProposalCreated {
+            proposal_id: proposal.proposal_id,
+            proposer: proposal.proposer,
+            proposal_type,
+            description,
+        });
+
+        Ok(())
+    }
+
+    pub fn vote_proposal(
+        ctx: Context<VoteProposal>,
+        vote: VoteType,
+    ) -> Result<()> {
+        let clock = Clock::get()?;
+        let proposal = &mut ctx.accounts.proposal;
+
+        require!(proposal.mode == ProposalMode::Standard, ErrorCode::InvalidProposalMode);
+        require!(clock.slot >= proposal.start_slot, ErrorCode::VotingNotStarted);
+        require!(clock.slot <= proposal.end_slot, ErrorCode::VotingPeriodEnded);
+
+        let vote_weight = ctx.accounts.voter_token_account.amount;
+
+        match vote {
+            VoteType::Yes => proposal.yes_votes += vote_weight,
+            VoteType::No => proposal.no_votes += vote_weight,
+            VoteType::Abstain => proposal.abstain_votes += vote_weight,
+        }
+
+        Ok(())
+    }
+
+    pub fn update_proposal_links(
+        ctx: Context<UpdateProposalLinks>,
+        linked_hashes: Vec<[u8; 32]>,
+    ) -> Result<()> {
+        let proposal = &mut ctx.accounts.proposal;
+
+        require!(Clock::get()?.slot < proposal.start_slot, ErrorCode::ProposalLinksFrozen);
+        require!(
+            linked_hashes.len() <= MAX_PROPOSAL_LINKS,
+            ErrorCode::TooManyProposalLinks
+        );
+
+        proposal.linked_hashes = linked_hashes.clone();
+
+        emit!(ProposalLinksUpdated {
+            proposal_id: proposal.proposal_id,
+            linked_hashes,
+        });
+
+        Ok(())
+    }
+
+    pub fn lock_conviction<'info>(
+        ctx: Context<'_, '_, 'info, 'info, LockConviction<'info>>,
+        amount: u64,
+    ) -> Result<()> {
+        require!(!ctx.accounts.global_state.paused, ErrorCode::SystemPaused);
+        require!(amount > 0, ErrorCode::InsufficientStake);
+
+        let proposal = &mut ctx.accounts.proposal;
+        require!(proposal.mode == ProposalMode::Conviction, ErrorCode::InvalidProposalMode);
+        require!(!proposal.executed, ErrorCode::ProposalNotApproved);
+        require!(program_clock(&ctx.accounts.global_state)?.slot >= proposal.start_slot, ErrorCode::VotingNotStarted);
+
+        let vault_balance = ctx.accounts.vault_token_account.amount;
+        let cpi_accounts = TransferChecked {
+            from: ctx.accounts.voter_token_account.to_account_info(),
+            mint: ctx.accounts.token_mint.to_account_info(),
+            to: ctx.accounts.vault_token_account.to_account_info(),
+            authority: ctx.accounts.voter.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts)
+            .with_remaining_accounts(ctx.remaining_accounts.to_vec());
+        transfer_checked_with_hooks(cpi_ctx, amount, ctx.accounts.token_mint.decimals)?;
+
+        // Token-2022 transfer fees are withheld in transit, so book what arrived
+        let amount = vault_received(&mut ctx.accounts.vault_token_account, vault_balance)?;
+
+        let slot = program_clock(&ctx.accounts.global_state)?.slot;
+        update_conviction(proposal, slot);
+        proposal.conviction_staked = proposal.conviction_staked
+            .checked_add(amount)
+            .ok_or(ErrorCode::Overflow)?;
+
+        let lock = &mut ctx.accounts.conviction_lock;
+        lock.voter = ctx.accounts.voter.key();
+        lock.proposal = proposal.key();
+        lock.amount = amount;
+        lock.locked_slot = slot;
+        lock.bump = ctx.bumps.conviction_lock;
+
+        ctx.accounts.token_vault.total_conviction_locked = ctx.accounts.token_vault.total_conviction_locked
+            .checked_add(amount)
+            .ok_or(ErrorCode::Overflow)?;
+
+        emit!(ConvictionChanged {
+            proposal_id: proposal.proposal_id,
+            voter: lock.voter,
+            locked_delta: amount as i64,
+            conviction_staked: proposal.conviction_staked,
+            conviction: proposal.conviction,
+        });
+
+        Ok(())
+    }
+
+    pub fn unlock_conviction<'info>(
+        ctx: Context<'_, '_, 'info, 'info, UnlockConviction<'info>>,
+    ) -> Result<()> {
+        let amount = ctx.accounts.conviction_lock.amount;
+
+        let seeds = ctx.accounts.global_state.custody_signer_seeds();
+        let signer = &[&seeds[..]];
+
+        let cpi_accounts = TransferChecked {
+            from: ctx.accounts.vault_token_account.to_account_info(),
+            mint: ctx.accounts.token_mint.to_account_info(),
+            to: ctx.accounts.voter_token_account.to_account_info(),
+            authority: ctx.accounts.vault_authority.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer)
+            .with_remaining_accounts(ctx.remaining_accounts.to_vec());
+        transfer_checked_with_hooks(cpi_ctx, amount, ctx.accounts.token_mint.decimals)?;
+
+        let proposal = &mut ctx.accounts.proposal;
+        if !proposal.executed {
+            update_conviction(proposal, program_clock(&ctx.accounts.global_state)?.slot);
+        }
+        proposal.conviction_staked = proposal.conviction_staked.saturating_sub(amount);
+
+        ctx.accounts.token_vault.total_conviction_locked = ctx.accounts.token_vault.total_conviction_locked
+            .checked_sub(amount)
+            .ok_or(ErrorCode::Overflow)?;
+
+        emit!(ConvictionChanged {
+            proposal_id: proposal.proposal_id,
+            voter: ctx.accounts.voter.key(),
+            locked_delta: -(amount as i64),
+            conviction_staked: proposal.conviction_staked,
+            conviction: proposal.conviction,
+        });
+
+        Ok(())
+    }
+
+    pub fn execute_proposal(
+        ctx: Context<ExecuteProposal>,
+    ) -> Result<()> {
+        let clock = optional_program_clock(ctx.accounts.global_state.as_deref())?;
+        let proposal = &mut ctx.accounts.proposal;
+        let governance = &ctx.accounts.governance_state;
+
+        require!(!proposal.executed, ErrorCode::ProposalNotApproved);
+
+        // Emergency proposals pass as soon as a supermajority of supply votes yes
+        if proposal.proposal_type.is_emergency() {
+            require!(clock.slot <= proposal.end_slot, ErrorCode::VotingPeriodEnded);
+            let supermajority = (governance.total_supply as u128
+                * governance.emergency_quorum_percentage as u128) / 100;
+            require!(
+                proposal.yes_votes as u128 >= supermajority,
+                ErrorCode::QuorumNotReached
+            );
+
+            proposal.executed = true;
+            proposal.execution_time = clock.unix_timestamp;
+
+            emit!(ProposalExecuted {
+                proposal_id: proposal.proposal_id,
+                proposal_type: proposal.proposal_type.clone(),
+            });
+
+            return Ok(());
+        }
+
+        match proposal.mode {
+            ProposalMode::Standard => {
+                require!(clock.slot > proposal.end_slot, ErrorCode::VotingPeriodActive);
+
+                // Check quorum
+                let total_votes = proposal.yes_votes + proposal.no_votes + proposal.abstain_votes;
+                let quorum = (governance.total_supply as u128 * governance.quorum_percentage as u128) / 100;
+
+                require!(total_votes as u128 >= quorum, ErrorCode::QuorumNotReached);
+                require!(proposal.yes_votes > proposal.no_votes, ErrorCode::ProposalNotApproved);
+            },
+            ProposalMode::Conviction => {
+                // Only gate the first call; once queued the timelock governs execution
+                if proposal.execution_time == 0 {
+                    update_conviction(proposal, clock.slot);
+                    let threshold = (governance.total_supply as u128
+                        * governance.conviction_threshold_percentage as u128) / 100;
+                    require!(
+                        proposal.conviction as u128 >= threshold,
+                        ErrorCode::ConvictionNotReached
+                    );
+                }
+            },
+        }
+
+        // Timelock mechanism
+        if proposal.execution_time == 0 {
+            proposal.execution_time = clock.unix_timestamp + governance.timelock_duration as i64;
+
+            let proposal_id = proposal.proposal_id;
+            let proposal_type = proposal.proposal_type.clone();
+            let effective_at = proposal.execution_time;
+            return queue_param_preview(ctx.accounts, proposal_id, &proposal_type, effective_at);
+        }
+
+        require!(
+            clock.unix_timestamp >= proposal.execution_time,
+            ErrorCode::TimelockNotExpired
+        );
+        require!(
+            clock.unix_timestamp <= proposal.execution_time.saturating_add(PROPOSAL_EXECUTION_WINDOW),
+            ErrorCode::ExecutionWindowClosed
+        );
+
+        proposal.executed = true;
+
+        emit!(ProposalExecuted {
+            proposal_id: proposal.proposal_id,
+            proposal_type: proposal.proposal_type.clone(),
+        });
+
+        Ok(())
+    }
+
+    /// Permissionless. Clears the preview of a queued proposal that was not executed
+    /// within PROPOSAL_EXECUTION_WINDOW of its timelock, and so never can be.
+    pub fn expire_proposal(
+        ctx: Context<ExpireProposal>,
+    ) -> Result<()> {
+        let clock = optional_program_clock(ctx.accounts.global_state.as_deref())?;
+        let proposal = &ctx.accounts.proposal;
+
+        require!(
+            !proposal.executed
+                && proposal.execution_time != 0
+                && clock.unix_timestamp > proposal.execution_time.saturating_add(PROPOSAL_EXECUTION_WINDOW),
+            ErrorCode::ProposalNotExpired
+        );
+
+        let proposal_id = proposal.proposal_id;
+        match &proposal.proposal_type {
+            ProposalType::UpdateRewardRate { .. } => {
+                let token_vault = ctx.accounts.token_vault.as_mut().ok_or(ErrorCode::ProposalTargetMismatch)?;
+                token_vault.pending_reward_rate.clear(proposal_id);
+            },
+            ProposalType::UpdateMinPublishers { feed, .. } => {
+                let feed_account = ctx.accounts.price_account.as_ref().ok_or(ErrorCode::ProposalTargetMismatch)?;
+                require_keys_eq!(feed_account.key(), *feed, ErrorCode::ProposalTargetMismatch);
+                feed_account.load_mut()?.pending_min_publishers.clear(proposal_id);
+            },
+            ProposalType::UpdateStakePerFeed { .. } => {
+                let global_state = ctx.accounts.global_state.as_mut().ok_or(ErrorCode::ProposalTargetMismatch)?;
+                global_state.pending_stake_per_feed.clear(proposal_id);
+            },
+            ProposalType::UpdateRegistrationLimit { .. } => {
+                let global_state = ctx.accounts.global_state.as_mut().ok_or(ErrorCode::ProposalTargetMismatch)?;
+                global_state.pending_registration_limit.clear(proposal_id);
+            },
+            ProposalType::UpdateUnbondingPeriod { .. } => {
+                let global_state = ctx.accounts.global_state.as_mut().ok_or(ErrorCode::ProposalTargetMismatch)?;
+                global_state.pending_unbonding_period.clear(proposal_id);
+            },
+            ProposalType::UpdateMinStake { .. } => {
+                let global_state = ctx.accounts.global_state.as_mut().ok_or(ErrorCode::ProposalTargetMismatch)?;
+                global_state.pending_min_stake.clear(proposal_id);
+            },
+            _ => {},
+        }
+
+        emit!(ProposalExpired {
+            proposal_id,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn execute_governance_action<'info>(
+        ctx: Context<'_, '_, 'info, 'info, ExecuteGovernanceAction<'info>>,
+    ) -> Result<()> {
+        require!(ctx.accounts.proposal.executed, ErrorCode::ProposalNotApproved);
+        require!(!ctx.accounts.proposal.action_executed, ErrorCode::ActionAlreadyExecuted);
+        let before = action_snapshot(ctx.accounts)?;
+        let proposal = &ctx.accounts.proposal;
+
+        match &proposal.proposal_type {
+            ProposalType::UpdateRewardRate { new_rate } => {
+                let slot = program_clock(&ctx.accounts.global_state)?.slot;
+                ctx.accounts.token_vault.settle_reward_index(slot);
+                ctx.accounts.token_vault.reward_rate = *new_rate;
+                ctx.accounts.token_vault.pending_reward_rate.clear(proposal.proposal_id);
+            },
+            ProposalType::UpdateMinPublishers { feed, new_min } => {
+                let feed_account = ctx.accounts.price_account
+                    .as_ref()
+                    .ok_or(ErrorCode::ProposalTargetMismatch)?;
+                require!(feed_account.key() == *feed, ErrorCode::ProposalTargetMismatch);
+                let price_account = &mut feed_account.load_mut()?;
+                // Publishers may have left since the change was queued
+                require!(*new_min <= price_account.publisher_count, ErrorCode::InvalidGovernanceParams);
+                price_account.min_publishers = *new_min;
+                price_account.pending_min_publishers.clear(proposal.proposal_id);
+            },
+            ProposalType::EmergencyPause => {
+                ctx.accounts.global_state.paused = true;
+                emit!(SystemPaused {
+                    timestamp: program_clock(&ctx.accounts.global_state)?.unix_timestamp,
+                    authority: ctx.accounts.authority.key(),
+                });
+            },
+            ProposalType::EmergencyUnpause => {
+                ctx.accounts.global_state.paused = false;
+                emit!(SystemUnpaused {
+                    timestamp: program_clock(&ctx.accounts.global_state)?.unix_timestamp,
+                    authority: ctx.accounts.authority.key(),
+                });
+            },
+            ProposalType::UpdateGovernanceParams { 
+                proposal_threshold,
+                voting_period,
+                quorum_percentage,
+                timelock_duration,
+                conviction_threshold_percentage,
+                severity_threshold_bps,
+            } => {
+                let gov = &mut ctx.accounts.governance_state;
+                if let Some(threshold) = proposal_threshold {
+                    gov.proposal_threshold = *threshold;
+                }
+                if let Some(period) = voting_period {
+                    gov.voting_period = *period;
+                }
+                if let Some(quorum) = quorum_percentage {
+                    gov.quorum_percentage = *quorum;
+                }
+                if let Some(timelock) = timelock_duration {
+                    gov.timelock_duration = *timelock;
+                }
+                if let Some(threshold) = conviction_threshold_percentage {
+                    gov.conviction_threshold_percentage = *threshold;
+                }
+                if let Some(curve) = severity_threshold_bps {
+                    gov.severity_threshold_bps = *curve;
+                }
+            },
+            ProposalType::UpdateStakePerFeed { stake_per_feed } => {
+                ctx.accounts.global_state.stake_per_feed = *stake_per_feed;
+                ctx.accounts.global_state.pending_stake_per_feed.clear(proposal.proposal_id);
+            },
+            ProposalType::ApprovePublisherRegistrations { count } => {
+                let global_state = &mut ctx.accounts.global_state;
+                global_state.registration_allowance = global_state.registration_allowance
+                    .checked_add(*count)
+                    .ok_or(ErrorCode::Overflow)?;
+            },
+            ProposalType::UpdateRegistrationLimit { max_per_epoch } => {
+                ctx.accounts.global_state.max_new_publishers_per_epoch = *max_per_epoch;
+                ctx.accounts.global_state.pending_registration_limit.clear(proposal.proposal_id);
+            },
+            ProposalType::SetFeedSla { feed, max_update_interval_slots, max_confidence_bps, rebate_bps } => {
+                let feed_sla = ctx.accounts.feed_sla
+                    .as_mut()
+                    .ok_or(ErrorCode::ProposalTargetMismatch)?;
+                require!(feed_sla.feed == *feed, ErrorCode::ProposalTargetMismatch);
+                require!(
+                    *max_confidence_bps <= 10_000 && *rebate_bps <= 10_000,
+                    ErrorCode::InvalidFeedConfig
+                );
+
+                feed_sla.max_update_interval_slots = *max_update_interval_slots;
+                feed_sla.max_confidence_bps = *max_confidence_bps;
+                feed_sla.rebate_bps = *rebate_bps;
+
+                emit!(FeedSlaUpdated {
+                    feed: *feed,
+                    max_update_interval_slots: *max_update_interval_slots,
+                    max_confidence_bps: *max_confidence_bps,
+                    rebate_bps: *rebate_bps,
+                    proposal_id: proposal.proposal_id,
+                });
+            },
+            ProposalType::AttestIdentity { publisher, operator_id: attested_operator } => {
+                let pub_account = ctx.accounts.publisher_account
+                    .as_mut()
+                    .ok_or(ErrorCode::ProposalTargetMismatch)?;
+                require!(pub_account.key() == *publisher, ErrorCode::ProposalTargetMismatch);
+                // The identity voted on must still be the one bound
+                let identity = ctx.accounts.publisher_identity
+                    .as_ref()
+                    .ok_or(ErrorCode::ProposalTargetMismatch)?;
+                require!(
+                    identity.publisher == *publisher && operator_id(&identity.identifier) == *attested_operator,
+                    ErrorCode::ProposalTargetMismatch
+                );
+                pub_account.operator_id = *attested_operator;
+
+                emit!(PublisherIdentityAttested {
+                    publisher: *publisher,
+                    operator_id: *attested_operator,
+                    proposal_id: proposal.proposal_id,
+                });
+            },
+            ProposalType::SetFeedQuota { feed, min_submissions, max_submissions } => {
+                let feed_quota = ctx.accounts.feed_quota
+                    .as_mut()
+                    .ok_or(ErrorCode::ProposalTargetMismatch)?;
+                require!(feed_quota.price_account == *feed, ErrorCode::ProposalTargetMismatch);
+                let feed_account = ctx.accounts.price_account
+                    .as_ref()
+                    .ok_or(ErrorCode::ProposalTargetMismatch)?;
+                require!(feed_account.key() == *feed, ErrorCode::ProposalTargetMismatch);
+                let price_account = &mut feed_account.load_mut()?;
+
+                feed_quota.min_submissions = *min_submissions;
+                feed_quota.max_submissions = *max_submissions;
+                price_account.submission_quota = (*min_submissions > 0 || *max_submissions > 0) as u8;
+
+                emit!(FeedQuotaUpdated {
+                    feed: *feed,
+                    min_submissions: *min_submissions,
+                    max_submissions: *max_submissions,
+                    proposal_id: proposal.proposal_id,
+                });
+            },
+            ProposalType::UpdateUnbondingPeriod { period } => {
+                ctx.accounts.global_state.unbonding_period = *period;
+                ctx.accounts.global_state.pending_unbonding_period.clear(proposal.proposal_id);
+            },
+            ProposalType::UpdateMinStake { min_stake } => {
+                let global_state = &mut ctx.accounts.global_state;
+                if *min_stake > global_state.min_stake {
+                    // Once a grace period is over every publisher meets the minimum, so it
+                    // becomes the floor; raises inside a grace period keep the earlier floor
+                    if global_state.current_epoch >= global_state.min_stake_raised_epoch + MIN_STAKE_GRACE_EPOCHS {
+                        global_state.previous_min_stake = global_state.min_stake;
+                    }
+                    global_state.min_stake_raised_epoch = global_state.current_epoch;
+                }
+                global_state.min_stake = *min_stake;
+                global_state.pending_min_stake.clear(proposal.proposal_id);
+            },
+            ProposalType::SetReceiptTransferability { transferable } => {
+                ctx.accounts.global_state.receipts_transferable = *transferable;
+            },
+            ProposalType::SetPositionTransferability { transferable } => {
+                ctx.accounts.global_state.positions_transferable = *transferable;
+            },
+            ProposalType::SetAssetTemplate { asset_type, template } => {
+                let asset_template = ctx.accounts.asset_template
+                    .as_mut()
+                    .ok_or(ErrorCode::ProposalTargetMismatch)?;
+                require!(asset_template.asset_type == *asset_type, ErrorCode::ProposalTargetMismatch);
+                asset_template.template = template.clone();
+            },
+            ProposalType::ResolveVaultDrift { drift: expected } => {
+                let token_vault = &mut ctx.accounts.token_vault;
+                let drift = token_vault.unreconciled_drift;
+                // Voters approved a specific amount; a later reconcile_vault needs a new vote
+                require!(drift == *expected, ErrorCode::VaultDriftChanged);
+
+                // Surplus (e.g. slashed stake) goes to the treasury; a deficit is absorbed by it
+                let resolved = if drift >= 0 {
+                    token_vault.treasury = token_vault.treasury
+                        .checked_add(drift as u64)
+                        .ok_or(ErrorCode::Overflow)?;
+                    drift
+                } else {
+                    let absorbed = drift.unsigned_abs().min(token_vault.treasury);
+                    token_vault.treasury -= absorbed;
+                    -(absorbed as i64)
+                };
+                token_vault.unreconciled_drift = drift - resolved;
+
+                emit!(VaultDriftResolved {
+                    proposal_id: proposal.proposal_id,
+                    resolved,
+                    remaining_drift: token_vault.unreconciled_drift,
+                });
+            },
+            ProposalType::UpdateRewardVesting { period } => {
+                ctx.accounts.token_vault.reward_vesting_period = *period;
+            },
+            ProposalType::UpdateListingCollateral { collateral } => {
+                ctx.accounts.global_state.listing_collateral = *collateral;
+            },
+            ProposalType::DrawListingCollateral { feed, amount } => {
+                let feed_collateral = ctx.accounts.feed_collateral.as_mut().ok_or(ErrorCode::ProposalTargetMismatch)?;
+                require!(feed_collateral.price_account == *feed, ErrorCode::ProposalTargetMismatch);
+
+                let drawn = (*amount).min(feed_collateral.amount);
+                feed_collateral.amount -= drawn;
+                let token_vault = &mut ctx.accounts.token_vault;
+                token_vault.listing_collateral -= drawn;
+                token_vault.insurance_fund = token_vault.insurance_fund
+                    .checked_add(drawn)
+                    .ok_or(ErrorCode::Overflow)?;
+
+                emit!(FeedCollateralDrawn {
+                    price_account: *feed,
+                    proposal_id: proposal.proposal_id,
+                    amount: drawn,
+                    remaining: feed_collateral.amount,
+                });
+            },
+            ProposalType::DisputeRound { round, excluded_publisher } => {
+                let feed_account = ctx.accounts.price_account.as_ref().ok_or(ErrorCode::ProposalTargetMismatch)?;
+                let price_account = &feed_account.load()?;
+                let archive = ctx.accounts.round_archive.as_mut().ok_or(ErrorCode::ProposalTargetMismatch)?;
+                require!(
+                    archive.key() == *round && archive.price_account == feed_account.key(),
+                    ErrorCode::ProposalTargetMismatch
+                );
+                require!(!archive.disputed, ErrorCode::RoundAlreadyDisputed);
+
+                // Current aggregation rules, evaluated as of the original round
+                let quotes: Vec<&PublisherPrice> = archive.quotes
+                    .iter()
+                    .filter(|q| Some(q.publisher) != *excluded_publisher)
+                    .collect();
+                let result = aggregation::aggregate(&aggregation_config(price_account, archive.original.slot), quotes, archive.original.timestamp);
+                let reaggregated = PriceData {
+                    price: result.price,
+                    confidence: result.confidence,
+                    status: PriceStatus::from(result.status) as u8,
+                    price_i128: result.price_i128.unwrap_or(0),
+                    _padding: [0; 11],
+                    ..archive.original
+                };
+
+                emit!(RoundDisputed {
+                    price_account: archive.price_account,
+                    round: *round,
+                    proposal_id: proposal.proposal_id,
+                    original_price: archive.original.price,
+                    reaggregated_price: reaggregated.price,
+                    reaggregated_status: reaggregated.status(),
+                    excluded_publisher: *excluded_publisher,
+                });
+
+                archive.disputed = true;
+                archive.reaggregated = Some(reaggregated);
+                archive.excluded_publisher = excluded_publisher.unwrap_or_default();
+            },
+            ProposalType::SetRewardMint { mint } => {
+                let token_vault = &mut ctx.accounts.token_vault;
+                require!(
+                    token_vault.reward_pool == 0 && token_vault.vesting_rewards == 0,
+                    ErrorCode::RewardPoolNotEmpty
+                );
+
+                // The rate and publishers' pending rewards are amounts of the old mint. The
+                // rate stops here and pending rewards lapse at each publisher's next accrual.
+                let slot = program_clock(&ctx.accounts.global_state)?.slot;
+                token_vault.settle_reward_index(slot);
+                token_vault.reward_rate = 0;
+                token_vault.pending_reward_rate = PendingParam::default();
+                token_vault.reward_mint_slot = slot;
+                token_vault.reward_mint_index = token_vault.reward_index;
+
+                token_vault.reward_mint = if *mint == token_vault.token_mint {
+                    Pubkey::default()
+                } else {
+                    *mint
+                };
+                token_vault.reward_vault_token_account = Pubkey::default();
+
+                emit!(RewardMintChanged {
+                    proposal_id: proposal.proposal_id,
+                    reward_mint: token_vault.payout_mint(),
+                });
+            },
+            ProposalType::RotateVault { new_vault_token_account } => {
+                // A separate reward mint's balance sits in another account of the custody authority
+                require!(
+                    !ctx.accounts.token_vault.has_separate_reward_mint(),
+                    ErrorCode::SeparateRewardVault
+                );
+                let custody_version = ctx.accounts.global_state.custody_version
+                    .checked_add(1)
+                    .ok_or(ErrorCode::Overflow)?;
+                let (new_authority, new_bump) = Pubkey::find_program_address(
+                    &[b"vault_authority", &[custody_version]],
+                    &crate::ID,
+                );
+
+                let accounts = &ctx.accounts;
+                let vault_token_account = accounts.vault_token_account.as_ref().ok_or(ErrorCode::ProposalTargetMismatch)?;
+                let vault_authority = accounts.vault_authority.as_ref().ok_or(ErrorCode::ProposalTargetMismatch)?;
+                let token_mint = accounts.token_mint.as_ref().ok_or(ErrorCode::ProposalTargetMismatch)?;
+                let token_program = accounts.token_program.as_ref().ok_or(ErrorCode::ProposalTargetMismatch)?;
+                let new_vault_authority = accounts.new_vault_authority.as_ref().ok_or(ErrorCode::ProposalTargetMismatch)?;
+                let destination = accounts.new_vault_token_account.as_ref().ok_or(ErrorCode::ProposalTargetMismatch)?;
+                require!(
+                    new_vault_authority.key() == new_authority && destination.key() == *new_vault_token_account,
+                    ErrorCode::ProposalTargetMismatch
+                );
+                require!(
+                    destination.owner == new_authority
+                        && destination.mint == token_mint.key()
+                        && destination.delegate.is_none()
+                        && destination.close_authority.is_none(),
+                    ErrorCode::InvalidVaultAccount
+                );
+
+                let previous_authority = vault_authority.key();
+                let previous_token_account = vault_token_account.key();
+                let amount = vault_token_account.amount;
+                let balance_before = destination.amount;
+                if amount > 0 {
+                    let seeds = accounts.global_state.custody_signer_seeds();
+                    let signer = &[&seeds[..]];
+
+                    let cpi_accounts = TransferChecked {
+                        from: vault_token_account.to_account_info(),
+                        mint: token_mint.to_account_info(),
+                        to: destination.to_account_info(),
+                        authority: vault_authority.to_account_info(),
+                    };
+                    let cpi_ctx = CpiContext::new_with_signer(token_program.to_account_info(), cpi_accounts, signer)
+                        .with_remaining_accounts(ctx.remaining_accounts.to_vec());
+                    transfer_checked_with_hooks(cpi_ctx, amount, token_mint.decimals)?;
+                }
+                // Any shortfall from transfer fees shows up as drift in the next reconcile_vault
+                let received = vault_received(
+                    ctx.accounts.new_vault_token_account.as_mut().ok_or(ErrorCode::ProposalTargetMismatch)?,
+                    balance_before,
+                )?;
+
+                let global_state = &mut ctx.accounts.global_state;
+                global_state.custody_version = custody_version;
+                global_state.custody_bump = new_bump;
+                let token_vault = &mut ctx.accounts.token_vault;
+                token_vault.vault_authority = new_authority;
+                token_vault.vault_token_account = *new_vault_token_account;
+
+                emit!(VaultRotated {
+                    proposal_id: proposal.proposal_id,
+                    custody_version,
+                    previous_authority,
+                    previous_token_account,
+                    new_authority,
+                    new_token_account: *new_vault_token_account,
+                    amount,
+                    received,
+                    slot: program_clock(&ctx.accounts.global_state)?.slot,
+                });
+            },
+            ProposalType::MigrateTokenMint { new_mint, new_vault_token_account, counterparty } => {
+                let token_vault = &ctx.accounts.token_vault;
+                require!(
+                    *new_mint != token_vault.token_mint
+                        && !(token_vault.has_separate_reward_mint() && *new_mint == token_vault.reward_mint),
+                    ErrorCode::InvalidMint
+                );
+
+                let accounts = &ctx.accounts;
+                let vault_token_account = accounts.vault_token_account.as_ref().ok_or(ErrorCode::ProposalTargetMismatch)?;
+                let vault_authority = accounts.vault_authority.as_ref().ok_or(ErrorCode::ProposalTargetMismatch)?;
+                let token_mint = accounts.token_mint.as_ref().ok_or(ErrorCode::ProposalTargetMismatch)?;
+                let token_program = accounts.token_program.as_ref().ok_or(ErrorCode::ProposalTargetMismatch)?;
+                let new_token_mint = accounts.new_token_mint.as_ref().ok_or(ErrorCode::ProposalTargetMismatch)?;
+                let destination = accounts.new_vault_token_account.as_ref().ok_or(ErrorCode::ProposalTargetMismatch)?;
+                let counterparty_account = accounts.migration_counterparty.as_ref().ok_or(ErrorCode::ProposalTargetMismatch)?;
+                require!(
+                    new_token_mint.key() == *new_mint
+                        && destination.key() == *new_vault_token_account
+                        && counterparty_account.key() == *counterparty,
+                    ErrorCode::ProposalTargetMismatch
+                );
+                // 1:1 means the same amount in base units as well as in whole tokens
+                require!(new_token_mint.decimals == token_mint.decimals, ErrorCode::InvalidMint);
+                require!(
+                    destination.owner == token_vault.vault_authority
+                        && destination.mint == *new_mint
+                        && destination.delegate.is_none()
+                        && destination.close_authority.is_none(),
+                    ErrorCode::InvalidVaultAccount
+                );
+
+                // Every balance the vault tracks must be backed in the new mint before the
+                // old tokens leave; stake, unbonding and locks carry over as they are
+                let amount = vault_token_account.amount;
+                require!(destination.amount >= amount, ErrorCode::MigrationUnderfunded);
+                let previous_mint = token_mint.key();
+                let previous_token_account = vault_token_account.key();
+                if amount > 0 {
+                    let seeds = accounts.global_state.custody_signer_seeds();
+                    let signer = &[&seeds[..]];
+
+                    let cpi_accounts = TransferChecked {
+                        from: vault_token_account.to_account_info(),
+                        mint: token_mint.to_account_info(),
+                        to: counterparty_account.to_account_info(),
+                        authority: vault_authority.to_account_info(),
+                    };
+                    let cpi_ctx = CpiContext::new_with_signer(token_program.to_account_info(), cpi_accounts, signer)
+                        .with_remaining_accounts(ctx.remaining_accounts.to_vec());
+                    transfer_checked_with_hooks(cpi_ctx, amount, token_mint.decimals)?;
+                }
+
+                // Funding beyond the vault's balance backs holders swapping their wallet tokens
+                let swap_reserve = destination.amount - amount;
+                ctx.accounts.global_state.token_mint = *new_mint;
+                ctx.accounts.governance_state.governance_token = *new_mint;
+                let token_vault = &mut ctx.accounts.token_vault;
+                token_vault.token_mint = *new_mint;
+                token_vault.vault_token_account = *new_vault_token_account;
+                token_vault.previous_token_mint = previous_mint;
+                token_vault.migration_counterparty = *counterparty;
+                token_vault.swap_reserve = swap_reserve;
+                if let Some(cluster_config) = ctx.accounts.cluster_config.as_mut() {
+                    cluster_config.token_mint = *new_mint;
+                }
+
+                emit!(TokenMintMigrated {
+                    proposal_id: proposal.proposal_id,
+                    previous_mint,
+                    new_mint: *new_mint,
+                    previous_token_account,
+                    new_token_account: *new_vault_token_account,
+                    counterparty: *counterparty,
+                    amount,
+                    swap_reserve,
+                    slot: program_clock(&ctx.accounts.global_state)?.slot,
+                });
+            },
+            ProposalType::UpgradeProgram { buffer } => {
+                let accounts = &ctx.accounts;
+                let program_data = accounts.program_data.as_ref().ok_or(ErrorCode::ProposalTargetMismatch)?;
+                let program = accounts.upgrade_program.as_ref().ok_or(ErrorCode::ProposalTargetMismatch)?;
+                let buffer_account = accounts.upgrade_buffer.as_ref().ok_or(ErrorCode::ProposalTargetMismatch)?;
+                let spill = accounts.spill.as_ref().ok_or(ErrorCode::ProposalTargetMismatch)?;
+                let rent = accounts.rent.as_ref().ok_or(ErrorCode::ProposalTargetMismatch)?;
+                let clock = accounts.clock.as_ref().ok_or(ErrorCode::ProposalTargetMismatch)?;
+                require!(accounts.bpf_loader.is_some(), ErrorCode::ProposalTargetMismatch);
+                require!(buffer_account.key() == *buffer, ErrorCode::ProposalTargetMismatch);
+
+                let ix = bpf_loader_upgradeable::upgrade(
+                    &crate::ID,
+                    buffer,
+                    &accounts.governance_state.key(),
+                    &spill.key(),
+                );
+                invoke_signed(
+                    &ix,
+                    &[
+                        program_data.to_account_info(),
+                        program.to_account_info(),
+                        buffer_account.to_account_info(),
+                        spill.to_account_info(),
+                        rent.to_account_info(),
+                        clock.to_account_info(),
+                        accounts.governance_state.to_account_info(),
+                    ],
+                    &[&[b"governance", &[accounts.governance_state.bump]]],
+                )?;
+
+                emit!(ProgramUpgraded {
+                    proposal_id: proposal.proposal_id,
+                    buffer: *buffer,
+                    slot: program_clock(&ctx.accounts.global_state)?.slot,
+                });
+            },
+            ProposalType::SetUpgradeAuthority { new_authority } => {
+                let program_data = ctx.accounts.program_data
+                    .as_ref()
+                    .ok_or(ErrorCode::ProposalTargetMismatch)?;
+                require!(ctx.accounts.bpf_loader.is_some(), ErrorCode::ProposalTargetMismatch);
+                let governance = ctx.accounts.governance_state.to_account_info();
+
+                let ix = bpf_loader_upgradeable::set_upgrade_authority(
+                    &crate::ID,
+                    governance.key,
+                    new_authority.as_ref(),
+                );
+                let mut account_infos = vec![program_data.to_account_info(), governance.clone()];
+                if let Some(new_authority) = new_authority {
+                    let new_authority_account = ctx.accounts.new_upgrade_authority
+                        .as_ref()
+                        .ok_or(ErrorCode::ProposalTargetMismatch)?;
+                    require!(new_authority_account.key() == *new_authority, ErrorCode::ProposalTargetMismatch);
+                    account_infos.push(new_authority_account.to_account_info());
+                }
+                invoke_signed(
+                    &ix,
+                    &account_infos,
+                    &[&[b"governance", &[ctx.accounts.governance_state.bump]]],
+                )?;
+
+                emit!(UpgradeAuthorityChanged {
+                    previous_authority: governance.key(),
+                    new_authority: *new_authority,
+                });
+            },
+            ProposalType::BulkUpdateFeeds { asset_type, update } => {
+                require!(
+                    !ctx.remaining_accounts.is_empty() && ctx.remaining_accounts.chunks_exact(2).remainder().is_empty(),
+                    ErrorCode::ProposalTargetMismatch
+                );
+
+                let mut feeds_updated: u32 = 0;
+                let mut updated_feeds: Vec<Pubkey> = Vec::new();
+                for pair in ctx.remaining_accounts.chunks_exact(2) {
+                    let product = Account::<ProductAccount>::try_from(&pair[0])?;
+                    let feed_account = AccountLoader::<PriceAccount>::try_from(&pair[1])?;
+                    require!(
+                        product.asset_type == *asset_type && product.price_account == feed_account.key(),
+                        ErrorCode::ProposalTargetMismatch
+                    );
+                    require!(pair[1].is_writable, ErrorCode::ProposalTargetMismatch);
+                    // Each feed once, so the count and events match the feeds actually changed
+                    require!(!updated_feeds.contains(pair[1].key), ErrorCode::ProposalTargetMismatch);
+                    updated_feeds.push(*pair[1].key);
+
+                    apply_feed_config(&mut *feed_account.load_mut()?, update, true, u8::MAX)?;
+                    feeds_updated += 1;
+
+                    emit!(FeedConfigChanged {
+                        price_account: feed_account.key(),
+                        update: update.clone(),
+                        changed_by: ctx.accounts.governance_state.key(),
+                        proposal_id: Some(proposal.proposal_id),
+                        slot: program_clock(&ctx.accounts.global_state)?.slot,
+                    });
+                }
+
+                emit!(FeedsBulkUpdated {
+                    proposal_id: proposal.proposal_id,
+                    asset_type: asset_type.clone(),
+                    feeds_updated,
+                });
+            },
+            ProposalType::FreezeFeed { feed } | ProposalType::UnfreezeFeed { feed } => {
+                let frozen = matches!(proposal.proposal_type, ProposalType::FreezeFeed { .. });
+                let feed_account = ctx.accounts.price_account
+                    .as_ref()
+                    .ok_or(ErrorCode::ProposalTargetMismatch)?;
+                require!(feed_account.key() == *feed, ErrorCode::ProposalTargetMismatch);
+                let price_account = &mut feed_account.load_mut()?;
+
+                price_account.frozen = frozen as u8;
+                if frozen {
+                    price_account.aggregate.status = PriceStatus::Halted as u8;
+                } else {
+                    // Governance lifting a freeze also ends any publisher emergency halt
+                    price_account.emergency_halt_until_slot = 0;
+                }
+
+                emit!(FeedFrozen {
+                    feed: *feed,
+                    frozen,
+                    proposal_id: proposal.proposal_id,
+                    timestamp: program_clock(&ctx.accounts.global_state)?.unix_timestamp,
+                });
+            },
+            ProposalType::DeclareVolatilityRegime { feed, widening_bps, duration_slots } => {
+                let feed_account = ctx.accounts.price_account
+                    .as_ref()
+                    .ok_or(ErrorCode::ProposalTargetMismatch)?;
+                require!(feed_account.key() == *feed, ErrorCode::ProposalTargetMismatch);
+                let price_account = &mut feed_account.load_mut()?;
+
+                // Counted from execution, so the regime cannot outlast its declared duration
+                let until_slot = if *duration_slots == 0 { 0 } else { program_clock(&ctx.accounts.global_state)?.slot + duration_slots };
+                price_account.volatility_until_slot = until_slot;
+                price_account.volatility_widening_bps = *widening_bps;
+
+                emit!(VolatilityRegimeDeclared {
+                    feed: *feed,
+                    widening_bps: *widening_bps,
+                    until_slot,
+                    proposal_id: proposal.proposal_id,
+                });
+            },
+            ProposalType::SlashPublisher { publisher, percentage, destination, publish_cooldown_slots } => {
+                let pub_account = ctx.accounts.publisher_account
+                    .as_mut()
+                    .ok_or(ErrorCode::ProposalTargetMismatch)?;
+                require!(pub_account.key() == *publisher, ErrorCode::ProposalTargetMismatch);
+
+                let stake_slash = (pub_account.staked_amount as u128 * *percentage as u128) / 100;
+                let stake_slash = stake_slash as u64;
+
+                // Unbonding tokens remain exposed until withdrawn
+                let unbonding_slash = (pub_account.stake_state.unbonding_amount() as u128 * *percentage as u128) / 100;
+                let unbonding_slash = unbonding_slash as u64;
+
+                let slash_amount = stake_slash
+                    .checked_add(unbonding_slash)
+                    .ok_or(ErrorCode::Overflow)?;
+
+                // Insurance absorbs the part above the deductible, staked tokens first
+                let insurance_covered = insurance_coverage(pub_account, &ctx.accounts.token_vault, slash_amount);
+                let stake_covered = insurance_covered.min(stake_slash);
+                let stake_slash = stake_slash - stake_covered;
+                let unbonding_slash = unbonding_slash - (insurance_covered - stake_covered);
+                let slash_amount = slash_amount - insurance_covered;
+                pub_account.insurance_claimed = pub_account.insurance_claimed
+                    .checked_add(insurance_covered)
+                    .ok_or(ErrorCode::Overflow)?;
+                let token_vault = &mut ctx.accounts.token_vault;
+                token_vault.insurance_fund -= insurance_covered;
+                token_vault.insurance_coverage = token_vault.insurance_coverage.saturating_sub(insurance_covered);
+
+                pub_account.staked_amount = pub_account.staked_amount
+                    .checked_sub(stake_slash)
+                    .ok_or(ErrorCode::Overflow)?;
+                // Positions keep their nominal amount; the lock never exceeds what is left
+                pub_account.locked_stake = pub_account.locked_stake.min(pub_account.staked_amount);
+                // A fully slashed batch returns the publisher to Bonded; otherwise its clock is kept
+                if let StakeState::Unbonding { amount, start, period } = pub_account.stake_state {
+                    let amount = amount
+                        .checked_sub(unbonding_slash)
+                        .ok_or(ErrorCode::Overflow)?;
+                    pub_account.stake_state = if amount == 0 {
+                        StakeState::Bonded
+                    } else {
+                        StakeState::Unbonding { amount, start, period }
+                    };
+                }
+                let slot = program_clock(&ctx.accounts.global_state)?.slot;
+                pub_account.slash_count += 1;
+                pub_account.last_slash_slot = slot;
+                pub_account.rewards_frozen_until_slot = slot + SLASH_REWARD_COOLDOWN_SLOTS;
+                // A shorter cooldown never lifts one still running from an earlier slash
+                pub_account.publishing_blocked_until_slot = pub_account.publishing_blocked_until_slot
+                    .max(slot + *publish_cooldown_slots);
+
+                // Rewards still vesting are forfeited back to the pool
+                let clawed_back = pub_account.reward_vesting.claw_back(program_clock(&ctx.accounts.global_state)?.unix_timestamp);
+                if clawed_back > 0 {
+                    let token_vault = &mut ctx.accounts.token_vault;
+                    token_vault.vesting_rewards -= clawed_back;
+                    token_vault.reward_pool = token_vault.reward_pool
+                        .checked_add(clawed_back)
+                        .ok_or(ErrorCode::Overflow)?;
+
+                    emit!(RewardsClawedBack {
+                        publisher: pub_account.key(),
+                        amount: clawed_back,
+                    });
+                }
+
+                ctx.accounts.token_vault.total_staked = ctx.accounts.token_vault.total_staked
+                    .checked_sub(slash_amount)
+                    .ok_or(ErrorCode::Overflow)?;
+
+                // The publisher's share plus the insured share leave the vault's books
+                let released = slash_amount + insurance_covered;
+                if let SlashDestination::InsuranceFund = destination {
+                    ctx.accounts.token_vault.insurance_fund = ctx.accounts.token_vault.insurance_fund
+                        .checked_add(released)
+                        .ok_or(ErrorCode::Overflow)?;
+                } else if released > 0 {
+                    let vault_token_account = ctx.accounts.vault_token_account
+                        .as_ref()
+                        .ok_or(ErrorCode::ProposalTargetMismatch)?;
+                    let vault_authority = ctx.accounts.vault_authority
+                        .as_ref()
+                        .ok_or(ErrorCode::ProposalTargetMismatch)?;
+                    let token_program = ctx.accounts.token_program
+                        .as_ref()
+                        .ok_or(ErrorCode::ProposalTargetMismatch)?;
+                    let token_mint = ctx.accounts.token_mint
+                        .as_ref()
+                        .ok_or(ErrorCode::ProposalTargetMismatch)?;
+
+                    let seeds = ctx.accounts.global_state.custody_signer_seeds();
+                    let signer = &[&seeds[..]];
+
+                    if let SlashDestination::Compensation { token_account } = destination {
+                        let compensation_account = ctx.accounts.compensation_account
+                            .as_ref()
+                            .ok_or(ErrorCode::ProposalTargetMismatch)?;
+                        require!(
+                            compensation_account.key() == *token_account,
+                            ErrorCode::ProposalTargetMismatch
+                        );
+
+                        let cpi_accounts = TransferChecked {
+                            from: vault_token_account.to_account_info(),
+                            mint: token_mint.to_account_info(),
+                            to: compensation_account.to_account_info(),
+                            authority: vault_authority.to_account_info(),
+                        };
+                        let cpi_ctx = CpiContext::new_with_signer(token_program.to_account_info(), cpi_accounts, signer)
+                            .with_remaining_accounts(ctx.remaining_accounts.to_vec());
+                        transfer_checked_with_hooks(cpi_ctx, released, token_mint.decimals)?;
+                    } else {
+                        let cpi_accounts = Burn {
+                            mint: token_mint.to_account_info(),
+                            from: vault_token_account.to_account_info(),
+                            authority: vault_authority.to_account_info(),
+                        };
+                        let cpi_ctx = CpiContext::new_with_signer(token_program.to_account_info(), cpi_accounts, signer);
+                        token_interface::burn(cpi_ctx, released)?;
+
+                        // Quorum and supermajority are measured against the supply that still exists
+                        let governance = &mut ctx.accounts.governance_state;
+                        governance.total_supply = governance.total_supply.saturating_sub(released);
+                    }
+                }
+
+                emit!(StakeChanged {
+                    publisher: pub_account.key(),
+                    transition: StakeTransition::Slashed,
+                    amount: stake_slash + unbonding_slash,
+                    staked_amount: pub_account.staked_amount,
+                    unbonding_amount: pub_account.stake_state.unbonding_amount(),
+                    timestamp: program_clock(&ctx.accounts.global_state)?.unix_timestamp,
+                });
+
+                emit!(PublisherSlashed {
+                    publisher: pub_account.key(),
+                    slash_amount,
+                    unbonding_slash_amount: unbonding_slash,
+                    insurance_covered,
+                    destination: destination.clone(),
+                    slash_percentage: *percentage,
+                    reason: "Governance proposal".to_string(),
+                });
+            },
+        }
+
+        let after = action_snapshot(ctx.accounts)?;
+        let clock = program_clock(&ctx.accounts.global_state)?;
+        let receipt_key = ctx.accounts.execution_receipt.key();
+        let proposal = &mut ctx.accounts.proposal;
+        let receipt = &mut ctx.accounts.execution_receipt;
+        receipt.proposal = proposal.key();
+        receipt.proposal_id = proposal.proposal_id;
+        receipt.before = before;
+        receipt.after = after;
+        receipt.slot = clock.slot;
+        receipt.executed_at = clock.unix_timestamp;
+        receipt.executor = ctx.accounts.authority.key();
+        receipt.bump = ctx.bumps.execution_receipt;
+        proposal.action_executed = true;
+
+        emit!(ActionExecuted {
+            proposal_id: receipt.proposal_id,
+            receipt: receipt_key,
+            slot: receipt.slot,
+        });
+
+        Ok(())
+    }
+
+    /// Hands the program's upgrade authority to the governance PDA, after which code
+    /// upgrades only happen through `UpgradeProgram` proposals
+    pub fn transfer_upgrade_authority_to_governance(
+        ctx: Context<TransferUpgradeAuthorityToGovernance>,
+    ) -> Result<()> {
+        let governance = ctx.accounts.governance_state.key();
+        let ix = bpf_loader_upgradeable::set_upgrade_authority(
+            &crate::ID,
+            &ctx.accounts.authority.key(),
+            Some(&governance),
+        );
+        invoke(
+            &ix,
+            &[
+                ctx.accounts.program_data.to_account_info(),
+                ctx.accounts.authority.to_account_info(),
+                ctx.accounts.governance_state.to_account_info(),
+            ],
+        )?;
+
+        emit!(UpgradeAuthorityChanged {
+            previous_authority: ctx.accounts.authority.key(),
+            new_authority: Some(governance),
+        });
+
+        Ok(())
+    }
+
+    // ========================================================================
+    // Optimistic Governance Instructions
+    // ========================================================================
+
+    pub fn propose_optimistic_change(
+        ctx: Context<ProposeOptimisticChange>,
+        change: OptimisticParam,
+    ) -> Result<()> {
+        require!(!ctx.accounts.global_state.paused, ErrorCode::SystemPaused);
+        require!(
+            ctx.accounts.proposer_token_account.amount >= ctx.accounts.governance_state.proposal_threshold,
+            ErrorCode::Unauthorized
+        );
+
+        // Only routine adjustments qualify; anything larger needs a full proposal
+        match &change {
+            OptimisticParam::RewardRate { new_rate } => {
+                let current = ctx.accounts.token_vault.reward_rate;
+                let max_delta = (current as u128 * OPTIMISTIC_MAX_RATE_CHANGE_BPS as u128) / 10_000;
+                require!(
+                    (current.abs_diff(*new_rate) as u128) <= max_delta,
+                    ErrorCode::NotOptimisticEligible
+                );
+            },
+            // A minimum above the feed's publishers would stop it publishing
+            OptimisticParam::MinPublishers { feed, new_min } => {
+                let feed_account = ctx.accounts.price_account
+                    .as_ref()
+                    .ok_or(ErrorCode::InvalidOptimisticState)?;
+                require!(feed_account.key() == *feed, ErrorCode::InvalidOptimisticState);
+                require!(
+                    *new_min > 0
+                        && *new_min as usize <= MAX_PUBLISHERS
+                        && *new_min <= feed_account.load()?.publisher_count,
+                    ErrorCode::NotOptimisticEligible
+                );
+            },
+        }
+
+        let slot = program_clock(&ctx.accounts.global_state)?.slot;
+        let governance = &mut ctx.accounts.governance_state;
+        let optimistic = &mut ctx.accounts.optimistic_change;
+
+        optimistic.proposer = ctx.accounts.proposer.key();
+        optimistic.change = change.clone();
+        optimistic.created_slot = slot;
+        optimistic.challenge_end_slot = slot + governance.optimistic_challenge_period;
+        optimistic.status = OptimisticStatus::Pending;
+        optimistic.vetoer = Pubkey::default();
+        optimistic.veto_stake = 0;
+        optimistic.change_id = governance.optimistic_change_count;
+        optimistic.bump = ctx.bumps.optimistic_change;
+
+        governance.optimistic_change_count += 1;
+
+        emit!(OptimisticChangeProposed {
+            change_id: optimistic.change_id,
+            proposer: optimistic.proposer,
+            change,
+            challenge_end_slot: optimistic.challenge_end_slot,
+        });
+
+        Ok(())
+    }
+
+    pub fn veto_optimistic_change<'info>(
+        ctx: Context<'_, '_, 'info, 'info, VetoOptimisticChange<'info>>,
+    ) -> Result<()> {
+        let optimistic = &mut ctx.accounts.optimistic_change;
+        require!(
+            optimistic.status == OptimisticStatus::Pending,
+            ErrorCode::InvalidOptimisticState
+        );
+        require!(
+            Clock::get()?.slot <= optimistic.challenge_end_slot,
+            ErrorCode::ChallengeWindowEnded
+        );
+
+        let veto_stake = ctx.accounts.governance_state.optimistic_veto_stake;
+
+        let vault_balance = ctx.accounts.vault_token_account.amount;
+        let cpi_accounts = TransferChecked {
+            from: ctx.accounts.vetoer_token_account.to_account_info(),
+            mint: ctx.accounts.token_mint.to_account_info(),
+            to: ctx.accounts.vault_token_account.to_account_info(),
+            authority: ctx.accounts.vetoer.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts)
+            .with_remaining_accounts(ctx.remaining_accounts.to_vec());
+        transfer_checked_with_hooks(cpi_ctx, veto_stake, ctx.accounts.token_mint.decimals)?;
+
+        // Token-2022 transfer fees are withheld in transit, so book what arrived
+        let veto_stake = vault_received(&mut ctx.accounts.vault_token_account, vault_balance)?;
+
+        optimistic.status = OptimisticStatus::Vetoed;
+        optimistic.vetoer = ctx.accounts.vetoer.key();
+        optimistic.veto_stake = veto_stake;
+
+        ctx.accounts.token_vault.total_veto_stake = ctx.accounts.token_vault.total_veto_stake
+            .checked_add(veto_stake)
+            .ok_or(ErrorCode::Overflow)?;
+
+        emit!(OptimisticChangeVetoed {
+            change_id: optimistic.change_id,
+            vetoer: optimistic.vetoer,
+            veto_stake,
+        });
+
+        Ok(())
+    }
+
+    pub fn reclaim_veto_stake<'info>(
+        ctx: Context<'_, '_, 'info, 'info, ReclaimVetoStake<'info>>,
+    ) -> Result<()> {
+        let optimistic = &mut ctx.accounts.optimistic_change;
+        require!(
+            optimistic.status == OptimisticStatus::Vetoed && optimistic.veto_stake > 0,
+            ErrorCode::InvalidOptimisticState
+        );
+        require!(
+            program_clock(&ctx.accounts.global_state)?.slot > optimistic.challenge_end_slot,
+            ErrorCode::ChallengeWindowActive
+        );
+
+        let amount = optimistic.veto_stake;
+
+        let seeds = ctx.accounts.global_state.custody_signer_seeds();
+        let signer = &[&seeds[..]];
+
+        let cpi_accounts = TransferChecked {
+            from: ctx.accounts.vault_token_account.to_account_info(),
+            mint: ctx.accounts.token_mint.to_account_info(),
+            to: ctx.accounts.vetoer_token_account.to_account_info(),
+            authority: ctx.accounts.vault_authority.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer)
+            .with_remaining_accounts(ctx.remaining_accounts.to_vec());
+        transfer_checked_with_hooks(cpi_ctx, amount, ctx.accounts.token_mint.decimals)?;
+
+        optimistic.veto_stake = 0;
+        ctx.accounts.token_vault.total_veto_stake = ctx.accounts.token_vault.total_veto_stake
+            .checked_sub(amount)
+            .ok_or(ErrorCode::Overflow)?;
+
+        Ok(())
+    }
+
+    pub fn finalize_optimistic_change(
+        ctx: Context<FinalizeOptimisticChange>,
+    ) -> Result<()> {
+        require!(!ctx.accounts.global_state.paused, ErrorCode::SystemPaused);
+
+        let optimistic = &mut ctx.accounts.optimistic_change;
+        require!(
+            optimistic.status == OptimisticStatus::Pending,
+            ErrorCode::InvalidOptimisticState
+        );
+        require!(
+            program_clock(&ctx.accounts.global_state)?.slot > optimistic.challenge_end_slot,
+            ErrorCode::ChallengeWindowActive
+        );
+
+        match &optimistic.change {
+            OptimisticParam::RewardRate { new_rate } => {
+                let slot = program_clock(&ctx.accounts.global_state)?.slot;
+                ctx.accounts.token_vault.settle_reward_index(slot);
+                ctx.accounts.token_vault.reward_rate = *new_rate;
+            },
+            OptimisticParam::MinPublishers { feed, new_min } => {
+                let feed_account = ctx.accounts.price_account
+                    .as_ref()
+                    .ok_or(ErrorCode::InvalidOptimisticState)?;
+                require!(feed_account.key() == *feed, ErrorCode::InvalidOptimisticState);
+                let price_account = &mut feed_account.load_mut()?;
+                // Publishers may have left during the challenge window
+                require!(*new_min <= price_account.publisher_count, ErrorCode::NotOptimisticEligible);
+                price_account.min_publishers = *new_min;
+            },
+        }
+
+        optimistic.status = OptimisticStatus::Applied;
+
+        emit!(OptimisticChangeApplied {
+            change_id: optimistic.change_id,
+            change: optimistic.change.clone(),
+        });
+
+        Ok(())
+    }
+
+    // ========================================================================
+    // Cross-Feed Guard Instructions
+    // ========================================================================
+
+    pub fn create_cross_rate_guard(
+        ctx: Context<CreateCrossRateGuard>,
+        tolerance_bps: u16,
+    ) -> Result<()> {
+        require!(
+            tolerance_bps > 0 && tolerance_bps <= 10_000,
+            ErrorCode::InvalidTolerance
+        );
+
+        let guard = &mut ctx.accounts.cross_rate_guard;
+        guard.base_feed = ctx.accounts.base_feed.key();
+        guard.quote_feed = ctx.accounts.quote_feed.key();
+        guard.direct_feed = ctx.accounts.direct_feed.key();
+        guard.tolerance_bps = tolerance_bps;
+        guard.last_implied_price = 0;
+        guard.last_direct_price = 0;
+        guard.last_deviation_bps = 0;
+        guard.diverged = false;
+        guard.last_checked_slot = 0;
+        guard.authority = ctx.accounts.authority.key();
+        guard.bump = ctx.bumps.cross_rate_guard;
+
+        Ok(())
+    }
+
+    pub fn check_cross_rate(
+        ctx: Context<CheckCrossRate>,
+    ) -> Result<()> {
+        let base = &ctx.accounts.base_feed.load()?.aggregate;
+        let quote = &ctx.accounts.quote_feed.load()?.aggregate;
+        let direct_feed = ctx.accounts.direct_feed.load()?;
+        let direct = &direct_feed.aggregate;
+
+        require!(
+            base.status().is_fresh() && quote.status().is_fresh() && direct.status().is_fresh(),
+            ErrorCode::PriceNotTrading
+        );
+
+        let implied = implied_cross_rate(
+            base.price,
+            base.exponent,
+            quote.price,
+            quote.exponent,
+            direct.exponent,
+            direct_feed.rounding_mode()?,
+        )
+        .ok_or(ErrorCode::InvalidPrice)?;
+        require!(direct.price > 0, ErrorCode::InvalidPrice);
+
+        let deviation_bps = ((implied as i128 - direct.price as i128).unsigned_abs() * 10_000
+            / direct.price as u128) as u64;
+
+        let slot = Clock::get()?.slot;
+        let guard = &mut ctx.accounts.cross_rate_guard;
+        guard.last_implied_price = implied;
+        guard.last_direct_price = direct.price;
+        guard.last_deviation_bps = deviation_bps;
+        guard.diverged = deviation_bps > guard.tolerance_bps as u64;
+        guard.last_checked_slot = slot;
+
+        if guard.diverged {
+            emit!(CrossRateDivergence {
+                direct_feed: guard.direct_feed,
+                base_feed: guard.base_feed,
+                quote_feed: guard.quote_feed,
+                implied_price: implied,
+                direct_price: direct.price,
+                deviation_bps,
+                tolerance_bps: guard.tolerance_bps,
+                slot,
+            });
+        }
+
+        Ok(())
+    }
+
+    // ========================================================================
+    // Vault Instructions
+    // ========================================================================
+
+    /// Permissionless check of the vault token balance against tracked liabilities
+    pub fn reconcile_vault(
+        ctx: Context<ReconcileVault>,
+    ) -> Result<()> {
+        let expected = vault_liabilities(&ctx.accounts.token_vault)?;
+        let actual = ctx.accounts.vault_token_account.amount;
+        let drift = i64::try_from(actual as i128 - expected as i128).map_err(|_| ErrorCode::Overflow)?;
+
+        ctx.accounts.token_vault.unreconciled_drift = drift;
+
+        if drift != 0 {
+            emit!(VaultDiscrepancy {
+                expected,
+                actual,
+                drift,
+                slot: Clock::get()?.slot,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Swaps a holder's old-mint tokens 1:1 for the new mint after a MigrateTokenMint
+    /// proposal, so voters and other wallet holders keep their balance. The old tokens go
+    /// to the migration counterparty and the new ones come out of the swap reserve.
+    pub fn swap_migrated_tokens<'info>(
+        ctx: Context<'_, '_, 'info, 'info, SwapMigratedTokens<'info>>,
+        amount: u64,
+    ) -> Result<()> {
+        require!(!ctx.accounts.global_state.paused, ErrorCode::SystemPaused);
+        let token_vault = &ctx.accounts.token_vault;
+        require!(token_vault.previous_token_mint != Pubkey::default(), ErrorCode::NoMintMigration);
+        require!(token_vault.swap_reserve >= amount, ErrorCode::MigrationUnderfunded);
+
+        // Transfer fees on the old mint would leave the counterparty short, so swap what arrives
+        let counterparty_balance = ctx.accounts.migration_counterparty.amount;
+        let cpi_accounts = TransferChecked {
+            from: ctx.accounts.holder_previous_token_account.to_account_info(),
+            mint: ctx.accounts.previous_token_mint.to_account_info(),
+            to: ctx.accounts.migration_counterparty.to_account_info(),
+            authority: ctx.accounts.holder.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new(ctx.accounts.previous_token_program.to_account_info(), cpi_accounts)
+            .with_remaining_accounts(ctx.remaining_accounts.to_vec());
+        transfer_checked_with_hooks(cpi_ctx, amount, ctx.accounts.previous_token_mint.decimals)?;
+        let amount = vault_received(&mut ctx.accounts.migration_counterparty, counterparty_balance)?;
+
+        let seeds = ctx.accounts.global_state.custody_signer_seeds();
+        let signer = &[&seeds[..]];
+        let cpi_accounts = TransferChecked {
+            from: ctx.accounts.vault_token_account.to_account_info(),
+            mint: ctx.accounts.token_mint.to_account_info(),
+            to: ctx.accounts.holder_token_account.to_account_info(),
+            authority: ctx.accounts.vault_authority.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(ctx.accounts.token_program.to_account_info(), cpi_accounts, signer)
+            .with_remaining_accounts(ctx.remaining_accounts.to_vec());
+        transfer_checked_with_hooks(cpi_ctx, amount, ctx.accounts.token_mint.decimals)?;
+
+        let token_vault = &mut ctx.accounts.token_vault;
+        token_vault.swap_reserve -= amount;
+
+        emit!(MigratedTokensSwapped {
+            holder: ctx.accounts.holder.key(),
+            previous_mint: token_vault.previous_token_mint,
+            new_mint: token_vault.token_mint,
+            amount,
+            swap_reserve: token_vault.swap_reserve,
+            slot: program_clock(&ctx.accounts.global_state)?.slot,
+        });
+
+        Ok(())
+    }
+
+    // ========================================================================
+    // Stake Receipt Instructions
+    // ========================================================================
+
+    pub fn create_receipt_mint(
+        ctx: Context<CreateReceiptMint>,
+    ) -> Result<()> {
+        ctx.accounts.global_state.receipt_mint = ctx.accounts.receipt_mint.key();
+        Ok(())
+    }
+
+    /// Mints receipts 1:1 against stake (bonded or unbonding) not yet represented.
+    /// Receipts are burned on withdrawal, so the holder must return them to exit.
+    pub fn mint_stake_receipts(
+        ctx: Context<MintStakeReceipts>,
+        amount: u64,
+    ) -> Result<()> {
+        require!(!ctx.accounts.global_state.paused, ErrorCode::SystemPaused);
+        require!(amount > 0, ErrorCode::InsufficientStake);
+
+        let publisher = &mut ctx.accounts.publisher_account;
+        let position = publisher.staked_amount
+            .checked_add(publisher.stake_state.unbonding_amount())
+            .ok_or(ErrorCode::Overflow)?;
+        let receipt_balance = publisher.receipt_balance
+            .checked_add(amount)
+            .ok_or(ErrorCode::Overflow)?;
+        require!(receipt_balance <= position, ErrorCode::ReceiptsExceedStake);
+
+        let vault_authority_bump = ctx.accounts.global_state.vault_authority_bump;
+        let seeds = &[
+            b"vault_authority".as_ref(),
+            &[vault_authority_bump],
+        ];
+        let signer = &[&seeds[..]];
+        let token_program = ctx.accounts.token_program.to_account_info();
+        let receipt_account = &ctx.accounts.publisher_receipt_account;
+
+        // Minting into a frozen account fails, so thaw first and refreeze afterwards
+        if receipt_account.is_frozen() {
+            let cpi_accounts = ThawAccount {
+                account: receipt_account.to_account_info(),
+                mint: ctx.accounts.receipt_mint.to_account_info(),
+                authority: ctx.accounts.vault_authority.to_account_info(),
+            };
+            token_interface::thaw_account(CpiContext::new_with_signer(token_program.clone(), cpi_accounts, signer))?;
+        }
+
+        let cpi_accounts = MintTo {
+            mint: ctx.accounts.receipt_mint.to_account_info(),
+            to: receipt_account.to_account_info(),
+            authority: ctx.accounts.vault_authority.to_account_info(),
+        };
+        token_interface::mint_to(CpiContext::new_with_signer(token_program.clone(), cpi_accounts, signer), amount)?;
+
+        if !ctx.accounts.global_state.receipts_transferable {
+            let cpi_accounts = FreezeAccount {
+                account: receipt_account.to_account_info(),
+                mint: ctx.accounts.receipt_mint.to_account_info(),
+                authority: ctx.accounts.vault_authority.to_account_info(),
+            };
+            token_interface::freeze_account(CpiContext::new_with_signer(token_program, cpi_accounts, signer))?;
+        }
+
+        publisher.receipt_balance = receipt_balance;
+
+        emit!(StakeReceiptsMinted {
+            publisher: publisher.key(),
+            amount,
+            receipt_balance,
+        });
+
+        Ok(())
+    }
+
+    /// Permissionless: freezes or thaws a receipt token account to match the
+    /// governance-set transferability
+    pub fn sync_receipt_account(
+        ctx: Context<SyncReceiptAccount>,
+    ) -> Result<()> {
+        let vault_authority_bump = ctx.accounts.global_state.vault_authority_bump;
+        let seeds = &[
+            b"vault_authority".as_ref(),
+            &[vault_authority_bump],
+        ];
+        let signer = &[&seeds[..]];
+        let token_program = ctx.accounts.token_program.to_account_info();
+        let receipt_account = &ctx.accounts.receipt_account;
+
+        if ctx.accounts.global_state.receipts_transferable && receipt_account.is_frozen() {
+            let cpi_accounts = ThawAccount {
+                account: receipt_account.to_account_info(),
+                mint: ctx.accounts.receipt_mint.to_account_info(),
+                authority: ctx.accounts.vault_authority.to_account_info(),
+            };
+            token_interface::thaw_account(CpiContext::new_with_signer(token_program, cpi_accounts, signer))?;
+        } else if !ctx.accounts.global_state.receipts_transferable && !receipt_account.is_frozen() {
+            let cpi_accounts = FreezeAccount {
+                account: receipt_account.to_account_info(),
+                mint: ctx.accounts.receipt_mint.to_account_info(),
+                authority: ctx.accounts.vault_authority.to_account_info(),
+            };
+            token_interface::freeze_account(CpiContext::new_with_signer(token_program, cpi_accounts, signer))?;
+        }
+
+        Ok(())
+    }
+
+    /// Locks part of the publisher's bonded stake for `tier` and mints a position NFT to
+    /// the publisher, with Metaplex metadata pointing at `uri`. The NFT is frozen unless
+    /// governance made positions transferable.
+    pub fn lock_stake_position(
+        ctx: Context<LockStakePosition>,
+        amount: u64,
+        tier: LockTier,
+        uri: String,
+    ) -> Result<()> {
+        require!(!ctx.accounts.global_state.paused, ErrorCode::SystemPaused);
+        require!(amount > 0, ErrorCode::InsufficientStake);
+        require!(uri.len() <= MAX_POSITION_URI_LEN, ErrorCode::InvalidPositionMetadata);
+
+        let publisher = &mut ctx.accounts.publisher_account;
+        let locked_stake = publisher.locked_stake
+            .checked_add(amount)
+            .ok_or(ErrorCode::Overflow)?;
+        require!(locked_stake <= publisher.staked_amount, ErrorCode::InsufficientStake);
+
+        let now = program_clock(&ctx.accounts.global_state)?.unix_timestamp;
+        let index = publisher.position_count;
+        let position = &mut ctx.accounts.stake_position;
+        position.publisher = publisher.key();
+        position.mint = ctx.accounts.position_mint.key();
+        position.index = index;
+        position.amount = amount;
+        position.tier = tier;
+        position.locked_at = now;
+        position.unlock_at = now + tier.lock_duration();
+        position.bump = ctx.bumps.stake_position;
+
+        publisher.locked_stake = locked_stake;
+        publisher.position_count = index.checked_add(1).ok_or(ErrorCode::Overflow)?;
+        let unlock_at = position.unlock_at;
+        let publisher_key = publisher.key();
+
+        let vault_authority_bump = ctx.accounts.global_state.vault_authority_bump;
+        let seeds = &[
+            b"vault_authority".as_ref(),
+            &[vault_authority_bump],
+        ];
+        let signer = &[&seeds[..]];
+        let token_program = ctx.accounts.token_program.to_account_info();
+
+        let cpi_accounts = MintTo {
+            mint: ctx.accounts.position_mint.to_account_info(),
+            to: ctx.accounts.position_token_account.to_account_info(),
+            authority: ctx.accounts.vault_authority.to_account_info(),
+        };
+        token_interface::mint_to(CpiContext::new_with_signer(token_program.clone(), cpi_accounts, signer), 1)?;
+
+        let name = format!("SFDN Stake {} #{}", tier.label(), index);
+        create_position_metadata(ctx.accounts, name, uri, signer)?;
+
+        if !ctx.accounts.global_state.positions_transferable {
+            let cpi_accounts = FreezeAccount {
+                account: ctx.accounts.position_token_account.to_account_info(),
+                mint: ctx.accounts.position_mint.to_account_info(),
+                authority: ctx.accounts.vault_authority.to_account_info(),
+            };
+            token_interface::freeze_account(CpiContext::new_with_signer(token_program, cpi_accounts, signer))?;
+        }
+
+        emit!(StakePositionLocked {
+            publisher: publisher_key,
+            position_mint: ctx.accounts.position_mint.key(),
+            amount,
+            tier,
+            unlock_at,
+            locked_stake,
+        });
+
+        Ok(())
+    }
+
+    /// Releases a matured stake position back to ordinary bonded stake. The publisher must
+    /// hold the position NFT, which is burned.
+    pub fn unlock_stake_position(
+        ctx: Context<UnlockStakePosition>,
+    ) -> Result<()> {
+        let position = &ctx.accounts.stake_position;
+        require!(
+            program_clock(&ctx.accounts.global_state)?.unix_timestamp >= position.unlock_at,
+            ErrorCode::PositionStillLocked
+        );
+
+        let vault_authority_bump = ctx.accounts.global_state.vault_authority_bump;
+        let seeds = &[
+            b"vault_authority".as_ref(),
+            &[vault_authority_bump],
+        ];
+        let signer = &[&seeds[..]];
+        let token_program = ctx.accounts.token_program.to_account_info();
+        let position_token_account = &ctx.accounts.position_token_account;
+
+        // Burning from a frozen account fails
+        if position_token_account.is_frozen() {
+            let cpi_accounts = ThawAccount {
+                account: position_token_account.to_account_info(),
+                mint: ctx.accounts.position_mint.to_account_info(),
+                authority: ctx.accounts.vault_authority.to_account_info(),
+            };
+            token_interface::thaw_account(CpiContext::new_with_signer(token_program.clone(), cpi_accounts, signer))?;
+        }
+
+        let cpi_accounts = Burn {
+            mint: ctx.accounts.position_mint.to_account_info(),
+            from: position_token_account.to_account_info(),
+            authority: ctx.accounts.publisher_authority.to_account_info(),
+        };
+        token_interface::burn(CpiContext::new(token_program, cpi_accounts), 1)?;
+
+        let publisher = &mut ctx.accounts.publisher_account;
+        publisher.locked_stake = publisher.locked_stake.saturating_sub(position.amount);
+
+        emit!(StakePositionUnlocked {
+            publisher: publisher.key(),
+            position_mint: position.mint,
+            amount: position.amount,
+            locked_stake: publisher.locked_stake,
+        });
+
+        Ok(())
+    }
+
+    /// Pays a matured position's stake to whoever holds its NFT, which is burned. The
+    /// position first serves the unbonding period after its lock ends, so it stays
+    /// slashable as unstaked stake would. Slashes since the lock reduce the payout to what
+    /// the publisher has left; the publisher must top up if this leaves it under its minimum.
+    pub fn redeem_stake_position<'info>(
+        ctx: Context<'_, '_, 'info, 'info, RedeemStakePosition<'info>>,
+    ) -> Result<()> {
+        let position = &ctx.accounts.stake_position;
+        let unbonded_at = position.unlock_at
+            .checked_add(ctx.accounts.global_state.unbonding_period)
+            .ok_or(ErrorCode::Overflow)?;
+        require!(
+            program_clock(&ctx.accounts.global_state)?.unix_timestamp >= unbonded_at,
+            ErrorCode::PositionStillLocked
+        );
+
+        // Position mints keep the original vault authority as freeze authority
+        let vault_authority_bump = ctx.accounts.global_state.vault_authority_bump;
+        let seeds = &[
+            b"vault_authority".as_ref(),
+            &[vault_authority_bump],
+        ];
+        let signer = &[&seeds[..]];
+        let token_program = ctx.accounts.token_program.to_account_info();
+        let position_token_account = &ctx.accounts.position_token_account;
+
+        if position_token_account.is_frozen() {
+            let cpi_accounts = ThawAccount {
+                account: position_token_account.to_account_info(),
+                mint: ctx.accounts.position_mint.to_account_info(),
+                authority: ctx.accounts.position_authority.to_account_info(),
+            };
+            token_interface::thaw_account(CpiContext::new_with_signer(token_program.clone(), cpi_accounts, signer))?;
+        }
+
+        let cpi_accounts = Burn {
+            mint: ctx.accounts.position_mint.to_account_info(),
+            from: position_token_account.to_account_info(),
+            authority: ctx.accounts.holder.to_account_info(),
+        };
+        token_interface::burn(CpiContext::new(token_program.clone(), cpi_accounts), 1)?;
+
+        let publisher = &mut ctx.accounts.publisher_account;
+        let amount = position.amount.min(publisher.staked_amount);
+        publisher.staked_amount -= amount;
+        publisher.locked_stake = publisher.locked_stake
+            .saturating_sub(position.amount)
+            .min(publisher.staked_amount);
+
+        let seeds = ctx.accounts.global_state.custody_signer_seeds();
+        let signer = &[&seeds[..]];
+        let cpi_accounts = TransferChecked {
+            from: ctx.accounts.vault_token_account.to_account_info(),
+            mint: ctx.accounts.token_mint.to_account_info(),
+            to: ctx.accounts.holder_token_account.to_account_info(),
+            authority: ctx.accounts.vault_authority.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(token_program, cpi_accounts, signer)
+            .with_remaining_accounts(ctx.remaining_accounts.to_vec());
+        transfer_checked_with_hooks(cpi_ctx, amount, ctx.accounts.token_mint.decimals)?;
+
+        ctx.accounts.token_vault.total_staked = ctx.accounts.token_vault.total_staked
+            .checked_sub(amount)
+            .ok_or(ErrorCode::Overflow)?;
+
+        emit!(StakePositionRedeemed {
+            publisher: publisher.key(),
+            position_mint: position.mint,
+            holder: ctx.accounts.holder.key(),
+            amount,
+            staked_amount: publisher.staked_amount,
+            locked_stake: publisher.locked_stake,
+        });
+
+        Ok(())
+    }
+
+    /// Permissionless: freezes or thaws a position NFT's token account to match the
+    /// governance-set transferability
+    pub fn sync_position_account(
+        ctx: Context<SyncPositionAccount>,
+    ) -> Result<()> {
+        let vault_authority_bump = ctx.accounts.global_state.vault_authority_bump;
+        let seeds = &[
+            b"vault_authority".as_ref(),
+            &[vault_authority_bump],
+        ];
+        let signer = &[&seeds[..]];
+        let token_program = ctx.accounts.token_program.to_account_info();
+        let position_token_account = &ctx.accounts.position_token_account;
+
+        if ctx.accounts.global_state.positions_transferable && position_token_account.is_frozen() {
+            let cpi_accounts = ThawAccount {
+                account: position_token_account.to_account_info(),
+                mint: ctx.accounts.position_mint.to_account_info(),
+                authority: ctx.accounts.vault_authority.to_account_info(),
+            };
+            token_interface::thaw_account(CpiContext::new_with_signer(token_program, cpi_accounts, signer))?;
+        } else if !ctx.accounts.global_state.positions_transferable && !position_token_account.is_frozen() {
+            let cpi_accounts = FreezeAccount {
+                account: position_token_account.to_account_info(),
+                mint: ctx.accounts.position_mint.to_account_info(),
+                authority: ctx.accounts.vault_authority.to_account_info(),
+            };
+            token_interface::freeze_account(CpiContext::new_with_signer(token_program, cpi_accounts, signer))?;
+        }
+
+        Ok(())
+    }
+
+    // ========================================================================
+    // SLA Instructions
+    // ========================================================================
+
+    /// Creates an empty SLA account for a feed; terms are set by a SetFeedSla proposal
+    pub fn create_feed_sla(
+        ctx: Context<CreateFeedSla>,
+    ) -> Result<()> {
+        let feed_sla = &mut ctx.accounts.feed_sla;
+        feed_sla.feed = ctx.accounts.price_account.key();
+        feed_sla.max_update_interval_slots = 0;
+        feed_sla.max_confidence_bps = 0;
+        feed_sla.breach_count = 0;
+        feed_sla.last_breach_slot = 0;
+        feed_sla.rebate_bps = 0;
+        feed_sla.total_subscribed = 0;
+        feed_sla.rebate_index_bps = 0;
+        feed_sla.bump = ctx.bumps.feed_sla;
+
+        Ok(())
+    }
+
+    /// Creates an empty quota account for a feed; quotas are set by a SetFeedQuota proposal
+    pub fn create_feed_quota(
+        ctx: Context<CreateFeedQuota>,
+    ) -> Result<()> {
+        let feed_quota = &mut ctx.accounts.feed_quota;
+        feed_quota.price_account = ctx.accounts.price_account.key();
+        feed_quota.min_submissions = 0;
+        feed_quota.max_submissions = 0;
+        feed_quota.epoch = ctx.accounts.global_state.current_epoch;
+        feed_quota.submissions = [0; MAX_PUBLISHERS];
+        feed_quota.bump = ctx.bumps.feed_quota;
+
+        Ok(())
+    }
+
+    /// Permissionless keeper check. Records at most one breach per SLA update interval.
+    pub fn record_sla_breach(
+        ctx: Context<RecordSlaBreach>,
+    ) -> Result<()> {
+        let slot = Clock::get()?.slot;
+        let aggregate = &ctx.accounts.price_account.load()?.aggregate;
+        let feed_sla = &mut ctx.accounts.feed_sla;
+
+        require!(feed_sla.max_update_interval_slots > 0, ErrorCode::SlaNotBreached);
+        require!(
+            feed_sla.breach_count == 0
+                || slot >= feed_sla.last_breach_slot + feed_sla.max_update_interval_slots,
+            ErrorCode::SlaNotBreached
+        );
+
+        let kind = if slot.saturating_sub(aggregate.slot) > feed_sla.max_update_interval_slots {
+            SlaBreachKind::UpdateFrequency
+        } else if feed_sla.max_confidence_bps > 0
+            && aggregate.confidence as u128 * 10_000
+                > feed_sla.max_confidence_bps as u128 * aggregate.price.unsigned_abs() as u128
+        {
+            SlaBreachKind::Confidence
+        } else {
+            return err!(ErrorCode::SlaNotBreached);
+        };
+
+        feed_sla.breach_count += 1;
+        feed_sla.last_breach_slot = slot;
+
+        // Credit subscribers rebate_bps of their fees, limited to what the treasury holds.
+        // Credits settle lazily per subscription through the rebate index.
+        let token_vault = &mut ctx.accounts.token_vault;
+        let owed = (feed_sla.total_subscribed as u128 * feed_sla.rebate_bps as u128 / 10_000) as u64;
+        let rebate_amount = owed.min(token_vault.treasury);
+        if rebate_amount > 0 {
+            let index_delta = (rebate_amount as u128 * 10_000 / feed_sla.total_subscribed as u128) as u64;
+            // Round the reserved amount to what the index will actually pay out
+            let rebate_amount = (feed_sla.total_subscribed as u128 * index_delta as u128 / 10_000) as u64;
+
+            feed_sla.rebate_index_bps = feed_sla.rebate_index_bps
+                .checked_add(index_delta)
+                .ok_or(ErrorCode::Overflow)?;
+            token_vault.treasury -= rebate_amount;
+            token_vault.pending_rebates = token_vault.pending_rebates
+                .checked_add(rebate_amount)
+                .ok_or(ErrorCode::Overflow)?;
+        }
+
+        emit!(SlaBreachRecorded {
+            feed: feed_sla.feed,
+            kind,
+            breach_count: feed_sla.breach_count,
+            rebate_amount,
+            slot,
+        });
+
+        Ok(())
+    }
+
+    // ========================================================================
+    // Subscription Instructions
+    // ========================================================================
+
+    pub fn create_subscription(
+        ctx: Context<CreateSubscription>,
+    ) -> Result<()> {
+        let subscription = &mut ctx.accounts.subscription;
+        subscription.consumer = ctx.accounts.consumer.key();
+        subscription.feed = ctx.accounts.feed_sla.feed;
+        subscription.fees_paid = 0;
+        subscription.rebate_index_bps = ctx.accounts.feed_sla.rebate_index_bps;
+        subscription.credits = 0;
+        subscription.rebates_earned = 0;
+        subscription.premium_epoch = 0;
+        subscription.premium = 0;
+        subscription.premium_breach_count = 0;
+        subscription.coverage_claimed = 0;
+        subscription.bump = ctx.bumps.subscription;
+
+        Ok(())
+    }
+
+    pub fn subscribe<'info>(
+        ctx: Context<'_, '_, 'info, 'info, Subscribe<'info>>,
+        fee: u64,
+    ) -> Result<()> {
+        require!(!ctx.accounts.global_state.paused, ErrorCode::SystemPaused);
+        require!(fee > 0, ErrorCode::InvalidPrice);
+
+        let vault_balance = ctx.accounts.vault_token_account.amount;
+        let cpi_accounts = TransferChecked {
+            from: ctx.accounts.consumer_token_account.to_account_info(),
+            mint: ctx.accounts.token_mint.to_account_info(),
+            to: ctx.accounts.vault_token_account.to_account_info(),
+            authority: ctx.accounts.consumer.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts)
+            .with_remaining_accounts(ctx.remaining_accounts.to_vec());
+        transfer_checked_with_hooks(cpi_ctx, fee, ctx.accounts.token_mint.decimals)?;
+
+        // Token-2022 transfer fees are withheld in transit, so book what arrived
+        let fee = vault_received(&mut ctx.accounts.vault_token_account, vault_balance)?;
+
+        let feed_sla = &mut ctx.accounts.feed_sla;
+        let subscription = &mut ctx.accounts.subscription;
+        settle_rebates(subscription, feed_sla, &mut ctx.accounts.token_vault)?;
+        subscription.fees_paid = subscription.fees_paid
+            .checked_add(fee)
+            .ok_or(ErrorCode::Overflow)?;
+        feed_sla.total_subscribed = feed_sla.total_subscribed
+            .checked_add(fee)
+            .ok_or(ErrorCode::Overflow)?;
+
+        let token_vault = &mut ctx.accounts.token_vault;
+        token_vault.treasury = token_vault.treasury
+            .checked_add(fee)
+            .ok_or(ErrorCode::Overflow)?;
+
+        emit!(Subscribed {
+            consumer: subscription.consumer,
+            feed: subscription.feed,
+            fee,
+            fees_paid: subscription.fees_paid,
+        });
+
+        Ok(())
+    }
+
+    /// Pays a subscription fee in the separate reward mint. The fee joins the reward pool,
+    /// so consumers fund publisher rewards directly; it does not count towards SLA rebates,
+    /// which the treasury pays in the staking mint.
+    pub fn subscribe_with_reward_mint<'info>(
+        ctx: Context<'_, '_, 'info, 'info, SubscribeWithRewardMint<'info>>,
+        fee: u64,
+    ) -> Result<()> {
+        require!(!ctx.accounts.global_state.paused, ErrorCode::SystemPaused);
+        require!(fee > 0, ErrorCode::InvalidPrice);
+        require!(ctx.accounts.token_vault.has_separate_reward_mint(), ErrorCode::InvalidMint);
+
+        let vault_balance = ctx.accounts.reward_vault_token_account.amount;
+        let cpi_accounts = TransferChecked {
+            from: ctx.accounts.consumer_token_account.to_account_info(),
+            mint: ctx.accounts.reward_mint.to_account_info(),
+            to: ctx.accounts.reward_vault_token_account.to_account_info(),
+            authority: ctx.accounts.consumer.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts)
+            .with_remaining_accounts(ctx.remaining_accounts.to_vec());
+        transfer_checked_with_hooks(cpi_ctx, fee, ctx.accounts.reward_mint.decimals)?;
+
+        // Token-2022 transfer fees are withheld in transit, so book what arrived
+        let fee = vault_received(&mut ctx.accounts.reward_vault_token_account, vault_balance)?;
+
+        let token_vault = &mut ctx.accounts.token_vault;
+        token_vault.reward_pool = token_vault.reward_pool
+            .checked_add(fee)
+            .ok_or(ErrorCode::Overflow)?;
+
+        emit!(RewardFeePaid {
+            consumer: ctx.accounts.subscription.consumer,
+            feed: ctx.accounts.subscription.feed,
+            fee,
+            reward_pool: token_vault.reward_pool,
+        });
+
+        Ok(())
+    }
+
+    pub fn claim_rebates<'info>(
+        ctx: Context<'_, '_, 'info, 'info, ClaimRebates<'info>>,
+    ) -> Result<()> {
+        let subscription = &mut ctx.accounts.subscription;
+        settle_rebates(subscription, &ctx.accounts.feed_sla, &mut ctx.accounts.token_vault)?;
+
+        let amount = subscription.credits;
+        require!(amount > 0, ErrorCode::NoRebateCredits);
+
+        let seeds = ctx.accounts.global_state.custody_signer_seeds();
+        let signer = &[&seeds[..]];
+
+        let cpi_accounts = TransferChecked {
+            from: ctx.accounts.vault_token_account.to_account_info(),
+            mint: ctx.accounts.token_mint.to_account_info(),
+            to: ctx.accounts.consumer_token_account.to_account_info(),
+            authority: ctx.accounts.vault_authority.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer)
+            .with_remaining_accounts(ctx.remaining_accounts.to_vec());
+        transfer_checked_with_hooks(cpi_ctx, amount, ctx.accounts.token_mint.decimals)?;
+
+        subscription.credits = 0;
+        let token_vault = &mut ctx.accounts.token_vault;
+        token_vault.pending_rebates = token_vault.pending_rebates.saturating_sub(amount);
+
+        emit!(RebateClaimed {
+            consumer: subscription.consumer,
+            feed: subscription.feed,
+            amount,
+        });
+
+        Ok(())
+    }
+
+    /// Buys insurance for the current program epoch. The premium goes into the insurance
+    /// fund and covers PREMIUM_COVERAGE_MULTIPLIER times its amount against SLA breaches
+    /// recorded later in the same epoch. One premium per subscription and epoch.
+    pub fn pay_insurance_premium<'info>(
+        ctx: Context<'_, '_, 'info, 'info, PayInsurancePremium<'info>>,
+        premium: u64,
+    ) -> Result<()> {
+        require!(!ctx.accounts.global_state.paused, ErrorCode::SystemPaused);
+        require!(premium > 0, ErrorCode::InvalidPrice);
+        require!(ctx.accounts.subscription.fees_paid > 0, ErrorCode::Unauthorized);
+
+        let epoch = ctx.accounts.global_state.current_epoch;
+        require!(
+            ctx.accounts.subscription.premium == 0 || ctx.accounts.subscription.premium_epoch != epoch,
+            ErrorCode::PremiumAlreadyPaid
+        );
+
+        let vault_balance = ctx.accounts.vault_token_account.amount;
+        let cpi_accounts = TransferChecked {
+            from: ctx.accounts.consumer_token_account.to_account_info(),
+            mint: ctx.accounts.token_mint.to_account_info(),
+            to: ctx.accounts.vault_token_account.to_account_info(),
+            authority: ctx.accounts.consumer.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts)
+            .with_remaining_accounts(ctx.remaining_accounts.to_vec());
+        transfer_checked_with_hooks(cpi_ctx, premium, ctx.accounts.token_mint.decimals)?;
+
+        let premium = vault_received(&mut ctx.accounts.vault_token_account, vault_balance)?;
+
+        // Only breaches after this point are covered
+        let subscription = &mut ctx.accounts.subscription;
+        subscription.premium_epoch = epoch;
+        subscription.premium = premium;
+        subscription.premium_breach_count = ctx.accounts.feed_sla.breach_count;
+        subscription.coverage_claimed = 0;
+
+        let token_vault = &mut ctx.accounts.token_vault;
+        token_vault.insurance_fund = token_vault.insurance_fund
+            .checked_add(premium)
+            .ok_or(ErrorCode::Overflow)?;
+
+        emit!(InsurancePremiumPaid {
+            consumer: subscription.consumer,
+            feed: subscription.feed,
+            epoch,
+            premium,
+            coverage: premium.saturating_mul(PREMIUM_COVERAGE_MULTIPLIER),
+        });
+
+        Ok(())
+    }
+
+    /// Pays out the coverage bought for the current epoch once the feed's SLA has been
+    /// breached since the premium was paid, limited to what the insurance fund holds.
+    /// Coverage lapses with the epoch.
+    pub fn claim_insurance<'info>(
+        ctx: Context<'_, '_, 'info, 'info, ClaimInsurance<'info>>,
+    ) -> Result<()> {
+        let epoch = ctx.accounts.global_state.current_epoch;
+        let subscription = &mut ctx.accounts.subscription;
+        require!(
+            subscription.premium > 0
+                && subscription.premium_epoch == epoch
+                && ctx.accounts.feed_sla.breach_count > subscription.premium_breach_count,
+            ErrorCode::NoInsuranceClaim
+        );
+
+        let coverage = subscription.premium.saturating_mul(PREMIUM_COVERAGE_MULTIPLIER);
+        let amount = coverage
+            .saturating_sub(subscription.coverage_claimed)
+            .min(ctx.accounts.token_vault.insurance_fund);
+        require!(amount > 0, ErrorCode::NoInsuranceClaim);
+
+        let seeds = ctx.accounts.global_state.custody_signer_seeds();
+        let signer = &[&seeds[..]];
+
+        let cpi_accounts = TransferChecked {
+            from: ctx.accounts.vault_token_account.to_account_info(),
+            mint: ctx.accounts.token_mint.to_account_info(),
+            to: ctx.accounts.consumer_token_account.to_account_info(),
+            authority: ctx.accounts.vault_authority.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer)
+            .with_remaining_accounts(ctx.remaining_accounts.to_vec());
+        transfer_checked_with_hooks(cpi_ctx, amount, ctx.accounts.token_mint.decimals)?;
+
+        subscription.coverage_claimed += amount;
+        ctx.accounts.token_vault.insurance_fund -= amount;
+
+        emit!(InsuranceClaimed {
+            consumer: subscription.consumer,
+            feed: subscription.feed,
+            epoch,
+            amount,
+        });
+
+        Ok(())
+    }
+
+    pub fn emergency_pause(
+        ctx: Context<EmergencyPause>,
+    ) -> Result<()> {
+        require!(
+            ctx.accounts.authority.key() == ctx.accounts.global_state.authority,
+            ErrorCode::Unauthorized
+        );
+
+        ctx.accounts.global_state.paused = true;
+
+        emit!(SystemPaused {
+            timestamp: program_clock(&ctx.accounts.global_state)?.unix_timestamp,
+            authority: ctx.accounts.authority.key(),
+        });
+
+        Ok(())
+    }
+
+    pub fn emergency_unpause(
+        ctx: Context<EmergencyUnpause>,
+    ) -> Result<()> {
+        require!(
+            ctx.accounts.authority.key() == ctx.accounts.global_state.authority,
+            ErrorCode::Unauthorized
+        );
+
+        ctx.accounts.global_state.paused = false;
+
+        emit!(SystemUnpaused {
+            timestamp: program_clock(&ctx.accounts.global_state)?.unix_timestamp,
+            authority: ctx.accounts.authority.key(),
+        });
+
+        Ok(())
+    }
+
+    /// Test builds only: moves the program's clock `seconds` further ahead of the
+    /// cluster clock, e.g. to end an unbonding period or timelock, or to let quotes go
+    /// stale, without waiting. Fails unless built with the `mock-clock` feature, and on
+    /// deployments whose ClusterConfig says mainnet, in case such a build is deployed there.
+    pub fn advance_mock_clock(
+        ctx: Context<AdvanceMockClock>,
+        seconds: i64,
+    ) -> Result<()> {
+        require!(cfg!(feature = "mock-clock"), ErrorCode::MockClockDisabled);
+        require!(
+            ctx.accounts.cluster_config.environment != ClusterEnvironment::Mainnet,
+            ErrorCode::MockClockOnMainnet
+        );
+        require!(seconds > 0, ErrorCode::InvalidTimestamp);
+
+        let global_state = &mut ctx.accounts.global_state;
+        global_state.mock_time_offset = global_state.mock_time_offset
+            .checked_add(seconds)
+            .ok_or(ErrorCode::Overflow)?;
+
+        emit!(MockClockAdvanced {
+            offset: global_state.mock_time_offset,
+            timestamp: program_clock(global_state)?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+}
+
+// ============================================================================
+// Consumer Interface
+// ============================================================================
+//
+// Anchor programs reading feeds depend on this crate with the `cpi` feature, which
+// exposes `cpi::accounts::*` and `cpi::read_price`, `cpi::aggregate_price` etc.
+// alongside the types below. Feeds are zero-copy: take them as
+// `AccountLoader<PriceAccount>` and read through `load()`.
+
+/// Validated snapshot of a feed aggregate for use by consuming programs
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct OraclePrice {
+    pub price: i64,
+    pub confidence: u64,
+    pub exponent: i32,
+    pub publish_time: i64,
+    pub slot: u64,
+}
+
+impl OraclePrice {
+    /// Reads the aggregate, rejecting feeds that are not fresh or are older than `max_age` seconds
+    pub fn from_price_account(price_account: &PriceAccount, max_age: i64, now: i64) -> Result<Self> {
+        Self::from_aggregate(&price_account.aggregate, max_age, now)
+    }
+
+    /// As `from_price_account`, but also rejects the feed outside its product's trading
+    /// calendar, which the aggregate only reflects once the next aggregation runs. The
+    /// caller checks that `product` is the feed's product.
+    pub fn from_feed(product: &ProductAccount, price_account: &PriceAccount, max_age: i64, now: i64) -> Result<Self> {
+        require!(product.calendar.is_open(now), ErrorCode::PriceNotTrading);
+        Self::from_price_account(price_account, max_age, now)
+    }
+
+    pub fn from_aggregate(aggregate: &PriceData, max_age: i64, now: i64) -> Result<Self> {
+        require!(aggregate.status().is_fresh(), ErrorCode::PriceNotTrading);
+        require!(now - aggregate.timestamp <= max_age, ErrorCode::PriceStale);
+        require!(aggregate.price > 0, ErrorCode::InvalidPrice);
+
+        Ok(Self {
+            price: aggregate.price,
+            confidence: aggregate.confidence,
+            exponent: aggregate.exponent,
+            publish_time: aggregate.timestamp,
+            slot: aggregate.slot,
+        })
+    }
+
+    /// Value of `quantity * 10^quantity_expo` units at this price, expressed at `10^target_expo`,
+    /// rounded down. Returns `None` on overflow.
+    pub fn checked_mul_price(&self, quantity: u64, quantity_expo: i32, target_expo: i32) -> Option<u64> {
+        checked_value(self.price, self.exponent, quantity, quantity_expo, target_expo)
+    }
+
+    /// Confidence as basis points of the price, rounded up
+    pub fn confidence_bps(&self) -> Option<u64> {
+        confidence_bps(self.price, self.confidence)
+    }
+
+    /// Price of the inverse pair (e.g. USD/JPY from JPY/USD) at `target_expo`, with the
+    /// confidence scaled to `c / p^2`. Returns `None` on overflow or a zero result.
+    pub fn invert(&self, target_expo: i32) -> Option<OraclePrice> {
+        let aggregate = PriceData {
+            price: self.price,
+            confidence: self.confidence,
+            exponent: self.exponent,
+            timestamp: self.publish_time,
+            slot: self.slot,
+            status: PriceStatus::Trading as u8,
+            price_i128: 0,
+            _padding: [0; 11],
+        }
+        .invert(target_expo)?;
+        if aggregate.price <= 0 {
+            return None;
+        }
+
+        Some(Self {
+            price: aggregate.price,
+            confidence: aggregate.confidence,
+            exponent: target_expo,
+            publish_time: self.publish_time,
+            slot: self.slot,
+        })
+    }
+
+    /// As `checked_mul_price`, valued at `price - confidence` (for collateral)
+    pub fn checked_mul_price_lower(&self, quantity: u64, quantity_expo: i32, target_expo: i32) -> Option<u64> {
+        let lower = self.price.checked_sub(i64::try_from(self.confidence).ok()?)?;
+        if lower <= 0 {
+            return Some(0);
+        }
+        checked_value(lower, self.exponent, quantity, quantity_expo, target_expo)
+    }
+
+    /// As `checked_mul_price`, valued at `price + confidence` (for debt)
+    pub fn checked_mul_price_upper(&self, quantity: u64, quantity_expo: i32, target_expo: i32) -> Option<u64> {
+        let upper = self.price.checked_add(i64::try_from(self.confidence).ok()?)?;
+        checked_value(upper, self.exponent, quantity, quantity_expo, target_expo)
+    }
+}
+
+/// Time-weighted average of a feed's aggregate price over a window ending at `end_time`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Twap {
+    pub price: i64,
+    pub exponent: i32,
+    pub window_seconds: i64,
+    pub end_time: i64,
+}
+
+impl Twap {
+    /// Averages the aggregate price as it stood over the `window_seconds` before `now`,
+    /// whatever its status, from the feed's accumulator and its PriceHistoryAccount.
+    /// Fails when the history does not reach back to the start of the window.
+    pub fn from_history(
+        price_account: &PriceAccount,
+        price_history: &PriceHistoryAccount,
+        window_seconds: i64,
+        now: i64,
+    ) -> Result<Self> {
+        require!(window_seconds > 0, ErrorCode::InvalidTwapWindow);
+        let start = now.checked_sub(window_seconds).ok_or(ErrorCode::InvalidTwapWindow)?;
+        let start_cumulative = price_history
+            .price_cumulative_at(start)
+            .ok_or(ErrorCode::TwapUnavailable)?;
+
+        let average = (price_account.price_cumulative_at(now) - start_cumulative) / window_seconds as i128;
+        Ok(Self {
+            price: i64::try_from(average).map_err(|_| ErrorCode::Overflow)?,
+            exponent: price_account.exponent,
+            window_seconds,
+            end_time: now,
+        })
+    }
+}
+
+/// Byte offset of `PriceAccount::aggregate`: discriminator, product_account, authority.
+/// The zero-copy `PriceData` has no implicit padding, so its bytes also decode as Borsh.
+const PRICE_ACCOUNT_AGGREGATE_OFFSET: usize = 8 + 32 + 32;
+
+/// Validates and reads many price accounts in one pass, e.g. from `remaining_accounts`.
+/// Each account must be owned by this program and carry the PriceAccount discriminator.
+/// Only the aggregate is decoded, so the full account never lands on the stack.
+pub fn load_oracle_prices(accounts: &[AccountInfo], max_age: i64, now: i64) -> Result<Vec<OraclePrice>> {
+    accounts
+        .iter()
+        .map(|info| {
+            require_keys_eq!(*info.owner, crate::ID, ErrorCode::InvalidPriceAccount);
+            let data = info.try_borrow_data()?;
+            require!(
+                data.len() > PRICE_ACCOUNT_AGGREGATE_OFFSET
+                    && data[..8] == PriceAccount::DISCRIMINATOR,
+                ErrorCode::InvalidPriceAccount
+            );
+            let aggregate = PriceData::deserialize(&mut &data[PRICE_ACCOUNT_AGGREGATE_OFFSET..])?;
+            OraclePrice::from_aggregate(&aggregate, max_age, now)
+        })
+        .collect()
+}
+
+/// Everything a liquidation engine reads from a feed, in one struct. Unlike
+/// `OraclePrice` it never rejects the feed; the engine decides from `status` and
+/// `age` whether to liquidate, wait or haircut.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
+pub struct LiquidationPriceInputs {
+    pub price: i64,
+    pub confidence: u64,
+    pub exponent: i32,
+    pub bid: i64, // `price - confidence`, floored at zero; value collateral here
+    pub ask: i64, // `price + confidence`; value debt here
+    pub status: PriceStatus,
+    pub publish_time: i64,
+    pub slot: u64,
+    pub age: i64, // Seconds since `publish_time`
+}
+
+impl LiquidationPriceInputs {
+    /// Whether the price is fresh, positive and at most `max_age` seconds old
+    pub fn is_usable(&self, max_age: i64) -> bool {
+        self.status.is_fresh() && self.price > 0 && self.age <= max_age
+    }
+
+    /// Spread between `bid` and `ask` as basis points of the price, rounded up
+    pub fn confidence_bps(&self) -> Option<u64> {
+        confidence_bps(self.price, self.confidence)
+    }
+}
+
+/// Liquidation inputs for a feed at `now`. Frozen or halted feeds report `Halted`
+/// whatever their last aggregate said.
+pub fn get_liquidation_price_inputs(price_account: &PriceAccount, now: i64) -> LiquidationPriceInputs {
+    let aggregate = &price_account.aggregate;
+    let confidence = i64::try_from(aggregate.confidence).unwrap_or(i64::MAX);
+    LiquidationPriceInputs {
+        price: aggregate.price,
+        confidence: aggregate.confidence,
+        exponent: aggregate.exponent,
+        bid: aggregate.price.saturating_sub(confidence).max(0),
+        ask: aggregate.price.saturating_add(confidence),
+        status: if price_account.frozen() || price_account.halted() {
+            PriceStatus::Halted
+        } else {
+            aggregate.status()
+        },
+        publish_time: aggregate.timestamp,
+        slot: aggregate.slot,
+        age: now.saturating_sub(aggregate.timestamp),
+    }
+}
+
+/// Byte offset of the packed aggregate in a PriceMirror account (after the 8-byte
+/// discriminator and the price account key)
+pub const PRICE_MIRROR_PACKED_OFFSET: usize = 40;
+
+/// Length of the packed aggregate
+pub const PACKED_PRICE_LEN: usize = 29;
+
+/// Packs an aggregate little-endian as price i64 (bytes 0..8), confidence u64 (8..16),
+/// exponent i32 (16..20), publish time i64 (20..28) and status u8 (28). Status values
+/// follow `PriceStatus`: 0 Trading, 1 Halted, 2 Auction, 3 Unknown, 4 Degraded, 5 Closed.
+pub fn pack_price(aggregate: &PriceData) -> [u8; PACKED_PRICE_LEN] {
+    let mut packed = [0u8; PACKED_PRICE_LEN];
+    packed[0..8].copy_from_slice(&aggregate.price.to_le_bytes());
+    packed[8..16].copy_from_slice(&aggregate.confidence.to_le_bytes());
+    packed[16..20].copy_from_slice(&aggregate.exponent.to_le_bytes());
+    packed[20..28].copy_from_slice(&aggregate.timestamp.to_le_bytes());
+    packed[28] = aggregate.status() as u8;
+    packed
+}
+
+/// Leaf committed for a quote in `PriceAccount::quotes_root`: keccak(0x00 || borsh(quote))
+pub fn quote_leaf(quote: &PublisherPrice) -> [u8; 32] {
+    anchor_lang::solana_program::keccak::hashv(&[
+        &[0u8],
+        quote.publisher.as_ref(),
+        &quote.price.to_le_bytes(),
+        &quote.confidence.to_le_bytes(),
+        &quote.timestamp.to_le_bytes(),
+        &quote.slot.to_le_bytes(),
+        &quote.stake.to_le_bytes(),
+        &[quote.active() as u8],
+        &quote.source_id.to_le_bytes(),
+    ])
+    .to_bytes()
+}
+
+/// Checks that `quote` was among the quotes committed under `root`, given the sibling
+/// hashes from leaf to root (levels where the node had no sibling are skipped)
+pub fn verify_quote_proof(root: &[u8; 32], quote: &PublisherPrice, proof: &[[u8; 32]]) -> bool {
+    verify_merkle_proof(root, quote_leaf(quote), proof)
+}
+
+fn checked_value(price: i64, price_expo: i32, quantity: u64, quantity_expo: i32, target_expo: i32) -> Option<u64> {
+    if price < 0 {
+        return None;
+    }
+    let product = (quantity as i128).checked_mul(price as i128)?;
+    let shift = quantity_expo.checked_add(price_expo)?.checked_sub(target_expo)?;
+    u64::try_from(rescale(product, shift, RoundingMode::Floor)?).ok()
+}
+
+/// Off-chain helpers for light clients and bridges that hold a price account snapshot
+/// and a trusted slot hash (e.g. relayed from the SlotHashes sysvar) instead of an RPC
+/// connection to a full node.
+///
+/// Proofs follow the legacy bank hash, which commits to a merkle tree of the accounts
+/// written in the slot (the accounts delta hash). Clusters that replaced it with the
+/// accounts lattice hash commit to a sum over every account instead, which has no
+/// per-account inclusion path, so their slot hashes never verify. The module is only
+/// built with the `legacy-bank-hash` feature, for clusters still on the old scheme.
+#[cfg(feature = "legacy-bank-hash")]
+pub mod client {
+    use super::*;
+    use anchor_lang::solana_program::{blake3, hash};
+
+    /// Children per node in the accounts delta hash tree
+    const ACCOUNTS_DELTA_FANOUT: usize = 16;
+
+    /// One level of the accounts delta merkle path, leaf first: the node's position
+    /// among its siblings and the other children's hashes in order
+    #[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+    pub struct AccountsDeltaLevel {
+        pub index: u8,
+        pub siblings: Vec<[u8; 32]>,
+    }
+
+    /// Proves a price account's bytes were written in `slot`. The account's hash is
+    /// walked up the accounts delta tree, then combined with the remaining bank hash
+    /// inputs and compared against the slot hash.
+    #[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+    pub struct PriceSnapshotProof {
+        pub slot: u64,
+        pub lamports: u64,
+        pub rent_epoch: u64,
+        pub path: Vec<AccountsDeltaLevel>,
+        pub parent_bank_hash: [u8; 32],
+        pub signature_count: u64,
+        pub last_blockhash: [u8; 32],
+    }
+
+    /// Verifies `data` as the contents of `price_account` at `proof.slot` against the
+    /// trusted `slot_hash` and returns the decoded account. Only slots in which the
+    /// account changed are provable, which for a live feed is any slot it aggregated in.
+    pub fn verify_price_snapshot(
+        price_account: &Pubkey,
+        data: &[u8],
+        proof: &PriceSnapshotProof,
+        slot_hash: &[u8; 32],
+    ) -> Result<PriceAccount> {
+        require!(
+            data.len() >= PRICE_ACCOUNT_SPACE && data[..8] == PriceAccount::DISCRIMINATOR,
+            ErrorCode::InvalidPriceAccount
+        );
+
+        let leaf = account_hash(price_account, data, proof.lamports, proof.rent_epoch);
+        let accounts_delta_hash = proof
+            .path
+            .iter()
+            .try_fold(leaf, delta_node)
+            .ok_or(ErrorCode::SnapshotProofInvalid)?;
+        let bank_hash = hash::hashv(&[
+            &proof.parent_bank_hash,
+            &accounts_delta_hash,
+            &proof.signature_count.to_le_bytes(),
+            &proof.last_blockhash,
+        ]);
+        require!(bank_hash.to_bytes() == *slot_hash, ErrorCode::SnapshotProofInvalid);
+
+        // Off-chain buffers carry no alignment guarantee, so copy rather than cast
+        Ok(anchor_lang::__private::bytemuck::pod_read_unaligned(&data[8..PRICE_ACCOUNT_SPACE]))
+    }
+
+    /// As `verify_price_snapshot`, then reads the aggregate with the usual freshness checks
+    pub fn verify_oracle_price(
+        price_account: &Pubkey,
+        data: &[u8],
+        proof: &PriceSnapshotProof,
+        slot_hash: &[u8; 32],
+        max_age: i64,
+        now: i64,
+    ) -> Result<OraclePrice> {
+        let account = verify_price_snapshot(price_account, data, proof, slot_hash)?;
+        OraclePrice::from_price_account(&account, max_age, now)
+    }
+
+    /// Account hash as committed by the runtime: blake3 over lamports, rent epoch, data,
+    /// executable flag, owner and address. Price accounts are never executable.
+    fn account_hash(address: &Pubkey, data: &[u8], lamports: u64, rent_epoch: u64) -> [u8; 32] {
+        blake3::hashv(&[
+            &lamports.to_le_bytes(),
+            &rent_epoch.to_le_bytes(),
+            data,
+            &[0u8],
+            crate::ID.as_ref(),
+            address.as_ref(),
+        ])
+        .to_bytes()
+    }
+
+    fn delta_node(node: [u8; 32], level: &AccountsDeltaLevel) -> Option<[u8; 32]> {
+        let index = level.index as usize;
+        if index > level.siblings.len() || level.siblings.len() >= ACCOUNTS_DELTA_FANOUT {
+            return None;
+        }
+
+        let mut children: Vec<&[u8]> = level.siblings.iter().map(|sibling| sibling.as_ref()).collect();
+        children.insert(index, node.as_ref());
+        Some(hash::hashv(&children).to_bytes())
+    }
+}
+
+// ============================================================================
+// Internal Functions (Optimized)
+// ============================================================================
+
+/// The cluster clock, with the timestamp moved ahead by `advance_mock_clock` in
+/// `mock-clock` builds so tests can step through unbonding, staleness and timelocks.
+/// Instructions that do not take the global state read the cluster clock directly.
+fn program_clock(global_state: &GlobalState) -> Result<Clock> {
+    let mut clock = Clock::get()?;
+    if cfg!(feature = "mock-clock") {
+        clock.unix_timestamp += global_state.mock_time_offset;
+    }
+    Ok(clock)
+}
+
+/// Clock for instructions where the global state is optional. Mock-clock builds require
+/// it, so callers cannot pick the cluster clock over the mocked one.
+fn optional_program_clock(global_state: Option<&GlobalState>) -> Result<Clock> {
+    match global_state {
+        Some(global_state) => program_clock(global_state),
+        None => {
+            require!(!cfg!(feature = "mock-clock"), ErrorCode::GlobalStateRequired);
+            Ok(Clock::get()?)
+        }
+    }
+}
+
+/// Accounts aggregation reads besides the feed, and the history it appends to
+struct AggregationAccounts<'a, 'info> {
+    product: &'a ProductAccount,
+    slot_hashes: Option<&'a AccountInfo<'info>>,
+    price_history: Option<&'a mut PriceHistoryAccount>,
+}
+
+fn aggregate_prices_internal(
+    price_account: &mut PriceAccount,
+    accounts: AggregationAccounts,
+    clock: &Clock,
+) -> Result<()> {
+    let AggregationAccounts { product, slot_hashes, price_history } = accounts;
+    let current_time = clock.unix_timestamp;
+    price_account.last_aggregation_slot = clock.slot;
+
+    if price_account.is_halted(clock.slot) {
+        price_account.aggregate.status = PriceStatus::Halted as u8;
+        return Ok(());
+    }
+
+    if !product.calendar.is_open(current_time) {
+        price_account.aggregate.status = PriceStatus::Closed as u8;
+        return Ok(());
+    }
+
+    // Quotes are still collected (e.g. as challenge evidence) but the poster owns the aggregate.
+    // Derived feeds are written from their source.
+    if price_account.optimistic() || price_account.derived() {
+        return Ok(());
+    }
+
+    let mut valid_prices = valid_publisher_prices(price_account, clock);
+    let quote_count = valid_prices.len() as u8;
+    let quotes_root = quote_merkle_root(&valid_prices);
+
+    // Large feeds aggregate a random subset; never fewer than min_publishers
+    let sample_size = price_account.sample_size.max(price_account.min_publishers) as usize;
+    let mut sample = None;
+    if price_account.sample_size > 0 && valid_prices.len() > sample_size {
+        let (seed_slot, seed_hash) = recent_slot_hash(slot_hashes.ok_or(ErrorCode::SlotHashesRequired)?)?;
+        let seed = anchor_lang::solana_program::keccak::hashv(&[
+            &seed_hash,
+            price_account.product_account.as_ref(),
+        ])
+        .to_bytes();
+
+        let candidates = valid_prices.len() as u8;
+        let (sampled, bitmap) = sample_quotes(price_account, valid_prices, sample_size, &seed);
+        valid_prices = sampled;
+        sample = Some((seed_slot, seed_hash, candidates, bitmap));
+    }
+    let (distinct_sources, top_source_share_bps) = aggregation::source_stats(&valid_prices);
+    let outliers = outlier_quotes(&valid_prices, aggregation_config(price_account, clock.slot).mad_multiplier);
+    // A quote that stays an outlier across aggregations is only reported the first time
+    let new_outliers: Vec<&PublisherPrice> = outliers.iter().copied().filter(|q| q.outlier_reported == 0).collect();
+    for outlier in &new_outliers {
+        emit_quote_rejected(price_account.product_account, outlier, QuoteRejectReason::Outlier, clock.slot);
+    }
+    if price_account.deviation_alert_mads > 0 && !new_outliers.is_empty() {
+        emit_deviation_alerts(price_account, &valid_prices, &new_outliers, clock.slot);
+    }
+    let outlier_bitmap = publisher_bitmap(price_account, &outliers);
+    let included_publishers = publisher_bitmap(price_account, &valid_prices) & !outlier_bitmap;
+
+    let aggregate = compute_aggregate(price_account, valid_prices, clock);
+    for (index, quote) in price_account.publishers.iter_mut().enumerate() {
+        if outlier_bitmap & (1u128 << index) != 0 {
+            quote.outlier_reported = 1;
+        }
+    }
+    let aggregate = match aggregate {
+        Some(aggregate) => aggregate,
+        None => {
+            price_account.aggregate.status = PriceStatus::Unknown as u8;
+            return Ok(());
+        }
+    };
+
+    let (median_price, confidence, status) = (aggregate.price, aggregate.confidence, aggregate.status());
+
+    // Update aggregate
+    price_account.accrue_cumulative(current_time);
+    price_account.aggregate = aggregate;
+    if let Some(price_history) = price_history {
+        price_history.record(price_account);
+    }
+    price_account.distinct_sources = distinct_sources;
+    price_account.top_source_share_bps = top_source_share_bps;
+    price_account.quotes_root = quotes_root;
+    price_account.sample_slot = 0;
+    price_account.sampled_publishers = 0;
+    price_account.included_publishers = included_publishers;
+    if let Some((seed_slot, seed_hash, candidates, bitmap)) = sample {
+        price_account.sample_slot = seed_slot;
+        price_account.sampled_publishers = bitmap;
+
+        emit!(QuotesSampled {
+            product: price_account.product_account,
+            seed_slot,
+            seed_hash,
+            candidates,
+            sampled_publishers: bitmap,
+        });
+    }
+
+    // Update EMA
+    price_account.ema = update_ema(price_account, median_price, confidence)?;
+
+    emit!(PriceUpdated {
+        product: price_account.product_account,
+        symbol: product.symbol.clone(),
+        price: median_price,
+        confidence,
+        timestamp: current_time,
+        slot: clock.slot,
+        status,
+        distinct_sources,
+        top_source_share_bps,
+    });
+
+    emit!(QuotesRootCommitted {
+        product: price_account.product_account,
+        slot: clock.slot,
+        root: quotes_root,
+        quote_count,
+    });
+
+    Ok(())
+}
+
+/// Merkle root over `quote_leaf` of each quote, in `publishers` order. Pairs are hashed
+/// sorted and an odd node is carried up unchanged, matching `verify_quote_proof`.
+fn quote_merkle_root(quotes: &[&PublisherPrice]) -> [u8; 32] {
+    let mut level: Vec<[u8; 32]> = quotes.iter().map(|q| quote_leaf(q)).collect();
+    if level.is_empty() {
+        return [0; 32];
+    }
+
+    while level.len() > 1 {
+        level = level
+            .chunks(2)
+            .map(|pair| match pair {
+                [left, right] => merkle_node(left, right),
+                [single] => *single,
+                _ => unreachable!(),
+            })
+            .collect();
+    }
+    level[0]
+}
+
+fn verify_merkle_proof(root: &[u8; 32], leaf: [u8; 32], proof: &[[u8; 32]]) -> bool {
+    let computed = proof
+        .iter()
+        .fold(leaf, |node, sibling| merkle_node(&node, sibling));
+    computed == *root
+}
+
+fn merkle_node(a: &[u8; 32], b: &[u8; 32]) -> [u8; 32] {
+    let (low, high) = if a <= b { (a, b) } else { (b, a) };
+    anchor_lang::solana_program::keccak::hashv(&[&[1u8], low, high]).to_bytes()
+}
+
+/// Most recent entry of the SlotHashes sysvar, read without deserializing the whole list
+fn recent_slot_hash(slot_hashes: &AccountInfo) -> Result<(u64, [u8; 32])> {
+    require_keys_eq!(
+        slot_hashes.key(),
+        anchor_lang::solana_program::sysvar::slot_hashes::ID,
+        ErrorCode::SlotHashesRequired
+    );
+
+    let data = slot_hashes.try_borrow_data()?;
+    require!(data.len() >= 48, ErrorCode::SlotHashesRequired);
+
+    let mut slot = [0u8; 8];
+    slot.copy_from_slice(&data[8..16]);
+    let mut hash = [0u8; 32];
+    hash.copy_from_slice(&data[16..48]);
+
+    Ok((u64::from_le_bytes(slot), hash))
+}
+
+/// Partial Fisher-Yates shuffle of the quotes keyed by keccak(seed, round). Returns the
+/// first `sample_size` quotes and a bitmap of their indices in `PriceAccount::publishers`.
+fn sample_quotes<'a>(
+    price_account: &'a PriceAccount,
+    mut quotes: Vec<&'a PublisherPrice>,
+    sample_size: usize,
+    seed: &[u8; 32],
+) -> (Vec<&'a PublisherPrice>, u128) {
+    for i in 0..sample_size {
+        let draw = anchor_lang::solana_program::keccak::hashv(&[seed, &(i as u64).to_le_bytes()]).to_bytes();
+        let mut bytes = [0u8; 8];
+        bytes.copy_from_slice(&draw[..8]);
+        let j = i + (u64::from_le_bytes(bytes) % (quotes.len() - i) as u64) as usize;
+        quotes.swap(i, j);
+    }
+    quotes.truncate(sample_size);
+
+    let bitmap = publisher_bitmap(price_account, &quotes);
+    (quotes, bitmap)
+}
+
+/// Bitmap of the `publishers` indices the given quotes (borrowed from the feed) occupy
+fn publisher_bitmap(price_account: &PriceAccount, quotes: &[&PublisherPrice]) -> u128 {
+    quotes.iter().fold(0u128, |bitmap, quote| {
+        match price_account.publishers.iter().position(|p| std::ptr::eq(p, *quote)) {
+            Some(index) => bitmap | (1u128 << index),
+            None => bitmap,
+        }
+    })
+}
+
+/// Aggregation settings of the feed at `slot`. A declared volatility regime widens the
+/// outlier band, the confidence cap and the halt threshold by its factor while it lasts.
+fn aggregation_config(price_account: &PriceAccount, slot: u64) -> aggregation::AggregationConfig {
+    let widen = |value: i64| {
+        if slot < price_account.volatility_until_slot {
+            value.saturating_mul(price_account.volatility_widening_bps as i64) / 10_000
+        } else {
+            value
+        }
+    };
+
+    aggregation::AggregationConfig {
+        min_publishers: price_account.min_publishers,
+        min_operators: price_account.min_operators,
+        staleness_threshold: if price_account.staleness_threshold > 0 {
+            price_account.staleness_threshold
+        } else {
+            STALENESS_THRESHOLD
+        },
+        halted_threshold: widen(HALTED_THRESHOLD),
+        mad_multiplier: widen(OUTLIER_MAD_MULTIPLIER),
+        degraded_confidence_bps: widen(price_account.degraded_confidence_bps as i64).min(u16::MAX as i64) as u16,
+        high_precision_decimals: if price_account.high_precision() {
+            Some(HIGH_PRECISION_DECIMALS)
+        } else {
+            None
+        },
+    }
+}
+
+/// Writes the value a just-queued parameter proposal will set into its target account.
+/// Proposals without a single previewable value queue without one.
+fn queue_param_preview(
+    accounts: &mut ExecuteProposal,
+    proposal_id: u64,
+    proposal_type: &ProposalType,
+    effective_at: i64,
+) -> Result<()> {
+    // Holds the loaded feed while its pending value is written
+    let mut price_account;
+    let (target, pending, value) = match proposal_type {
+        ProposalType::UpdateRewardRate { new_rate } => {
+            let token_vault = accounts.token_vault.as_mut().ok_or(ErrorCode::ProposalTargetMismatch)?;
+            (token_vault.key(), &mut token_vault.pending_reward_rate, *new_rate)
+        },
+        ProposalType::UpdateMinPublishers { feed, new_min } => {
+            let feed_account = accounts.price_account.as_ref().ok_or(ErrorCode::ProposalTargetMismatch)?;
+            require_keys_eq!(feed_account.key(), *feed, ErrorCode::ProposalTargetMismatch);
+            price_account = feed_account.load_mut()?;
+            require!(*new_min <= price_account.publisher_count, ErrorCode::InvalidGovernanceParams);
+            (feed_account.key(), &mut price_account.pending_min_publishers, *new_min as u64)
+        },
+        ProposalType::UpdateStakePerFeed { stake_per_feed } => {
+            let global_state = accounts.global_state.as_mut().ok_or(ErrorCode::ProposalTargetMismatch)?;
+            (global_state.key(), &mut global_state.pending_stake_per_feed, *stake_per_feed)
+        },
+        ProposalType::UpdateRegistrationLimit { max_per_epoch } => {
+            let global_state = accounts.global_state.as_mut().ok_or(ErrorCode::ProposalTargetMismatch)?;
+            (global_state.key(), &mut global_state.pending_registration_limit, *max_per_epoch as u64)
+        },
+        // Validated against MIN_UNBONDING_PERIOD at creation, so never negative
+        ProposalType::UpdateUnbondingPeriod { period } => {
+            let global_state = accounts.global_state.as_mut().ok_or(ErrorCode::ProposalTargetMismatch)?;
+            (global_state.key(), &mut global_state.pending_unbonding_period, *period as u64)
+        },
+        ProposalType::UpdateMinStake { min_stake } => {
+            let global_state = accounts.global_state.as_mut().ok_or(ErrorCode::ProposalTargetMismatch)?;
+            (global_state.key(), &mut global_state.pending_min_stake, *min_stake)
+        },
+        _ => return Ok(()),
+    };
+
+    *pending = PendingParam {
+        value,
+        effective_at,
+        proposal_id,
+    };
+
+    emit!(ParamChangeQueued {
+        proposal_id,
+        target,
+        value,
+        effective_at,
+    });
+
+    Ok(())
+}
+
+/// Quotes the MAD filter drops from `quotes`
+fn outlier_quotes<'a>(quotes: &[&'a PublisherPrice], mad_multiplier: i64) -> Vec<&'a PublisherPrice> {
+    let mut sorted = quotes.to_vec();
+    sorted.sort_by_key(|p| p.price);
+    let kept = aggregation::filter_outliers(&sorted, mad_multiplier);
+    if kept.len() == sorted.len() {
+        return Vec::new();
+    }
+
+    sorted
+        .into_iter()
+        .filter(|p| !kept.iter().any(|k| std::ptr::eq(*k, *p)))
+        .collect()
+}
+
+/// Emits QuoteDeviationAlert for each of `outliers` more than the feed's
+/// `deviation_alert_mads` MADs from the median of `quotes`
+fn emit_deviation_alerts(price_account: &PriceAccount, quotes: &[&PublisherPrice], outliers: &[&PublisherPrice], slot: u64) {
+    let mut sorted = quotes.to_vec();
+    sorted.sort_by_key(|p| p.price);
+    let (median, mad) = match aggregation::median_and_mad(&sorted) {
+        Some(dispersion) => dispersion,
+        None => return,
+    };
+    let threshold = mad.saturating_mul(price_account.deviation_alert_mads as u64);
+
+    for outlier in outliers {
+        let deviation = outlier.price.abs_diff(median);
+        if deviation > threshold {
+            emit!(QuoteDeviationAlert {
+                product: price_account.product_account,
+                publisher: outlier.publisher,
+                price: outlier.price,
+                median,
+                mad,
+                deviation,
+                slot,
+            });
+        }
+    }
+}
+
+fn emit_quote_rejected(product: Pubkey, quote: &PublisherPrice, reason: QuoteRejectReason, slot: u64) {
+    emit!(QuoteRejected {
+        product,
+        publisher: quote.publisher,
+        price: quote.price,
+        confidence: quote.confidence,
+        timestamp: quote.timestamp,
+        reason,
+        slot,
+    });
+}
+
+/// Accounts for the slots since the last observation, closing any windows that ended,
+/// then notes how long the feed's current aggregate keeps it Trading
+fn record_feed_stats(stats: &mut FeedStats, price_account: &PriceAccount, slot: u64) {
+    while slot >= stats.window_start_slot + EPOCH_LENGTH_SLOTS {
+        let window_end = stats.window_start_slot + EPOCH_LENGTH_SLOTS;
+        stats.accrue(window_end);
+        stats.last_window_start_slot = stats.window_start_slot;
+        stats.last_uptime_bps = (stats.trading_slots as u128 * 10_000 / EPOCH_LENGTH_SLOTS as u128) as u16;
+        stats.trading_slots = 0;
+        stats.window_start_slot = window_end;
+
+        // Once the last aggregate has gone stale, every later complete window was down
+        let idle_windows = (slot - window_end) / EPOCH_LENGTH_SLOTS;
+        if idle_windows > 0 && stats.trading_until_slot <= window_end {
+            stats.window_start_slot = window_end + idle_windows * EPOCH_LENGTH_SLOTS;
+            stats.last_window_start_slot = stats.window_start_slot - EPOCH_LENGTH_SLOTS;
+            stats.last_uptime_bps = 0;
+            stats.observed_slot = stats.window_start_slot;
+        }
+
+        emit!(FeedUptimeRecorded {
+            price_account: stats.price_account,
+            window_start_slot: stats.last_window_start_slot,
+            uptime_bps: stats.last_uptime_bps,
+        });
+    }
+    stats.accrue(slot);
+
+    let aggregate = &price_account.aggregate;
+    stats.trading_until_slot = if aggregate.status() == PriceStatus::Trading {
+        let fresh_slots = aggregation_config(price_account, slot).staleness_threshold as u64 * 1_000
+            / anchor_lang::solana_program::clock::DEFAULT_MS_PER_SLOT;
+        aggregate.slot + fresh_slots
+    } else {
+        0
+    };
+}
+
+/// Active quotes younger than the feed's staleness threshold
+fn valid_publisher_prices<'a>(price_account: &'a PriceAccount, clock: &Clock) -> Vec<&'a PublisherPrice> {
+    let config = aggregation_config(price_account, clock.slot);
+
+    price_account
+        .publishers
+        .iter()
+        .filter(|p| p.active() && aggregation::is_fresh(&config, p.timestamp, clock.unix_timestamp))
+        .collect()
+}
+
+/// Aggregates the given quotes without touching the feed. Returns `None` when too few
+/// quotes survive outlier filtering to publish a price.
+fn compute_aggregate(
+    price_account: &PriceAccount,
+    valid_prices: Vec<&PublisherPrice>,
+    clock: &Clock,
+) -> Option<PriceData> {
+    let aggregate = aggregation::aggregate(&aggregation_config(price_account, clock.slot), valid_prices, clock.unix_timestamp);
+    if aggregate.status == aggregation::Status::Unknown {
+        return None;
+    }
+
+    Some(PriceData {
+        price: aggregate.price,
+        confidence: aggregate.confidence,
+        exponent: price_account.exponent,
+        timestamp: clock.unix_timestamp,
+        slot: clock.slot,
+        status: PriceStatus::from(aggregate.status) as u8,
+        price_i128: aggregate.price_i128.unwrap_or(0),
+        _padding: [0; 11],
+    })
+}
+
+/// Stake weight a publisher's quote carries in aggregation. New publishers start at
+/// PROBATION_WEIGHT_BPS and ramp linearly to full weight by the end of probation.
+fn aggregation_weight(publisher: &PublisherAccount, epoch: u64) -> u64 {
+    aggregation::probation_weight(
+        publisher.staked_amount,
+        publisher.registered_epoch,
+        publisher.probation_end_epoch,
+        epoch,
+        PROBATION_WEIGHT_BPS,
+    )
+}
+
+/// Tightest confidence a publisher may claim: more stake allows a tighter interval
+fn min_confidence_for_stake(price_account: &PriceAccount, staked_amount: u64) -> u64 {
+    if price_account.confidence_stake_scale == 0 || staked_amount == 0 {
+        return 0;
+    }
+
+    ((price_account.confidence_stake_scale as u128 * DEFAULT_MIN_STAKE_AMOUNT as u128)
+        / staked_amount as u128) as u64
+}
+
+fn read_legacy_account<T: AnchorDeserialize>(info: &AccountInfo, discriminator: &[u8; 8]) -> Result<T> {
+    let data = info.try_borrow_data()?;
+    require!(
+        data.len() >= 8 && data[..8] == discriminator[..],
+        ErrorCode::MigrationNotRequired
+    );
+    T::deserialize(&mut &data[8..]).map_err(|_| ErrorCode::MigrationNotRequired.into())
+}
+
+/// Checks the account receiving a publisher payout. When the associated token program is
+/// supplied it must be the publisher's ATA, which is created first if missing, so payouts
+/// still land after an operator rotates wallets.
+fn prepare_payout_account<'info>(
+    payout_account: &UncheckedAccount<'info>,
+    publisher_authority: &Signer<'info>,
+    token_mint: &InterfaceAccount<'info, Mint>,
+    token_program: &Interface<'info, TokenInterface>,
+    associated_token_program: Option<&Program<'info, AssociatedToken>>,
+    system_program: Option<&Program<'info, System>>,
+) -> Result<()> {
+    if let (Some(associated_token_program), Some(system_program)) = (associated_token_program, system_program) {
+        require_keys_eq!(
+            payout_account.key(),
+            get_associated_token_address_with_program_id(
+                &publisher_authority.key(),
+                &token_mint.key(),
+                &token_program.key(),
+            ),
+            ErrorCode::InvalidPayoutAccount
+        );
+        associated_token::create_idempotent(CpiContext::new(
+            associated_token_program.to_account_info(),
+            associated_token::Create {
+                payer: publisher_authority.to_account_info(),
+                associated_token: payout_account.to_account_info(),
+                authority: publisher_authority.to_account_info(),
+                mint: token_mint.to_account_info(),
+                system_program: system_program.to_account_info(),
+                token_program: token_program.to_account_info(),
+            },
+        ))?;
+    }
+
+    require_keys_eq!(*payout_account.owner, token_program.key(), ErrorCode::InvalidPayoutAccount);
+    let data = payout_account.try_borrow_data()?;
+    let token_account = TokenAccount::try_deserialize(&mut &data[..])?;
+    require!(
+        token_account.owner == publisher_authority.key() && token_account.mint == token_mint.key(),
+        ErrorCode::InvalidPayoutAccount
+    );
+    Ok(())
+}
+
+/// Rejects Token-2022 mints whose extensions could move tokens out of the vault
+/// without the vault authority (permanent delegate)
+fn require_supported_mint(mint: &AccountInfo) -> Result<()> {
+    use spl_token_2022::extension::{
+        permanent_delegate::PermanentDelegate, BaseStateWithExtensions, StateWithExtensions,
+    };
+
+    if *mint.owner != spl_token_2022::ID {
+        return Ok(());
+    }
+    let data = mint.try_borrow_data()?;
+    let state = StateWithExtensions::<spl_token_2022::state::Mint>::unpack(&data)?;
+    require!(
+        state.get_extension::<PermanentDelegate>().is_err(),
+        ErrorCode::InvalidMint
+    );
+    Ok(())
+}
+
+/// `token_interface::transfer_checked` that also forwards the CPI context's remaining
+/// accounts, which Token-2022 hands to the mint's transfer hook program
+fn transfer_checked_with_hooks<'info>(
+    ctx: CpiContext<'_, '_, '_, 'info, TransferChecked<'info>>,
+    amount: u64,
+    decimals: u8,
+) -> Result<()> {
+    let mut ix = spl_token_2022::instruction::transfer_checked(
+        ctx.program.key,
+        ctx.accounts.from.key,
+        ctx.accounts.mint.key,
+        ctx.accounts.to.key,
+        ctx.accounts.authority.key,
+        &[],
+        amount,
+        decimals,
+    )?;
+    let mut account_infos = vec![
+        ctx.accounts.from,
+        ctx.accounts.mint,
+        ctx.accounts.to,
+        ctx.accounts.authority,
+    ];
+    for account in ctx.remaining_accounts {
+        ix.accounts.push(AccountMeta {
+            pubkey: account.key(),
+            is_signer: account.is_signer,
+            is_writable: account.is_writable,
+        });
+        account_infos.push(account);
+    }
+    invoke_signed(&ix, &account_infos, ctx.signer_seeds)
+        .map_err(Into::into)
+}
+
+/// Tokens the vault gained since `balance_before`. Token-2022 transfer fees are withheld
+/// from deposits, so this, not the requested amount, is what gets booked.
+fn vault_received(vault_token_account: &mut InterfaceAccount<TokenAccount>, balance_before: u64) -> Result<u64> {
+    vault_token_account.reload()?;
+    vault_token_account.amount
+        .checked_sub(balance_before)
+        .ok_or(ErrorCode::Overflow.into())
+}
+
+/// Borsh layout of Metaplex `CreateMetadataAccountArgsV3`. Creators, collection and uses
+/// are flattened to tuples and always left unset.
+#[derive(AnchorSerialize)]
+struct CreateMetadataAccountArgsV3 {
+    name: String,
+    symbol: String,
+    uri: String,
+    seller_fee_basis_points: u16,
+    creators: Option<Vec<(Pubkey, bool, u8)>>,
+    collection: Option<(bool, Pubkey)>,
+    uses: Option<(u8, u64, u64)>,
+    is_mutable: bool,
+    collection_details: Option<(u8, u64)>,
+}
+
+/// Creates the Metaplex metadata account of a position NFT, with the vault authority as
+/// update authority so wallets can display the position
+fn create_position_metadata(
+    accounts: &LockStakePosition<'_>,
+    name: String,
+    uri: String,
+    signer: &[&[&[u8]]],
+) -> Result<()> {
+    let args = CreateMetadataAccountArgsV3 {
+        name,
+        symbol: POSITION_NFT_SYMBOL.to_string(),
+        uri,
+        seller_fee_basis_points: 0,
+        creators: None,
+        collection: None,
+        uses: None,
+        is_mutable: true,
+        collection_details: None,
+    };
+    let mut data = vec![CREATE_METADATA_ACCOUNT_V3_DISCRIMINATOR];
+    args.serialize(&mut data)?;
+
+    let ix = Instruction {
+        program_id: token_metadata::ID,
+        accounts: vec![
+            AccountMeta::new(accounts.position_metadata.key(), false),
+            AccountMeta::new_readonly(accounts.position_mint.key(), false),
+            AccountMeta::new_readonly(accounts.vault_authority.key(), true),
+            AccountMeta::new(accounts.publisher_authority.key(), true),
+            AccountMeta::new_readonly(accounts.vault_authority.key(), true),
+            AccountMeta::new_readonly(accounts.system_program.key(), false),
+        ],
+        data,
+    };
+    invoke_signed(
+        &ix,
+        &[
+            accounts.position_metadata.to_account_info(),
+            accounts.position_mint.to_account_info(),
+            accounts.vault_authority.to_account_info(),
+            accounts.publisher_authority.to_account_info(),
+            accounts.system_program.to_account_info(),
+            accounts.token_metadata_program.to_account_info(),
+        ],
+        signer,
+    )?;
+    Ok(())
+}
+
+/// Grows `info` to `space` (topping up rent from `payer`) and writes `state` with its discriminator
+fn rewrite_account<'info, T: AccountSerialize>(
+    info: &AccountInfo<'info>,
+    payer: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    state: &T,
+    space: usize,
+) -> Result<()> {
+    resize_account(info, payer, system_program, space)?;
+    let mut data = info.try_borrow_mut_data()?;
+    let mut writer: &mut [u8] = &mut data;
+    state.try_serialize(&mut writer)?;
+    Ok(())
+}
+
+/// Resizes `info` to `space`, topping up its rent exemption from `payer`
+fn resize_account<'info>(
+    info: &AccountInfo<'info>,
+    payer: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    space: usize,
+) -> Result<()> {
+    let required_lamports = Rent::get()?.minimum_balance(space);
+    let current_lamports = info.lamports();
+    if required_lamports > current_lamports {
+        anchor_lang::system_program::transfer(
+            CpiContext::new(
+                system_program.clone(),
+                anchor_lang::system_program::Transfer {
+                    from: payer.clone(),
+                    to: info.clone(),
+                },
+            ),
+            required_lamports - current_lamports,
+        )?;
+    }
+
+    info.realloc(space, false)?;
+    Ok(())
+}
+
+/// The feed's current settings as an update with every field set
+fn current_feed_config(price_account: &PriceAccount) -> Result<FeedConfigUpdate> {
+    Ok(FeedConfigUpdate {
+        confidence_stake_scale: Some(price_account.confidence_stake_scale),
+        halted: Some(price_account.halted()),
+        staleness_threshold: Some(price_account.staleness_threshold),
+        degraded_confidence_bps: Some(price_account.degraded_confidence_bps),
+        rounding_mode: Some(price_account.rounding_mode()?),
+        high_precision: Some(price_account.high_precision()),
+        commit_reveal: Some(price_account.commit_reveal()),
+        sample_size: Some(price_account.sample_size),
+        confidence_mode: Some(price_account.confidence_mode()?),
+        min_operators: Some(price_account.min_operators),
+        ema_alpha: Some(price_account.ema_alpha),
+        ema_confidence_weighted: Some(price_account.ema_confidence_weighted()),
+        min_slots_between_aggregations: Some(price_account.min_slots_between_aggregations),
+        permissioned: Some(price_account.permissioned()),
+        deviation_alert_mads: Some(price_account.deviation_alert_mads),
+    })
+}
+
+/// Applies a feed config update. Authority-only fields require `is_authority`; the
+/// operational fields require the matching FEED_ADMIN_* bit in `permissions`.
+fn apply_feed_config(
+    price_account: &mut PriceAccount,
+    update: &FeedConfigUpdate,
+    is_authority: bool,
+    permissions: u8,
+) -> Result<()> {
+    if let Some(scale) = update.confidence_stake_scale {
+        require!(is_authority, ErrorCode::Unauthorized);
+        price_account.confidence_stake_scale = scale;
+    }
+
+    if let Some(bps) = update.degraded_confidence_bps {
+        require!(is_authority, ErrorCode::Unauthorized);
+        require!(bps <= 10_000, ErrorCode::InvalidFeedConfig);
+        price_account.degraded_confidence_bps = bps;
+    }
+
+    if let Some(mode) = update.rounding_mode {
+        require!(is_authority, ErrorCode::Unauthorized);
+        price_account.rounding_mode = mode as u8;
+    }
+
+    if let Some(high_precision) = update.high_precision {
+        require!(is_authority, ErrorCode::Unauthorized);
+        price_account.high_precision = high_precision as u8;
+    }
+
+    if let Some(commit_reveal) = update.commit_reveal {
+        require!(is_authority, ErrorCode::Unauthorized);
+        price_account.commit_reveal = commit_reveal as u8;
+    }
+
+    if let Some(mode) = update.confidence_mode {
+        require!(is_authority, ErrorCode::Unauthorized);
+        price_account.confidence_mode = mode as u8;
+    }
+
+    if let Some(sample_size) = update.sample_size {
+        require!(is_authority, ErrorCode::Unauthorized);
+        require!(sample_size as usize <= MAX_PUBLISHERS, ErrorCode::InvalidFeedConfig);
+        price_account.sample_size = sample_size;
+    }
+
+    if let Some(min_operators) = update.min_operators {
+        require!(is_authority, ErrorCode::Unauthorized);
+        require!(min_operators <= price_account.min_publishers, ErrorCode::InvalidFeedConfig);
+        price_account.min_operators = min_operators;
+    }
+
+    if let Some(alpha) = update.ema_alpha {
+        require!(is_authority, ErrorCode::Unauthorized);
+        require!(alpha <= 1_000_000, ErrorCode::InvalidFeedConfig);
+        price_account.ema_alpha = alpha;
+    }
+
+    if let Some(weighted) = update.ema_confidence_weighted {
+        require!(is_authority, ErrorCode::Unauthorized);
+        price_account.ema_confidence_weighted = weighted as u8;
+    }
+
+    if let Some(slots) = update.min_slots_between_aggregations {
+        require!(is_authority, ErrorCode::Unauthorized);
+        require!(slots <= MAX_AGGREGATION_INTERVAL_SLOTS, ErrorCode::InvalidFeedConfig);
+        price_account.min_slots_between_aggregations = slots;
+    }
+
+    if let Some(permissioned) = update.permissioned {
+        require!(is_authority, ErrorCode::Unauthorized);
+        price_account.permissioned = permissioned as u8;
+    }
+
+    if let Some(mads) = update.deviation_alert_mads {
+        require!(is_authority, ErrorCode::Unauthorized);
+        price_account.deviation_alert_mads = mads;
+    }
+
+    if let Some(halted) = update.halted {
+        require!(permissions & FEED_ADMIN_SET_STATUS != 0, ErrorCode::Unauthorized);
+        price_account.halted = halted as u8;
+        // Readers of the stored aggregate must not see Trading on a halted feed
+        if halted {
+            price_account.aggregate.status = PriceStatus::Halted as u8;
+        }
+    }
+
+    if let Some(threshold) = update.staleness_threshold {
+        require!(permissions & FEED_ADMIN_SET_STALENESS != 0, ErrorCode::Unauthorized);
+        require!(
+            threshold > 0 && threshold <= HALTED_THRESHOLD,
+            ErrorCode::InvalidFeedConfig
+        );
+        price_account.staleness_threshold = threshold;
+    }
+
+    Ok(())
+}
+
+/// A quote as submitted, before stake weighting
+struct QuoteInput {
+    price: i64,
+    confidence: u64,
+    source_id: u64,
+    timestamp: i64,
+    slot: u64,
+    sequence: Option<u32>, // Direct quotes only; batched quotes are ordered by their batch
+}
+
+/// Direct and commit-reveal updates: the signing publisher quotes at the current time
+fn record_quote(
+    accounts: &mut UpdatePrice<'_>,
+    price: i64,
+    confidence: u64,
+    source_id: u64,
+    sequence: u32,
+) -> Result<()> {
+    require_cosigner(&accounts.publisher_account, accounts.cosigner.as_ref())?;
+
+    let publisher = accounts.publisher_account.authority;
+    let price_account = &mut accounts.price_account.load_mut()?;
+    require!(
+        !price_account.permissioned() || accounts.feed_permission.is_some(),
+        ErrorCode::PublisherNotPermitted
+    );
+    if !within_quota(
+        accounts.feed_quota.as_deref_mut(),
+        price_account,
+        &publisher,
+        accounts.global_state.current_epoch,
+    )? {
+        return Ok(());
+    }
+    let joined = quote_index(price_account, &publisher).is_none();
+
+    let clock = program_clock(&accounts.global_state)?;
+    let mut price_history = accounts.price_history.as_ref().map(|history| history.load_mut()).transpose()?;
+    write_quote(
+        &accounts.global_state,
+        &accounts.token_vault,
+        price_account,
+        &mut accounts.publisher_account,
+        AggregationAccounts {
+            product: &accounts.product_account,
+            slot_hashes: accounts.slot_hashes.as_ref().map(|a| a.as_ref()),
+            price_history: price_history.as_deref_mut(),
+        },
+        QuoteInput {
+            price,
+            confidence,
+            source_id,
+            timestamp: clock.unix_timestamp,
+            slot: clock.slot,
+            sequence: Some(sequence),
+        },
+    )?;
+
+    if let Some(feed_quota) = accounts.feed_quota.as_deref_mut() {
+        count_submission(feed_quota, price_account, &publisher, joined);
+    }
+    if let Some(feed_stats) = accounts.feed_stats.as_mut() {
+        record_feed_stats(feed_stats, price_account, clock.slot);
+    }
+    if let Some(price_mirror) = accounts.price_mirror.as_mut() {
+        price_mirror.packed = pack_price(&price_account.aggregate);
+    }
+
+    Ok(())
+}
+
+/// Validates a publisher quote and writes it into the feed, aggregating when enough
+/// publishers have contributed. A quote older than the publisher's current one, a direct
+/// quote whose sequence number is not above it, or a batched quote no newer than it, is
+/// rejected.
+fn write_quote(
+    global_state: &GlobalState,
+    token_vault: &TokenVault,
+    price_account: &mut PriceAccount,
+    publisher: &mut PublisherAccount,
+    aggregation: AggregationAccounts,
+    quote: QuoteInput,
+) -> Result<()> {
+    require!(!global_state.paused, ErrorCode::SystemPaused);
+    require!(!price_account.frozen(), ErrorCode::FeedFrozen);
+    require!(quote.price > 0, ErrorCode::InvalidPrice);
+    require!(quote.timestamp > 0, ErrorCode::InvalidTimestamp);
+
+    let confidence = quote_confidence(price_account, quote.price, quote.confidence)?;
+    let (price, confidence) = roll_adjusted(price_account, quote.price, confidence)?;
+    require!(
+        confidence >= min_confidence_for_stake(price_account, publisher.staked_amount),
+        ErrorCode::ConfidenceTooTight
+    );
+
+    let clock = program_clock(global_state)?;
+    let slot = clock.slot;
+    require!(slot >= publisher.publishing_blocked_until_slot, ErrorCode::PublisherCoolingDown);
+    accrue_rewards(publisher, token_vault, slot)?;
+    publisher.last_active_epoch = global_state.current_epoch;
+
+    let publisher_price = PublisherPrice {
+        publisher: publisher.authority,
+        price,
+        confidence,
+        timestamp: quote.timestamp,
+        slot: quote.slot,
+        stake: aggregation_weight(publisher, global_state.current_epoch),
+        active: 1,
+        source_id: quote.source_id,
+        operator: publisher.operator_id,
+        sequence: quote.sequence.unwrap_or(0),
+        outlier_reported: 0,
+        _padding: [0; 2],
+    };
+
+    // Find existing slot or add new one
+    let mut found = false;
+    for i in 0..MAX_PUBLISHERS {
+        if price_account.publishers[i].active() && 
+           price_account.publishers[i].publisher == publisher.authority {
+            require!(
+                price_account.publishers[i].timestamp <= quote.timestamp,
+                ErrorCode::QuoteOutdated
+            );
+            let sequence = match quote.sequence {
+                Some(sequence) => {
+                    require!(sequence > price_account.publishers[i].sequence, ErrorCode::QuoteReplayed);
+                    sequence
+                }
+                // Batched quotes carry no sequence; a newer timestamp is what rules out replays
+                None => {
+                    require!(price_account.publishers[i].timestamp < quote.timestamp, ErrorCode::QuoteReplayed);
+                    price_account.publishers[i].sequence
+                }
+            };
+            price_account.publishers[i] = PublisherPrice { sequence, ..publisher_price };
+            found = true;
+            break;
+        }
+    }
+
+    if !found {
+        // Joining a new feed increases exposure, so the stake must cover it
+        let required = required_stake(global_state, publisher, publisher.feed_count + 1)?;
+        require!(
+            publisher.staked_amount >= required,
+            ErrorCode::InsufficientExposureStake
+        );
+
+        // Find empty slot
+        let mut added = false;
+        for i in 0..MAX_PUBLISHERS {
+            if !price_account.publishers[i].active() {
+                price_account.publishers[i] = publisher_price;
+                price_account.publisher_count += 1;
+                added = true;
+                break;
+            }
+        }
+        require!(added, ErrorCode::PublishersArrayFull);
+        publisher.feed_count += 1;
+    }
+
+    price_account.last_update_slot = slot;
+
+    let config = aggregation_config(price_account, slot);
+    if !aggregation::is_fresh(&config, quote.timestamp, clock.unix_timestamp) {
+        emit_quote_rejected(price_account.product_account, &publisher_price, QuoteRejectReason::Stale, slot);
+    }
+    if config.degraded_confidence_bps > 0
+        && confidence_bps(price, confidence).unwrap_or(u64::MAX) > config.degraded_confidence_bps as u64
+    {
+        emit_quote_rejected(price_account.product_account, &publisher_price, QuoteRejectReason::BandViolation, slot);
+    }
+
+    // Trigger aggregation if enough publishers, at most once per throttle window
+    if price_account.publisher_count >= price_account.min_publishers
+        && slot >= price_account.last_aggregation_slot.saturating_add(price_account.min_slots_between_aggregations)
+    {
+        // Running out of compute mid-aggregation would fail the quote too, so leave the
+        // round to a crank when the transaction's budget is nearly spent
+        let required = AGGREGATION_BASE_COMPUTE_UNITS
+            + AGGREGATION_COMPUTE_UNITS_PER_QUOTE * price_account.publisher_count as u64;
+        let remaining = sol_remaining_compute_units();
+        if remaining >= required {
+            aggregate_prices_internal(price_account, aggregation, &clock)?;
+        } else {
+            emit!(AggregationDeferred {
+                product: price_account.product_account,
+                slot,
+                remaining_compute_units: remaining,
+                required_compute_units: required,
+            });
+        }
+    }
+
+    Ok(())
+}
+
+/// Quote slot a full feed gives up to a higher-ranked publisher: stale quotes rank below
+/// fresh ones, then lower stake below higher, then older quotes below newer ones
+fn lowest_ranked_quote(price_account: &PriceAccount, clock: &Clock) -> Option<usize> {
+    let config = aggregation_config(price_account, clock.slot);
+    price_account
+        .publishers
+        .iter()
+        .enumerate()
+        .filter(|(_, quote)| quote.active())
+        .min_by_key(|(_, quote)| {
+            (aggregation::is_fresh(&config, quote.timestamp, clock.unix_timestamp), quote.stake, quote.timestamp)
+        })
+        .map(|(i, _)| i)
+}
+
+/// Index of the publisher's quote in the feed, if it has one
+fn quote_index(price_account: &PriceAccount, publisher: &Pubkey) -> Option<usize> {
+    price_account.publishers
+        .iter()
+        .position(|p| p.active() && p.publisher == *publisher)
+}
+
+/// Whether a quote fits the publisher's submission quota, closing the quota's epoch first
+/// once the program epoch has moved on. Quotes beyond the maximum are dropped by the
+/// caller without an error or event, so a flooding publisher neither fails nor fills logs.
+fn within_quota(
+    feed_quota: Option<&mut FeedQuota>,
+    price_account: &PriceAccount,
+    publisher: &Pubkey,
+    epoch: u64,
+) -> Result<bool> {
+    let feed_quota = match feed_quota {
+        Some(feed_quota) => feed_quota,
+        None => {
+            require!(!price_account.submission_quota(), ErrorCode::FeedQuotaRequired);
+            return Ok(true);
+        }
+    };
+
+    if feed_quota.epoch != epoch {
+        let mut below_minimum = 0u128;
+        if feed_quota.min_submissions > 0 {
+            for (i, quote) in price_account.publishers.iter().enumerate() {
+                if quote.active() && feed_quota.submissions[i] < feed_quota.min_submissions {
+                    below_minimum |= 1 << i;
+                }
+            }
+        }
+        emit!(QuotaEpochClosed {
+            feed: feed_quota.price_account,
+            epoch: feed_quota.epoch,
+            below_minimum,
+        });
+        feed_quota.epoch = epoch;
+        feed_quota.submissions = [0; MAX_PUBLISHERS];
+    }
+
+    Ok(match quote_index(price_account, publisher) {
+        Some(i) => feed_quota.max_submissions == 0 || feed_quota.submissions[i] < feed_quota.max_submissions,
+        None => true,
+    })
+}
+
+/// Counts a written quote. A publisher new to the feed may have taken the slot of one
+/// that left, so its count starts over.
+fn count_submission(feed_quota: &mut FeedQuota, price_account: &PriceAccount, publisher: &Pubkey, joined: bool) {
+    if let Some(i) = quote_index(price_account, publisher) {
+        if joined {
+            feed_quota.submissions[i] = 0;
+        }
+        feed_quota.submissions[i] = feed_quota.submissions[i].saturating_add(1);
+    }
+}
+
+/// Raw contract quote scaled onto a futures feed's continuous series
+fn roll_adjusted(price_account: &PriceAccount, price: i64, confidence: u64) -> Result<(i64, u64)> {
+    if price_account.roll_adjustment == 0 {
+        return Ok((price, confidence));
+    }
+
+    let scale = |value: i128, rounding| {
+        div_round(
+            value.checked_mul(price_account.roll_adjustment as i128)?,
+            ROLL_ADJUSTMENT_SCALE as i128,
+            rounding,
+        )
+    };
+    let price = scale(price as i128, price_account.rounding_mode()?)
+        .and_then(|p| i64::try_from(p).ok())
+        .ok_or(ErrorCode::Overflow)?;
+    let confidence = scale(confidence as i128, RoundingMode::Ceil)
+        .and_then(|c| u64::try_from(c).ok())
+        .ok_or(ErrorCode::Overflow)?;
+    require!(price > 0, ErrorCode::InvalidPrice);
+    Ok((price, confidence))
+}
+
+fn validate_contract(contract_id: &str, expiry: i64, global_state: Option<&GlobalState>) -> Result<()> {
+    require!(
+        !contract_id.is_empty()
+            && contract_id.len() <= MAX_CONTRACT_ID_LEN
+            && expiry > optional_program_clock(global_state)?.unix_timestamp,
+        ErrorCode::InvalidContract
+    );
+    Ok(())
+}
+
+/// Base and quote currency of a "BASE/QUOTE" symbol; empty for other symbols
+fn currency_pair(symbol: &str) -> (String, String) {
+    match symbol.split_once('/') {
+        Some((base, quote))
+            if !base.is_empty()
+                && !quote.is_empty()
+                && base.len() <= MAX_CURRENCY_LEN
+                && quote.len() <= MAX_CURRENCY_LEN =>
+        {
+            (base.to_string(), quote.to_string())
+        }
+        _ => (String::new(), String::new()),
+    }
+}
+
+/// Submitted confidence in absolute price units, converting bps on bps-mode feeds
+fn quote_confidence(price_account: &PriceAccount, price: i64, confidence: u64) -> Result<u64> {
+    match price_account.confidence_mode()? {
+        ConfidenceMode::Absolute => Ok(confidence),
+        ConfidenceMode::Bps => {
+            let absolute = div_round(
+                price as i128 * confidence as i128,
+                10_000,
+                price_account.rounding_mode()?,
+            )
+            .ok_or(ErrorCode::Overflow)?;
+            u64::try_from(absolute).map_err(|_| ErrorCode::Overflow.into())
+        }
+    }
+}
+
+fn confidence_bps(price: i64, confidence: u64) -> Option<u64> {
+    if price <= 0 {
+        return None;
+    }
+    let bps = div_round(confidence as i128 * 10_000, price as i128, RoundingMode::Ceil)?;
+    u64::try_from(bps).ok()
+}
+
+/// Leaf a publisher commits to for one feed in a `PriceBatch` root
+fn batch_leaf(price_account: &Pubkey, sequence: u64, price: i64, confidence: u64, source_id: u64) -> [u8; 32] {
+    anchor_lang::solana_program::keccak::hashv(&[
+        &[0u8],
+        price_account.as_ref(),
+        &sequence.to_le_bytes(),
+        &price.to_le_bytes(),
+        &confidence.to_le_bytes(),
+        &source_id.to_le_bytes(),
+    ])
+    .to_bytes()
+}
+
+/// Pays the keeper the budget's per-crank reward, once per `min_interval_slots`. Cranks
+/// inside the interval or beyond the budget still succeed, just unpaid.
+fn reimburse_keeper(crank_budget: &mut Account<CrankBudget>, keeper: &Signer, slot: u64) -> Result<()> {
+    if crank_budget.last_paid_slot > 0 && slot < crank_budget.last_paid_slot + crank_budget.min_interval_slots {
+        return Ok(());
+    }
+
+    let budget_info = crank_budget.to_account_info();
+    let rent_exempt = Rent::get()?.minimum_balance(budget_info.data_len());
+    let available = budget_info.lamports().saturating_sub(rent_exempt);
+    let lamports = crank_budget.reward_lamports;
+    if lamports == 0 || available < lamports {
+        return Ok(());
+    }
+
+    **budget_info.try_borrow_mut_lamports()? -= lamports;
+    **keeper.to_account_info().try_borrow_mut_lamports()? += lamports;
+
+    crank_budget.last_paid_slot = slot;
+    crank_budget.total_paid = crank_budget.total_paid
+        .checked_add(lamports)
+        .ok_or(ErrorCode::Overflow)?;
+
+    emit!(CrankReimbursed {
+        price_account: crank_budget.price_account,
+        keeper: keeper.key(),
+        lamports,
+        remaining: available - lamports,
+    });
+
+    Ok(())
+}
+
+/// Four-eyes check: a publisher with a registered cosigner needs it to sign as well
+fn require_cosigner(publisher: &PublisherAccount, cosigner: Option<&Signer>) -> Result<()> {
+    if publisher.quote_cosigner != Pubkey::default() {
+        let cosigner = cosigner.ok_or(ErrorCode::CosignerRequired)?;
+        require_keys_eq!(cosigner.key(), publisher.quote_cosigner, ErrorCode::CosignerRequired);
+    }
+    Ok(())
+}
+
+/// Hash a publisher commits to before revealing a quote. Binding the feed and publisher
+/// stops another publisher from copying a commitment and revealing the same quote.
+fn quote_commitment_hash(
+    price_account: &Pubkey,
+    publisher: &Pubkey,
+    price: i64,
+    confidence: u64,
+    source_id: u64,
+    salt: &[u8; 32],
+) -> [u8; 32] {
+    anchor_lang::solana_program::keccak::hashv(&[
+        price_account.as_ref(),
+        publisher.as_ref(),
+        &price.to_le_bytes(),
+        &confidence.to_le_bytes(),
+        &source_id.to_le_bytes(),
+        salt,
+    ])
+    .to_bytes()
+}
+
+/// Unclaimed coverage the publisher has bought with its contributions
+fn remaining_insurance_coverage(publisher: &PublisherAccount) -> u64 {
+    publisher.insurance_contributed
+        .saturating_mul(INSURANCE_COVERAGE_MULTIPLIER)
+        .saturating_sub(publisher.insurance_claimed)
+}
+
+/// Portion of a slash the insurance fund covers for the publisher: everything above the
+/// deductible, limited by the publisher's remaining coverage and by its pro rata share of
+/// the fund, so that early claims cannot exhaust the fund backing everyone else's coverage
+fn insurance_coverage(publisher: &PublisherAccount, token_vault: &TokenVault, slash_amount: u64) -> u64 {
+    let deductible = (slash_amount as u128 * INSURANCE_DEDUCTIBLE_BPS as u128 / 10_000) as u64;
+    let remaining_coverage = remaining_insurance_coverage(publisher);
+    let total_coverage = token_vault.insurance_coverage.max(remaining_coverage).max(1);
+    let fund_share = (token_vault.insurance_fund as u128 * remaining_coverage as u128 / total_coverage as u128) as u64;
+
+    (slash_amount - deductible)
+        .min(remaining_coverage)
+        .min(fund_share)
+}
+
+/// Borsh encoding of the parameters a proposal's action changes, read from the accounts it
+/// targets. Actions whose effects live outside these accounts (upgrades, bulk feed updates)
+/// record nothing.
+fn action_snapshot(accounts: &ExecuteGovernanceAction) -> Result<Vec<u8>> {
+    let global_state = &accounts.global_state;
+    let token_vault = &accounts.token_vault;
+    let snapshot = match &accounts.proposal.proposal_type {
+        ProposalType::UpdateRewardRate { .. } => token_vault.reward_rate.try_to_vec()?,
+        ProposalType::UpdateMinPublishers { .. } => accounts.price_account
+            .as_ref()
+            .map(|feed| feed.load().map(|price_account| price_account.min_publishers))
+            .transpose()?
+            .try_to_vec()?,
+        ProposalType::EmergencyPause | ProposalType::EmergencyUnpause => global_state.paused.try_to_vec()?,
+        ProposalType::UpdateGovernanceParams { .. } => {
+            let gov = &accounts.governance_state;
+            (
+                gov.proposal_threshold,
+                gov.voting_period,
+                gov.quorum_percentage,
+                gov.timelock_duration,
+                gov.conviction_threshold_percentage,
+                gov.severity_threshold_bps,
+            ).try_to_vec()?
+        },
+        ProposalType::UpdateStakePerFeed { .. } => global_state.stake_per_feed.try_to_vec()?,
+        ProposalType::ApprovePublisherRegistrations { .. } => global_state.registration_allowance.try_to_vec()?,
+        ProposalType::UpdateRegistrationLimit { .. } => global_state.max_new_publishers_per_epoch.try_to_vec()?,
+        ProposalType::SetFeedSla { .. } => accounts.feed_sla
+            .as_ref()
+            .map(|sla| (sla.max_update_interval_slots, sla.max_confidence_bps, sla.rebate_bps))
+            .try_to_vec()?,
+        ProposalType::SetFeedQuota { .. } => accounts.feed_quota
+            .as_ref()
+            .map(|quota| (quota.min_submissions, quota.max_submissions))
+            .try_to_vec()?,
+        ProposalType::AttestIdentity { .. } => accounts.publisher_account
+            .as_ref()
+            .map(|publisher| publisher.operator_id)
+            .try_to_vec()?,
+        ProposalType::UpdateUnbondingPeriod { .. } => global_state.unbonding_period.try_to_vec()?,
+        ProposalType::UpdateMinStake { .. } => (global_state.min_stake, global_state.previous_min_stake).try_to_vec()?,
+        ProposalType::SetReceiptTransferability { .. } => global_state.receipts_transferable.try_to_vec()?,
+        ProposalType::SetPositionTransferability { .. } => global_state.positions_transferable.try_to_vec()?,
+        ProposalType::SetAssetTemplate { .. } => accounts.asset_template
+            .as_ref()
+            .map(|asset_template| asset_template.template.clone())
+            .try_to_vec()?,
+        ProposalType::ResolveVaultDrift { .. } => (token_vault.treasury, token_vault.unreconciled_drift).try_to_vec()?,
+        ProposalType::UpdateRewardVesting { .. } => token_vault.reward_vesting_period.try_to_vec()?,
+        ProposalType::UpdateListingCollateral { .. } => global_state.listing_collateral.try_to_vec()?,
+        ProposalType::DrawListingCollateral { .. } => (
+            accounts.feed_collateral.as_ref().map(|collateral| collateral.amount),
+            token_vault.insurance_fund,
+        ).try_to_vec()?,
+        ProposalType::DisputeRound { .. } => accounts.round_archive
+            .as_ref()
+            .map(|archive| (archive.disputed, archive.reaggregated.as_ref().map(|round| round.price)))
+            .try_to_vec()?,
+        ProposalType::SetRewardMint { .. } => token_vault.payout_mint().try_to_vec()?,
+        ProposalType::MigrateTokenMint { .. } => (
+            token_vault.token_mint,
+            token_vault.vault_token_account,
+        ).try_to_vec()?,
+        ProposalType::RotateVault { .. } => (
+            global_state.custody_version,
+            token_vault.vault_authority,
+            token_vault.vault_token_account,
+        ).try_to_vec()?,
+        ProposalType::FreezeFeed { .. } | ProposalType::UnfreezeFeed { .. } => accounts.price_account
+            .as_ref()
+            .map(|feed| feed.load().map(|price_account| price_account.frozen()))
+            .transpose()?
+            .try_to_vec()?,
+        ProposalType::DeclareVolatilityRegime { .. } => accounts.price_account
+            .as_ref()
+            .map(|feed| feed.load().map(|price_account| (price_account.volatility_until_slot, price_account.volatility_widening_bps)))
+            .transpose()?
+            .try_to_vec()?,
+        ProposalType::SlashPublisher { .. } => accounts.publisher_account
+            .as_ref()
+            .map(|publisher| (
+                publisher.staked_amount,
+                publisher.stake_state.unbonding_amount(),
+                publisher.slash_count,
+                publisher.publishing_blocked_until_slot,
+            ))
+            .try_to_vec()?,
+        ProposalType::UpgradeProgram { .. }
+        | ProposalType::SetUpgradeAuthority { .. }
+        | ProposalType::BulkUpdateFeeds { .. } => Vec::new(),
+    };
+    require!(snapshot.len() <= MAX_RECEIPT_SNAPSHOT_LEN, ErrorCode::Overflow);
+    Ok(snapshot)
+}
+
+/// Tokens the vault owes: stake (including unbonding), reward pool, listing collateral,
+/// treasury, insurance fund, reserved rebates and locked governance tokens
+fn vault_liabilities(token_vault: &TokenVault) -> Result<u64> {
+    // A separate reward mint keeps the pool in its own vault
+    let (reward_pool, vesting_rewards) = if token_vault.has_separate_reward_mint() {
+        (0, 0)
+    } else {
+        (token_vault.reward_pool, token_vault.vesting_rewards)
+    };
+
+    [
+        reward_pool,
+        vesting_rewards,
+        token_vault.listing_collateral,
+        token_vault.treasury,
+        token_vault.insurance_fund,
+        token_vault.pending_rebates,
+        token_vault.swap_reserve,
+        token_vault.total_conviction_locked,
+        token_vault.total_veto_stake,
+    ]
+    .iter()
+    .try_fold(token_vault.total_staked, |total, amount| total.checked_add(*amount))
+    .ok_or(error!(ErrorCode::Overflow))
+}
+
+/// Moves rebates accrued since the subscription's last settlement into its credits. A
+/// subscription never earns more in rebates than it paid in fees; what breaches reserved
+/// beyond that goes back to the treasury.
+fn settle_rebates(subscription: &mut Subscription, feed_sla: &FeedSla, token_vault: &mut TokenVault) -> Result<()> {
+    let delta = feed_sla.rebate_index_bps.saturating_sub(subscription.rebate_index_bps);
+    let accrued = (subscription.fees_paid as u128 * delta as u128 / 10_000) as u64;
+    let credited = accrued.min(subscription.fees_paid.saturating_sub(subscription.rebates_earned));
+
+    subscription.credits = subscription.credits
+        .checked_add(credited)
+        .ok_or(ErrorCode::Overflow)?;
+    subscription.rebates_earned += credited;
+    subscription.rebate_index_bps = feed_sla.rebate_index_bps;
+
+    let excess = (accrued - credited).min(token_vault.pending_rebates);
+    token_vault.pending_rebates -= excess;
+    token_vault.treasury = token_vault.treasury
+        .checked_add(excess)
+        .ok_or(ErrorCode::Overflow)?;
+
+    Ok(())
+}
+
+/// Accrues the publisher's stake-weighted share of rewards since it last accrued, from
+/// the growth of the reward index. Slashed publishers accrue nothing until their reward
+/// cooldown ends, nor while they are barred from publishing. Rewards accrued before the reward mint last changed were owed in the
+/// old mint and lapse.
+fn accrue_rewards(publisher: &mut PublisherAccount, token_vault: &TokenVault, slot: u64) -> Result<()> {
+    let last_reward_slot = publisher.last_reward_slot;
+    let elapsed = slot.saturating_sub(last_reward_slot) as u128;
+    if publisher.last_reward_slot < token_vault.reward_mint_slot {
+        publisher.pending_rewards = 0;
+        publisher.reward_index = publisher.reward_index.max(token_vault.reward_mint_index);
+    }
+    let index = token_vault.reward_index_at(slot);
+    let mut growth = index.saturating_sub(publisher.reward_index);
+    publisher.last_reward_slot = slot;
+    publisher.reward_index = index;
+
+    let frozen_until_slot = publisher.rewards_frozen_until_slot.max(publisher.publishing_blocked_until_slot);
+    if slot <= frozen_until_slot || elapsed == 0 || growth == 0 {
+        return Ok(());
+    }
+    // The index grew over the whole gap; the part inside the cooldown is forfeited
+    if last_reward_slot < frozen_until_slot {
+        growth = growth * (slot - frozen_until_slot) as u128 / elapsed;
+    }
+
+    let reward = growth.saturating_mul(publisher.staked_amount as u128) / REWARD_INDEX_SCALE;
+
+    publisher.pending_rewards = publisher.pending_rewards
+        .checked_add(u64::try_from(reward).map_err(|_| ErrorCode::Overflow)?)
+        .ok_or(ErrorCode::Overflow)?;
+
+    Ok(())
+}
+
+fn required_stake(global_state: &GlobalState, publisher: &PublisherAccount, feed_count: u32) -> Result<u64> {
+    global_state
+        .stake_per_feed
+        .checked_mul(feed_count as u64)
+        .and_then(|exposure| exposure.checked_add(min_stake_for(global_state, publisher)))
+        .ok_or(ErrorCode::Overflow.into())
+}
+
+/// Minimum stake a publisher must keep. For MIN_STAKE_GRACE_EPOCHS after a raise,
+/// publishers keep the minimum they registered under so they can top up, but never less
+/// than `previous_min_stake`, which every publisher already had to meet.
+fn min_stake_for(global_state: &GlobalState, publisher: &PublisherAccount) -> u64 {
+    if global_state.current_epoch < global_state.min_stake_raised_epoch + MIN_STAKE_GRACE_EPOCHS {
+        publisher
+            .registered_min_stake
+            .max(global_state.previous_min_stake)
+            .min(global_state.min_stake)
+    } else {
+        global_state.min_stake
+    }
+}
+
+/// Moves conviction toward the currently locked amount with per-slot exponential decay:
+/// y_t = x + (y_0 - x) * a^t
+fn update_conviction(proposal: &mut Proposal, slot: u64) {
+    let elapsed = slot.saturating_sub(proposal.conviction_updated_slot);
+    if elapsed == 0 {
+        return;
+    }
+
+    let decay = pow_scaled(CONVICTION_DECAY_SCALED as u128, elapsed);
+    let staked = proposal.conviction_staked as u128;
+    let current = proposal.conviction as u128;
+
+    let conviction = if current >= staked {
+        staked + (current - staked) * decay / 1_000_000_000
+    } else {
+        staked - (staked - current) * decay / 1_000_000_000
+    };
+
+    proposal.conviction = conviction as u64;
+    proposal.conviction_updated_slot = slot;
+}
+
+/// Exponentiation by squaring for a 1e9-scaled fixed-point base
+fn pow_scaled(base: u128, mut exp: u64) -> u128 {
+    let mut result: u128 = 1_000_000_000;
+    let mut base = base;
+    while exp > 0 {
+        if exp & 1 == 1 {
+            result = result * base / 1_000_000_000;
+        }
+        base = base * base / 1_000_000_000;
+        exp >>= 1;
+    }
+    result
+}
+
+/// Computes base/quote expressed at `target_expo`, or None on overflow or a zero quote
+fn implied_cross_rate(
+    base_price: i64,
+    base_expo: i32,
+    quote_price: i64,
+    quote_expo: i32,
+    target_expo: i32,
+    rounding: RoundingMode,
+) -> Option<i64> {
+    if quote_price == 0 {
+        return None;
+    }
+
+    // base * 10^base_expo / (quote * 10^quote_expo) = implied * 10^target_expo
+    let shift = base_expo.checked_sub(quote_expo)?.checked_sub(target_expo)?;
+    let (numerator, denominator) = if shift >= 0 {
+        (
+            (base_price as i128).checked_mul(10i128.checked_pow(shift as u32)?)?,
+            quote_price as i128,
+        )
+    } else {
+        (
+            base_price as i128,
+            (quote_price as i128).checked_mul(10i128.checked_pow(shift.unsigned_abs())?)?,
+        )
+    };
+
+    i64::try_from(div_round(numerator, denominator, rounding)?).ok()
+}
+
+/// Multiplies by `10^shift`, or divides by `10^-shift` with the given rounding
+fn rescale(value: i128, shift: i32, rounding: RoundingMode) -> Option<i128> {
+    aggregation::rescale(value, shift, rounding.into())
+}
+
+/// Whether two prices are within a factor of ten of each other. Zero (unpriced) matches
+/// anything.
+fn same_magnitude(a: i128, b: i128) -> bool {
+    if a == 0 || b == 0 {
+        return true;
+    }
+    (a > 0) == (b > 0)
+        && a.unsigned_abs() < b.unsigned_abs().saturating_mul(10)
+        && b.unsigned_abs() < a.unsigned_abs().saturating_mul(10)
+}
+
+/// Divides with the feed's rounding mode. Returns `None` on a zero denominator.
+fn div_round(numerator: i128, denominator: i128, rounding: RoundingMode) -> Option<i128> {
+    aggregation::div_round(numerator, denominator, rounding.into())
+}
+
+/// Folds an observation into the feed's EMA with its configured alpha and weighting
+fn update_ema(price_account: &PriceAccount, new_price: i64, new_confidence: u64) -> Result<EmaData> {
+    let current_ema = &price_account.ema;
+    let alpha = if price_account.ema_alpha == 0 {
+        EMA_ALPHA_SCALED
+    } else {
+        price_account.ema_alpha as i64
+    };
+    let alpha = if price_account.ema_confidence_weighted() {
+        let min_alpha = (alpha / EMA_MIN_ALPHA_DIVISOR).max(1);
+        aggregation::confidence_weighted_alpha(alpha, min_alpha, current_ema.ema_confidence, new_confidence)
+    } else {
+        alpha
+    };
+    let ema = aggregation::ema_step(
+        aggregation::Ema {
+            price: current_ema.ema_price,
+            confidence: current_ema.ema_confidence,
+            observations: current_ema.num_observations,
+        },
+        new_price,
+        new_confidence,
+        alpha,
+        price_account.rounding_mode()?.into(),
+    );
+
+    Ok(EmaData {
+        ema_price: ema.price,
+        ema_confidence: ema.confidence,
+        num_observations: ema.observations,
+    })
+}
+
+impl aggregation::Quote for PublisherPrice {
+    fn price(&self) -> i64 {
+        self.price
+    }
+
+    fn stake(&self) -> u64 {
+        self.stake
+    }
+
+    fn timestamp(&self) -> i64 {
+        self.timestamp
+    }
+
+    fn source_id(&self) -> u64 {
+        self.source_id
+    }
+
+    fn operator(&self) -> u64 {
+        self.operator
+    }
+}
+
+/// Operator key shared by every publisher bound to the same identifier (case-insensitive),
+/// never 0
+fn operator_id(identifier: &str) -> u64 {
+    let hash = anchor_lang::solana_program::keccak::hash(identifier.to_ascii_lowercase().as_bytes());
+    let mut id = [0u8; 8];
+    id.copy_from_slice(&hash.to_bytes()[..8]);
+    u64::from_le_bytes(id).max(1)
+}
+
+impl From<RoundingMode> for aggregation::Rounding {
+    fn from(mode: RoundingMode) -> Self {
+        match mode {
+            RoundingMode::Floor => aggregation::Rounding::Floor,
+            RoundingMode::Ceil => aggregation::Rounding::Ceil,
+            RoundingMode::HalfEven => aggregation::Rounding::HalfEven,
+        }
+    }
+}
+
+impl TryFrom<u8> for PriceStatus {
+    type Error = Error;
+
+    fn try_from(value: u8) -> Result<Self> {
+        Ok(match value {
+            0 => PriceStatus::Trading,
+            1 => PriceStatus::Halted,
+            2 => PriceStatus::Auction,
+            3 => PriceStatus::Unknown,
+            4 => PriceStatus::Degraded,
+            5 => PriceStatus::Closed,
+            _ => return err!(ErrorCode::InvalidDiscriminant),
+        })
+    }
+}
+
+impl TryFrom<u8> for PriceType {
+    type Error = Error;
+
+    fn try_from(value: u8) -> Result<Self> {
+        Ok(match value {
+            0 => PriceType::Spot,
+            1 => PriceType::Futures,
+            2 => PriceType::Option,
+            _ => return err!(ErrorCode::InvalidDiscriminant),
+        })
+    }
+}
+
+impl TryFrom<u8> for RoundingMode {
+    type Error = Error;
+
+    fn try_from(value: u8) -> Result<Self> {
+        Ok(match value {
+            0 => RoundingMode::Floor,
+            1 => RoundingMode::Ceil,
+            2 => RoundingMode::HalfEven,
+            _ => return err!(ErrorCode::InvalidDiscriminant),
+        })
+    }
+}
+
+impl TryFrom<u8> for ConfidenceMode {
+    type Error = Error;
+
+    fn try_from(value: u8) -> Result<Self> {
+        Ok(match value {
+            0 => ConfidenceMode::Absolute,
+            1 => ConfidenceMode::Bps,
+            _ => return err!(ErrorCode::InvalidDiscriminant),
+        })
+    }
+}
+
+impl From<aggregation::Status> for PriceStatus {
+    fn from(status: aggregation::Status) -> Self {
+        match status {
+            aggregation::Status::Trading => PriceStatus::Trading,
+            aggregation::Status::Degraded => PriceStatus::Degraded,
+            aggregation::Status::Halted => PriceStatus::Halted,
+            aggregation::Status::Unknown => PriceStatus::Unknown,
+        }
+    }
+}
+
+// ============================================================================
+// Aggregation Core
+// ============================================================================
+
+/// The aggregation pipeline as plain functions over quote values: staleness, MAD
+/// outlier filter, stake-weighted median, confidence, status, EMA and probation weights.
+/// It only uses `core` and `alloc`, takes every constant as an input and never panics on
+/// arithmetic, so it can be fuzzed, model-checked or lifted into a `no_std` crate as is.
+pub mod aggregation {
+    extern crate alloc;
+
+    use alloc::vec::Vec;
+
+    /// A quote as seen by the aggregation. `stake` is the aggregation weight.
+    pub trait Quote {
+        fn price(&self) -> i64;
+        fn stake(&self) -> u64;
+        fn timestamp(&self) -> i64;
+        fn source_id(&self) -> u64;
+        fn operator(&self) -> u64; // Identity-linked operator, 0 when not linked
+    }
+
+    impl<T: Quote> Quote for &T {
+        fn price(&self) -> i64 {
+            (**self).price()
+        }
+
+        fn stake(&self) -> u64 {
+            (**self).stake()
+        }
+
+        fn timestamp(&self) -> i64 {
+            (**self).timestamp()
+        }
+
+        fn source_id(&self) -> u64 {
+            (**self).source_id()
+        }
+
+        fn operator(&self) -> u64 {
+            (**self).operator()
+        }
+    }
+
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub enum Rounding {
+        Floor,
+        Ceil,
+        HalfEven,
+    }
+
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub enum Status {
+        Trading,
+        Degraded,
+        Halted,
+        Unknown, // Too few quotes survived filtering to publish
+    }
+
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub struct AggregationConfig {
+        pub min_publishers: u8,
+        pub min_operators: u8, // Distinct linked operators among contributors (0 disables)
+        pub staleness_threshold: i64, // Seconds before a quote is ignored
+        pub halted_threshold: i64, // Seconds since the newest quote before the feed is halted
+        pub mad_multiplier: i64,
+        pub degraded_confidence_bps: u16, // 0 disables the confidence band
+        pub high_precision_decimals: Option<u32>, // Extra decimals carried by Aggregate::price_i128
+    }
+
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub struct Aggregate {
+        pub price: i64,
+        pub confidence: u64,
+        pub status: Status,
+        pub price_i128: Option<i128>,
+        pub contributors: usize, // Quotes left after outlier filtering
+    }
+
+    #[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+    pub struct Ema {
+        pub price: i64,
+        pub confidence: u64,
+        pub observations: u64,
+    }
+
+    pub fn is_fresh(config: &AggregationConfig, timestamp: i64, current_time: i64) -> bool {
+        current_time.saturating_sub(timestamp) < config.staleness_threshold
+    }
+
+    /// Runs the pipeline over fresh quotes (in any order). An unpublishable result has
+    /// `Status::Unknown` and a zero price.
+    pub fn aggregate<Q: Quote + Copy>(config: &AggregationConfig, mut quotes: Vec<Q>, current_time: i64) -> Aggregate {
+        quotes.sort_by_key(|q| q.price());
+        let filtered = filter_outliers(&quotes, config.mad_multiplier);
+
+        let unknown = Aggregate {
+            price: 0,
+            confidence: 0,
+            status: Status::Unknown,
+            price_i128: None,
+            contributors: filtered.len(),
+        };
+        if filtered.len() < config.min_publishers as usize
+            || distinct_operators(&filtered) < config.min_operators as usize
+        {
+            return unknown;
+        }
+        let price = match stake_weighted_median(&filtered) {
+            Some(price) => price,
+            None => return unknown,
+        };
+        let confidence = confidence(&filtered, price);
+
+        let mut status = status(&quotes, config.min_publishers, current_time, config.halted_threshold);
+        if status == Status::Trading && is_degraded(config, price, confidence, filtered.len()) {
+            status = Status::Degraded;
+        }
+
+        Aggregate {
+            price,
+            confidence,
+            status,
+            price_i128: config
+                .high_precision_decimals
+                .and_then(|decimals| high_precision_price(price, decimals)),
+            contributors: filtered.len(),
+        }
+    }
+
+    /// Number of distinct operators behind the quotes; unlinked quotes don't count
+    pub fn distinct_operators<Q: Quote>(quotes: &[Q]) -> usize {
+        let mut operators: Vec<u64> = quotes.iter().map(|q| q.operator()).filter(|op| *op != 0).collect();
+        operators.sort_unstable();
+        operators.dedup();
+        operators.len()
+    }
+
+    /// Drops quotes further than `mad_multiplier` median absolute deviations from the
+    /// median. Expects quotes sorted by price; fewer than three are kept as is.
+    pub fn filter_outliers<Q: Quote + Copy>(sorted: &[Q], mad_multiplier: i64) -> Vec<Q> {
+        if sorted.len() < 3 {
+            return sorted.to_vec();
+        }
+
+        let (median, mad) = match median_and_mad(sorted) {
+            Some(dispersion) => dispersion,
+            None => return sorted.to_vec(),
+        };
+        let threshold = mad.saturating_mul(mad_multiplier.unsigned_abs());
+
+        sorted
+            .iter()
+            .filter(|q| q.price().abs_diff(median) <= threshold)
+            .copied()
+            .collect()
+    }
+
+    /// Median price and median absolute deviation from it. Expects quotes sorted by
+    /// price; `None` when there are none.
+    pub fn median_and_mad<Q: Quote>(sorted: &[Q]) -> Option<(i64, u64)> {
+        let median = sorted.get(sorted.len() / 2)?.price();
+        let mut deviations: Vec<u64> = sorted.iter().map(|q| q.price().abs_diff(median)).collect();
+        deviations.sort_unstable();
+
+        Some((median, deviations[deviations.len() / 2]))
+    }
+
+    /// Price at which cumulative stake reaches half the total. Expects quotes sorted by
+    /// price; `None` when there are none.
+    pub fn stake_weighted_median<Q: Quote>(sorted: &[Q]) -> Option<i64> {
+        let total_stake: u128 = sorted.iter().map(|q| q.stake() as u128).sum();
+        let median_stake = total_stake / 2;
+
+        let mut cumulative_stake: u128 = 0;
+        for quote in sorted {
+            cumulative_stake += quote.stake() as u128;
+            if cumulative_stake >= median_stake {
+                return Some(quote.price());
+            }
+        }
+
+        sorted.first().map(|q| q.price())
+    }
+
+    /// Stake-weighted standard deviation around `median`, at least 1
+    pub fn confidence<Q: Quote>(quotes: &[Q], median: i64) -> u64 {
+        let total_stake: u128 = quotes.iter().map(|q| q.stake() as u128).sum();
+        if total_stake == 0 {
+            return 1;
+        }
+
+        let variance = quotes.iter().fold(0u128, |variance, q| {
+            let diff = q.price().abs_diff(median) as u128;
+            let weighted = (diff * diff).saturating_mul(q.stake() as u128) / total_stake;
+            variance.saturating_add(weighted)
+        });
+
+        (isqrt(variance).min(u64::MAX as u128) as u64).max(1)
+    }
+
+    pub fn status<Q: Quote>(quotes: &[Q], min_publishers: u8, current_time: i64, halted_threshold: i64) -> Status {
+        if quotes.len() < min_publishers as usize {
+            return Status::Unknown;
+        }
+
+        if let Some(latest) = quotes.iter().map(|q| q.timestamp()).max() {
+            if current_time.saturating_sub(latest) > halted_threshold {
+                return Status::Halted;
+            }
+        }
+
+        Status::Trading
+    }
+
+    /// A fresh aggregate is degraded when its confidence exceeds the feed's band or only
+    /// the bare minimum of publishers survived filtering.
+    pub fn is_degraded(config: &AggregationConfig, price: i64, confidence: u64, contributors: usize) -> bool {
+        if config.min_publishers > 0 && contributors == config.min_publishers as usize {
+            return true;
+        }
+
+        config.degraded_confidence_bps > 0
+            && confidence as u128 * 10_000 > config.degraded_confidence_bps as u128 * price.unsigned_abs() as u128
+    }
+
+    /// The aggregate price scaled up by `10^decimals`, so the high-precision track is the
+    /// same stake-weighted median as `price` and never disagrees with it
+    pub fn high_precision_price(price: i64, decimals: u32) -> Option<i128> {
+        (price as i128).checked_mul(10i128.checked_pow(decimals)?)
+    }
+
+    /// Counts distinct source ids among the quotes and the stake share (bps) held by the
+    /// most common one, so consumers can spot dependence on a single upstream exchange.
+    pub fn source_stats<Q: Quote>(quotes: &[Q]) -> (u8, u16) {
+        let mut by_source: Vec<(u64, u128)> = Vec::with_capacity(quotes.len());
+        let mut total_stake: u128 = 0;
+
+        for q in quotes {
+            total_stake += q.stake() as u128;
+            match by_source.iter_mut().find(|(id, _)| *id == q.source_id()) {
+                Some((_, stake)) => *stake += q.stake() as u128,
+                None => by_source.push((q.source_id(), q.stake() as u128)),
+            }
+        }
+
+        let top_stake = by_source.iter().map(|(_, stake)| *stake).max().unwrap_or(0);
+        let top_share_bps = (top_stake * 10_000).checked_div(total_stake).unwrap_or(0) as u16;
+
+        (by_source.len().min(u8::MAX as usize) as u8, top_share_bps)
+    }
+
+    /// Stake weight during probation: `initial_weight_bps` at registration, ramping
+    /// linearly to full weight at `probation_end_epoch`.
+    pub fn probation_weight(
+        staked_amount: u64,
+        registered_epoch: u64,
+        probation_end_epoch: u64,
+        epoch: u64,
+        initial_weight_bps: u64,
+    ) -> u64 {
+        if epoch >= probation_end_epoch {
+            return staked_amount;
+        }
+
+        let duration = probation_end_epoch.saturating_sub(registered_epoch).max(1) as u128;
+        let elapsed = epoch.saturating_sub(registered_epoch) as u128;
+        let initial = initial_weight_bps.min(10_000) as u128;
+        let weight_bps = initial + (10_000 - initial) * elapsed / duration;
+
+        ((staked_amount as u128 * weight_bps) / 10_000) as u64
+    }
+
+    /// One EMA step with weight `alpha_scaled / 1_000_000` on the new observation. The
+    /// first observation seeds the average.
+    pub fn ema_step(current: Ema, price: i64, confidence: u64, alpha_scaled: i64, rounding: Rounding) -> Ema {
+        if current.observations == 0 {
+            return Ema {
+                price,
+                confidence,
+                observations: 1,
+            };
+        }
+
+        let alpha = alpha_scaled.clamp(0, 1_000_000) as i128;
+        let one_minus_alpha = 1_000_000 - alpha;
+
+        let ema_price = div_round(
+            alpha * price as i128 + one_minus_alpha * current.price as i128,
+            1_000_000,
+            rounding,
+        ).unwrap_or(current.price as i128) as i64;
+
+        let ema_confidence = div_round(
+            alpha * confidence as i128 + one_minus_alpha * current.confidence as i128,
+            1_000_000,
+            rounding,
+        ).unwrap_or(current.confidence as i128) as u64;
+
+        Ema {
+            price: ema_price,
+            confidence: ema_confidence,
+            observations: current.observations.saturating_add(1),
+        }
+    }
+
+    /// Scales `alpha_scaled` by `ema_confidence / confidence` when the observation is less
+    /// precise than the running average, so noisy observations move the EMA less. Never
+    /// below `min_alpha_scaled`, so the EMA still follows a lasting jump in volatility
+    /// instead of freezing at its old confidence.
+    pub fn confidence_weighted_alpha(
+        alpha_scaled: i64,
+        min_alpha_scaled: i64,
+        ema_confidence: u64,
+        confidence: u64,
+    ) -> i64 {
+        if confidence <= ema_confidence {
+            return alpha_scaled;
+        }
+        let weighted = (alpha_scaled as i128 * ema_confidence as i128 / confidence as i128) as i64;
+        weighted.max(min_alpha_scaled)
+    }
+
+    /// Multiplies by `10^shift`, or divides by `10^-shift` with the given rounding
+    pub fn rescale(value: i128, shift: i32, rounding: Rounding) -> Option<i128> {
+        if shift >= 0 {
+            value.checked_mul(10i128.checked_pow(shift as u32)?)
+        } else {
+            div_round(value, 10i128.checked_pow(shift.unsigned_abs())?, rounding)
+        }
+    }
+
+    /// Divides with the given rounding. Returns `None` on a zero denominator or overflow.
+    pub fn div_round(numerator: i128, denominator: i128, rounding: Rounding) -> Option<i128> {
+        if denominator == 0 {
+            return None;
+        }
+        let (numerator, denominator) = if denominator < 0 {
+            (numerator.checked_neg()?, denominator.checked_neg()?)
+        } else {
+            (numerator, denominator)
+        };
+
+        let floor = numerator.checked_div_euclid(denominator)?;
+        let remainder = numerator.checked_rem_euclid(denominator)?;
+        if remainder == 0 {
+            return Some(floor);
+        }
+
+        match rounding {
+            Rounding::Floor => Some(floor),
+            Rounding::Ceil => floor.checked_add(1),
+            Rounding::HalfEven => {
+                let twice = remainder.checked_mul(2)?;
+                if twice > denominator || (twice == denominator && floor % 2 != 0) {
+                    floor.checked_add(1)
+                } else {
+                    Some(floor)
+                }
+            }
+        }
+    }
+
+    /// Floor square root by Newton's method, so confidence doesn't depend on floats
+    fn isqrt(value: u128) -> u128 {
+        if value < 2 {
+            return value;
+        }
+
+        let mut x = value;
+        let mut y = x / 2 + 1;
+        while y < x {
+            x = y;
+            y = (x + value / x) / 2;
+        }
+        x
+    }
+}
+
+// ============================================================================
+// Context Structs
+// ============================================================================
+
+#[derive(Accounts)]
+pub struct InitializeProgram<'info> {
+    #[account(
+        init,
+        payer = payer,
+        space = GLOBAL_STATE_SPACE,
+        seeds = [b"global_state"],
+        bump
+    )]
+    pub global_state: Account<'info, GlobalState>,
+
+    /// CHECK: PDA used as vault authority for token transfers
+    #[account(
+        seeds = [b"vault_authority"],
+        bump
+    )]
+    pub vault_authority: UncheckedAccount<'info>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = TOKEN_VAULT_SPACE,
+        seeds = [b"token_vault"],
+        bump
+    )]
+    pub token_vault: Account<'info, TokenVault>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = GOVERNANCE_STATE_SPACE,
+        seeds = [b"governance"],
+        bump
+    )]
+    pub governance_state: Account<'info, GovernanceState>,
+
+    pub token_mint: InterfaceAccount<'info, Mint>,
+    
+    #[account(
+        constraint = vault_token_account.mint == token_mint.key() @ ErrorCode::InvalidMint,
+        constraint = vault_token_account.owner == vault_authority.key() @ ErrorCode::InvalidVaultAccount,
+        constraint = vault_token_account.delegate.is_none() @ ErrorCode::InvalidVaultAccount,
+        constraint = vault_token_account.close_authority.is_none() @ ErrorCode::InvalidVaultAccount
+    )]
+    pub vault_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    // Only the upgrade authority may initialize, so the canonical state can't be front-run
+    #[account(constraint = program.programdata_address()? == Some(program_data.key()))]
+    pub program: Program<'info, crate::program::SfdnOracle>,
+
+    #[account(
+        constraint = program_data.upgrade_authority_address == Some(authority.key()) @ ErrorCode::Unauthorized
+    )]
+    pub program_data: Account<'info, ProgramData>,
+
+    pub authority: Signer<'info>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct BootstrapV2<'info> {
+    /// CHECK: v1 layout; validated and rewritten in the handler
+    #[account(mut, seeds = [b"global_state"], bump, owner = crate::ID)]
+    pub global_state: UncheckedAccount<'info>,
+
+    /// CHECK: v1 layout; validated and rewritten in the handler
+    #[account(mut, seeds = [b"token_vault"], bump, owner = crate::ID)]
+    pub token_vault: UncheckedAccount<'info>,
+
+    /// CHECK: v1 layout; validated and rewritten in the handler
+    #[account(mut, seeds = [b"governance"], bump, owner = crate::ID)]
+    pub governance_state: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct BootstrapV3<'info> {
+    /// CHECK: v2 layout; validated and rewritten in the handler
+    #[account(mut, seeds = [b"global_state"], bump, owner = crate::ID)]
+    pub global_state: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct MigratePriceAccount<'info> {
+    /// CHECK: pre-zero-copy layout; validated and rewritten in the handler
+    #[account(mut, owner = crate::ID)]
+    pub price_account: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateProductAccount<'info> {
+    /// CHECK: pre-calendar layout; validated and rewritten in the handler
+    #[account(mut, owner = crate::ID)]
+    pub product_account: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct MigratePublisherAccount<'info> {
+    #[account(seeds = [b"global_state"], bump = global_state.bump)]
+    pub global_state: Account<'info, GlobalState>,
+
+    #[account(seeds = [b"token_vault"], bump = token_vault.bump)]
+    pub token_vault: Account<'info, TokenVault>,
+
+    /// CHECK: pre-stake-state layout; validated and rewritten in the handler
+    #[account(mut, owner = crate::ID)]
+    pub publisher_account: UncheckedAccount<'info>,
+
+    #[account(mut, constraint = authority.key() == global_state.authority @ ErrorCode::Unauthorized)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateProposal<'info> {
+    /// CHECK: pre-mode layout; validated and rewritten in the handler
+    #[account(mut, owner = crate::ID)]
+    pub proposal: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeClusterConfig<'info> {
+    #[account(
+        seeds = [b"global_state"],
+        bump = global_state.bump,
+        has_one = authority
+    )]
+    pub global_state: Account<'info, GlobalState>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + 1 + 32 + 32 + (4 + MAX_CLUSTER_LABEL_LEN) + 1 + 8 + 1,
+        seeds = [b"cluster_config"],
+        bump
+    )]
+    pub cluster_config: Account<'info, ClusterConfig>,
+
+    #[account(address = global_state.token_mint @ ErrorCode::InvalidMint)]
+    pub token_mint: InterfaceAccount<'info, Mint>,
+
+    pub authority: Signer<'info>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct AssertCluster<'info> {
+    #[account(
+        seeds = [b"cluster_config"],
+        bump = cluster_config.bump
+    )]
+    pub cluster_config: Account<'info, ClusterConfig>,
+}
+
+#[derive(Accounts)]
+pub struct ExportConfig<'info> {
+    #[account(
+        seeds = [b"global_state"],
+        bump = global_state.bump
+    )]
+    pub global_state: Account<'info, GlobalState>,
+
+    #[account(
+        seeds = [b"governance"],
+        bump = governance_state.bump
+    )]
+    pub governance_state: Account<'info, GovernanceState>,
+
+    #[account(
+        seeds = [b"token_vault"],
+        bump = token_vault.bump
+    )]
+    pub token_vault: Account<'info, TokenVault>,
+}
+
+#[derive(Accounts)]
+#[instruction(symbol: String, asset_type: AssetType)]
+pub struct CreateProduct<'info> {
+    #[account(mut)]
+    pub global_state: Account<'info, GlobalState>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = PRODUCT_ACCOUNT_SPACE,
+        seeds = [b"product", symbol.as_bytes()],
+        bump
+    )]
+    pub product_account: Account<'info, ProductAccount>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = PRICE_ACCOUNT_SPACE,
+        seeds = [b"price", symbol.as_bytes()],
+        bump
+    )]
+    pub price_account: AccountLoader<'info, PriceAccount>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + 32 + 32 + 1 + 8 + 1 + 8,
+        seeds = [b"feed_collateral", price_account.key().as_ref()],
+        bump
+    )]
+    pub feed_collateral: Account<'info, FeedCollateral>,
+
+    #[account(mut)]
+    pub token_vault: Account<'info, TokenVault>,
+
+    #[account(
+        mut,
+        constraint = creator_token_account.owner == authority.key()
+    )]
+    pub creator_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        constraint = vault_token_account.key() == token_vault.vault_token_account
+    )]
+    pub vault_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(address = token_vault.token_mint @ ErrorCode::InvalidMint)]
+    pub token_mint: InterfaceAccount<'info, Mint>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+
+    /// Applied when passed
+    #[account(
+        seeds = [b"asset_template", asset_type.template_seed()],
+        bump = asset_template.bump
+    )]
+    pub asset_template: Option<Account<'info, AssetTemplate>>,
+
+    pub authority: Signer<'info>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(asset_type: AssetType)]
+pub struct CreateAssetTemplate<'info> {
+    #[account(
+        init,
+        payer = payer,
+        space = ASSET_TEMPLATE_SPACE,
+        seeds = [b"asset_template", asset_type.template_seed()],
+        bump
+    )]
+    pub asset_template: Account<'info, AssetTemplate>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct AddPublisher<'info> {
+    #[account(mut)]
+    pub global_state: Account<'info, GlobalState>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = PUBLISHER_ACCOUNT_SPACE,
+        seeds = [b"publisher", publisher_authority.key().as_ref()],
+        bump
+    )]
+    pub publisher_account: Account<'info, PublisherAccount>,
+
+    #[account(mut)]
+    pub token_vault: Account<'info, TokenVault>,
+
+    #[account(
+        mut,
+        constraint = publisher_token_account.mint == token_vault.token_mint,
+        constraint = publisher_token_account.owner == publisher_authority.key()
+    )]
+    pub publisher_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        constraint = vault_token_account.key() == token_vault.vault_token_account
+    )]
+    pub vault_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    pub publisher_authority: Signer<'info>,
+    
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    #[account(address = token_vault.token_mint @ ErrorCode::InvalidMint)]
+    pub token_mint: InterfaceAccount<'info, Mint>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdatePrice<'info> {
+    pub global_state: Account<'info, GlobalState>,
+
+    pub product_account: Account<'info, ProductAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"price", product_account.symbol.as_bytes()],
+        bump = price_account.load()?.bump
+    )]
+    pub price_account: AccountLoader<'info, PriceAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"publisher", publisher_authority.key().as_ref()],
+        bump = publisher_account.bump,
+        constraint = publisher_account.authority == publisher_authority.key()
+    )]
+    pub publisher_account: Account<'info, PublisherAccount>,
+
+    #[account(
+        seeds = [b"token_vault"],
+        bump = token_vault.bump
+    )]
+    pub token_vault: Account<'info, TokenVault>,
+
+    /// CHECK: SlotHashes sysvar, required when a sampled feed aggregates
+    #[account(address = anchor_lang::solana_program::sysvar::slot_hashes::ID)]
+    pub slot_hashes: Option<UncheckedAccount<'info>>,
+
+    pub publisher_authority: Signer<'info>,
+
+    /// Required when the publisher has a quote cosigner
+    pub cosigner: Option<Signer<'info>>,
+
+    #[account(
+        mut,
+        seeds = [b"feed_stats", price_account.key().as_ref()],
+        bump = feed_stats.bump
+    )]
+    pub feed_stats: Option<Account<'info, FeedStats>>,
+
+    #[account(
+        mut,
+        seeds = [b"price_mirror", price_account.key().as_ref()],
+        bump = price_mirror.bump
+    )]
+    pub price_mirror: Option<Account<'info, PriceMirror>>,
+
+    /// Required when the feed has submission quotas
+    #[account(
+        mut,
+        seeds = [b"feed_quota", price_account.key().as_ref()],
+        bump = feed_quota.bump
+    )]
+    pub feed_quota: Option<Account<'info, FeedQuota>>,
+
+    /// Appended to when the quote aggregates
+    #[account(
+        mut,
+        seeds = [b"price_history", product_account.key().as_ref()],
+        bump = price_history.load()?.bump,
+        constraint = price_history.load()?.price_account == price_account.key() @ ErrorCode::InvalidPriceAccount
+    )]
+    pub price_history: Option<AccountLoader<'info, PriceHistoryAccount>>,
+
+    /// Required when the feed is permissioned
+    #[account(
+        seeds = [b"feed_permission", price_account.key().as_ref(), publisher_authority.key().as_ref()],
+        bump = feed_permission.bump
+    )]
+    pub feed_permission: Option<Account<'info, FeedPermission>>,
+}
+
+#[derive(Accounts)]
+pub struct CreateFastFeed<'info> {
+    #[account(has_one = authority)]
+    pub price_account: AccountLoader<'info, PriceAccount>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + 32 + 128 + (32 * MAX_FAST_PUBLISHERS) + (96 * MAX_FAST_PUBLISHERS) + 1 + 1 + 1,
+        seeds = [b"fast_price", price_account.key().as_ref()],
+        bump
+    )]
+    pub fast_price_account: Account<'info, FastPriceAccount>,
+
+    pub authority: Signer<'info>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetFastPublishers<'info> {
+    #[account(has_one = authority)]
+    pub price_account: AccountLoader<'info, PriceAccount>,
+
+    #[account(
+        mut,
+        has_one = price_account,
+        seeds = [b"fast_price", price_account.key().as_ref()],
+        bump = fast_price_account.bump
+    )]
+    pub fast_price_account: Account<'info, FastPriceAccount>,
+
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateFastPrice<'info> {
+    pub global_state: Account<'info, GlobalState>,
+
+    pub price_account: AccountLoader<'info, PriceAccount>,
+
+    #[account(
+        mut,
+        has_one = price_account,
+        seeds = [b"fast_price", price_account.key().as_ref()],
+        bump = fast_price_account.bump
+    )]
+    pub fast_price_account: Account<'info, FastPriceAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"publisher", publisher_authority.key().as_ref()],
+        bump = publisher_account.bump
+    )]
+    pub publisher_account: Account<'info, PublisherAccount>,
+
+    pub publisher_authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CreateOptimisticFeed<'info> {
+    #[account(mut, has_one = authority)]
+    pub price_account: AccountLoader<'info, PriceAccount>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + 32 + 32 + 8 + 8 + 8 + 8 + 1 + 32 + 8 + 8 + 8 + 1,
+        seeds = [b"optimistic_feed", price_account.key().as_ref()],
+        bump
+    )]
+    pub optimistic_feed: Account<'info, OptimisticFeed>,
+
+    pub authority: Signer<'info>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CloseOptimisticFeed<'info> {
+    #[account(mut, has_one = authority)]
+    pub price_account: AccountLoader<'info, PriceAccount>,
+
+    #[account(
+        mut,
+        close = authority,
+        has_one = price_account,
+        seeds = [b"optimistic_feed", price_account.key().as_ref()],
+        bump = optimistic_feed.bump
+    )]
+    pub optimistic_feed: Account<'info, OptimisticFeed>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct PostOptimisticPrice<'info> {
+    pub global_state: Account<'info, GlobalState>,
+
+    #[account(mut)]
+    pub price_account: AccountLoader<'info, PriceAccount>,
+
+    #[account(
+        mut,
+        has_one = price_account,
+        seeds = [b"optimistic_feed", price_account.key().as_ref()],
+        bump = optimistic_feed.bump,
+        constraint = optimistic_feed.poster == poster.key() @ ErrorCode::NotOptimisticPoster
+    )]
+    pub optimistic_feed: Account<'info, OptimisticFeed>,
+
+    #[account(
+        mut,
+        seeds = [b"publisher", poster.key().as_ref()],
+        bump = publisher_account.bump
+    )]
+    pub publisher_account: Account<'info, PublisherAccount>,
+
+    pub poster: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ChallengeOptimisticPrice<'info> {
+    pub global_state: Account<'info, GlobalState>,
+
+    #[account(mut)]
+    pub price_account: AccountLoader<'info, PriceAccount>,
+
+    #[account(
+        mut,
+        has_one = price_account,
+        seeds = [b"optimistic_feed", price_account.key().as_ref()],
+        bump = optimistic_feed.bump
+    )]
+    pub optimistic_feed: Account<'info, OptimisticFeed>,
+
+    #[account(
+        mut,
+        seeds = [b"publisher", publisher_authority.key().as_ref()],
+        bump = publisher_account.bump
+    )]
+    pub publisher_account: Account<'info, PublisherAccount>,
+
+    pub publisher_authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ResolveOptimisticDispute<'info> {
+    pub global_state: Account<'info, GlobalState>,
+
+    #[account(mut, has_one = authority)]
+    pub price_account: AccountLoader<'info, PriceAccount>,
+
+    #[account(
+        mut,
+        has_one = price_account,
+        seeds = [b"optimistic_feed", price_account.key().as_ref()],
+        bump = optimistic_feed.bump
+    )]
+    pub optimistic_feed: Account<'info, OptimisticFeed>,
+
+    #[account(
+        mut,
+        seeds = [b"publisher", optimistic_feed.challenger.as_ref()],
+        bump = challenger_account.bump
+    )]
+    pub challenger_account: Account<'info, PublisherAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"token_vault"],
+        bump = token_vault.bump
+    )]
+    pub token_vault: Account<'info, TokenVault>,
+
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CreateInverseFeed<'info> {
+    #[account(mut, has_one = authority)]
+    pub price_account: AccountLoader<'info, PriceAccount>,
+
+    #[account(constraint = product_account.price_account == price_account.key() @ ErrorCode::InvalidPriceAccount)]
+    pub product_account: Account<'info, ProductAccount>,
+
+    #[account(constraint = source_feed.key() != price_account.key() @ ErrorCode::InvalidPriceAccount)]
+    pub source_feed: AccountLoader<'info, PriceAccount>,
+
+    #[account(constraint = source_product.price_account == source_feed.key() @ ErrorCode::InvalidPriceAccount)]
+    pub source_product: Account<'info, ProductAccount>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + 32 + 32 + 1,
+        seeds = [b"inverse_feed", price_account.key().as_ref()],
+        bump
+    )]
+    pub inverse_feed: Account<'info, InverseFeed>,
+
+    pub authority: Signer<'info>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CloseInverseFeed<'info> {
+    #[account(mut, has_one = authority)]
+    pub price_account: AccountLoader<'info, PriceAccount>,
+
+    #[account(
+        mut,
+        close = authority,
+        has_one = price_account,
+        seeds = [b"inverse_feed", price_account.key().as_ref()],
+        bump = inverse_feed.bump
+    )]
+    pub inverse_feed: Account<'info, InverseFeed>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateInverseFeed<'info> {
+    #[account(
+        has_one = price_account,
+        has_one = source_feed,
+        seeds = [b"inverse_feed", price_account.key().as_ref()],
+        bump = inverse_feed.bump
+    )]
+    pub inverse_feed: Account<'info, InverseFeed>,
+
+    #[account(mut)]
+    pub price_account: AccountLoader<'info, PriceAccount>,
+
+    #[account(constraint = product_account.price_account == price_account.key() @ ErrorCode::InvalidPriceAccount)]
+    pub product_account: Account<'info, ProductAccount>,
+
+    pub source_feed: AccountLoader<'info, PriceAccount>,
+
+    /// Passed to read the program clock, which differs from the cluster clock only in
+    /// mock-clock builds, where it is required
+    #[account(
+        seeds = [b"global_state"],
+        bump = global_state.bump
+    )]
+    pub global_state: Option<Account<'info, GlobalState>>,
+}
+
+#[derive(Accounts)]
+pub struct CreateFuturesContract<'info> {
+    #[account(has_one = authority)]
+    pub price_account: AccountLoader<'info, PriceAccount>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + 32 + (4 + MAX_CONTRACT_ID_LEN) + 8 + (4 + MAX_CONTRACT_ID_LEN) + 4 + 8 + 1,
+        seeds = [b"futures_contract", price_account.key().as_ref()],
+        bump
+    )]
+    pub futures_contract: Account<'info, FuturesContract>,
+
+    pub authority: Signer<'info>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+
+    /// Passed to read the program clock, which differs from the cluster clock only in
+    /// mock-clock builds, where it is required
+    #[account(
+        seeds = [b"global_state"],
+        bump = global_state.bump
+    )]
+    pub global_state: Option<Account<'info, GlobalState>>,
+}
+
+#[derive(Accounts)]
+pub struct RollFuturesContract<'info> {
+    #[account(mut, has_one = authority)]
+    pub price_account: AccountLoader<'info, PriceAccount>,
+
+    #[account(
+        mut,
+        has_one = price_account,
+        seeds = [b"futures_contract", price_account.key().as_ref()],
+        bump = futures_contract.bump
+    )]
+    pub futures_contract: Account<'info, FuturesContract>,
+
+    pub authority: Signer<'info>,
+
+    /// Passed to read the program clock, which differs from the cluster clock only in
+    /// mock-clock builds, where it is required
+    #[account(
+        seeds = [b"global_state"],
+        bump = global_state.bump
+    )]
+    pub global_state: Option<Account<'info, GlobalState>>,
+}
+
+#[derive(Accounts)]
+pub struct CreatePriceBatch<'info> {
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + 32 + 32 + 8 + 8 + 8 + 8 + 1,
+        seeds = [b"price_batch", publisher_authority.key().as_ref()],
+        bump
+    )]
+    pub price_batch: Account<'info, PriceBatch>,
+
+    pub publisher_authority: Signer<'info>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SubmitPriceBatch<'info> {
+    pub global_state: Account<'info, GlobalState>,
+
+    #[account(
+        seeds = [b"publisher", publisher_authority.key().as_ref()],
+        bump = publisher_account.bump
+    )]
+    pub publisher_account: Account<'info, PublisherAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"price_batch", publisher_authority.key().as_ref()],
+        bump = price_batch.bump
+    )]
+    pub price_batch: Account<'info, PriceBatch>,
+
+    pub publisher_authority: Signer<'info>,
+
+    /// Required when the publisher has a quote cosigner
+    pub cosigner: Option<Signer<'info>>,
+}
+
+#[derive(Accounts)]
+pub struct ApplyBatchedPrice<'info> {
+    pub global_state: Account<'info, GlobalState>,
+
+    pub product_account: Account<'info, ProductAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"price", product_account.symbol.as_bytes()],
+        bump = price_account.load()?.bump
+    )]
+    pub price_account: AccountLoader<'info, PriceAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"publisher", price_batch.publisher.as_ref()],
+        bump = publisher_account.bump
+    )]
+    pub publisher_account: Account<'info, PublisherAccount>,
+
+    #[account(
+        seeds = [b"price_batch", price_batch.publisher.as_ref()],
+        bump = price_batch.bump
+    )]
+    pub price_batch: Account<'info, PriceBatch>,
+
+    #[account(
+        seeds = [b"token_vault"],
+        bump = token_vault.bump
+    )]
+    pub token_vault: Account<'info, TokenVault>,
+
+    /// CHECK: SlotHashes sysvar, required when a sampled feed aggregates
+    #[account(address = anchor_lang::solana_program::sysvar::slot_hashes::ID)]
+    pub slot_hashes: Option<UncheckedAccount<'info>>,
+
+    /// Required when the feed has submission quotas
+    #[account(
+        mut,
+        seeds = [b"feed_quota", price_account.key().as_ref()],
+        bump = feed_quota.bump
+    )]
+    pub feed_quota: Option<Account<'info, FeedQuota>>,
+
+    /// Appended to when the quote aggregates
+    #[account(
+        mut,
+        seeds = [b"price_history", product_account.key().as_ref()],
+        bump = price_history.load()?.bump,
+        constraint = price_history.load()?.price_account == price_account.key() @ ErrorCode::InvalidPriceAccount
+    )]
+    pub price_history: Option<AccountLoader<'info, PriceHistoryAccount>>,
+
+    /// Required when the feed is permissioned
+    #[account(
+        seeds = [b"feed_permission", price_account.key().as_ref(), price_batch.publisher.as_ref()],
+        bump = feed_permission.bump
+    )]
+    pub feed_permission: Option<Account<'info, FeedPermission>>,
+}
+
+#[derive(Accounts)]
+pub struct SubmitCompactQuotes<'info> {
+    pub global_state: Account<'info, GlobalState>,
+
+    #[account(
+        mut,
+        seeds = [b"publisher", publisher_authority.key().as_ref()],
+        bump = publisher_account.bump,
+        constraint = publisher_account.authority == publisher_authority.key()
+    )]
+    pub publisher_account: Account<'info, PublisherAccount>,
+
+    #[account(
+        seeds = [b"token_vault"],
+        bump = token_vault.bump
+    )]
+    pub token_vault: Account<'info, TokenVault>,
+
+    /// CHECK: SlotHashes sysvar, required when a sampled feed aggregates
+    #[account(address = anchor_lang::solana_program::sysvar::slot_hashes::ID)]
+    pub slot_hashes: Option<UncheckedAccount<'info>>,
+
+    pub publisher_authority: Signer<'info>,
+
+    /// Required when the publisher has a quote cosigner
+    pub cosigner: Option<Signer<'info>>,
+}
+
+#[derive(Accounts)]
+pub struct CreateQuoteCommitment<'info> {
+    pub price_account: AccountLoader<'info, PriceAccount>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + 32 + 32 + 32 + 8 + 1,
+        seeds = [b"quote_commitment", price_account.key().as_ref(), publisher_authority.key().as_ref()],
+        bump
+    )]
+    pub quote_commitment: Account<'info, QuoteCommitment>,
+
+    pub publisher_authority: Signer<'info>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CommitPrice<'info> {
+    pub global_state: Account<'info, GlobalState>,
+
+    pub price_account: AccountLoader<'info, PriceAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"quote_commitment", price_account.key().as_ref(), publisher_authority.key().as_ref()],
+        bump = quote_commitment.bump
+    )]
+    pub quote_commitment: Account<'info, QuoteCommitment>,
+
+    pub publisher_authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct RevealPrice<'info> {
+    pub update: UpdatePrice<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"quote_commitment", update.price_account.key().as_ref(), update.publisher_authority.key().as_ref()],
+        bump = quote_commitment.bump
+    )]
+    pub quote_commitment: Account<'info, QuoteCommitment>,
+}
+
+#[derive(Accounts)]
+pub struct StakeTokens<'info> {
+    pub global_state: Account<'info, GlobalState>,
+
+    #[account(
+        mut,
+        seeds = [b"publisher", publisher_authority.key().as_ref()],
+        bump = publisher_account.bump
+    )]
+    pub publisher_account: Account<'info, PublisherAccount>,
+
+    #[account(mut)]
+    pub token_vault: Account<'info, TokenVault>,
+
+    #[account(
+        mut,
+        constraint = publisher_token_account.owner == publisher_authority.key()
+    )]
+    pub publisher_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        constraint = vault_token_account.key() == token_vault.vault_token_account
+    )]
+    pub vault_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    pub publisher_authority: Signer<'info>,
+
+    #[account(address = token_vault.token_mint @ ErrorCode::InvalidMint)]
+    pub token_mint: InterfaceAccount<'info, Mint>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct UnstakeTokens<'info> {
+    pub global_state: Account<'info, GlobalState>,
+
+    #[account(
+        mut,
+        seeds = [b"publisher", publisher_authority.key().as_ref()],
+        bump = publisher_account.bump
+    )]
+    pub publisher_account: Account<'info, PublisherAccount>,
+
+    pub publisher_authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawUnbonded<'info> {
+    #[account(
+        seeds = [b"global_state"],
+        bump = global_state.bump
+    )]
+    pub global_state: Account<'info, GlobalState>,
+
+    #[account(
+        mut,
+        seeds = [b"publisher", publisher_authority.key().as_ref()],
+        bump = publisher_account.bump
+    )]
+    pub publisher_account: Account<'info, PublisherAccount>,
+
+    /// CHECK: PDA vault authority
+    #[account(
+        seeds = [b"vault_authority", global_state.custody_seed()],
+        bump = global_state.custody_bump
+    )]
+    pub vault_authority: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"token_vault"],
+        bump = token_vault.bump
+    )]
+    pub token_vault: Account<'info, TokenVault>,
+
+    /// CHECK: publisher's token account for the stake mint, checked in prepare_payout_account
+    /// after the optional ATA creation
+    #[account(mut)]
+    pub publisher_token_account: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        constraint = vault_token_account.key() == token_vault.vault_token_account
+    )]
+    pub vault_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    /// Required when the withdrawal leaves stake receipts to burn
+    #[account(
+        mut,
+        address = global_state.receipt_mint @ ErrorCode::InvalidReceiptAccount
+    )]
+    pub receipt_mint: Option<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mut,
+        token::mint = global_state.receipt_mint,
+        token::authority = publisher_authority
+    )]
+    pub publisher_receipt_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub publisher_authority: Signer<'info>,
+
+    #[account(address = token_vault.token_mint @ ErrorCode::InvalidMint)]
+    pub token_mint: InterfaceAccount<'info, Mint>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    /// With the system program, creates the publisher's ATA if it doesn't exist yet
+    pub associated_token_program: Option<Program<'info, AssociatedToken>>,
+    pub system_program: Option<Program<'info, System>>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateFeedConfig<'info> {
+    #[account(mut)]
+    pub price_account: AccountLoader<'info, PriceAccount>,
+
+    /// Required when `admin` is a delegated operator rather than the feed authority
+    #[account(
+        seeds = [b"feed_admin", price_account.key().as_ref(), admin.key().as_ref()],
+        bump = feed_admin.bump
+    )]
+    pub feed_admin: Option<Account<'info, FeedAdmin>>,
+
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetTradingCalendar<'info> {
+    #[account(mut, has_one = authority)]
+    pub product_account: Account<'info, ProductAccount>,
+
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(operator: Pubkey)]
+pub struct GrantFeedAdmin<'info> {
+    #[account(has_one = authority)]
+    pub price_account: AccountLoader<'info, PriceAccount>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + 32 + 32 + 1 + 1,
+        seeds = [b"feed_admin", price_account.key().as_ref(), operator.as_ref()],
+        bump
+    )]
+    pub feed_admin: Account<'info, FeedAdmin>,
+
+    pub authority: Signer<'info>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RevokeFeedAdmin<'info> {
+    #[account(has_one = authority)]
+    pub price_account: AccountLoader<'info, PriceAccount>,
+
+    #[account(
+        mut,
+        close = authority,
+        has_one = price_account,
+        seeds = [b"feed_admin", price_account.key().as_ref(), feed_admin.operator.as_ref()],
+        bump = feed_admin.bump
+    )]
+    pub feed_admin: Account<'info, FeedAdmin>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(publisher: Pubkey)]
+pub struct PermissionPublisher<'info> {
+    #[account(has_one = authority)]
+    pub price_account: AccountLoader<'info, PriceAccount>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + 32 + 32 + 1,
+        seeds = [b"feed_permission", price_account.key().as_ref(), publisher.as_ref()],
+        bump
+    )]
+    pub feed_permission: Account<'info, FeedPermission>,
+
+    pub authority: Signer<'info>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RevokePublisherPermission<'info> {
+    #[account(has_one = authority)]
+    pub price_account: AccountLoader<'info, PriceAccount>,
+
+    #[account(
+        mut,
+        close = authority,
+        has_one = price_account,
+        seeds = [b"feed_permission", price_account.key().as_ref(), feed_permission.publisher.as_ref()],
+        bump = feed_permission.bump
+    )]
+    pub feed_permission: Account<'info, FeedPermission>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct AnnounceRetirement<'info> {
+    pub global_state: Account<'info, GlobalState>,
+
+    #[account(has_one = authority)]
+    pub price_account: AccountLoader<'info, PriceAccount>,
+
+    #[account(
+        mut,
+        has_one = price_account,
+        seeds = [b"feed_collateral", price_account.key().as_ref()],
+        bump = feed_collateral.bump
+    )]
+    pub feed_collateral: Account<'info, FeedCollateral>,
+
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct RetireFeed<'info> {
+    pub global_state: Account<'info, GlobalState>,
+
+    #[account(mut, has_one = authority)]
+    pub price_account: AccountLoader<'info, PriceAccount>,
+
+    #[account(
+        mut,
+        close = creator,
+        has_one = price_account,
+        has_one = creator,
+        seeds = [b"feed_collateral", price_account.key().as_ref()],
+        bump = feed_collateral.bump
+    )]
+    pub feed_collateral: Account<'info, FeedCollateral>,
+
+    /// CHECK: receives the collateral account's rent; bound by has_one on feed_collateral
+    #[account(mut)]
+    pub creator: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        constraint = creator_token_account.owner == feed_collateral.creator,
+        constraint = creator_token_account.mint == token_vault.token_mint
+    )]
+    pub creator_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub token_vault: Account<'info, TokenVault>,
+
+    #[account(
+        mut,
+        constraint = vault_token_account.key() == token_vault.vault_token_account
+    )]
+    pub vault_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    /// CHECK: PDA vault authority
+    #[account(
+        seeds = [b"vault_authority", global_state.custody_seed()],
+        bump = global_state.custody_bump
+    )]
+    pub vault_authority: UncheckedAccount<'info>,
+
+    #[account(address = token_vault.token_mint @ ErrorCode::InvalidMint)]
+    pub token_mint: InterfaceAccount<'info, Mint>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CreateFeedHaltVote<'info> {
+    pub price_account: AccountLoader<'info, PriceAccount>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + 32 + 8 + 16 + 8 + 1,
+        seeds = [b"feed_halt", price_account.key().as_ref()],
+        bump
+    )]
+    pub halt_vote: Account<'info, FeedHaltVote>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct HaltFeed<'info> {
+    #[account(mut)]
+    pub price_account: AccountLoader<'info, PriceAccount>,
+
+    #[account(
+        mut,
+        has_one = price_account,
+        seeds = [b"feed_halt", price_account.key().as_ref()],
+        bump = halt_vote.bump
+    )]
+    pub halt_vote: Account<'info, FeedHaltVote>,
+
+    pub publisher_authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct FundRewards<'info> {
+    #[account(
+        mut,
+        seeds = [b"token_vault"],
+        bump = token_vault.bump
+    )]
+    pub token_vault: Account<'info, TokenVault>,
+
+    #[account(
+        mut,
+        constraint = funder_token_account.mint == token_vault.payout_mint(),
+        constraint = funder_token_account.owner == funder.key()
+    )]
+    pub funder_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        constraint = reward_vault_token_account.key() == token_vault.payout_vault() @ ErrorCode::InvalidVaultAccount
+    )]
+    pub reward_vault_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    pub funder: Signer<'info>,
+
+    #[account(address = token_vault.payout_mint() @ ErrorCode::InvalidMint)]
+    pub reward_mint: InterfaceAccount<'info, Mint>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimRewards<'info> {
+    pub global_state: Account<'info, GlobalState>,
+
+    #[account(
+        mut,
+        seeds = [b"publisher", publisher_authority.key().as_ref()],
+        bump = publisher_account.bump
+    )]
+    pub publisher_account: Account<'info, PublisherAccount>,
+
+    /// CHECK: PDA vault authority
+    #[account(
+        seeds = [b"vault_authority", global_state.custody_seed()],
+        bump = global_state.custody_bump
+    )]
+    pub vault_authority: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"token_vault"],
+        bump = token_vault.bump
+    )]
+    pub token_vault: Account<'info, TokenVault>,
+
+    /// CHECK: publisher's token account for the reward mint, checked in prepare_payout_account
+    /// after the optional ATA creation
+    #[account(mut)]
+    pub publisher_token_account: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        constraint = reward_vault_token_account.key() == token_vault.payout_vault() @ ErrorCode::InvalidVaultAccount
+    )]
+    pub reward_vault_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub publisher_authority: Signer<'info>,
+
+    #[account(address = token_vault.payout_mint() @ ErrorCode::InvalidMint)]
+    pub reward_mint: InterfaceAccount<'info, Mint>,
+
+    /// Token program of the reward mint
+    pub token_program: Interface<'info, TokenInterface>,
+    /// With the system program, creates the publisher's ATA if it doesn't exist yet
+    pub associated_token_program: Option<Program<'info, AssociatedToken>>,
+    pub system_program: Option<Program<'info, System>>,
+}
+
+#[derive(Accounts)]
+pub struct CreateRewardVault<'info> {
+    #[account(
+        mut,
+        seeds = [b"token_vault"],
+        bump = token_vault.bump
+    )]
+    pub token_vault: Account<'info, TokenVault>,
+
+    /// CHECK: PDA vault authority
+    #[account(address = token_vault.vault_authority)]
+    pub vault_authority: UncheckedAccount<'info>,
+
+    #[account(
+        address = token_vault.reward_mint @ ErrorCode::InvalidMint,
+        owner = token_program.key()
+    )]
+    pub reward_mint: InterfaceAccount<'info, Mint>,
+
+    /// CHECK: created here as the vault authority's ATA for the reward mint
+    #[account(
+        mut,
+        address = get_associated_token_address_with_program_id(
+            &vault_authority.key(),
+            &reward_mint.key(),
+            &token_program.key(),
+        ) @ ErrorCode::InvalidVaultAccount
+    )]
+    pub reward_vault_token_account: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct BindIdentity<'info> {
+    #[account(
+        mut,
+        seeds = [b"publisher", publisher_authority.key().as_ref()],
+        bump = publisher_account.bump
+    )]
+    pub publisher_account: Account<'info, PublisherAccount>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + 32 + 1 + (4 + MAX_IDENTITY_LEN) + 32 + 8 + 1,
+        seeds = [b"publisher_identity", publisher_account.key().as_ref()],
+        bump
+    )]
+    pub publisher_identity: Account<'info, PublisherIdentity>,
+
+    pub publisher_authority: Signer<'info>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+
+    /// Passed to read the program clock, which differs from the cluster clock only in
+    /// mock-clock builds, where it is required
+    #[account(
+        seeds = [b"global_state"],
+        bump = global_state.bump
+    )]
+    pub global_state: Option<Account<'info, GlobalState>>,
+}
+
+#[derive(Accounts)]
+pub struct UnbindIdentity<'info> {
+    #[account(
+        mut,
+        seeds = [b"publisher", publisher_authority.key().as_ref()],
+        bump = publisher_account.bump
+    )]
+    pub publisher_account: Account<'info, PublisherAccount>,
+
+    #[account(
+        mut,
+        close = publisher_authority,
+        seeds = [b"publisher_identity", publisher_account.key().as_ref()],
+        bump = publisher_identity.bump
+    )]
+    pub publisher_identity: Account<'info, PublisherIdentity>,
+
+    #[account(mut)]
+    pub publisher_authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetQuoteCosigner<'info> {
+    #[account(
+        mut,
+        seeds = [b"publisher", publisher_authority.key().as_ref()],
+        bump = publisher_account.bump
+    )]
+    pub publisher_account: Account<'info, PublisherAccount>,
+
+    pub publisher_authority: Signer<'info>,
+
+    /// Required when a cosigner is already registered
+    pub current_cosigner: Option<Signer<'info>>,
+}
+
+#[derive(Accounts)]
+pub struct SetInsuranceShare<'info> {
+    #[account(
+        mut,
+        seeds = [b"publisher", publisher_authority.key().as_ref()],
+        bump = publisher_account.bump
+    )]
+    pub publisher_account: Account<'info, PublisherAccount>,
+
+    pub publisher_authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct AdvanceEpoch<'info> {
+    #[account(
+        mut,
+        seeds = [b"global_state"],
+        bump = global_state.bump
+    )]
+    pub global_state: Account<'info, GlobalState>,
+
+    #[account(
+        mut,
+        seeds = [b"token_vault"],
+        bump = token_vault.bump
+    )]
+    pub token_vault: Account<'info, TokenVault>,
+}
+
+#[derive(Accounts)]
+pub struct AggregatePrice<'info> {
+    pub product_account: Account<'info, ProductAccount>,
+    
+    #[account(mut)]
+    pub price_account: AccountLoader<'info, PriceAccount>,
+
+    /// CHECK: SlotHashes sysvar, required when a sampled feed aggregates
+    #[account(address = anchor_lang::solana_program::sysvar::slot_hashes::ID)]
+    pub slot_hashes: Option<UncheckedAccount<'info>>,
+
+    /// Reimburses `keeper` when passed together
+    #[account(
+        mut,
+        seeds = [b"crank_budget", price_account.key().as_ref()],
+        bump = crank_budget.bump
+    )]
+    pub crank_budget: Option<Account<'info, CrankBudget>>,
+
+    #[account(mut)]
+    pub keeper: Option<Signer<'info>>,
+
+    #[account(
+        mut,
+        seeds = [b"feed_stats", price_account.key().as_ref()],
+        bump = feed_stats.bump
+    )]
+    pub feed_stats: Option<Account<'info, FeedStats>>,
+
+    #[account(
+        mut,
+        seeds = [b"price_mirror", price_account.key().as_ref()],
+        bump = price_mirror.bump
+    )]
+    pub price_mirror: Option<Account<'info, PriceMirror>>,
+
+    #[account(
+        mut,
+        seeds = [b"price_history", product_account.key().as_ref()],
+        bump = price_history.load()?.bump,
+        constraint = price_history.load()?.price_account == price_account.key() @ ErrorCode::InvalidPriceAccount
+    )]
+    pub price_history: Option<AccountLoader<'info, PriceHistoryAccount>>,
+
+    /// Passed to read the program clock, which differs from the cluster clock only in
+    /// mock-clock builds, where it is required
+    #[account(
+        seeds = [b"global_state"],
+        bump = global_state.bump
+    )]
+    pub global_state: Option<Account<'info, GlobalState>>,
+}
+
+#[derive(Accounts)]
+pub struct PruneFeed<'info> {
+    #[account(mut)]
+    pub price_account: AccountLoader<'info, PriceAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"crank_budget", price_account.key().as_ref()],
+        bump = crank_budget.bump
+    )]
+    pub crank_budget: Option<Account<'info, CrankBudget>>,
+
+    #[account(mut)]
+    pub keeper: Option<Signer<'info>>,
+
+    #[account(
+        seeds = [b"global_state"],
+        bump = global_state.bump
+    )]
+    pub global_state: Account<'info, GlobalState>,
+}
+
+#[derive(Accounts)]
+pub struct DisplacePublisher<'info> {
+    #[account(
+        seeds = [b"global_state"],
+        bump = global_state.bump
+    )]
+    pub global_state: Account<'info, GlobalState>,
+
+    #[account(mut)]
+    pub price_account: AccountLoader<'info, PriceAccount>,
+
+    #[account(
+        seeds = [b"publisher", publisher_authority.key().as_ref()],
+        bump = publisher_account.bump,
+        constraint = publisher_account.authority == publisher_authority.key()
+    )]
+    pub publisher_account: Account<'info, PublisherAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"publisher", displaced_publisher.authority.as_ref()],
+        bump = displaced_publisher.bump
+    )]
+    pub displaced_publisher: Account<'info, PublisherAccount>,
+
+    pub publisher_authority: Signer<'info>,
+
+    /// Required when the feed is permissioned
+    #[account(
+        seeds = [b"feed_permission", price_account.key().as_ref(), publisher_authority.key().as_ref()],
+        bump = feed_permission.bump
+    )]
+    pub feed_permission: Option<Account<'info, FeedPermission>>,
+}
+
+#[derive(Accounts)]
+pub struct DeregisterInactivePublisher<'info> {
+    #[account(
+        mut,
+        seeds = [b"global_state"],
+        bump = global_state.bump
+    )]
+    pub global_state: Account<'info, GlobalState>,
+
+    #[account(
+        mut,
+        seeds = [b"publisher", publisher_authority.key().as_ref()],
+        bump = publisher_account.bump
+    )]
+    pub publisher_account: Account<'info, PublisherAccount>,
+
+    /// CHECK: receives the publisher account's rent; bound by the publisher PDA seeds
+    #[account(mut)]
+    pub publisher_authority: UncheckedAccount<'info>,
+
+    /// CHECK: PDA vault authority
+    #[account(
+        seeds = [b"vault_authority", global_state.custody_seed()],
+        bump = global_state.custody_bump
+    )]
+    pub vault_authority: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"token_vault"],
+        bump = token_vault.bump
+    )]
+    pub token_vault: Account<'info, TokenVault>,
+
+    #[account(
+        mut,
+        token::mint = token_mint,
+        token::authority = publisher_authority,
+        token::token_program = token_program
+    )]
+    pub publisher_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        constraint = vault_token_account.key() == token_vault.vault_token_account
+    )]
+    pub vault_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(address = token_vault.token_mint @ ErrorCode::InvalidMint)]
+    pub token_mint: InterfaceAccount<'info, Mint>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+
+    pub keeper: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CreateCrankBudget<'info> {
+    #[account(has_one = authority)]
+    pub price_account: AccountLoader<'info, PriceAccount>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + 32 + 32 + 8 + 8 + 8 + 8 + 1,
+        seeds = [b"crank_budget", price_account.key().as_ref()],
+        bump
+    )]
+    pub crank_budget: Account<'info, CrankBudget>,
+
+    pub authority: Signer<'info>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct FundCrankBudget<'info> {
+    #[account(
+        mut,
+        seeds = [b"crank_budget", crank_budget.price_account.as_ref()],
+        bump = crank_budget.bump
+    )]
+    pub crank_budget: Account<'info, CrankBudget>,
+
+    #[account(mut)]
+    pub funder: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CloseCrankBudget<'info> {
+    #[account(
+        mut,
+        close = owner,
+        has_one = owner,
+        seeds = [b"crank_budget", crank_budget.price_account.as_ref()],
+        bump = crank_budget.bump
+    )]
+    pub crank_budget: Account<'info, CrankBudget>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SimulateAggregation<'info> {
+    pub global_state: Account<'info, GlobalState>,
+
+    pub price_account: AccountLoader<'info, PriceAccount>,
+
+    /// Required when simulating a quote
+    pub publisher_account: Option<Account<'info, PublisherAccount>>,
+}
+
+#[derive(Accounts)]
+pub struct CreateFeedStats<'info> {
+    pub price_account: AccountLoader<'info, PriceAccount>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + 32 + 8 + 8 + 8 + 8 + 8 + 2 + 1,
+        seeds = [b"feed_stats", price_account.key().as_ref()],
+        bump
+    )]
+    pub feed_stats: Account<'info, FeedStats>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateFeedStats<'info> {
+    pub price_account: AccountLoader<'info, PriceAccount>,
+
+    #[account(
+        mut,
+        has_one = price_account,
+        seeds = [b"feed_stats", price_account.key().as_ref()],
+        bump = feed_stats.bump
+    )]
+    pub feed_stats: Account<'info, FeedStats>,
+}
+
+#[derive(Accounts)]
+pub struct CreatePriceMirror<'info> {
+    pub price_account: AccountLoader<'info, PriceAccount>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + 32 + PACKED_PRICE_LEN + 1,
+        seeds = [b"price_mirror", price_account.key().as_ref()],
+        bump
+    )]
+    pub price_mirror: Account<'info, PriceMirror>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CreatePriceHistory<'info> {
+    #[account(has_one = price_account)]
+    pub product_account: Account<'info, ProductAccount>,
+
+    pub price_account: AccountLoader<'info, PriceAccount>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = PRICE_HISTORY_SPACE,
+        seeds = [b"price_history", product_account.key().as_ref()],
+        bump
+    )]
+    pub price_history: AccountLoader<'info, PriceHistoryAccount>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SyncPriceMirror<'info> {
+    pub price_account: AccountLoader<'info, PriceAccount>,
+
+    #[account(
+        mut,
+        has_one = price_account,
+        seeds = [b"price_mirror", price_account.key().as_ref()],
+        bump = price_mirror.bump
+    )]
+    pub price_mirror: Account<'info, PriceMirror>,
+}
+
+#[derive(Accounts)]
+pub struct CreateFeedReadStats<'info> {
+    #[account(
+        seeds = [b"global_state"],
+        bump = global_state.bump
+    )]
+    pub global_state: Account<'info, GlobalState>,
+
+    pub price_account: AccountLoader<'info, PriceAccount>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + 32 + 8 + 8 + 8 + 8 + 1,
+        seeds = [b"feed_reads", price_account.key().as_ref()],
+        bump
+    )]
+    pub feed_read_stats: Account<'info, FeedReadStats>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ReadPrice<'info> {
+    #[account(
+        seeds = [b"global_state"],
+        bump = global_state.bump
+    )]
+    pub global_state: Account<'info, GlobalState>,
+
+    pub price_account: AccountLoader<'info, PriceAccount>,
+
+    #[account(has_one = price_account)]
+    pub product_account: Account<'info, ProductAccount>,
+
+    #[account(
+        mut,
+        has_one = price_account,
+        seeds = [b"feed_reads", price_account.key().as_ref()],
+        bump = feed_read_stats.bump
+    )]
+    pub feed_read_stats: Account<'info, FeedReadStats>,
+}
+
+#[derive(Accounts)]
+pub struct GetTwap<'info> {
+    #[account(
+        seeds = [b"global_state"],
+        bump = global_state.bump
+    )]
+    pub global_state: Account<'info, GlobalState>,
+
+    pub price_account: AccountLoader<'info, PriceAccount>,
+
+    #[account(
+        constraint = price_history.load()?.price_account == price_account.key() @ ErrorCode::InvalidPriceAccount
+    )]
+    pub price_history: AccountLoader<'info, PriceHistoryAccount>,
+}
+
+#[derive(Accounts)]
+pub struct AttestFreshness<'info> {
+    #[account(
+        seeds = [b"global_state"],
+        bump = global_state.bump
+    )]
+    pub global_state: Account<'info, GlobalState>,
+
+    pub price_account: AccountLoader<'info, PriceAccount>,
+
+    #[account(has_one = price_account)]
+    pub product_account: Account<'info, ProductAccount>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + 32 + 8 + 8 + 1 + 8
+    )]
+    pub attestation: Account<'info, FreshnessAttestation>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ArchiveRound<'info> {
+    pub price_account: AccountLoader<'info, PriceAccount>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = RoundArchive::space(price_account.load()?.publisher_count as usize),
+        seeds = [b"round", price_account.key().as_ref(), price_account.load()?.aggregate.slot.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub round_archive: Account<'info, RoundArchive>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct AuditFeedScaling<'info> {
+    pub price_account: AccountLoader<'info, PriceAccount>,
+}
+
+#[derive(Accounts)]
+pub struct CreateProposal<'info> {
+    pub global_state: Account<'info, GlobalState>,
+
+    #[account(mut)]
+    pub governance_state: Account<'info, GovernanceState>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = PROPOSAL_SPACE,
+        seeds = [b"proposal", governance_state.proposal_count.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub proposal: Account<'info, Proposal>,
+
+    #[account(
+        constraint = proposer_token_account.owner == proposer.key()
+    )]
+    pub proposer_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    pub proposer: Signer<'info>,
+
+    /// Collateral account of the feed targeted by a DrawListingCollateral proposal
+    #[account(mut)]
+    pub feed_collateral: Option<Account<'info, FeedCollateral>>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct VoteProposal<'info> {
+    #[account(mut)]
+    pub proposal: Account<'info, Proposal>,
+
+    #[account(
+        constraint = voter_token_account.owner == voter.key()
+    )]
+    pub voter_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    pub voter: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateProposalLinks<'info> {
+    #[account(mut, has_one = proposer)]
+    pub proposal: Account<'info, Proposal>,
+
+    pub proposer: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct LockConviction<'info> {
+    pub global_state: Account<'info, GlobalState>,
+
+    #[account(mut)]
+    pub proposal: Account<'info, Proposal>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + 32 + 32 + 8 + 8 + 1,
+        seeds = [b"conviction_lock", proposal.key().as_ref(), voter.key().as_ref()],
+        bump
+    )]
+    pub conviction_lock: Account<'info, ConvictionLock>,
+
+    #[account(
+        mut,
+        seeds = [b"token_vault"],
+        bump = token_vault.bump
+    )]
+    pub token_vault: Account<'info, TokenVault>,
+
+    #[account(
+        mut,
+        constraint = voter_token_account.mint == token_vault.token_mint,
+        constraint = voter_token_account.owner == voter.key()
+    )]
+    pub voter_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        constraint = vault_token_account.key() == token_vault.vault_token_account
+    )]
+    pub vault_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    pub voter: Signer<'info>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(address = token_vault.token_mint @ ErrorCode::InvalidMint)]
+    pub token_mint: InterfaceAccount<'info, Mint>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UnlockConviction<'info> {
+    pub global_state: Account<'info, GlobalState>,
+
+    #[account(mut)]
+    pub proposal: Account<'info, Proposal>,
+
+    #[account(
+        mut,
+        close = voter,
+        seeds = [b"conviction_lock", proposal.key().as_ref(), voter.key().as_ref()],
+        bump = conviction_lock.bump,
+        has_one = voter
+    )]
+    pub conviction_lock: Account<'info, ConvictionLock>,
+
+    /// CHECK: PDA vault authority
+    #[account(
+        seeds = [b"vault_authority", global_state.custody_seed()],
+        bump = global_state.custody_bump
+    )]
+    pub vault_authority: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"token_vault"],
+        bump = token_vault.bump
+    )]
+    pub token_vault: Account<'info, TokenVault>,
+
+    #[account(
+        mut,
+        constraint = voter_token_account.owner == voter.key()
+    )]
+    pub voter_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        constraint = vault_token_account.key() == token_vault.vault_token_account
+    )]
+    pub vault_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub voter: Signer<'info>,
+
+    #[account(address = token_vault.token_mint @ ErrorCode::InvalidMint)]
+    pub token_mint: InterfaceAccount<'info, Mint>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct ExecuteProposal<'info> {
+    #[account(mut)]
+    pub proposal: Account<'info, Proposal>,
+    pub governance_state: Account<'info, GovernanceState>,
+
+    /// Targets of parameter proposals, which get the pending value when the timelock starts.
+    /// The global state also supplies the program clock, so mock-clock builds require it.
+    #[account(
+        mut,
+        seeds = [b"global_state"],
+        bump = global_state.bump
+    )]
+    pub global_state: Option<Account<'info, GlobalState>>,
+
+    #[account(
+        mut,
+        seeds = [b"token_vault"],
+        bump = token_vault.bump
+    )]
+    pub token_vault: Option<Account<'info, TokenVault>>,
+
+    #[account(mut)]
+    pub price_account: Option<AccountLoader<'info, PriceAccount>>,
+}
+
+#[derive(Accounts)]
+pub struct ExpireProposal<'info> {
+    pub proposal: Account<'info, Proposal>,
+
+    /// The target holding the proposal's preview, as passed to `execute_proposal`. The
+    /// global state also supplies the program clock, so mock-clock builds require it.
+    #[account(
+        mut,
+        seeds = [b"global_state"],
+        bump = global_state.bump
+    )]
+    pub global_state: Option<Account<'info, GlobalState>>,
+
+    #[account(
+        mut,
+        seeds = [b"token_vault"],
+        bump = token_vault.bump
+    )]
+    pub token_vault: Option<Account<'info, TokenVault>>,
+
+    #[account(mut)]
+    pub price_account: Option<AccountLoader<'info, PriceAccount>>,
+}
+
+#[derive(Accounts)]
+pub struct ExecuteGovernanceAction<'info> {
+    #[account(mut)]
+    pub global_state: Account<'info, GlobalState>,
+
+    #[account(mut)]
+    pub proposal: Account<'info, Proposal>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + 32 + 8 + (4 + MAX_RECEIPT_SNAPSHOT_LEN) * 2 + 8 + 8 + 32 + 1,
+        seeds = [b"execution_receipt", proposal.key().as_ref()],
+        bump
+    )]
+    pub execution_receipt: Account<'info, ExecutionReceipt>,
+
+    #[account(mut)]
+    pub governance_state: Account<'info, GovernanceState>,
+
+    #[account(mut)]
+    pub token_vault: Account<'info, TokenVault>,
+
+    #[account(mut)]
+    pub price_account: Option<AccountLoader<'info, PriceAccount>>,
+
+    #[account(mut)]
+    pub publisher_account: Option<Account<'info, PublisherAccount>>,
+
+    /// Identity an AttestIdentity proposal vets
+    pub publisher_identity: Option<Account<'info, PublisherIdentity>>,
+
+    #[account(mut)]
+    pub feed_sla: Option<Account<'info, FeedSla>>,
+
+    #[account(mut)]
+    pub feed_quota: Option<Account<'info, FeedQuota>>,
+
+    #[account(mut)]
+    pub round_archive: Option<Account<'info, RoundArchive>>,
+
+    #[account(mut)]
+    pub feed_collateral: Option<Account<'info, FeedCollateral>>,
+
+    #[account(mut)]
+    pub asset_template: Option<Account<'info, AssetTemplate>>,
+
+    /// CHECK: vault custody authority, required for Burn and Compensation slashes and RotateVault
+    #[account(
+        seeds = [b"vault_authority", global_state.custody_seed()],
+        bump = global_state.custody_bump
+    )]
+    pub vault_authority: Option<UncheckedAccount<'info>>,
+
+    #[account(
+        mut,
+        constraint = vault_token_account.key() == token_vault.vault_token_account
+    )]
+    pub vault_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        constraint = token_mint.key() == token_vault.token_mint
+    )]
+    pub token_mint: Option<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mut,
+        constraint = compensation_account.mint == token_vault.token_mint
+    )]
+    pub compensation_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    pub token_program: Option<Interface<'info, TokenInterface>>,
+
+    /// CHECK: matched against the next custody authority PDA, for RotateVault proposals
+    pub new_vault_authority: Option<UncheckedAccount<'info>>,
+
+    #[account(mut)]
+    pub new_vault_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Mint a MigrateTokenMint proposal moves the vault to
+    pub new_token_mint: Option<InterfaceAccount<'info, Mint>>,
+
+    /// Old-mint account of the migration counterparty, matched against the proposal
+    #[account(
+        mut,
+        constraint = migration_counterparty.mint == token_vault.token_mint @ ErrorCode::InvalidMint
+    )]
+    pub migration_counterparty: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Kept on the staking mint by MigrateTokenMint when passed
+    #[account(
+        mut,
+        seeds = [b"cluster_config"],
+        bump = cluster_config.bump
+    )]
+    pub cluster_config: Option<Account<'info, ClusterConfig>>,
+
+    /// CHECK: this program's ProgramData, for UpgradeProgram and SetUpgradeAuthority proposals; the loader checks it
+    #[account(mut)]
+    pub program_data: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: this program's executable account
+    #[account(mut, address = crate::ID)]
+    pub upgrade_program: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: buffer holding the new code, matched against the proposal
+    #[account(mut)]
+    pub upgrade_buffer: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: receives the buffer's lamports
+    #[account(mut)]
+    pub spill: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: matched against the proposal's new authority
+    pub new_upgrade_authority: Option<UncheckedAccount<'info>>,
+
+    pub rent: Option<Sysvar<'info, Rent>>,
+    pub clock: Option<Sysvar<'info, Clock>>,
+
+    /// CHECK: BPF upgradeable loader
+    #[account(address = bpf_loader_upgradeable::ID)]
+    pub bpf_loader: Option<UncheckedAccount<'info>>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct TransferUpgradeAuthorityToGovernance<'info> {
+    #[account(
+        seeds = [b"governance"],
+        bump = governance_state.bump
+    )]
+    pub governance_state: Account<'info, GovernanceState>,
 
-        ctx.accounts.token_vault.total_staked += initial_stake;
-        ctx.accounts.global_state.total_publishers += 1;
+    #[account(
+        mut,
+        seeds = [crate::ID.as_ref()],
+        bump,
+        seeds::program = bpf_loader_upgradeable::ID,
+        constraint = program_data.upgrade_authority_address == Some(authority.key()) @ ErrorCode::Unauthorized
+    )]
+    pub program_data: Account<'info, ProgramData>,
 
-        emit!(PublisherAdded {
-            publisher: ctx.accounts.publisher_account.key(),
-            authority: ctx.accounts.publisher_authority.key(),
-            stake_amount: initial_stake,
-            name,
-        });
+    pub authority: Signer<'info>,
 
-        Ok(())
-    }
+    /// CHECK: BPF upgradeable loader
+    #[account(address = bpf_loader_upgradeable::ID)]
+    pub bpf_loader: UncheckedAccount<'info>,
+}
 
-    // ========================================================================
-    // Publisher Instructions
-    // ========================================================================
+#[derive(Accounts)]
+pub struct ProposeOptimisticChange<'info> {
+    pub global_state: Account<'info, GlobalState>,
 
-    pub fn update_price(
-        ctx: Context<UpdatePrice>,
-        price: i64,
-        confidence: u64,
-    ) -> Result<()> {
-        require!(!ctx.accounts.global_state.paused, ErrorCode::SystemPaused);
-        require!(price > 0, ErrorCode::InvalidPrice);
-        
-        let clock = Clock::get()?;
-        let timestamp = clock.unix_timestamp;
-        let slot = clock.slot;
+    #[account(
+        mut,
+        seeds = [b"governance"],
+        bump = governance_state.bump
+    )]
+    pub governance_state: Account<'info, GovernanceState>,
 
-        require!(timestamp > 0, ErrorCode::InvalidTimestamp);
+    #[account(
+        seeds = [b"token_vault"],
+        bump = token_vault.bump
+    )]
+    pub token_vault: Account<'info, TokenVault>,
 
-        let price_account = &mut ctx.accounts.price_account;
-        let publisher = &ctx.accounts.publisher_account;
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + 32 + 42 + 8 + 8 + 1 + 32 + 8 + 8 + 1,
+        seeds = [b"optimistic_change", governance_state.optimistic_change_count.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub optimistic_change: Account<'info, OptimisticChange>,
 
-        let publisher_price = PublisherPrice {
-            publisher: publisher.authority,
-            price,
-            confidence,
-            timestamp,
-            slot,
-            stake: publisher.staked_amount,
-            active: true,
-        };
+    #[account(
+        constraint = proposer_token_account.owner == proposer.key(),
+        constraint = proposer_token_account.mint == governance_state.governance_token
+    )]
+    pub proposer_token_account: InterfaceAccount<'info, TokenAccount>,
 
-        // Find existing slot or add new one
-        let mut found = false;
-        for i in 0..MAX_PUBLISHERS {
-            if price_account.publishers[i].active && 
-               price_account.publishers[i].publisher == publisher.authority {
-                price_account.publishers[i] = publisher_price;
-                found = true;
-                break;
-            }
-        }
+    /// Required for MinPublishers changes, to bound the new minimum by the feed's publishers
+    pub price_account: Option<AccountLoader<'info, PriceAccount>>,
 
-        if !found {
-            // Find empty slot
-            let mut added = false;
-            for i in 0..MAX_PUBLISHERS {
-                if !price_account.publishers[i].active {
-                    price_account.publishers[i] = publisher_price;
-                    price_account.publisher_count += 1;
-                    added = true;
-                    break;
-                }
-            }
-            require!(added, ErrorCode::PublishersArrayFull);
-        }
+    pub proposer: Signer<'info>,
 
-        price_account.last_update_slot = slot;
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
 
-        // Trigger aggregation if enough publishers
-        if price_account.publisher_count >= price_account.min_publishers {
-            aggregate_prices_internal(price_account, &ctx.accounts.product_account.symbol)?;
-        }
+#[derive(Accounts)]
+pub struct VetoOptimisticChange<'info> {
+    #[account(
+        seeds = [b"governance"],
+        bump = governance_state.bump
+    )]
+    pub governance_state: Account<'info, GovernanceState>,
 
-        Ok(())
-    }
+    #[account(mut)]
+    pub optimistic_change: Account<'info, OptimisticChange>,
 
-    pub fn stake_tokens(
-        ctx: Context<StakeTokens>,
-        amount: u64,
-    ) -> Result<()> {
-        require!(!ctx.accounts.global_state.paused, ErrorCode::SystemPaused);
-        require!(amount > 0, ErrorCode::InsufficientStake);
+    #[account(
+        mut,
+        seeds = [b"token_vault"],
+        bump = token_vault.bump
+    )]
+    pub token_vault: Account<'info, TokenVault>,
 
-        let cpi_accounts = Transfer {
-            from: ctx.accounts.publisher_token_account.to_account_info(),
-            to: ctx.accounts.vault_token_account.to_account_info(),
-            authority: ctx.accounts.publisher_authority.to_account_info(),
-        };
-        let cpi_program = ctx.accounts.token_program.to_account_info();
-        let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts);
-        token::transfer(cpi_ctx, amount)?;
+    #[account(
+        mut,
+        constraint = vetoer_token_account.mint == token_vault.token_mint,
+        constraint = vetoer_token_account.owner == vetoer.key()
+    )]
+    pub vetoer_token_account: InterfaceAccount<'info, TokenAccount>,
 
-        ctx.accounts.publisher_account.staked_amount += amount;
-        ctx.accounts.token_vault.total_staked += amount;
+    #[account(
+        mut,
+        constraint = vault_token_account.key() == token_vault.vault_token_account
+    )]
+    pub vault_token_account: InterfaceAccount<'info, TokenAccount>,
 
-        Ok(())
-    }
+    pub vetoer: Signer<'info>,
 
-    pub fn unstake_tokens(
-        ctx: Context<UnstakeTokens>,
-        amount: u64,
-    ) -> Result<()> {
-        require!(!ctx.accounts.global_state.paused, ErrorCode::SystemPaused);
-        
-        let publisher = &mut ctx.accounts.publisher_account;
-        let remaining = publisher.staked_amount.checked_sub(amount)
-            .ok_or(ErrorCode::InsufficientStake)?;
-        
-        require!(remaining >= MIN_STAKE_AMOUNT, ErrorCode::InsufficientStake);
+    #[account(address = token_vault.token_mint @ ErrorCode::InvalidMint)]
+    pub token_mint: InterfaceAccount<'info, Mint>,
 
-        publisher.unbonding_amount = amount;
-        publisher.unbonding_start = Clock::get()?.unix_timestamp;
-        publisher.staked_amount = remaining;
+    pub token_program: Interface<'info, TokenInterface>,
+}
 
-        Ok(())
-    }
+#[derive(Accounts)]
+pub struct ReclaimVetoStake<'info> {
+    pub global_state: Account<'info, GlobalState>,
 
-    pub fn withdraw_unbonded(
-        ctx: Context<WithdrawUnbonded>,
-    ) -> Result<()> {
-        let publisher = &mut ctx.accounts.publisher_account;
-        let clock = Clock::get()?;
-        
-        require!(
-            clock.unix_timestamp - publisher.unbonding_start >= UNBONDING_PERIOD,
-            ErrorCode::UnbondingPeriodActive
-        );
+    #[account(
+        mut,
+        has_one = vetoer
+    )]
+    pub optimistic_change: Account<'info, OptimisticChange>,
 
-        let amount = publisher.unbonding_amount;
-        require!(amount > 0, ErrorCode::InsufficientStake);
+    /// CHECK: PDA vault authority
+    #[account(
+        seeds = [b"vault_authority", global_state.custody_seed()],
+        bump = global_state.custody_bump
+    )]
+    pub vault_authority: UncheckedAccount<'info>,
 
-        // Transfer using vault authority PDA
-        let vault_authority_bump = ctx.accounts.global_state.vault_authority_bump;
-        let seeds = &[
-            b"vault_authority".as_ref(),
-            &[vault_authority_bump],
-        ];
-        let signer = &[&seeds[..]];
+    #[account(
+        mut,
+        seeds = [b"token_vault"],
+        bump = token_vault.bump
+    )]
+    pub token_vault: Account<'info, TokenVault>,
 
-        let cpi_accounts = Transfer {
-            from: ctx.accounts.vault_token_account.to_account_info(),
-            to: ctx.accounts.publisher_token_account.to_account_info(),
-            authority: ctx.accounts.vault_authority.to_account_info(),
-        };
-        let cpi_program = ctx.accounts.token_program.to_account_info();
-        let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
-        token::transfer(cpi_ctx, amount)?;
+    #[account(
+        mut,
+        constraint = vetoer_token_account.owner == vetoer.key()
+    )]
+    pub vetoer_token_account: InterfaceAccount<'info, TokenAccount>,
 
-        publisher.unbonding_amount = 0;
-        publisher.unbonding_start = 0;
-        ctx.accounts.token_vault.total_staked = ctx.accounts.token_vault.total_staked
-            .checked_sub(amount)
-            .ok_or(ErrorCode::Overflow)?;
+    #[account(
+        mut,
+        constraint = vault_token_account.key() == token_vault.vault_token_account
+    )]
+    pub vault_token_account: InterfaceAccount<'info, TokenAccount>,
 
-        Ok(())
-    }
+    pub vetoer: Signer<'info>,
 
-    // ========================================================================
-    // Aggregation
-    // ========================================================================
+    #[account(address = token_vault.token_mint @ ErrorCode::InvalidMint)]
+    pub token_mint: InterfaceAccount<'info, Mint>,
 
-    pub fn aggregate_price(
-        ctx: Context<AggregatePrice>,
-    ) -> Result<()> {
-        aggregate_prices_internal(
-            &mut ctx.accounts.price_account,
-            &ctx.accounts.product_account.symbol
-        )?;
-        Ok(())
-    }
+    pub token_program: Interface<'info, TokenInterface>,
+}
 
-    // ========================================================================
-    // Governance Instructions
-    // ========================================================================
+#[derive(Accounts)]
+pub struct FinalizeOptimisticChange<'info> {
+    pub global_state: Account<'info, GlobalState>,
 
-    pub fn create_proposal(
-        ctx: Context<CreateProposal>,
-        proposal_type: ProposalType,
-        description: String,
-    ) -> Result<()> {
-        require!(!ctx.accounts.global_state.paused, ErrorCode::SystemPaused);
-        
-        let proposer_balance = ctx.accounts.proposer_token_account.amount;
-        require!(
-            proposer_balance >= ctx.accounts.governance_state.proposal_threshold,
-            ErrorCode::Unauthorized
-        );
+    #[account(mut)]
+    pub optimistic_change: Account<'info, OptimisticChange>,
 
-        let clock = Clock::get()?;
-        let proposal = &mut ctx.accounts.proposal;
-        let governance = &mut ctx.accounts.governance_state;
+    #[account(
+        mut,
+        seeds = [b"token_vault"],
+        bump = token_vault.bump
+    )]
+    pub token_vault: Account<'info, TokenVault>,
 
-        proposal.proposer = ctx.accounts.proposer.key();
-        proposal.proposal_type = proposal_type.clone();
-        proposal.description = description.clone();
-        proposal.yes_votes = 0;
-        proposal.no_votes = 0;
-        proposal.abstain_votes = 0;
-        proposal.start_slot = clock.slot;
-        proposal.end_slot = clock.slot + governance.voting_period;
-        proposal.executed = false;
-        proposal.execution_time = 0;
-        proposal.proposal_id = governance.proposal_count;
-        proposal.bump = ctx.bumps.proposal;
+    #[account(mut)]
+    pub price_account: Option<AccountLoader<'info, PriceAccount>>,
+}
 
-        governance.proposal_count += 1;
+#[derive(Accounts)]
+pub struct CreateCrossRateGuard<'info> {
+    pub base_feed: AccountLoader<'info, PriceAccount>,
 
-        emit!(ProposalCreated {
-            proposal_id: proposal.proposal_id,
-            proposer: proposal.proposer,
-            proposal_type,
-            description,
-        });
+    pub quote_feed: AccountLoader<'info, PriceAccount>,
 
-        Ok(())
-    }
+    #[account(constraint = direct_feed.load()?.authority == authority.key() @ ErrorCode::Unauthorized)]
+    pub direct_feed: AccountLoader<'info, PriceAccount>,
 
-    pub fn vote_proposal(
-        ctx: Context<VoteProposal>,
-        vote: VoteType,
-    ) -> Result<()> {
-        let clock = Clock::get()?;
-        let proposal = &mut ctx.accounts.proposal;
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + 32 + 32 + 32 + 2 + 8 + 8 + 8 + 1 + 8 + 32 + 1,
+        seeds = [b"cross_rate_guard", direct_feed.key().as_ref()],
+        bump
+    )]
+    pub cross_rate_guard: Account<'info, CrossRateGuard>,
 
-        require!(clock.slot <= proposal.end_slot, ErrorCode::VotingPeriodEnded);
+    pub authority: Signer<'info>,
 
-        let vote_weight = ctx.accounts.voter_token_account.amount;
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
 
-        match vote {
-            VoteType::Yes => proposal.yes_votes += vote_weight,
-            VoteType::No => proposal.no_votes += vote_weight,
-            VoteType::Abstain => proposal.abstain_votes += vote_weight,
-        }
+#[derive(Accounts)]
+pub struct CheckCrossRate<'info> {
+    #[account(
+        mut,
+        seeds = [b"cross_rate_guard", direct_feed.key().as_ref()],
+        bump = cross_rate_guard.bump,
+        has_one = base_feed,
+        has_one = quote_feed,
+        has_one = direct_feed
+    )]
+    pub cross_rate_guard: Account<'info, CrossRateGuard>,
+
+    pub base_feed: AccountLoader<'info, PriceAccount>,
+    pub quote_feed: AccountLoader<'info, PriceAccount>,
+    pub direct_feed: AccountLoader<'info, PriceAccount>,
+}
+
+#[derive(Accounts)]
+pub struct ReconcileVault<'info> {
+    #[account(
+        mut,
+        seeds = [b"token_vault"],
+        bump = token_vault.bump
+    )]
+    pub token_vault: Account<'info, TokenVault>,
 
-        Ok(())
-    }
+    #[account(
+        constraint = vault_token_account.key() == token_vault.vault_token_account
+    )]
+    pub vault_token_account: InterfaceAccount<'info, TokenAccount>,
+}
 
-    pub fn execute_proposal(
-        ctx: Context<ExecuteProposal>,
-    ) -> Result<()> {
-        let clock = Clock::get()?;
-        let proposal = &mut ctx.accounts.proposal;
-        let governance = &ctx.accounts.governance_state;
+#[derive(Accounts)]
+pub struct SwapMigratedTokens<'info> {
+    #[account(
+        seeds = [b"global_state"],
+        bump = global_state.bump
+    )]
+    pub global_state: Account<'info, GlobalState>,
 
-        require!(clock.slot > proposal.end_slot, ErrorCode::VotingPeriodActive);
-        require!(!proposal.executed, ErrorCode::ProposalNotApproved);
+    #[account(
+        mut,
+        seeds = [b"token_vault"],
+        bump = token_vault.bump
+    )]
+    pub token_vault: Account<'info, TokenVault>,
 
-        // Check quorum
-        let total_votes = proposal.yes_votes + proposal.no_votes + proposal.abstain_votes;
-        let quorum = (governance.total_supply as u128 * governance.quorum_percentage as u128) / 100;
-        
-        require!(total_votes as u128 >= quorum, ErrorCode::QuorumNotReached);
-        require!(proposal.yes_votes > proposal.no_votes, ErrorCode::ProposalNotApproved);
+    /// CHECK: PDA vault authority
+    #[account(
+        seeds = [b"vault_authority", global_state.custody_seed()],
+        bump = global_state.custody_bump
+    )]
+    pub vault_authority: UncheckedAccount<'info>,
 
-        // Timelock mechanism
-        if proposal.execution_time == 0 {
-            proposal.execution_time = clock.unix_timestamp + governance.timelock_duration as i64;
-            return Ok(());
-        }
+    #[account(
+        mut,
+        constraint = vault_token_account.key() == token_vault.vault_token_account
+    )]
+    pub vault_token_account: InterfaceAccount<'info, TokenAccount>,
 
-        require!(
-            clock.unix_timestamp >= proposal.execution_time,
-            ErrorCode::TimelockNotExpired
-        );
+    #[account(address = token_vault.token_mint @ ErrorCode::InvalidMint)]
+    pub token_mint: InterfaceAccount<'info, Mint>,
 
-        proposal.executed = true;
+    #[account(address = token_vault.previous_token_mint @ ErrorCode::InvalidMint)]
+    pub previous_token_mint: InterfaceAccount<'info, Mint>,
 
-        emit!(ProposalExecuted {
-            proposal_id: proposal.proposal_id,
-            proposal_type: proposal.proposal_type.clone(),
-        });
+    #[account(
+        mut,
+        address = token_vault.migration_counterparty @ ErrorCode::InvalidPayoutAccount
+    )]
+    pub migration_counterparty: InterfaceAccount<'info, TokenAccount>,
 
-        Ok(())
-    }
+    #[account(
+        mut,
+        token::mint = previous_token_mint,
+        token::authority = holder
+    )]
+    pub holder_previous_token_account: InterfaceAccount<'info, TokenAccount>,
 
-    pub fn execute_governance_action(
-        ctx: Context<ExecuteGovernanceAction>,
-    ) -> Result<()> {
-        let proposal = &ctx.accounts.proposal;
-        require!(proposal.executed, ErrorCode::ProposalNotApproved);
+    #[account(
+        mut,
+        token::mint = token_mint
+    )]
+    pub holder_token_account: InterfaceAccount<'info, TokenAccount>,
 
-        match &proposal.proposal_type {
-            ProposalType::UpdateRewardRate { new_rate } => {
-                ctx.accounts.token_vault.reward_rate = *new_rate;
-            },
-            ProposalType::UpdateMinPublishers { feed: _, new_min } => {
-                if let Some(price_account) = ctx.accounts.price_account.as_mut() {
-                    price_account.min_publishers = *new_min;
-                }
-            },
-            ProposalType::EmergencyPause => {
-                ctx.accounts.global_state.paused = true;
-                emit!(SystemPaused {
-                    timestamp: Clock::get()?.unix_timestamp,
-                    authority: ctx.accounts.authority.key(),
-                });
-            },
-            ProposalType::EmergencyUnpause => {
-                ctx.accounts.global_state.paused = false;
-                emit!(SystemUnpaused {
-                    timestamp: Clock::get()?.unix_timestamp,
-                    authority: ctx.accounts.authority.key(),
-                });
-            },
-            ProposalType::UpdateGovernanceParams { 
-                proposal_threshold,
-                voting_period,
-                quorum_percentage,
-                timelock_duration,
-            } => {
-                let gov = &mut ctx.accounts.governance_state;
-                if let Some(threshold) = proposal_threshold {
-                    gov.proposal_threshold = *threshold;
-                }
-                if let Some(period) = voting_period {
-                    gov.voting_period = *period;
-                }
-                if let Some(quorum) = quorum_percentage {
-                    gov.quorum_percentage = *quorum;
-                }
-                if let Some(timelock) = timelock_duration {
-                    gov.timelock_duration = *timelock;
-                }
-            },
-            ProposalType::SlashPublisher { publisher: _, percentage } => {
-                if let Some(pub_account) = ctx.accounts.publisher_account.as_mut() {
-                    let slash_amount = (pub_account.staked_amount as u128 * *percentage as u128) / 100;
-                    let slash_amount = slash_amount as u64;
+    pub holder: Signer<'info>,
 
-                    pub_account.staked_amount = pub_account.staked_amount
-                        .checked_sub(slash_amount)
-                        .ok_or(ErrorCode::Overflow)?;
-                    pub_account.slash_count += 1;
-                    pub_account.last_slash_slot = Clock::get()?.slot;
+    pub token_program: Interface<'info, TokenInterface>,
+    /// Token program of the old mint, which may differ from the new one's
+    pub previous_token_program: Interface<'info, TokenInterface>,
+}
 
-                    ctx.accounts.token_vault.total_staked = ctx.accounts.token_vault.total_staked
-                        .checked_sub(slash_amount)
-                        .ok_or(ErrorCode::Overflow)?;
+#[derive(Accounts)]
+pub struct CreateReceiptMint<'info> {
+    #[account(
+        mut,
+        seeds = [b"global_state"],
+        bump = global_state.bump,
+        has_one = authority
+    )]
+    pub global_state: Account<'info, GlobalState>,
 
-                    emit!(PublisherSlashed {
-                        publisher: pub_account.key(),
-                        slash_amount,
-                        slash_percentage: *percentage,
-                        reason: "Governance proposal".to_string(),
-                    });
-                }
-            },
-        }
+    /// CHECK: PDA vault authority, mint and freeze authority of the receipt mint
+    #[account(
+        seeds = [b"vault_authority"],
+        bump = global_state.vault_authority_bump
+    )]
+    pub vault_authority: UncheckedAccount<'info>,
 
-        Ok(())
-    }
+    #[account(address = global_state.token_mint @ ErrorCode::InvalidMint)]
+    pub token_mint: InterfaceAccount<'info, Mint>,
 
-    pub fn emergency_pause(
-        ctx: Context<EmergencyPause>,
-    ) -> Result<()> {
-        require!(
-            ctx.accounts.authority.key() == ctx.accounts.global_state.authority,
-            ErrorCode::Unauthorized
-        );
+    #[account(
+        init,
+        payer = payer,
+        seeds = [b"stake_receipt_mint"],
+        bump,
+        mint::decimals = TOKEN_DECIMALS,
+        mint::authority = vault_authority,
+        mint::freeze_authority = vault_authority
+    )]
+    pub receipt_mint: InterfaceAccount<'info, Mint>,
 
-        ctx.accounts.global_state.paused = true;
+    pub authority: Signer<'info>,
 
-        emit!(SystemPaused {
-            timestamp: Clock::get()?.unix_timestamp,
-            authority: ctx.accounts.authority.key(),
-        });
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    // Receipts are burned when stake is withdrawn, so they live under the stake mint's program
+    #[account(address = *token_mint.to_account_info().owner @ ErrorCode::InvalidMint)]
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
 
-        Ok(())
-    }
+#[derive(Accounts)]
+pub struct MintStakeReceipts<'info> {
+    #[account(
+        seeds = [b"global_state"],
+        bump = global_state.bump
+    )]
+    pub global_state: Account<'info, GlobalState>,
 
-    pub fn emergency_unpause(
-        ctx: Context<EmergencyUnpause>,
-    ) -> Result<()> {
-        require!(
-            ctx.accounts.authority.key() == ctx.accounts.global_state.authority,
-            ErrorCode::Unauthorized
-        );
+    #[account(
+        mut,
+        seeds = [b"publisher", publisher_authority.key().as_ref()],
+        bump = publisher_account.bump
+    )]
+    pub publisher_account: Account<'info, PublisherAccount>,
 
-        ctx.accounts.global_state.paused = false;
+    /// CHECK: PDA vault authority
+    #[account(
+        seeds = [b"vault_authority"],
+        bump = global_state.vault_authority_bump
+    )]
+    pub vault_authority: UncheckedAccount<'info>,
 
-        emit!(SystemUnpaused {
-            timestamp: Clock::get()?.unix_timestamp,
-            authority: ctx.accounts.authority.key(),
-        });
+    #[account(
+        mut,
+        address = global_state.receipt_mint @ ErrorCode::InvalidReceiptAccount
+    )]
+    pub receipt_mint: InterfaceAccount<'info, Mint>,
 
-        Ok(())
-    }
+    #[account(
+        mut,
+        token::mint = receipt_mint,
+        token::authority = publisher_authority
+    )]
+    pub publisher_receipt_account: InterfaceAccount<'info, TokenAccount>,
+
+    pub publisher_authority: Signer<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
 }
 
-// ============================================================================
-// Internal Functions (Optimized)
-// ============================================================================
+#[derive(Accounts)]
+pub struct SyncReceiptAccount<'info> {
+    #[account(
+        seeds = [b"global_state"],
+        bump = global_state.bump
+    )]
+    pub global_state: Account<'info, GlobalState>,
 
-fn aggregate_prices_internal(price_account: &mut PriceAccount, symbol: &str) -> Result<()> {
-    let clock = Clock::get()?;
-    let current_time = clock.unix_timestamp;
+    /// CHECK: PDA vault authority
+    #[account(
+        seeds = [b"vault_authority"],
+        bump = global_state.vault_authority_bump
+    )]
+    pub vault_authority: UncheckedAccount<'info>,
 
-    // Collect active, non-stale prices
-    let mut valid_prices: Vec<&PublisherPrice> = price_account
-        .publishers
-        .iter()
-        .filter(|p| p.active && current_time - p.timestamp < STALENESS_THRESHOLD)
-        .collect();
+    #[account(address = global_state.receipt_mint @ ErrorCode::InvalidReceiptAccount)]
+    pub receipt_mint: InterfaceAccount<'info, Mint>,
 
-    if valid_prices.is_empty() {
-        price_account.aggregate.status = PriceStatus::Unknown;
-        return Ok(());
-    }
+    #[account(
+        mut,
+        token::mint = receipt_mint
+    )]
+    pub receipt_account: InterfaceAccount<'info, TokenAccount>,
 
-    // Sort by price (in-place, no cloning)
-    valid_prices.sort_by_key(|p| p.price);
+    pub token_program: Interface<'info, TokenInterface>,
+}
 
-    // Remove outliers using MAD
-    let filtered_prices = filter_outliers_optimized(&valid_prices);
+#[derive(Accounts)]
+pub struct LockStakePosition<'info> {
+    #[account(
+        seeds = [b"global_state"],
+        bump = global_state.bump
+    )]
+    pub global_state: Account<'info, GlobalState>,
 
-    if filtered_prices.len() < price_account.min_publishers as usize {
-        price_account.aggregate.status = PriceStatus::Unknown;
-        return Ok(());
-    }
+    #[account(
+        mut,
+        seeds = [b"publisher", publisher_authority.key().as_ref()],
+        bump = publisher_account.bump
+    )]
+    pub publisher_account: Account<'info, PublisherAccount>,
 
-    // Calculate stake-weighted median
-    let median_price = calculate_stake_weighted_median_optimized(&filtered_prices)?;
+    #[account(
+        init,
+        payer = publisher_authority,
+        space = 8 + 32 + 32 + 4 + 8 + 1 + 8 + 8 + 1,
+        seeds = [b"stake_position", position_mint.key().as_ref()],
+        bump
+    )]
+    pub stake_position: Account<'info, StakePosition>,
 
-    // Calculate confidence (using u128 to prevent overflow)
-    let confidence = calculate_confidence_safe(&filtered_prices, median_price)?;
+    /// CHECK: PDA vault authority, mint, freeze and metadata update authority of position NFTs
+    #[account(
+        seeds = [b"vault_authority"],
+        bump = global_state.vault_authority_bump
+    )]
+    pub vault_authority: UncheckedAccount<'info>,
 
-    // Determine status
-    let status = determine_status_optimized(&valid_prices, price_account.min_publishers, current_time);
+    #[account(
+        init,
+        payer = publisher_authority,
+        seeds = [
+            b"position_mint",
+            publisher_account.key().as_ref(),
+            publisher_account.position_count.to_le_bytes().as_ref()
+        ],
+        bump,
+        mint::decimals = 0,
+        mint::authority = vault_authority,
+        mint::freeze_authority = vault_authority
+    )]
+    pub position_mint: Box<InterfaceAccount<'info, Mint>>,
 
-    // Update aggregate
-    price_account.aggregate = PriceData {
-        price: median_price,
-        confidence,
-        exponent: price_account.exponent,
-        timestamp: current_time,
-        slot: clock.slot,
-        status: status.clone(),
-    };
+    #[account(
+        init,
+        payer = publisher_authority,
+        associated_token::mint = position_mint,
+        associated_token::authority = publisher_authority
+    )]
+    pub position_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
 
-    // Update EMA
-    price_account.ema = update_ema(&price_account.ema, median_price, confidence);
+    /// CHECK: Metaplex metadata PDA of the position mint; created by the metadata program
+    #[account(
+        mut,
+        seeds = [b"metadata", token_metadata_program.key().as_ref(), position_mint.key().as_ref()],
+        bump,
+        seeds::program = token_metadata_program.key()
+    )]
+    pub position_metadata: UncheckedAccount<'info>,
 
-    emit!(PriceUpdated {
-        product: price_account.product_account,
-        symbol: symbol.to_string(),
-        price: median_price,
-        confidence,
-        timestamp: current_time,
-        slot: clock.slot,
-        status,
-    });
+    #[account(mut)]
+    pub publisher_authority: Signer<'info>,
 
-    Ok(())
+    /// CHECK: Metaplex token metadata program
+    #[account(address = token_metadata::ID)]
+    pub token_metadata_program: UncheckedAccount<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
 }
 
-fn filter_outliers_optimized<'a>(prices: &[&'a PublisherPrice]) -> Vec<&'a PublisherPrice> {
-    if prices.len() < 3 {
-        return prices.to_vec();
-    }
+#[derive(Accounts)]
+pub struct UnlockStakePosition<'info> {
+    #[account(
+        seeds = [b"global_state"],
+        bump = global_state.bump
+    )]
+    pub global_state: Account<'info, GlobalState>,
+
+    #[account(
+        mut,
+        seeds = [b"publisher", publisher_authority.key().as_ref()],
+        bump = publisher_account.bump
+    )]
+    pub publisher_account: Account<'info, PublisherAccount>,
 
-    let median_idx = prices.len() / 2;
-    let median = prices[median_idx].price;
+    #[account(
+        mut,
+        close = publisher_authority,
+        seeds = [b"stake_position", position_mint.key().as_ref()],
+        bump = stake_position.bump,
+        constraint = stake_position.publisher == publisher_account.key() @ ErrorCode::InvalidStakePosition
+    )]
+    pub stake_position: Account<'info, StakePosition>,
 
-    // Calculate MAD without additional allocation
-    let mut deviations: Vec<i64> = prices
-        .iter()
-        .map(|p| (p.price - median).abs())
-        .collect();
-    deviations.sort_unstable();
+    /// CHECK: PDA vault authority
+    #[account(
+        seeds = [b"vault_authority"],
+        bump = global_state.vault_authority_bump
+    )]
+    pub vault_authority: UncheckedAccount<'info>,
 
-    let mad = deviations[deviations.len() / 2];
-    let threshold = mad.saturating_mul(OUTLIER_MAD_MULTIPLIER);
+    #[account(mut)]
+    pub position_mint: InterfaceAccount<'info, Mint>,
 
-    prices
-        .iter()
-        .filter(|p| (p.price - median).abs() <= threshold)
-        .copied()
-        .collect()
+    #[account(
+        mut,
+        token::mint = position_mint,
+        token::authority = publisher_authority
+    )]
+    pub position_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub publisher_authority: Signer<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
 }
 
-fn calculate_stake_weighted_median_optimized(prices: &[&PublisherPrice]) -> Result<i64> {
-    let total_stake: u128 = prices.iter().map(|p| p.stake as u128).sum();
-    let median_stake = total_stake / 2;
+#[derive(Accounts)]
+pub struct RedeemStakePosition<'info> {
+    #[account(
+        seeds = [b"global_state"],
+        bump = global_state.bump
+    )]
+    pub global_state: Account<'info, GlobalState>,
 
-    let mut cumulative_stake: u128 = 0;
-    for price in prices {
-        cumulative_stake += price.stake as u128;
-        if cumulative_stake >= median_stake {
-            return Ok(price.price);
-        }
-    }
+    #[account(
+        mut,
+        constraint = publisher_account.key() == stake_position.publisher @ ErrorCode::InvalidStakePosition
+    )]
+    pub publisher_account: Account<'info, PublisherAccount>,
 
-    Ok(prices[0].price)
-}
+    /// CHECK: receives the position's rent, as it paid for it
+    #[account(mut, address = publisher_account.authority)]
+    pub publisher_authority: UncheckedAccount<'info>,
 
-fn calculate_confidence_safe(prices: &[&PublisherPrice], median: i64) -> Result<u64> {
-    let total_stake: u128 = prices.iter().map(|p| p.stake as u128).sum();
-    
-    if total_stake == 0 {
-        return Ok(1);
-    }
+    #[account(
+        mut,
+        close = publisher_authority,
+        seeds = [b"stake_position", position_mint.key().as_ref()],
+        bump = stake_position.bump
+    )]
+    pub stake_position: Account<'info, StakePosition>,
 
-    let variance: u128 = prices
-        .iter()
-        .map(|p| {
-            let diff = (p.price - median).abs() as i128;
-            let diff_squared = (diff * diff) as u128;
-            (diff_squared * p.stake as u128) / total_stake
-        })
-        .sum();
+    /// CHECK: PDA freeze authority of position mints
+    #[account(
+        seeds = [b"vault_authority"],
+        bump = global_state.vault_authority_bump
+    )]
+    pub position_authority: UncheckedAccount<'info>,
 
-    let std_dev = (variance as f64).sqrt() as u64;
-    Ok(std_dev.max(1))
-}
+    /// CHECK: PDA vault authority
+    #[account(
+        seeds = [b"vault_authority", global_state.custody_seed()],
+        bump = global_state.custody_bump
+    )]
+    pub vault_authority: UncheckedAccount<'info>,
 
-fn determine_status_optimized(
-    prices: &[&PublisherPrice],
-    min_publishers: u8,
-    current_time: i64
-) -> PriceStatus {
-    if prices.len() < min_publishers as usize {
-        return PriceStatus::Unknown;
-    }
+    #[account(
+        mut,
+        seeds = [b"token_vault"],
+        bump = token_vault.bump
+    )]
+    pub token_vault: Account<'info, TokenVault>,
 
-    if let Some(latest) = prices.iter().map(|p| p.timestamp).max() {
-        if current_time - latest > HALTED_THRESHOLD {
-            return PriceStatus::Halted;
-        }
-    }
+    #[account(
+        mut,
+        constraint = vault_token_account.key() == token_vault.vault_token_account
+    )]
+    pub vault_token_account: InterfaceAccount<'info, TokenAccount>,
 
-    PriceStatus::Trading
-}
+    #[account(mut)]
+    pub position_mint: InterfaceAccount<'info, Mint>,
 
-fn update_ema(current_ema: &EmaData, new_price: i64, new_confidence: u64) -> EmaData {
-    if current_ema.num_observations == 0 {
-        return EmaData {
-            ema_price: new_price,
-            ema_confidence: new_confidence,
-            num_observations: 1,
-        };
-    }
+    #[account(
+        mut,
+        token::mint = position_mint,
+        token::authority = holder
+    )]
+    pub position_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        token::mint = token_mint
+    )]
+    pub holder_token_account: InterfaceAccount<'info, TokenAccount>,
 
-    let one_minus_alpha = 1_000_000 - EMA_ALPHA_SCALED;
+    pub holder: Signer<'info>,
 
-    let new_ema_price = ((EMA_ALPHA_SCALED as i128 * new_price as i128 
-        + one_minus_alpha as i128 * current_ema.ema_price as i128) / 1_000_000) as i64;
-    
-    let new_ema_confidence = ((EMA_ALPHA_SCALED as u128 * new_confidence as u128 
-        + one_minus_alpha as u128 * current_ema.ema_confidence as u128) / 1_000_000) as u64;
+    #[account(address = token_vault.token_mint @ ErrorCode::InvalidMint)]
+    pub token_mint: InterfaceAccount<'info, Mint>,
 
-    EmaData {
-        ema_price: new_ema_price,
-        ema_confidence: new_ema_confidence,
-        num_observations: current_ema.num_observations.saturating_add(1),
-    }
+    pub token_program: Interface<'info, TokenInterface>,
 }
 
-// ============================================================================
-// Context Structs
-// ============================================================================
-
 #[derive(Accounts)]
-pub struct InitializeProgram<'info> {
+pub struct SyncPositionAccount<'info> {
     #[account(
-        init,
-        payer = authority,
-        space = 8 + 32 + 32 + 32 + 32 + 32 + 1 + 8 + 8 + 1 + 1 + 1,
         seeds = [b"global_state"],
-        bump
+        bump = global_state.bump
     )]
     pub global_state: Account<'info, GlobalState>,
 
-    /// CHECK: PDA used as vault authority for token transfers
+    /// CHECK: PDA vault authority
     #[account(
         seeds = [b"vault_authority"],
-        bump
+        bump = global_state.vault_authority_bump
     )]
     pub vault_authority: UncheckedAccount<'info>,
 
+    // Only position mints have a StakePosition at this address
     #[account(
-        init,
-        payer = authority,
-        space = 8 + 8 + 8 + 8 + 8 + 32 + 32 + 32 + 32 + 1,
-        seeds = [b"token_vault"],
-        bump
+        seeds = [b"stake_position", position_mint.key().as_ref()],
+        bump = stake_position.bump
     )]
-    pub token_vault: Account<'info, TokenVault>,
+    pub stake_position: Account<'info, StakePosition>,
 
-    #[account(
-        init,
-        payer = authority,
-        space = 8 + 32 + 8 + 8 + 1 + 8 + 8 + 8 + 32 + 1,
-        seeds = [b"governance"],
-        bump
-    )]
-    pub governance_state: Account<'info, GovernanceState>,
+    pub position_mint: InterfaceAccount<'info, Mint>,
 
-    pub token_mint: Account<'info, Mint>,
-    
     #[account(
-        constraint = vault_token_account.mint == token_mint.key(),
-        constraint = vault_token_account.owner == vault_authority.key()
+        mut,
+        token::mint = position_mint
     )]
-    pub vault_token_account: Account<'info, TokenAccount>,
+    pub position_token_account: InterfaceAccount<'info, TokenAccount>,
 
-    #[account(mut)]
-    pub authority: Signer<'info>,
-    pub system_program: Program<'info, System>,
+    pub token_program: Interface<'info, TokenInterface>,
 }
 
 #[derive(Accounts)]
-#[instruction(symbol: String)]
-pub struct CreateProduct<'info> {
-    #[account(mut)]
+pub struct CreateFeedQuota<'info> {
     pub global_state: Account<'info, GlobalState>,
 
-    #[account(
-        init,
-        payer = authority,
-        space = 8 + 64 + 1 + 256 + 32 + 32 + 1,
-        seeds = [b"product", symbol.as_bytes()],
-        bump
-    )]
-    pub product_account: Account<'info, ProductAccount>,
+    pub price_account: AccountLoader<'info, PriceAccount>,
 
     #[account(
         init,
-        payer = authority,
-        space = 8 + 32 + 1 + 128 + (MAX_PUBLISHERS * 96) + 1 + 1 + 8 + 32 + 32 + 4 + 1,
-        seeds = [b"price", symbol.as_bytes()],
+        payer = payer,
+        space = 8 + 32 + 4 + 4 + 8 + (4 * MAX_PUBLISHERS) + 1,
+        seeds = [b"feed_quota", price_account.key().as_ref()],
         bump
     )]
-    pub price_account: Account<'info, PriceAccount>,
+    pub feed_quota: Account<'info, FeedQuota>,
 
     #[account(mut)]
-    pub authority: Signer<'info>,
+    pub payer: Signer<'info>,
     pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-pub struct AddPublisher<'info> {
-    #[account(mut)]
-    pub global_state: Account<'info, GlobalState>,
+pub struct CreateFeedSla<'info> {
+    pub price_account: AccountLoader<'info, PriceAccount>,
 
     #[account(
         init,
         payer = payer,
-        space = 8 + 32 + 8 + 32 + 8 + 64 + 8 + 4 + 8 + 8 + 8 + 1,
-        seeds = [b"publisher", publisher_authority.key().as_ref()],
+        space = 8 + 32 + 8 + 2 + 8 + 8 + 2 + 8 + 8 + 1,
+        seeds = [b"feed_sla", price_account.key().as_ref()],
         bump
     )]
-    pub publisher_account: Account<'info, PublisherAccount>,
+    pub feed_sla: Account<'info, FeedSla>,
 
     #[account(mut)]
-    pub token_vault: Account<'info, TokenVault>,
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
 
+#[derive(Accounts)]
+pub struct RecordSlaBreach<'info> {
     #[account(
         mut,
-        constraint = publisher_token_account.mint == token_vault.token_mint,
-        constraint = publisher_token_account.owner == publisher_authority.key()
+        seeds = [b"feed_sla", price_account.key().as_ref()],
+        bump = feed_sla.bump
     )]
-    pub publisher_token_account: Account<'info, TokenAccount>,
+    pub feed_sla: Account<'info, FeedSla>,
+
+    pub price_account: AccountLoader<'info, PriceAccount>,
 
     #[account(
         mut,
-        constraint = vault_token_account.key() == token_vault.vault_token_account
+        seeds = [b"token_vault"],
+        bump = token_vault.bump
+    )]
+    pub token_vault: Account<'info, TokenVault>,
+}
+
+#[derive(Accounts)]
+pub struct CreateSubscription<'info> {
+    #[account(
+        seeds = [b"feed_sla", feed_sla.feed.as_ref()],
+        bump = feed_sla.bump
     )]
-    pub vault_token_account: Account<'info, TokenAccount>,
+    pub feed_sla: Account<'info, FeedSla>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + 32 + 32 + 8 + 8 + 8 + 8 + 8 + 8 + 8 + 8 + 1,
+        seeds = [b"subscription", feed_sla.feed.as_ref(), consumer.key().as_ref()],
+        bump
+    )]
+    pub subscription: Account<'info, Subscription>,
+
+    pub consumer: Signer<'info>,
 
-    pub publisher_authority: Signer<'info>,
-    
     #[account(mut)]
     pub payer: Signer<'info>,
-    
-    pub token_program: Program<'info, Token>,
     pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-pub struct UpdatePrice<'info> {
+pub struct Subscribe<'info> {
     pub global_state: Account<'info, GlobalState>,
 
-    pub product_account: Account<'info, ProductAccount>,
-
     #[account(
         mut,
-        seeds = [b"price", product_account.symbol.as_bytes()],
-        bump = price_account.bump
+        seeds = [b"feed_sla", feed_sla.feed.as_ref()],
+        bump = feed_sla.bump
     )]
-    pub price_account: Account<'info, PriceAccount>,
+    pub feed_sla: Account<'info, FeedSla>,
 
     #[account(
-        seeds = [b"publisher", publisher_authority.key().as_ref()],
-        bump = publisher_account.bump,
-        constraint = publisher_account.authority == publisher_authority.key()
+        mut,
+        seeds = [b"subscription", feed_sla.feed.as_ref(), consumer.key().as_ref()],
+        bump = subscription.bump
     )]
-    pub publisher_account: Account<'info, PublisherAccount>,
-
-    pub publisher_authority: Signer<'info>,
-}
-
-#[derive(Accounts)]
-pub struct StakeTokens<'info> {
-    pub global_state: Account<'info, GlobalState>,
+    pub subscription: Account<'info, Subscription>,
 
     #[account(
         mut,
-        seeds = [b"publisher", publisher_authority.key().as_ref()],
-        bump = publisher_account.bump
+        seeds = [b"token_vault"],
+        bump = token_vault.bump
     )]
-    pub publisher_account: Account<'info, PublisherAccount>,
-
-    #[account(mut)]
     pub token_vault: Account<'info, TokenVault>,
 
     #[account(
         mut,
-        constraint = publisher_token_account.owner == publisher_authority.key()
+        constraint = consumer_token_account.mint == token_vault.token_mint,
+        constraint = consumer_token_account.owner == consumer.key()
     )]
-    pub publisher_token_account: Account<'info, TokenAccount>,
+    pub consumer_token_account: InterfaceAccount<'info, TokenAccount>,
 
     #[account(
         mut,
         constraint = vault_token_account.key() == token_vault.vault_token_account
     )]
-    pub vault_token_account: Account<'info, TokenAccount>,
+    pub vault_token_account: InterfaceAccount<'info, TokenAccount>,
 
-    pub publisher_authority: Signer<'info>,
-    pub token_program: Program<'info, Token>,
+    pub consumer: Signer<'info>,
+
+    #[account(address = token_vault.token_mint @ ErrorCode::InvalidMint)]
+    pub token_mint: InterfaceAccount<'info, Mint>,
+
+    pub token_program: Interface<'info, TokenInterface>,
 }
 
 #[derive(Accounts)]
-pub struct UnstakeTokens<'info> {
+pub struct SubscribeWithRewardMint<'info> {
     pub global_state: Account<'info, GlobalState>,
 
+    #[account(
+        seeds = [b"subscription", subscription.feed.as_ref(), consumer.key().as_ref()],
+        bump = subscription.bump
+    )]
+    pub subscription: Account<'info, Subscription>,
+
     #[account(
         mut,
-        seeds = [b"publisher", publisher_authority.key().as_ref()],
-        bump = publisher_account.bump
+        seeds = [b"token_vault"],
+        bump = token_vault.bump
     )]
-    pub publisher_account: Account<'info, PublisherAccount>,
+    pub token_vault: Account<'info, TokenVault>,
 
-    pub publisher_authority: Signer<'info>,
+    #[account(
+        mut,
+        constraint = consumer_token_account.mint == token_vault.reward_mint,
+        constraint = consumer_token_account.owner == consumer.key()
+    )]
+    pub consumer_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        constraint = reward_vault_token_account.key() == token_vault.reward_vault_token_account @ ErrorCode::InvalidVaultAccount
+    )]
+    pub reward_vault_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    pub consumer: Signer<'info>,
+
+    #[account(address = token_vault.reward_mint @ ErrorCode::InvalidMint)]
+    pub reward_mint: InterfaceAccount<'info, Mint>,
+
+    pub token_program: Interface<'info, TokenInterface>,
 }
 
 #[derive(Accounts)]
-pub struct WithdrawUnbonded<'info> {
+pub struct ClaimRebates<'info> {
     pub global_state: Account<'info, GlobalState>,
 
+    #[account(
+        seeds = [b"feed_sla", feed_sla.feed.as_ref()],
+        bump = feed_sla.bump
+    )]
+    pub feed_sla: Account<'info, FeedSla>,
+
     #[account(
         mut,
-        seeds = [b"publisher", publisher_authority.key().as_ref()],
-        bump = publisher_account.bump
+        seeds = [b"subscription", feed_sla.feed.as_ref(), consumer.key().as_ref()],
+        bump = subscription.bump
     )]
-    pub publisher_account: Account<'info, PublisherAccount>,
+    pub subscription: Account<'info, Subscription>,
 
     /// CHECK: PDA vault authority
     #[account(
-        seeds = [b"vault_authority"],
-        bump = global_state.vault_authority_bump
+        seeds = [b"vault_authority", global_state.custody_seed()],
+        bump = global_state.custody_bump
     )]
     pub vault_authority: UncheckedAccount<'info>,
 
@@ -1210,93 +13476,118 @@ pub struct WithdrawUnbonded<'info> {
 
     #[account(
         mut,
-        constraint = publisher_token_account.owner == publisher_authority.key()
+        constraint = consumer_token_account.owner == consumer.key()
     )]
-    pub publisher_token_account: Account<'info, TokenAccount>,
+    pub consumer_token_account: InterfaceAccount<'info, TokenAccount>,
 
     #[account(
         mut,
         constraint = vault_token_account.key() == token_vault.vault_token_account
     )]
-    pub vault_token_account: Account<'info, TokenAccount>,
+    pub vault_token_account: InterfaceAccount<'info, TokenAccount>,
 
-    pub publisher_authority: Signer<'info>,
-    pub token_program: Program<'info, Token>,
-}
+    pub consumer: Signer<'info>,
 
-#[derive(Accounts)]
-pub struct AggregatePrice<'info> {
-    pub product_account: Account<'info, ProductAccount>,
-    
-    #[account(mut)]
-    pub price_account: Account<'info, PriceAccount>,
+    #[account(address = token_vault.token_mint @ ErrorCode::InvalidMint)]
+    pub token_mint: InterfaceAccount<'info, Mint>,
+
+    pub token_program: Interface<'info, TokenInterface>,
 }
 
 #[derive(Accounts)]
-pub struct CreateProposal<'info> {
+pub struct PayInsurancePremium<'info> {
     pub global_state: Account<'info, GlobalState>,
 
-    #[account(mut)]
-    pub governance_state: Account<'info, GovernanceState>,
-
     #[account(
-        init,
-        payer = proposer,
-        space = 8 + 32 + 256 + 256 + 8 + 8 + 8 + 8 + 8 + 1 + 8 + 8 + 1,
-        seeds = [b"proposal", governance_state.proposal_count.to_le_bytes().as_ref()],
-        bump
+        seeds = [b"feed_sla", feed_sla.feed.as_ref()],
+        bump = feed_sla.bump
     )]
-    pub proposal: Account<'info, Proposal>,
+    pub feed_sla: Account<'info, FeedSla>,
 
     #[account(
-        constraint = proposer_token_account.owner == proposer.key()
+        mut,
+        seeds = [b"subscription", feed_sla.feed.as_ref(), consumer.key().as_ref()],
+        bump = subscription.bump
     )]
-    pub proposer_token_account: Account<'info, TokenAccount>,
-
-    #[account(mut)]
-    pub proposer: Signer<'info>,
-    pub system_program: Program<'info, System>,
-}
+    pub subscription: Account<'info, Subscription>,
 
-#[derive(Accounts)]
-pub struct VoteProposal<'info> {
-    #[account(mut)]
-    pub proposal: Account<'info, Proposal>,
+    #[account(
+        mut,
+        seeds = [b"token_vault"],
+        bump = token_vault.bump
+    )]
+    pub token_vault: Account<'info, TokenVault>,
 
     #[account(
-        constraint = voter_token_account.owner == voter.key()
+        mut,
+        constraint = consumer_token_account.mint == token_vault.token_mint,
+        constraint = consumer_token_account.owner == consumer.key()
     )]
-    pub voter_token_account: Account<'info, TokenAccount>,
+    pub consumer_token_account: InterfaceAccount<'info, TokenAccount>,
 
-    pub voter: Signer<'info>,
-}
+    #[account(
+        mut,
+        constraint = vault_token_account.key() == token_vault.vault_token_account
+    )]
+    pub vault_token_account: InterfaceAccount<'info, TokenAccount>,
 
-#[derive(Accounts)]
-pub struct ExecuteProposal<'info> {
-    #[account(mut)]
-    pub proposal: Account<'info, Proposal>,
-    pub governance_state: Account<'info, GovernanceState>,
+    pub consumer: Signer<'info>,
+
+    #[account(address = token_vault.token_mint @ ErrorCode::InvalidMint)]
+    pub token_mint: InterfaceAccount<'info, Mint>,
+
+    pub token_program: Interface<'info, TokenInterface>,
 }
 
 #[derive(Accounts)]
-pub struct ExecuteGovernanceAction<'info> {
-    #[account(mut)]
+pub struct ClaimInsurance<'info> {
     pub global_state: Account<'info, GlobalState>,
 
-    pub proposal: Account<'info, Proposal>,
+    #[account(
+        seeds = [b"feed_sla", feed_sla.feed.as_ref()],
+        bump = feed_sla.bump
+    )]
+    pub feed_sla: Account<'info, FeedSla>,
 
-    pub governance_state: Account<'info, GovernanceState>,
+    #[account(
+        mut,
+        seeds = [b"subscription", feed_sla.feed.as_ref(), consumer.key().as_ref()],
+        bump = subscription.bump
+    )]
+    pub subscription: Account<'info, Subscription>,
 
-    #[account(mut)]
+    /// CHECK: PDA vault authority
+    #[account(
+        seeds = [b"vault_authority", global_state.custody_seed()],
+        bump = global_state.custody_bump
+    )]
+    pub vault_authority: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"token_vault"],
+        bump = token_vault.bump
+    )]
     pub token_vault: Account<'info, TokenVault>,
 
-    #[account(mut)]
-    pub price_account: Option<Account<'info, PriceAccount>>,
+    #[account(
+        mut,
+        constraint = consumer_token_account.owner == consumer.key()
+    )]
+    pub consumer_token_account: InterfaceAccount<'info, TokenAccount>,
 
-    #[account(mut)]
-    pub publisher_account: Option<Account<'info, PublisherAccount>>,
+    #[account(
+        mut,
+        constraint = vault_token_account.key() == token_vault.vault_token_account
+    )]
+    pub vault_token_account: InterfaceAccount<'info, TokenAccount>,
 
-    pub authority: Signer<'info>,
+    pub consumer: Signer<'info>,
+
+    #[account(address = token_vault.token_mint @ ErrorCode::InvalidMint)]
+    pub token_mint: InterfaceAccount<'info, Mint>,
+
+    pub token_program: Interface<'info, TokenInterface>,
 }
 
 #[derive(Accounts)]
@@ -1312,6 +13603,22 @@ pub struct EmergencyPause<'info> {
     pub authority: Signer<'info>,
 }
 
+#[derive(Accounts)]
+pub struct AdvanceMockClock<'info> {
+    #[account(
+        mut,
+        seeds = [b"global_state"],
+        bump = global_state.bump,
+        has_one = authority
+    )]
+    pub global_state: Account<'info, GlobalState>,
+
+    #[account(seeds = [b"cluster_config"], bump = cluster_config.bump)]
+    pub cluster_config: Account<'info, ClusterConfig>,
+
+    pub authority: Signer<'info>,
+}
+
 #[derive(Accounts)]
 pub struct EmergencyUnpause<'info> {
     #[account(
@@ -1325,6 +13632,200 @@ pub struct EmergencyUnpause<'info> {
     pub authority: Signer<'info>,
 }
 
+// ============================================================================
+// Legacy Layouts (read by the bootstrap and migrate instructions)
+// ============================================================================
+
+#[derive(AnchorSerialize, AnchorDeserialize)]
+pub struct GlobalStateV1 {
+    pub authority: Pubkey,
+    pub token_mint: Pubkey,
+    pub token_vault: Pubkey,
+    pub vault_authority: Pubkey,
+    pub governance: Pubkey,
+    pub paused: bool,
+    pub total_products: u64,
+    pub total_publishers: u64,
+    pub version: u8,
+    pub bump: u8,
+    pub vault_authority_bump: u8,
+}
+
+/// GlobalState before `mock_time_offset` was added
+#[derive(AnchorSerialize, AnchorDeserialize)]
+pub struct GlobalStateV2 {
+    pub authority: Pubkey,
+    pub token_mint: Pubkey,
+    pub token_vault: Pubkey,
+    pub vault_authority: Pubkey,
+    pub governance: Pubkey,
+    pub paused: bool,
+    pub total_products: u64,
+    pub total_publishers: u64,
+    pub stake_per_feed: u64,
+    pub max_new_publishers_per_epoch: u32,
+    pub current_epoch: u64,
+    pub epoch_start_slot: u64,
+    pub epoch_stake_snapshot: u64,
+    pub registrations_this_epoch: u32,
+    pub registration_allowance: u32,
+    pub unbonding_period: i64,
+    pub min_stake: u64,
+    pub previous_min_stake: u64,
+    pub min_stake_raised_epoch: u64,
+    pub receipt_mint: Pubkey,
+    pub receipts_transferable: bool,
+    pub positions_transferable: bool,
+    pub pending_stake_per_feed: PendingParam,
+    pub pending_registration_limit: PendingParam,
+    pub pending_unbonding_period: PendingParam,
+    pub pending_min_stake: PendingParam,
+    pub listing_collateral: u64,
+    pub version: u8,
+    pub bump: u8,
+    pub vault_authority_bump: u8,
+    pub custody_version: u8,
+    pub custody_bump: u8,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize)]
+pub struct TokenVaultV1 {
+    pub total_staked: u64,
+    pub total_rewards_distributed: u64,
+    pub reward_rate: u64,
+    pub last_distribution_slot: u64,
+    pub token_mint: Pubkey,
+    pub vault_token_account: Pubkey,
+    pub vault_authority: Pubkey,
+    pub authority: Pubkey,
+    pub bump: u8,
+}
+
+/// ProductAccount before the trading calendar was added
+#[derive(AnchorSerialize, AnchorDeserialize)]
+pub struct ProductAccountV1 {
+    pub symbol: String,
+    pub asset_type: AssetType,
+    pub description: String,
+    pub price_account: Pubkey,
+    pub authority: Pubkey,
+    pub bump: u8,
+}
+
+/// PublisherAccount before stake states, feed exposure and rewards were tracked
+#[derive(AnchorSerialize, AnchorDeserialize)]
+pub struct PublisherAccountV1 {
+    pub authority: Pubkey,
+    pub staked_amount: u64,
+    pub stake_account: Pubkey,
+    pub reputation: u64,
+    pub name: String,
+    pub registered_at: i64,
+    pub slash_count: u32,
+    pub last_slash_slot: u64,
+    pub unbonding_amount: u64,
+    pub unbonding_start: i64,
+    pub bump: u8,
+}
+
+/// ProposalType variants that existed before proposal modes; the order fixes their tags
+#[derive(AnchorSerialize, AnchorDeserialize)]
+pub enum ProposalTypeV1 {
+    UpdateRewardRate { new_rate: u64 },
+    UpdateMinPublishers { feed: Pubkey, new_min: u8 },
+    SlashPublisher { publisher: Pubkey, percentage: u8 },
+    EmergencyPause,
+    EmergencyUnpause,
+    UpdateGovernanceParams {
+        proposal_threshold: Option<u64>,
+        voting_period: Option<u64>,
+        quorum_percentage: Option<u8>,
+        timelock_duration: Option<u64>,
+    },
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize)]
+pub struct ProposalV1 {
+    pub proposer: Pubkey,
+    pub proposal_type: ProposalTypeV1,
+    pub description: String,
+    pub yes_votes: u64,
+    pub no_votes: u64,
+    pub abstain_votes: u64,
+    pub start_slot: u64,
+    pub end_slot: u64,
+    pub executed: bool,
+    pub execution_time: i64,
+    pub proposal_id: u64,
+    pub bump: u8,
+}
+
+/// ProductAccount before FX currencies were added
+#[derive(AnchorSerialize, AnchorDeserialize)]
+pub struct ProductAccountV2 {
+    pub symbol: String,
+    pub asset_type: AssetType,
+    pub description: String,
+    pub price_account: Pubkey,
+    pub authority: Pubkey,
+    pub calendar: TradingCalendar,
+    pub bump: u8,
+}
+
+/// Start of the original borsh PriceAccount, up to its publisher array
+#[derive(AnchorSerialize, AnchorDeserialize)]
+pub struct PriceAccountV1Head {
+    pub product_account: Pubkey,
+    pub price_type: PriceType,
+    pub aggregate: PriceDataV1,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize)]
+pub struct PriceDataV1 {
+    pub price: i64,
+    pub confidence: u64,
+    pub exponent: i32,
+    pub timestamp: i64,
+    pub slot: u64,
+    pub status: PriceStatus,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize)]
+pub struct PublisherPriceV1 {
+    pub publisher: Pubkey,
+    pub price: i64,
+    pub confidence: u64,
+    pub timestamp: i64,
+    pub slot: u64,
+    pub stake: u64,
+    pub active: bool,
+}
+
+/// Rest of the original borsh PriceAccount, after its MAX_PUBLISHERS publisher slots
+#[derive(AnchorSerialize, AnchorDeserialize)]
+pub struct PriceAccountV1Tail {
+    pub publisher_count: u8,
+    pub min_publishers: u8,
+    pub last_update_slot: u64,
+    pub ema: EmaData,
+    pub authority: Pubkey,
+    pub exponent: i32,
+    pub bump: u8,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize)]
+pub struct GovernanceStateV1 {
+    pub governance_token: Pubkey,
+    pub proposal_threshold: u64,
+    pub voting_period: u64,
+    pub quorum_percentage: u8,
+    pub timelock_duration: u64,
+    pub proposal_count: u64,
+    pub total_supply: u64,
+    pub authority: Pubkey,
+    pub bump: u8,
+}
+
 // ============================================================================
 // Default Implementations
 // ============================================================================
@@ -1335,6 +13836,21 @@ impl Default for PriceStatus {
     }
 }
 
+impl Default for PriceData {
+    fn default() -> Self {
+        PriceData {
+            price: 0,
+            confidence: 0,
+            timestamp: 0,
+            slot: 0,
+            price_i128: 0,
+            exponent: 0,
+            status: PriceStatus::Unknown as u8,
+            _padding: [0; 11],
+        }
+    }
+}
+
 impl Default for AssetType {
     fn default() -> Self {
         AssetType::Crypto
@@ -1345,4 +13861,159 @@ impl Default for PriceType {
     fn default() -> Self {
         PriceType::Spot
     }
-}
\ No newline at end of file
+}
+
+// ============================================================================
+// Compact Quote Codec
+// ============================================================================
+
+/// The quote encoding of `submit_compact_quotes`. Each quote is three LEB128 varints:
+/// the zigzag-encoded difference between its price and a reference price (the feed's
+/// current aggregate), its confidence and its source id. A quote close to the aggregate
+/// takes a handful of bytes instead of the 24 of the fixed-width arguments.
+pub mod quote_codec {
+    extern crate alloc;
+
+    use alloc::vec::Vec;
+
+    /// Bytes needed for a full u64
+    pub const MAX_VARINT_LEN: usize = 10;
+
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub struct CompactQuote {
+        pub price: i64,
+        pub confidence: u64,
+        pub source_id: u64,
+    }
+
+    pub fn encode(quote: &CompactQuote, reference_price: i64, out: &mut Vec<u8>) {
+        write_varint(zigzag(quote.price.wrapping_sub(reference_price)), out);
+        write_varint(quote.confidence, out);
+        write_varint(quote.source_id, out);
+    }
+
+    /// Decodes the quote at the front of `data` and advances past it. None on truncated
+    /// or overlong varints.
+    pub fn decode(data: &mut &[u8], reference_price: i64) -> Option<CompactQuote> {
+        let delta = unzigzag(read_varint(data)?);
+        let confidence = read_varint(data)?;
+        let source_id = read_varint(data)?;
+
+        Some(CompactQuote {
+            price: reference_price.wrapping_add(delta),
+            confidence,
+            source_id,
+        })
+    }
+
+    fn zigzag(value: i64) -> u64 {
+        ((value << 1) ^ (value >> 63)) as u64
+    }
+
+    fn unzigzag(value: u64) -> i64 {
+        ((value >> 1) as i64) ^ -((value & 1) as i64)
+    }
+
+    fn write_varint(mut value: u64, out: &mut Vec<u8>) {
+        while value >= 0x80 {
+            out.push(value as u8 | 0x80);
+            value >>= 7;
+        }
+        out.push(value as u8);
+    }
+
+    fn read_varint(data: &mut &[u8]) -> Option<u64> {
+        let mut value = 0u64;
+        for (i, byte) in data.iter().enumerate().take(MAX_VARINT_LEN) {
+            // The last byte only has room for the top bit of a u64
+            if i == MAX_VARINT_LEN - 1 && *byte > 1 {
+                return None;
+            }
+            value |= ((byte & 0x7f) as u64) << (7 * i);
+            if byte & 0x80 == 0 {
+                *data = &data[i + 1..];
+                return Some(value);
+            }
+        }
+        None
+    }
+}
+
+// ============================================================================
+// Testing Harness
+// ============================================================================
+
+/// Replay helpers over `aggregation`, the pure core the program runs, for replaying
+/// recorded quotes and property-testing off-chain with the program's default settings.
+#[cfg(feature = "testing")]
+pub mod testing {
+    use super::*;
+
+    pub use super::aggregation::{
+        confidence, ema_step, filter_outliers, stake_weighted_median, Aggregate, AggregationConfig, Ema,
+        Rounding, Status,
+    };
+
+    /// A publisher quote as it enters aggregation (after confidence and roll adjustment)
+    #[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+    pub struct Quote {
+        pub price: i64,
+        pub confidence: u64,
+        pub timestamp: i64,
+        pub stake: u64, // Aggregation weight, i.e. stake after probation ramp
+        pub source_id: u64,
+        pub operator: u64,
+    }
+
+    impl aggregation::Quote for Quote {
+        fn price(&self) -> i64 {
+            self.price
+        }
+
+        fn stake(&self) -> u64 {
+            self.stake
+        }
+
+        fn timestamp(&self) -> i64 {
+            self.timestamp
+        }
+
+        fn source_id(&self) -> u64 {
+            self.source_id
+        }
+
+        fn operator(&self) -> u64 {
+            self.operator
+        }
+    }
+
+    /// Settings of a freshly created feed with `min_publishers`
+    pub fn default_config(min_publishers: u8) -> AggregationConfig {
+        AggregationConfig {
+            min_publishers,
+            min_operators: 0,
+            staleness_threshold: STALENESS_THRESHOLD,
+            halted_threshold: HALTED_THRESHOLD,
+            mad_multiplier: OUTLIER_MAD_MULTIPLIER,
+            degraded_confidence_bps: 0,
+            high_precision_decimals: None,
+        }
+    }
+
+    /// Aggregates quotes as `aggregate_prices` would at `current_time` on a feed without
+    /// sampling, dropping stale ones first. Sampled feeds aggregate a subset seeded by a slot hash on
+    /// chain, which this does not reproduce.
+    pub fn aggregate_quotes(quotes: &[Quote], cfg: &AggregationConfig, current_time: i64) -> Aggregate {
+        let fresh: Vec<Quote> = quotes
+            .iter()
+            .filter(|q| aggregation::is_fresh(cfg, q.timestamp, current_time))
+            .copied()
+            .collect();
+        aggregation::aggregate(cfg, fresh, current_time)
+    }
+
+    /// One EMA step with the program's smoothing factor
+    pub fn default_ema_step(current: Ema, price: i64, confidence: u64, rounding: Rounding) -> Ema {
+        ema_step(current, price, confidence, EMA_ALPHA_SCALED, rounding)
+    }
+}